@@ -1,3 +1,4 @@
+use std::io::SeekFrom;
 use std::vec::IntoIter;
 
 use crate::storage;
@@ -6,25 +7,129 @@ use crate::{
     relation::Relation,
     storage::{
         freespace,
-        page::{page_add_item, ItemId},
-        BufferPool,
+        page::{page_add_item, page_free_space, ItemId, ITEM_ID_SIZE},
+        BufferPool, Page, PageNumber, PageWriter,
     },
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-use super::heaptuple::HeapTuple;
+use super::heaptuple::{HeapTuple, HeapTupleHeaderFields, HEAP_PENDING, HEAP_TUPLE_HEADER_SIZE};
+
+/// Range of consecutive item ids of a heap page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemRange {
+    /// Page of the items.
+    pub page_number: PageNumber,
+
+    /// Index of the first item id on page.
+    pub first: usize,
+
+    /// Number of items.
+    pub count: usize,
+}
 
 /// Insert a new tuple into a heap page of the given relation.
 pub fn heap_insert(buffer_pool: &BufferPool, rel: &Relation, tuple: &HeapTuple) -> Result<()> {
-    let buffer = freespace::get_page_with_free_space(buffer_pool, rel)?;
+    let item = tuple.encode()?;
+
+    let buffer = freespace::get_page_with_free_space(buffer_pool, rel, item.len())?;
 
-    page_add_item(&buffer.page, &tuple.encode()?)?;
+    page_add_item(&buffer.page, &item)?;
 
     buffer_pool.unpin_buffer(&buffer, true)?;
 
     Ok(())
 }
 
+/// Insert multiple tuples into the heap pages of the given relation.
+///
+/// This is like heap_insert but the target page is kept pinned while there is free space on
+/// it, so the free space map is only checked when a page is full. It is used by bulk load
+/// operations, such as COPY FROM. Return the ranges of items where the tuples were written.
+pub fn heap_multi_insert(
+    buffer_pool: &BufferPool,
+    rel: &Relation,
+    tuples: &[HeapTuple],
+) -> Result<Vec<ItemRange>> {
+    let mut buffer: Option<Buffer> = None;
+    let mut ranges: Vec<ItemRange> = Vec::new();
+
+    for tuple in tuples {
+        let item = tuple.encode()?;
+
+        if let Some(current) = &buffer {
+            if page_free_space(&current.page)? < item.len() + ITEM_ID_SIZE {
+                buffer_pool.unpin_buffer(current, true)?;
+                buffer = None;
+            }
+        }
+
+        let target = match buffer.take() {
+            Some(buffer) => buffer,
+            None => freespace::get_page_with_free_space(buffer_pool, rel, item.len())?,
+        };
+
+        let index = page_add_item(&target.page, &item)?;
+        let page_number = target.page_number();
+        match ranges.last_mut() {
+            Some(range)
+                if range.page_number == page_number && range.first + range.count == index =>
+            {
+                range.count += 1
+            }
+            _ => ranges.push(ItemRange {
+                page_number,
+                first: index,
+                count: 1,
+            }),
+        }
+        buffer = Some(target);
+    }
+
+    if let Some(buffer) = buffer {
+        buffer_pool.unpin_buffer(&buffer, true)?;
+    }
+
+    Ok(ranges)
+}
+
+/// Make visible the pending tuples written on the given ranges of items of a relation.
+pub fn heap_clear_pending(
+    buffer_pool: &BufferPool,
+    rel: &Relation,
+    ranges: &[ItemRange],
+) -> Result<()> {
+    for range in ranges {
+        let buffer = buffer_pool.fetch_buffer(rel, range.page_number)?;
+        let result = page_clear_pending(&buffer.page, range);
+        buffer_pool.unpin_buffer(&buffer, true)?;
+        result?;
+    }
+    Ok(())
+}
+
+/// Clear the pending flag of the tuples on the given range of items of a page. The header of
+/// a tuple has a fixed size, so it is overwritten in place.
+fn page_clear_pending(page: &Page, range: &ItemRange) -> Result<()> {
+    let items = storage::item_id_data_from_page(page)?;
+    let items = items
+        .get(range.first..range.first + range.count)
+        .ok_or_else(|| anyhow!("invalid item range {:?}", range))?;
+
+    let mut writer = PageWriter::new(page);
+    for item in items {
+        let data = storage::value_from_page_item(page, item)?;
+        let mut fields: HeapTupleHeaderFields =
+            bincode::deserialize(&data[..HEAP_TUPLE_HEADER_SIZE])?;
+        fields.t_infomask &= !HEAP_PENDING;
+        writer.write_at(
+            &bincode::serialize(&fields)?,
+            SeekFrom::Start(item.offset as u64),
+        )?;
+    }
+    Ok(())
+}
+
 /// Heap tuple iterator iterate over all heap tuples of a given relation.
 ///
 /// HeapTupleIterator implements the Iterator trait.
@@ -32,6 +137,16 @@ pub struct HeapScanner {
     /// Buffer pool used to fetch buffers and get buffer page contents.
     buffer_pool: BufferPool,
 
+    /// Relation being scanned.
+    rel: Relation,
+
+    /// Number of pages of relation when the scan started. Pages added after
+    /// the scan started are not scanned.
+    npages: PageNumber,
+
+    /// Page number of the current buffer.
+    page_number: PageNumber,
+
     /// Cursor used to read item id pointers.
     item_id_iter: IntoIter<ItemId>,
 
@@ -43,45 +158,74 @@ pub struct HeapScanner {
 impl HeapScanner {
     /// Create a new heap tuple iterator over the given relation.
     pub fn new(buffer_pool: &BufferPool, rel: &Relation) -> Result<Self> {
-        // TODO: Iterate over all pages on relation
-        let buffer = buffer_pool.fetch_buffer(rel, 1)?;
-
-        let item_id_data = storage::item_id_data_from_page(&buffer.page)?;
-
-        Ok(Self {
+        let mut scanner = Self {
             buffer_pool: buffer_pool.clone(),
-            buffer: Some(buffer),
-            item_id_iter: item_id_data.into_iter(),
-        })
+            rel: rel.clone(),
+            npages: buffer_pool.size_of_relation(rel)?,
+            page_number: 0,
+            buffer: None,
+            item_id_iter: Vec::new().into_iter(),
+        };
+        scanner.next_page()?;
+
+        Ok(scanner)
     }
 
     /// Return the next tuple from buffer if exists. If the all tuples was readed
     /// from current buffer, next_tuple will check if there is more buffer's to
     /// be readed, if not, return None.
     pub fn next_tuple(&mut self) -> Result<Option<HeapTuple>> {
-        match &self.buffer {
-            Some(buffer) => {
-                match self.item_id_iter.next() {
+        loop {
+            match &self.buffer {
+                Some(buffer) => match self.item_id_iter.next() {
                     Some(item_id) => {
                         // Slice the raw page to get a refenrece to a tuple inside the page.
                         let data = storage::value_from_page_item(&buffer.page, &item_id)?;
                         let tuple = HeapTuple::decode(&data)?;
 
-                        Ok(Some(tuple))
-                    }
-                    None => {
-                        // All item data pointers was readed, unpin the buffer
-                        // and return None.
-                        //
-                        // TODO: Check if there is more buffers to read.
-                        self.buffer_pool
-                            .unpin_buffer(&buffer, false /* is_dirty*/)?;
-                        return Ok(None);
+                        // Tuples of a command that did not finish are not visible.
+                        if !tuple.header.is_pending() {
+                            return Ok(Some(tuple));
+                        }
                     }
-                }
+                    // All item data pointers was readed, move to the next page.
+                    None => self.next_page()?,
+                },
+                // There is no more buffer's to scan.
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Unpin the current buffer and fetch the next page of relation. The
+    /// current buffer is set to None if there is no more pages to scan.
+    fn next_page(&mut self) -> Result<()> {
+        if let Some(buffer) = self.buffer.take() {
+            self.buffer_pool
+                .unpin_buffer(&buffer, false /* is_dirty*/)?;
+        }
+
+        if self.page_number >= self.npages {
+            return Ok(());
+        }
+
+        // Pages start at 1.
+        self.page_number += 1;
+        let buffer = self.buffer_pool.fetch_buffer(&self.rel, self.page_number)?;
+        self.item_id_iter = storage::item_id_data_from_page(&buffer.page)?.into_iter();
+        self.buffer = Some(buffer);
+
+        Ok(())
+    }
+}
+
+impl Drop for HeapScanner {
+    fn drop(&mut self) {
+        // Release the current buffer if the scan was not completed.
+        if let Some(buffer) = self.buffer.take() {
+            if let Err(err) = self.buffer_pool.unpin_buffer(&buffer, false) {
+                log::error!("failed to unpin buffer of heap scan: {}", err);
             }
-            // There is no more buffer's to scan.
-            None => Ok(None),
         }
     }
 }
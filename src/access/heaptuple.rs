@@ -14,6 +14,10 @@ const HEAP_HASNULL: u16 = 0x0001;
 /// Bit flag stored on t_infomask informing if a tuple has variable-width attribute(s).
 const HEAP_HASVARWIDTH: u16 = 0x0002;
 
+/// Bit flag stored on t_infomask informing that a tuple was written by a command that did not
+/// finish yet, so it is not visible to scans.
+pub const HEAP_PENDING: u16 = 0x0004;
+
 /// Describe the structure of tuples. Basically it holds the columns of tables.
 pub struct TupleDesc {
     /// Columns of table.
@@ -83,6 +87,20 @@ impl HeapTupleHeader {
     pub fn has_var_width(&self) -> bool {
         self.fields.t_infomask & HEAP_HASVARWIDTH != 0
     }
+
+    /// Return true if heap tuple was written by a command that did not finish yet.
+    pub fn is_pending(&self) -> bool {
+        self.fields.t_infomask & HEAP_PENDING != 0
+    }
+
+    /// Change whether heap tuple was written by a command that did not finish yet.
+    pub fn set_pending(&mut self, pending: bool) {
+        if pending {
+            self.fields.t_infomask |= HEAP_PENDING;
+        } else {
+            self.fields.t_infomask &= !HEAP_PENDING;
+        }
+    }
 }
 
 impl HeapTuple {
@@ -110,16 +128,20 @@ impl HeapTuple {
 
             match datum {
                 Some(datum) => {
+                    heaptuple.header.t_bits.push(false);
+                    heaptuple.header.fields.t_nattrs += 1;
+
                     if attr.attlen < 0 {
                         // Add HEAP_HASVARWIDTH flag on tuple header to inform that
                         // the tuple has varlena fields.
                         heaptuple.header.fields.t_infomask |= HEAP_HASVARWIDTH;
-                    }
 
-                    heaptuple.header.t_bits.push(false);
-                    heaptuple.header.fields.t_nattrs += 1;
-
-                    heaptuple.data.extend_from_slice(datum);
+                        // Variable-length values are stored with the varlena header, which is
+                        // removed when the attribute is fetched with get_attr.
+                        bincode::serialize_into(&mut heaptuple.data, &Varlena::new(datum)?)?;
+                    } else {
+                        heaptuple.data.extend_from_slice(datum);
+                    }
                 }
                 None => {
                     // Add HEAP_HASNULL bit flag on heap header and add true on t_bits
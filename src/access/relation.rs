@@ -2,8 +2,9 @@ use std::sync::Arc;
 
 use crate::{
     catalog::{
-        pg_attribute, pg_class, pg_database,
+        pg_attrdef, pg_attribute, pg_cast, pg_class, pg_database, pg_enum, pg_sequence,
         pg_tablespace::{self, DEFAULTTABLESPACE_OID, GLOBALTABLESPACE_OID},
+        pg_type,
     },
     relation::{Relation, RelationData},
     storage::relation_locator::RelationLocatorData,
@@ -42,6 +43,56 @@ pub fn open_pg_attribute_relation(db_oid: &Oid) -> Relation {
     )
 }
 
+/// Return the pg_type Relation.
+pub fn open_pg_type_relation(db_oid: &Oid) -> Relation {
+    open_relation(
+        pg_type::RELATION_OID,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_type::RELATION_NAME,
+    )
+}
+
+/// Return the pg_cast Relation.
+pub fn open_pg_cast_relation(db_oid: &Oid) -> Relation {
+    open_relation(
+        pg_cast::RELATION_OID,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_cast::RELATION_NAME,
+    )
+}
+
+/// Return the pg_enum Relation.
+pub fn open_pg_enum_relation(db_oid: &Oid) -> Relation {
+    open_relation(
+        pg_enum::RELATION_OID,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_enum::RELATION_NAME,
+    )
+}
+
+/// Return the pg_sequence Relation.
+pub fn open_pg_sequence_relation(db_oid: &Oid) -> Relation {
+    open_relation(
+        pg_sequence::RELATION_OID,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_sequence::RELATION_NAME,
+    )
+}
+
+/// Return the pg_attrdef Relation.
+pub fn open_pg_attrdef_relation(db_oid: &Oid) -> Relation {
+    open_relation(
+        pg_attrdef::RELATION_OID,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_attrdef::RELATION_NAME,
+    )
+}
+
 /// Return the pg_database Relation.
 pub fn open_pg_database_relation() -> Relation {
     open_relation(
@@ -0,0 +1,171 @@
+//! Prepared statements and portals of the extended query protocol.
+//!
+//! A Parse message creates a prepared statement, which is a parsed statement with the types of its
+//! parameters. A Bind message creates a portal from a prepared statement and the values of its
+//! parameters, which is run by Execute messages. The unnamed statement and portal, whose name is
+//! empty, are replaced every time that a new one is created, while named ones must be closed
+//! before the name is used again.
+use std::{collections::HashMap, vec};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    postgres_protocol::commands::Target,
+    sql::{parser, session::Param, RowDescriptor},
+    Datums, Oid,
+};
+
+/// Errors related with prepared statements and portals.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("prepared statement \"{0}\" does not exist")]
+    UndefinedStatement(String),
+
+    #[error("prepared statement \"{0}\" already exists")]
+    DuplicateStatement(String),
+
+    #[error("portal \"{0}\" does not exist")]
+    UndefinedPortal(String),
+
+    #[error("portal \"{0}\" already exists")]
+    DuplicatePortal(String),
+
+    #[error("cannot insert multiple commands into a prepared statement")]
+    MultipleCommands,
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::UndefinedStatement(_) => "26000",
+            Error::DuplicateStatement(_) => "42P05",
+            Error::UndefinedPortal(_) => "34000",
+            Error::DuplicatePortal(_) => "42P03",
+            Error::MultipleCommands => "42601",
+        }
+    }
+}
+
+/// A statement prepared by a Parse message.
+pub struct PreparedStatement {
+    /// Parsed statement, None if the query string is empty.
+    pub stmt: Option<parser::Statement>,
+
+    /// Type of each parameter, unknown if the type was not given by the client nor inferred
+    /// from the statement.
+    pub param_types: Vec<Oid>,
+
+    /// Description of the rows returned by the statement, None if it returns no rows.
+    pub desc: Option<RowDescriptor>,
+}
+
+/// A prepared statement bound to the values of its parameters by a Bind message.
+pub struct Portal {
+    /// Statement executed by the portal, None if the query string is empty.
+    pub stmt: Option<parser::Statement>,
+
+    pub params: Vec<Param>,
+
+    /// Description of the rows returned by the portal, with the format of each column requested
+    /// by the client. None if the portal returns no rows.
+    pub desc: Option<RowDescriptor>,
+
+    pub state: PortalState,
+}
+
+/// Execution state of a portal.
+pub enum PortalState {
+    /// The statement was not executed yet.
+    Ready,
+
+    /// The statement was executed, but not all rows were sent because of the row limit of the
+    /// Execute message. The remaining rows are sent by the next Execute.
+    Suspended {
+        rows: vec::IntoIter<Datums>,
+        tag: String,
+    },
+
+    /// All rows were sent, the command was completed with the given tag.
+    Done(String),
+}
+
+/// State of the extended query protocol of a connection.
+#[derive(Default)]
+pub struct ExtendedQuery {
+    statements: HashMap<String, PreparedStatement>,
+
+    portals: HashMap<String, Portal>,
+
+    /// True after a message failed. The following messages are ignored until a Sync message is
+    /// received, since they may depend on the failed one.
+    pub failed: bool,
+
+    /// True after the implicit transaction of the messages until the next Sync was started.
+    pub in_transaction: bool,
+}
+
+impl ExtendedQuery {
+    /// Add a prepared statement with the given name.
+    pub fn add_statement(&mut self, name: String, statement: PreparedStatement) -> Result<()> {
+        if !name.is_empty() && self.statements.contains_key(&name) {
+            bail!(Error::DuplicateStatement(name));
+        }
+        self.statements.insert(name, statement);
+        Ok(())
+    }
+
+    /// Return the prepared statement with the given name.
+    pub fn statement(&self, name: &str) -> Result<&PreparedStatement> {
+        match self.statements.get(name) {
+            Some(statement) => Ok(statement),
+            None => bail!(Error::UndefinedStatement(name.to_string())),
+        }
+    }
+
+    /// Add a portal with the given name.
+    pub fn add_portal(&mut self, name: String, portal: Portal) -> Result<()> {
+        if !name.is_empty() && self.portals.contains_key(&name) {
+            bail!(Error::DuplicatePortal(name));
+        }
+        self.portals.insert(name, portal);
+        Ok(())
+    }
+
+    /// Return the portal with the given name.
+    pub fn portal(&self, name: &str) -> Result<&Portal> {
+        match self.portals.get(name) {
+            Some(portal) => Ok(portal),
+            None => bail!(Error::UndefinedPortal(name.to_string())),
+        }
+    }
+
+    /// Remove the portal with the given name, so it can be executed while the connection is
+    /// used. The portal should be added back after it is executed.
+    pub fn take_portal(&mut self, name: &str) -> Result<Portal> {
+        match self.portals.remove(name) {
+            Some(portal) => Ok(portal),
+            None => bail!(Error::UndefinedPortal(name.to_string())),
+        }
+    }
+
+    /// Close a prepared statement or a portal. Closing one that does not exist is not an error.
+    pub fn close(&mut self, target: &Target) {
+        match target {
+            Target::Statement(name) => {
+                self.statements.remove(name);
+            }
+            Target::Portal(name) => {
+                self.portals.remove(name);
+            }
+        }
+    }
+
+    /// End the implicit transaction of the messages received since the last Sync, which closes
+    /// all portals.
+    pub fn sync(&mut self) {
+        self.portals.clear();
+        self.failed = false;
+        self.in_transaction = false;
+    }
+}
@@ -0,0 +1,463 @@
+use std::{
+    fs,
+    net::IpAddr,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{bail, Result};
+
+/// Name of the host-based authentication file inside the data directory.
+pub const HBA_FILE_NAME: &str = "tinydb_hba.conf";
+
+/// Default content of tinydb_hba.conf written by initdb. Only connections from the local
+/// machine are accepted.
+pub const DEFAULT_HBA_FILE: &str = "\
+# tinydb client authentication configuration file
+#
+# Each line is a record with the format:
+#
+# TYPE  DATABASE        USER            ADDRESS                 METHOD
+#
+# TYPE can be \"local\" (Unix-domain socket) or \"host\" (TCP/IP socket). DATABASE and
+# USER can be \"all\" or a comma separated list of names. ADDRESS is \"all\", an IP address
+# or an IP address range in CIDR notation, it is not used on \"local\" records. METHOD can
+# be \"trust\" or \"reject\".
+#
+# The first record that matches the connection type, database, user and client address
+# is used to authenticate the connection. If no record matches, access is denied.
+#
+# Send SIGHUP to the server process to reload this file.
+
+local   all             all                                     trust
+host    all             all             127.0.0.1/32            trust
+host    all             all             ::1/128                 trust
+";
+
+/// Errors related with client authentication.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("tinydb_hba.conf rejects connection for host \"{host}\", user \"{user}\", database \"{database}\"")]
+    Rejected {
+        host: String,
+        user: String,
+        database: String,
+    },
+
+    #[error(
+        "no tinydb_hba.conf entry for host \"{host}\", user \"{user}\", database \"{database}\""
+    )]
+    NoEntry {
+        host: String,
+        user: String,
+        database: String,
+    },
+
+    #[error("invalid tinydb_hba.conf entry at line {line}: {reason}")]
+    InvalidEntry { line: usize, reason: String },
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::Rejected { .. } | Error::NoEntry { .. } => "28000",
+            Error::InvalidEntry { .. } => "F0000",
+        }
+    }
+}
+
+/// Kind of socket that a client used to connect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionType {
+    /// Unix-domain socket connection.
+    Local,
+
+    /// TCP/IP socket connection.
+    Host,
+}
+
+/// Authentication method applied to a connection matching an hba record.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthMethod {
+    /// Allow the connection unconditionally.
+    Trust,
+
+    /// Reject the connection unconditionally.
+    Reject,
+}
+
+/// A list of names matched by an hba record, used for both database and user fields.
+#[derive(Debug, Clone, PartialEq)]
+enum NameMatch {
+    All,
+    Names(Vec<String>),
+}
+
+impl NameMatch {
+    fn parse(field: &str) -> Self {
+        if field == "all" {
+            return Self::All;
+        }
+        Self::Names(field.split(',').map(String::from).collect())
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Names(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+/// Client address range matched by an hba record.
+#[derive(Debug, Clone, PartialEq)]
+enum AddressMatch {
+    All,
+    Cidr { addr: IpAddr, prefix: u8 },
+}
+
+impl AddressMatch {
+    fn parse(field: &str) -> Result<Self, String> {
+        if field == "all" {
+            return Ok(Self::All);
+        }
+
+        let (addr, prefix) = match field.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (field, None),
+        };
+
+        let addr =
+            IpAddr::from_str(addr).map_err(|_| format!("invalid IP address \"{}\"", addr))?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => match prefix.parse::<u8>() {
+                Ok(prefix) if prefix <= max_prefix => prefix,
+                _ => return Err(format!("invalid CIDR mask in address \"{}\"", field)),
+            },
+            None => max_prefix,
+        };
+
+        Ok(Self::Cidr { addr, prefix })
+    }
+
+    fn matches(&self, client: &IpAddr) -> bool {
+        match self {
+            Self::All => true,
+            Self::Cidr { addr, prefix } => match (addr, client) {
+                (IpAddr::V4(addr), IpAddr::V4(client)) => {
+                    prefix_matches(&addr.octets(), &client.octets(), *prefix)
+                }
+                (IpAddr::V6(addr), IpAddr::V6(client)) => {
+                    prefix_matches(&addr.octets(), &client.octets(), *prefix)
+                }
+                (IpAddr::V4(addr), IpAddr::V6(client)) => match client.to_ipv4_mapped() {
+                    Some(client) => prefix_matches(&addr.octets(), &client.octets(), *prefix),
+                    None => false,
+                },
+                (IpAddr::V6(_), IpAddr::V4(_)) => false,
+            },
+        }
+    }
+}
+
+/// Return true if the first prefix bits of addr and client are equal.
+fn prefix_matches(addr: &[u8], client: &[u8], prefix: u8) -> bool {
+    let mut bits = prefix as usize;
+    for (a, c) in addr.iter().zip(client) {
+        if bits == 0 {
+            return true;
+        }
+        let mask = if bits >= 8 {
+            0xff
+        } else {
+            0xffu8 << (8 - bits)
+        };
+        if a & mask != c & mask {
+            return false;
+        }
+        bits = bits.saturating_sub(8);
+    }
+    true
+}
+
+/// A single record of tinydb_hba.conf.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HbaLine {
+    /// Line number of this record on tinydb_hba.conf.
+    pub line_number: usize,
+
+    /// Connection type that this record matches.
+    pub conn_type: ConnectionType,
+
+    /// Database names that this record matches.
+    databases: NameMatch,
+
+    /// User names that this record matches.
+    users: NameMatch,
+
+    /// Client address range that this record matches. Always All for local records.
+    address: AddressMatch,
+
+    /// Authentication method to use for connections matching this record.
+    pub method: AuthMethod,
+}
+
+impl HbaLine {
+    /// Return true if a connection with the given parameters matches this record.
+    fn matches(
+        &self,
+        conn_type: ConnectionType,
+        database: &str,
+        user: &str,
+        addr: Option<&IpAddr>,
+    ) -> bool {
+        if self.conn_type != conn_type {
+            return false;
+        }
+
+        if !self.databases.matches(database) || !self.users.matches(user) {
+            return false;
+        }
+
+        match addr {
+            Some(addr) => self.address.matches(addr),
+            None => true,
+        }
+    }
+}
+
+/// Parsed rules of tinydb_hba.conf, in the same order that they appear on file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HbaRules {
+    lines: Vec<HbaLine>,
+}
+
+/// Shared reference to the current hba rules. The rules are replaced when the server
+/// reloads the configuration file.
+pub type SharedHbaRules = Arc<RwLock<HbaRules>>;
+
+impl HbaRules {
+    /// Load the hba rules from the tinydb_hba.conf file inside data_dir.
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(HBA_FILE_NAME);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => bail!("could not load {}: {}", path.display(), err),
+        };
+        Ok(Self::parse(&content)?)
+    }
+
+    /// Parse the content of a tinydb_hba.conf file.
+    pub fn parse(content: &str) -> Result<Self, Error> {
+        let mut lines = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line_number = i + 1;
+
+            // Ignore comments and empty lines.
+            let line = match line.find('#') {
+                Some(pos) => &line[..pos],
+                None => line,
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+
+            let invalid = |reason: String| Error::InvalidEntry {
+                line: line_number,
+                reason,
+            };
+
+            let conn_type = match fields[0] {
+                "local" => ConnectionType::Local,
+                "host" => ConnectionType::Host,
+                other => return Err(invalid(format!("invalid connection type \"{}\"", other))),
+            };
+
+            // Local records don't have the address field.
+            let expected_fields = match conn_type {
+                ConnectionType::Local => 4,
+                ConnectionType::Host => 5,
+            };
+            if fields.len() != expected_fields {
+                return Err(invalid(format!(
+                    "expected {} fields, got {}",
+                    expected_fields,
+                    fields.len()
+                )));
+            }
+
+            let address = match conn_type {
+                ConnectionType::Local => AddressMatch::All,
+                ConnectionType::Host => AddressMatch::parse(fields[3]).map_err(invalid)?,
+            };
+
+            let method = match fields[expected_fields - 1] {
+                "trust" => AuthMethod::Trust,
+                "reject" => AuthMethod::Reject,
+                other => {
+                    return Err(invalid(format!(
+                        "invalid authentication method \"{}\"",
+                        other
+                    )))
+                }
+            };
+
+            lines.push(HbaLine {
+                line_number,
+                conn_type,
+                databases: NameMatch::parse(fields[1]),
+                users: NameMatch::parse(fields[2]),
+                address,
+                method,
+            });
+        }
+
+        Ok(Self { lines })
+    }
+
+    /// Return the first record that matches the given connection parameters. The address
+    /// is None for local connections.
+    pub fn find(
+        &self,
+        conn_type: ConnectionType,
+        database: &str,
+        user: &str,
+        addr: Option<&IpAddr>,
+    ) -> Option<&HbaLine> {
+        self.lines
+            .iter()
+            .find(|line| line.matches(conn_type, database, user, addr))
+    }
+
+    /// Check if a connection with the given parameters is allowed to connect.
+    ///
+    /// Return an error if no record matches the connection or if the matched record rejects
+    /// the connection, otherwise the authentication method that should be applied.
+    pub fn check(
+        &self,
+        conn_type: ConnectionType,
+        database: &str,
+        user: &str,
+        addr: Option<&IpAddr>,
+    ) -> Result<AuthMethod, Error> {
+        let host = match addr {
+            Some(addr) => addr.to_string(),
+            None => String::from("[local]"),
+        };
+
+        match self.find(conn_type, database, user, addr) {
+            Some(line) => match line.method {
+                AuthMethod::Reject => Err(Error::Rejected {
+                    host,
+                    user: user.to_string(),
+                    database: database.to_string(),
+                }),
+                method => Ok(method),
+            },
+            None => Err(Error::NoEntry {
+                host,
+                user: user.to_string(),
+                database: database.to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn test_parse_default_hba_file() -> Result<(), Error> {
+        let rules = HbaRules::parse(DEFAULT_HBA_FILE)?;
+        assert_eq!(rules.lines.len(), 3);
+        assert_eq!(rules.lines[0].conn_type, ConnectionType::Local);
+        assert_eq!(rules.lines[1].method, AuthMethod::Trust);
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_matching_record_is_used() -> Result<(), Error> {
+        let rules = HbaRules::parse(
+            "host tinydb bob 10.0.0.0/8 reject\n\
+             host all all 0.0.0.0/0 trust # trailing comment\n",
+        )?;
+
+        let bob = IpAddr::V4(Ipv4Addr::new(10, 1, 2, 3));
+        assert!(matches!(
+            rules.check(ConnectionType::Host, "tinydb", "bob", Some(&bob)),
+            Err(Error::Rejected { .. })
+        ));
+        assert_eq!(
+            rules.check(ConnectionType::Host, "tinydb", "alice", Some(&bob))?,
+            AuthMethod::Trust
+        );
+
+        let other = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
+        assert_eq!(
+            rules.check(ConnectionType::Host, "tinydb", "bob", Some(&other))?,
+            AuthMethod::Trust
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_matching_record() -> Result<(), Error> {
+        let rules = HbaRules::parse("host db1,db2 all 127.0.0.1 trust")?;
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+        assert!(rules
+            .check(ConnectionType::Host, "db2", "bob", Some(&localhost))
+            .is_ok());
+        assert!(matches!(
+            rules.check(ConnectionType::Host, "db3", "bob", Some(&localhost)),
+            Err(Error::NoEntry { .. })
+        ));
+        assert!(matches!(
+            rules.check(ConnectionType::Local, "db1", "bob", None),
+            Err(Error::NoEntry { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ipv4_mapped_address() -> Result<(), Error> {
+        let rules = HbaRules::parse("host all all 127.0.0.1/32 trust")?;
+        let mapped = IpAddr::V6(Ipv4Addr::LOCALHOST.to_ipv6_mapped());
+        assert!(rules
+            .check(ConnectionType::Host, "tinydb", "bob", Some(&mapped))
+            .is_ok());
+
+        let v6 = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        assert!(rules
+            .check(ConnectionType::Host, "tinydb", "bob", Some(&v6))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_entries() {
+        for content in [
+            "hostssl all all all trust",
+            "host all all trust",
+            "host all all 127.0.0.1/33 trust",
+            "host all all localhost trust",
+            "local all all md5",
+        ] {
+            assert!(
+                matches!(
+                    HbaRules::parse(content),
+                    Err(Error::InvalidEntry { line: 1, .. })
+                ),
+                "expected invalid entry: {}",
+                content
+            );
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+
+/// Name of the lock file inside the data directory.
+pub const LOCK_FILE_NAME: &str = "tinydb.pid";
+
+/// Errors related with the data directory lock file.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("lock file \"{path}\" already exists: is another tinydb (PID {pid}) running in data directory \"{data_dir}\"?")]
+    AlreadyRunning {
+        path: String,
+        pid: String,
+        data_dir: String,
+    },
+
+    #[error("lock file \"{path}\" already exists: is another tinydb (PID {pid}) using socket file \"{socket_path}\"?")]
+    SocketInUse {
+        path: String,
+        pid: String,
+        socket_path: String,
+    },
+}
+
+/// Lock file that prevents two servers from using the same data directory.
+///
+/// The lock file stores the pid, the data directory, the start time and the port of the
+/// server, and an advisory lock is held on it while the server is running. The kernel
+/// releases the advisory lock when the process exits, so a lock file that can be locked was
+/// left behind by a process that is not running anymore and is reused.
+///
+/// The lock file is removed when dropped.
+#[derive(Debug)]
+pub struct DataDirLock {
+    // The lock is only held until dropped.
+    _lock: LockFile,
+}
+
+impl DataDirLock {
+    /// Create the lock file of the given data directory, failing if another server is
+    /// running on it.
+    pub fn acquire(data_dir: &Path, port: u32) -> Result<Self> {
+        let path = data_dir.join(LOCK_FILE_NAME);
+
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        let content = format!(
+            "{}\n{}\n{}\n{}\n",
+            process::id(),
+            data_dir.display(),
+            start_time,
+            port
+        );
+
+        let lock = LockFile::acquire(&path, &content, |pid| Error::AlreadyRunning {
+            path: path.display().to_string(),
+            pid,
+            data_dir: data_dir.display().to_string(),
+        })?;
+
+        Ok(Self { _lock: lock })
+    }
+}
+
+/// Lock file that prevents two servers from using the same unix-domain socket.
+///
+/// The lock file is created next to the socket file, with the ".lock" suffix, and stores
+/// the pid and the data directory of the server. Since the lock is held by every server
+/// that owns a socket file, a socket file whose lock file can be locked was left behind by a
+/// server that is not running anymore and is removed.
+///
+/// The socket file and the lock file are removed when dropped.
+#[derive(Debug)]
+pub struct SocketLock {
+    // The lock is only held until dropped.
+    _lock: LockFile,
+    socket_path: PathBuf,
+}
+
+impl SocketLock {
+    /// Create the lock file of the given socket path, failing if another server owns the
+    /// socket. A stale socket file is removed, so the socket can be bound.
+    pub fn acquire(socket_path: &Path, data_dir: &Path) -> Result<Self> {
+        let mut path = socket_path.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        let content = format!("{}\n{}\n", process::id(), data_dir.display());
+
+        let lock = LockFile::acquire(&path, &content, |pid| Error::SocketInUse {
+            path: path.display().to_string(),
+            pid,
+            socket_path: socket_path.display().to_string(),
+        })?;
+
+        match fs::remove_file(socket_path) {
+            Ok(()) => log::warn!("removing stale socket file {}", socket_path.display()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        Ok(Self {
+            _lock: lock,
+            socket_path: socket_path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for SocketLock {
+    fn drop(&mut self) {
+        // The socket file is removed before the lock file, so the socket file is never left
+        // without a lock.
+        if let Err(err) = fs::remove_file(&self.socket_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                log::error!(
+                    "could not remove socket file {}: {}",
+                    self.socket_path.display(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// A file holding an advisory lock, removed when dropped.
+#[derive(Debug)]
+struct LockFile {
+    file: File,
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Create and lock the file at the given path, replacing its content with the given
+    /// content. The content must start with the pid of the process on the first line.
+    ///
+    /// If another process holds the lock, the error returned by locked is returned, it
+    /// receives the pid read from the file.
+    fn acquire(path: &Path, content: &str, locked: impl Fn(String) -> Error) -> Result<Self> {
+        loop {
+            // The file is not truncated, its content is used to report who holds the lock.
+            let mut file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .read(true)
+                .write(true)
+                .open(path)?;
+
+            let mut previous = String::new();
+            if file.try_lock().is_err() {
+                file.read_to_string(&mut previous)?;
+                bail!(locked(
+                    previous.lines().next().unwrap_or_default().to_string()
+                ));
+            }
+
+            // The previous owner could have removed the file between opening and locking it,
+            // in that case the lock is held on a file that nobody else can see anymore.
+            match path.metadata() {
+                Ok(metadata) if metadata.ino() == file.metadata()?.ino() => {}
+                _ => continue,
+            }
+
+            file.read_to_string(&mut previous)?;
+            if let Some(pid) = previous.lines().next() {
+                log::warn!(
+                    "removing stale lock file {} of process {}",
+                    path.display(),
+                    pid
+                );
+            }
+
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+
+            return Ok(Self {
+                file,
+                path: path.to_path_buf(),
+            });
+        }
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // Remove the file while still holding the lock, so that a new server never sees a
+        // file that is about to be removed as unlocked.
+        if let Err(err) = fs::remove_file(&self.path) {
+            log::error!(
+                "could not remove lock file {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_dir_lock() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let path = data_dir.path().join(LOCK_FILE_NAME);
+
+        let lock = DataDirLock::acquire(data_dir.path(), 6379)?;
+        let content = std::fs::read_to_string(&path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(process::id().to_string(), lines[0]);
+        assert_eq!("6379", lines[3]);
+
+        let err = DataDirLock::acquire(data_dir.path(), 6380).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::AlreadyRunning { pid, .. }) if *pid == process::id().to_string()
+        ));
+
+        drop(lock);
+        assert!(!path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_dir_stale_lock() -> Result<()> {
+        let data_dir = tempfile::tempdir()?;
+        let path = data_dir.path().join(LOCK_FILE_NAME);
+
+        // Lock file left behind by a server that was killed.
+        std::fs::write(&path, "4194304\n/old/data\n0\n5432\n")?;
+
+        let _lock = DataDirLock::acquire(data_dir.path(), 6379)?;
+        let content = std::fs::read_to_string(&path)?;
+        assert_eq!(
+            Some(process::id().to_string().as_str()),
+            content.lines().next()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_socket_lock() -> Result<()> {
+        let socket_dir = tempfile::tempdir()?;
+        let socket_path = socket_dir.path().join(".s.PGSQL.6379");
+        let lock_path = socket_dir.path().join(".s.PGSQL.6379.lock");
+
+        // Socket file left behind by a server that was killed.
+        std::fs::write(&socket_path, "")?;
+
+        let lock = SocketLock::acquire(&socket_path, Path::new("/data"))?;
+        assert!(!socket_path.exists());
+        let content = std::fs::read_to_string(&lock_path)?;
+        assert_eq!(format!("{}\n/data\n", process::id()), content);
+
+        // The socket is owned by a running server, so it is not removed.
+        std::fs::write(&socket_path, "")?;
+        let err = SocketLock::acquire(&socket_path, Path::new("/other")).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::SocketInUse { pid, .. }) if *pid == process::id().to_string()
+        ));
+        assert!(socket_path.exists());
+
+        drop(lock);
+        assert!(!socket_path.exists());
+        assert!(!lock_path.exists());
+
+        Ok(())
+    }
+}
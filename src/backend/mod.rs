@@ -1,22 +1,96 @@
+pub mod extended_query;
+pub mod hba;
+pub mod lockfile;
+
 use crate::{
-    catalog::get_datase_oid,
-    postgres_protocol::{commands::Message, Connection},
-    sql::{ConnectionExecutor, ExecutorConfig, SQLError},
+    advance_oid_counter,
+    catalog::{self, get_datase_oid, pg_type},
+    control::{self, ClusterState},
+    postgres_protocol::{
+        codec::ProtocolError,
+        commands::{Bind, Message, Parse, Target},
+        ClientAddr, Connection, Socket,
+    },
+    sql::{
+        copy::{self, CopyOptions},
+        parser, sequence,
+        session::{self, Param, Session},
+        ConnectionExecutor, ExecutorConfig, PGResult, RowDescriptor, SQLError,
+    },
+    start_oid_counter,
     storage::{smgr::StorageManager, BufferPool},
+    types::{self, datetime::TimeZone},
+    write_oid_counter, INVALID_OID,
 };
 use anyhow::{anyhow, bail, Result};
-use sqlparser::{ast::Statement, dialect::PostgreSqlDialect, parser::Parser};
-use std::{collections::HashMap, future::Future, path::PathBuf};
-use tokio::{net::TcpListener, task};
+use sqlparser::{
+    ast::{CopyTarget, Ident, ObjectName, Statement},
+    dialect::PostgreSqlDialect,
+};
+use std::{
+    collections::HashMap,
+    future::{self, Future},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tokio::{
+    net::{TcpListener, UnixListener},
+    signal::unix::{signal, SignalKind},
+    sync::Semaphore,
+    task, time,
+};
+
+use self::{
+    extended_query::{ExtendedQuery, Portal, PortalState, PreparedStatement},
+    hba::{ConnectionType, HbaRules, SharedHbaRules},
+};
 
-/// Backend TCP listener. It includes a `start` method which performs the TCP listening and
-/// initialization of per-connection state.
+/// Backend TCP and Unix-domain socket listener. It includes a `start` method which performs the
+/// listening and initialization of per-connection state.
 pub struct Backend {
     /// TCP listener supplied by the `start` caller.
     listener: TcpListener,
 
+    /// Unix-domain socket listener supplied by the `start` caller, if any.
+    unix_listener: Option<UnixListener>,
+
     /// Shared buffer pool used by all connection handlers.
     buffer_pool: BufferPool,
+
+    /// Host-based authentication rules used to accept or reject new connections.
+    hba: SharedHbaRules,
+
+    /// Limit the max number of connections.
+    ///
+    /// A permit is acquired for each new connection and released when the
+    /// connection is closed. Connections that can not acquire a permit are
+    /// rejected with an error.
+    limit_connections: Arc<Semaphore>,
+
+    /// Maximum time that a client has to complete the startup handshake.
+    authentication_timeout: Duration,
+}
+
+/// Errors related with the connection lifecycle.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("sorry, too many clients already")]
+    TooManyConnections,
+
+    #[error("canceling authentication due to timeout")]
+    AuthenticationTimeout,
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::TooManyConnections => "53300",
+            Error::AuthenticationTimeout => "57014",
+        }
+    }
 }
 
 /// Per-connection handler. Reads requests from `connection` and applies the
@@ -35,6 +109,9 @@ struct Handler {
     ///
     /// When a command is received from `connection`, it is executed with `conn_executor`.
     conn_executor: ConnectionExecutor,
+
+    /// Prepared statements and portals of the extended query protocol.
+    extended_query: ExtendedQuery,
 }
 
 const DIALECT: PostgreSqlDialect = PostgreSqlDialect {};
@@ -50,15 +127,54 @@ impl Handler {
     async fn run(&mut self) -> Result<()> {
         log::info!("new connection accepted");
         loop {
-            let msg = self.connection.receive().await?;
-            if let Message::Terminate = msg {
-                log::info!("closing connection with {}", self.connection.peer_addr()?);
-                return Ok(());
-            }
+            let msg = match self.connection.receive().await {
+                Ok(msg) => msg,
+                Err(err) => {
+                    // The message stream can not be trusted after a protocol violation, so
+                    // the client is informed and the connection is closed.
+                    if let Some(ProtocolError::InvalidMessageLength(_))
+                    | Some(ProtocolError::InvalidMessageType(_))
+                    | Some(ProtocolError::InvalidMessageFormat(_)) = err.downcast_ref()
+                    {
+                        let msg = err.to_string();
+                        self.connection.send_fatal_error(err).await?;
+                        bail!(msg);
+                    }
+                    return Err(err);
+                }
+            };
 
-            if let Err(err) = self.exec_message(msg).await {
-                self.connection.send_error(err).await?;
-                self.connection.ready_for_query().await?;
+            match msg {
+                Message::Terminate => {
+                    log::info!("closing connection with {}", self.connection.peer_addr());
+                    return Ok(());
+                }
+                Message::Sync => {
+                    self.extended_query.sync();
+                    self.connection.ready_for_query().await?;
+                }
+                Message::Flush => self.connection.flush().await?,
+                Message::Parse(_)
+                | Message::Bind(_)
+                | Message::Describe(_)
+                | Message::Execute(_)
+                | Message::Close(_) => {
+                    // The client is only ready for a new query after the next Sync, and the
+                    // messages received until then are ignored if one of them fails.
+                    if self.extended_query.failed {
+                        continue;
+                    }
+                    if let Err(err) = self.exec_extended_query_message(msg).await {
+                        self.extended_query.failed = true;
+                        self.connection.send_error(err).await?;
+                    }
+                }
+                msg => {
+                    if let Err(err) = self.exec_message(msg).await {
+                        self.connection.send_error(err).await?;
+                    }
+                    self.connection.ready_for_query().await?;
+                }
             }
         }
     }
@@ -66,28 +182,16 @@ impl Handler {
     async fn exec_message(&mut self, message: Message) -> Result<()> {
         match message {
             Message::Query(query) => {
-                let ast = Parser::parse_sql(&DIALECT, &query.query)?;
-                for stmt in ast {
-                    match stmt {
-                        Statement::Query(query) => {
-                            let result = self.conn_executor.exec_query(&query)?;
-                            self.connection.send_result(result).await?;
-                        }
-                        Statement::Insert {
-                            table_name,
-                            columns,
-                            source,
-                            ..
-                        } => {
-                            self.conn_executor
-                                .exec_insert(&table_name, &columns, &source)?;
-                            self.connection.command_complete(&"INSERT").await?;
-                        }
-                        Statement::CreateTable { name, columns, .. } => {
-                            self.conn_executor.exec_create_table(&name, &columns)?;
-                            self.connection.command_complete(&"CREATE").await?;
-                        }
-                        _ => bail!(SQLError::Unsupported(stmt.to_string())),
+                let sql = terminate_query(query.query);
+
+                // All statements of a query message run on the same implicit transaction.
+                session::start_transaction();
+
+                for stmt in parser::parse_sql(&DIALECT, &sql)? {
+                    let (result, tag) = self.exec_statement(stmt).await?;
+                    match result {
+                        Some(result) => self.connection.send_result(result, &tag).await?,
+                        None => self.connection.command_complete(&tag).await?,
                     }
                 }
             }
@@ -95,58 +199,534 @@ impl Handler {
         };
         Ok(())
     }
+
+    /// Execute a parsed statement. Return the rows returned by the statement, if any, and the
+    /// command tag.
+    async fn exec_statement(
+        &mut self,
+        stmt: parser::Statement,
+    ) -> Result<(Option<PGResult>, String)> {
+        match stmt {
+            parser::Statement::Sql(stmt) => self.exec_sql_statement(*stmt).await,
+            parser::Statement::Insert {
+                table_name,
+                columns,
+                source,
+                on_conflict,
+                returning,
+            } => {
+                let (rows, result) = self.conn_executor.exec_insert(
+                    &table_name,
+                    &columns,
+                    &source,
+                    on_conflict.as_ref(),
+                    &returning,
+                )?;
+                Ok((result, format!("INSERT 0 {}", rows)))
+            }
+            parser::Statement::CreateTable {
+                name,
+                columns,
+                identities,
+            } => {
+                self.conn_executor
+                    .exec_create_table(&name, &columns, &identities)?;
+                Ok((None, String::from("CREATE")))
+            }
+            parser::Statement::CreateSequence {
+                name,
+                if_not_exists,
+                options,
+            } => {
+                self.conn_executor
+                    .exec_create_sequence(&name, if_not_exists, &options)?;
+                Ok((None, String::from("CREATE SEQUENCE")))
+            }
+            parser::Statement::CreateType {
+                name,
+                representation,
+            } => {
+                self.conn_executor
+                    .exec_create_type(&name, &representation)?;
+                Ok((None, String::from("CREATE TYPE")))
+            }
+        }
+    }
+
+    /// Execute a statement parsed by the SQL parser.
+    async fn exec_sql_statement(&mut self, stmt: Statement) -> Result<(Option<PGResult>, String)> {
+        match stmt {
+            Statement::Query(query) => {
+                let result = self.conn_executor.exec_query(&query)?;
+                let tag = format!("SELECT {}", result.tuples.len());
+                Ok((Some(result), tag))
+            }
+            Statement::CreateTable { name, columns, .. } => {
+                self.conn_executor.exec_create_table(&name, &columns, &[])?;
+                Ok((None, String::from("CREATE")))
+            }
+            Statement::Copy {
+                table_name,
+                columns,
+                to,
+                target,
+                options,
+                legacy_options,
+                ..
+            } => {
+                let options = CopyOptions::new(&options, &legacy_options)?;
+                let total = match (to, target) {
+                    (false, CopyTarget::Stdin) => {
+                        self.copy_from_stdin(&table_name, &columns, options).await?
+                    }
+                    (true, CopyTarget::Stdout) => {
+                        self.copy_to_stdout(&table_name, &columns, options).await?
+                    }
+                    (to, target) => bail!(SQLError::Unsupported(format!(
+                        "COPY {} {}",
+                        if to { "TO" } else { "FROM" },
+                        target
+                    ))),
+                };
+                Ok((None, format!("COPY {}", total)))
+            }
+            Statement::SetVariable {
+                variable, value, ..
+            } => {
+                self.conn_executor.exec_set(&variable, &value)?;
+                Ok((None, String::from("SET")))
+            }
+            Statement::ShowVariable { variable } => {
+                let result = self.conn_executor.exec_show(&variable)?;
+                Ok((Some(result), String::from("SHOW")))
+            }
+            _ => bail!(SQLError::Unsupported(stmt.to_string())),
+        }
+    }
+
+    /// Execute a message of the extended query protocol.
+    async fn exec_extended_query_message(&mut self, message: Message) -> Result<()> {
+        // All messages until the next Sync run on the same implicit transaction.
+        if !self.extended_query.in_transaction {
+            session::start_transaction();
+            self.extended_query.in_transaction = true;
+        }
+
+        match message {
+            Message::Parse(parse) => {
+                self.prepare_statement(parse)?;
+                self.connection.send_status(Message::ParseComplete).await?;
+            }
+            Message::Bind(bind) => {
+                self.bind_portal(bind)?;
+                self.connection.send_status(Message::BindComplete).await?;
+            }
+            Message::Describe(Target::Statement(name)) => {
+                let statement = self.extended_query.statement(&name)?;
+                // Parameters whose type was not inferred are sent as text, which is used like a
+                // quoted literal.
+                let param_types = statement
+                    .param_types
+                    .iter()
+                    .map(|typ| match *typ {
+                        pg_type::UNKNOWN_OID => pg_type::TEXT_OID,
+                        typ => typ,
+                    })
+                    .collect();
+                let desc = statement.desc.clone();
+                self.connection
+                    .send_parameter_description(param_types)
+                    .await?;
+                self.connection.send_row_description(desc).await?;
+            }
+            Message::Describe(Target::Portal(name)) => {
+                let desc = self.extended_query.portal(&name)?.desc.clone();
+                self.connection.send_row_description(desc).await?;
+            }
+            Message::Execute(execute) => {
+                let mut portal = self.extended_query.take_portal(&execute.portal)?;
+                let result = self.execute_portal(&mut portal, execute.max_rows).await;
+                self.extended_query.add_portal(execute.portal, portal)?;
+                result?;
+            }
+            Message::Close(target) => {
+                self.extended_query.close(&target);
+                self.connection.send_status(Message::CloseComplete).await?;
+            }
+            _ => bail!("Unexpected message type to execute"),
+        }
+        Ok(())
+    }
+
+    /// Parse the query of a Parse message and add the prepared statement.
+    ///
+    /// The statement is analyzed with NULL parameters to describe the rows that it returns,
+    /// which also infers the type of the parameters whose type was not given by the client.
+    fn prepare_statement(&mut self, parse: Parse) -> Result<()> {
+        let sql = terminate_query(parse.query);
+        let mut stmts = parser::parse_sql(&DIALECT, &sql)?;
+        if stmts.len() > 1 {
+            bail!(extended_query::Error::MultipleCommands);
+        }
+        let stmt = stmts.pop();
+
+        let nparams = parser::count_params(&DIALECT, &sql)?.max(parse.param_types.len());
+        let mut params = Vec::with_capacity(nparams);
+        for i in 0..nparams {
+            let typ = match parse.param_types.get(i) {
+                Some(&INVALID_OID) | None => pg_type::UNKNOWN_OID,
+                Some(typ) => types::lookup_type(*typ)?.oid(),
+            };
+            params.push(Param { typ, value: None });
+        }
+
+        session::set_params(params);
+        let desc = match &stmt {
+            Some(stmt) => self.describe_statement(stmt),
+            None => Ok(None),
+        };
+        let param_types = session::params().iter().map(|param| param.typ).collect();
+        session::set_params(Vec::new());
+
+        self.extended_query.add_statement(
+            parse.name,
+            PreparedStatement {
+                stmt,
+                param_types,
+                desc: desc?,
+            },
+        )
+    }
+
+    /// Return the description of the rows returned by a statement, None if it returns no rows.
+    fn describe_statement(&self, stmt: &parser::Statement) -> Result<Option<RowDescriptor>> {
+        match stmt {
+            parser::Statement::Sql(stmt) => match stmt.as_ref() {
+                Statement::Query(query) => Ok(Some(self.conn_executor.describe_query(query)?)),
+                Statement::ShowVariable { variable } => {
+                    Ok(Some(self.conn_executor.exec_show(variable)?.desc))
+                }
+                _ => Ok(None),
+            },
+            parser::Statement::Insert {
+                table_name,
+                columns,
+                source,
+                returning,
+                ..
+            } => self
+                .conn_executor
+                .describe_insert(table_name, columns, source, returning),
+            _ => Ok(None),
+        }
+    }
+
+    /// Create a portal from the prepared statement and the parameter values of a Bind message.
+    ///
+    /// Parameters are received in text or binary format and converted with the input or the
+    /// receive function of their type.
+    fn bind_portal(&mut self, bind: Bind) -> Result<()> {
+        let statement = self.extended_query.statement(&bind.statement)?;
+        let params = bind.param_values(&statement.param_types)?;
+
+        let mut desc = statement.desc.clone();
+        if let Some(desc) = &mut desc {
+            let formats = bind.result_formats(desc.fields.len())?;
+            for (field, format) in desc.fields.iter_mut().zip(formats) {
+                field.format = format.code();
+            }
+        }
+
+        let portal = Portal {
+            stmt: statement.stmt.clone(),
+            params,
+            desc,
+            state: PortalState::Ready,
+        };
+        self.extended_query.add_portal(bind.portal, portal)
+    }
+
+    /// Execute a portal, sending at most max_rows rows if it is not 0. If there are more rows,
+    /// the portal is suspended and the remaining rows are sent by the next execution.
+    async fn execute_portal(&mut self, portal: &mut Portal, max_rows: usize) -> Result<()> {
+        let stmt = match &portal.stmt {
+            Some(stmt) => stmt,
+            None => {
+                return self
+                    .connection
+                    .send_status(Message::EmptyQueryResponse)
+                    .await
+            }
+        };
+
+        let (mut rows, tag) = match std::mem::replace(&mut portal.state, PortalState::Ready) {
+            PortalState::Ready => {
+                session::set_params(portal.params.clone());
+                let result = self.exec_statement(stmt.clone()).await;
+                session::set_params(Vec::new());
+
+                match result? {
+                    (Some(result), tag) => (result.tuples.into_iter(), tag),
+                    (None, tag) => {
+                        self.connection.command_complete(&tag).await?;
+                        portal.state = PortalState::Done(tag);
+                        return Ok(());
+                    }
+                }
+            }
+            PortalState::Suspended { rows, tag } => (rows, tag),
+            PortalState::Done(tag) => (Vec::new().into_iter(), tag),
+        };
+
+        let limit = if max_rows == 0 { rows.len() } else { max_rows };
+        let desc = match &portal.desc {
+            Some(desc) => desc.clone(),
+            None => RowDescriptor { fields: Vec::new() },
+        };
+        self.connection
+            .send_rows(PGResult {
+                desc,
+                tuples: rows.by_ref().take(limit).collect(),
+            })
+            .await?;
+
+        if rows.len() > 0 {
+            self.connection
+                .send_status(Message::PortalSuspended)
+                .await?;
+            portal.state = PortalState::Suspended { rows, tag };
+        } else {
+            self.connection.command_complete(&tag).await?;
+            portal.state = PortalState::Done(tag);
+        }
+        Ok(())
+    }
+
+    /// Receive the data of a COPY FROM STDIN from the client and insert the rows on table.
+    ///
+    /// If an error happens while processing the data, the remaining data is discarded until the
+    /// client finishes the copy, and the error is returned. Return the number of inserted rows.
+    async fn copy_from_stdin(
+        &mut self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        options: CopyOptions,
+    ) -> Result<u64> {
+        let mut copy = self.conn_executor.copy_from(table_name, columns, options)?;
+
+        self.connection.start_copy_in(copy.ncolumns()).await?;
+
+        let mut error = None;
+        loop {
+            match self.connection.receive().await? {
+                Message::CopyData(data) => {
+                    if error.is_none() {
+                        error = copy.process(&data).err();
+                    }
+                }
+                Message::CopyDone => break,
+                // Flush and Sync may be sent by clients that started the COPY with the extended
+                // query protocol, and are ignored until the COPY is finished.
+                Message::Flush | Message::Sync => {}
+                Message::CopyFail(msg) => {
+                    error = Some(anyhow!(copy::Error::Failed(msg)));
+                    break;
+                }
+                msg => bail!(ProtocolError::InvalidMessageFormat(format!(
+                    "unexpected message {:?} during COPY from stdin",
+                    msg
+                ))),
+            }
+        }
+
+        if let Some(err) = error {
+            return Err(err);
+        }
+
+        copy.finish()
+    }
+
+    /// Send the rows of a table to the client as they are read from the table. Return the
+    /// number of rows sent.
+    async fn copy_to_stdout(
+        &mut self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        options: CopyOptions,
+    ) -> Result<u64> {
+        let mut copy = self.conn_executor.copy_to(table_name, columns, options)?;
+
+        self.connection.start_copy_out(copy.ncolumns()).await?;
+        while let Some(row) = copy.next_row()? {
+            self.connection.send_copy_data(row).await?;
+        }
+        self.connection.finish_copy_out().await?;
+        Ok(copy.processed())
+    }
 }
 
 impl Backend {
-    /// Create a new backend using the given listener to accept incoming tcp connections. The given
-    /// buffer pool is shared with all connections handlers.
-    pub fn new(listener: TcpListener, buffer_pool: BufferPool) -> Self {
+    /// Create a new backend using the given listeners to accept incoming tcp and unix-domain
+    /// socket connections. The given buffer pool is shared with all connections handlers and
+    /// the hba rules are checked for each new connection.
+    pub fn new(
+        listener: TcpListener,
+        unix_listener: Option<UnixListener>,
+        buffer_pool: BufferPool,
+        hba: SharedHbaRules,
+        config: &Config,
+    ) -> Self {
         Self {
             listener,
+            unix_listener,
             buffer_pool,
+            hba,
+            limit_connections: Arc::new(Semaphore::new(config.max_connections)),
+            authentication_timeout: config.authentication_timeout,
         }
     }
 
     /// Start the backend.
     ///
     /// Listen for inbound connections. For each inbound connection, spawn a
-    /// task to process that connection.
+    /// task to perform the startup handshake and process that connection.
+    ///
+    /// Errors that happen during the handshake of a connection are sent back
+    /// to the client and do not stop the backend.
     pub async fn start(&self) -> Result<()> {
         log::info!("database system is ready to accept connections");
         loop {
-            let (socket, _) = self.listener.accept().await?;
+            let (socket, peer_addr) = self.accept().await?;
 
-            let mut connection = Connection::new(socket);
+            // Try to reserve a connection slot. If all slots are in use the
+            // handshake is still performed so that the client can receive the
+            // error message.
+            let permit = self.limit_connections.clone().try_acquire_owned().ok();
 
-            let startup_message = connection.startup_message().await?;
+            let buffer_pool = self.buffer_pool.clone();
+            let hba = self.hba.clone();
+            let authentication_timeout = self.authentication_timeout;
 
-            match executor_config_from_startup_parameters(
-                &self.buffer_pool,
-                startup_message.parameters,
-            ) {
-                Ok(config) => {
-                    connection.send_authentication_ok().await?;
+            task::spawn(async move {
+                let mut connection = Connection::new(socket, peer_addr);
 
-                    let mut handler = Handler {
-                        connection,
-                        conn_executor: ConnectionExecutor::new(config, self.buffer_pool.clone()),
-                    };
+                let handshake = handshake(&mut connection, &buffer_pool, &hba, permit.is_some());
 
-                    task::spawn(async move {
-                        if let Err(err) = handler.run().await {
-                            log::error!("connection serve error: {}", err);
+                let config = match time::timeout(authentication_timeout, handshake).await {
+                    Ok(Ok(config)) => config,
+                    Ok(Err(err)) => {
+                        log::error!("failed to authenticate {}: {}", peer_addr, err);
+                        if let Err(err) = connection.send_fatal_error(err).await {
+                            log::error!("failed to send error to {}: {}", peer_addr, err);
                         }
-                    });
-                }
-                Err(err) => {
-                    log::error!("failed to authenticate: {}", err);
-                    connection.send_error(err).await?;
+                        return;
+                    }
+                    Err(_) => {
+                        log::error!("authentication timeout for {}", peer_addr);
+                        let err = anyhow!(Error::AuthenticationTimeout);
+                        if let Err(err) = connection.send_fatal_error(err).await {
+                            log::error!("failed to send error to {}: {}", peer_addr, err);
+                        }
+                        return;
+                    }
+                };
+
+                let session = Session::new(
+                    config.timezone.clone(),
+                    config.database,
+                    buffer_pool.clone(),
+                );
+                let mut handler = Handler {
+                    connection,
+                    conn_executor: ConnectionExecutor::new(config, buffer_pool),
+                    extended_query: ExtendedQuery::default(),
+                };
+
+                if let Err(err) = session::scope(session, handler.run()).await {
+                    log::error!("connection serve error: {}", err);
                 }
+
+                // Release the connection slot only after the connection is closed.
+                drop(permit);
+            });
+        }
+    }
+
+    /// Accept a new inbound connection from any of the listeners.
+    async fn accept(&self) -> Result<(Box<dyn Socket>, ClientAddr)> {
+        let unix_accept = async {
+            match &self.unix_listener {
+                Some(listener) => listener.accept().await,
+                // Without a unix-domain socket listener only tcp connections are accepted.
+                None => future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            res = self.listener.accept() => {
+                let (socket, addr) = res?;
+                Ok((Box::new(socket), ClientAddr::Tcp(addr)))
+            }
+            res = unix_accept => {
+                let (socket, _) = res?;
+                Ok((Box::new(socket), ClientAddr::Local))
             }
         }
     }
 }
 
+/// Terminate the last statement of a query string with a semicolon, since the parser only accepts
+/// a COPY FROM STDIN terminated by a semicolon, but clients are not required to send it.
+fn terminate_query(mut sql: String) -> String {
+    if !sql.trim_end().ends_with(';') {
+        sql.push_str("\n;");
+    }
+    sql
+}
+
+/// Return the path of the unix-domain socket file inside the given directory for the given
+/// port. This is the same path that libpq clients use when the host is a directory.
+pub fn unix_socket_path(socket_dir: &Path, port: u32) -> PathBuf {
+    socket_dir.join(format!(".s.PGSQL.{}", port))
+}
+
+/// Perform the startup handshake of a new connection.
+///
+/// The startup message is read from the client, the connection is checked against the hba
+/// rules and an AuthenticationOk is sent back if the client is allowed to connect.
+///
+/// has_slot informs if a connection slot was reserved for this connection, if not, the
+/// startup message is consumed and an error is returned.
+async fn handshake(
+    connection: &mut Connection,
+    buffer_pool: &BufferPool,
+    hba: &SharedHbaRules,
+    has_slot: bool,
+) -> Result<ExecutorConfig> {
+    let startup_message = connection.startup_message().await?;
+
+    if !has_slot {
+        bail!(Error::TooManyConnections);
+    }
+
+    let (conn_type, addr) = match connection.peer_addr() {
+        ClientAddr::Tcp(addr) => (ConnectionType::Host, Some(addr.ip())),
+        ClientAddr::Local => (ConnectionType::Local, None),
+    };
+
+    let config = executor_config_from_startup_parameters(
+        buffer_pool,
+        hba,
+        conn_type,
+        addr.as_ref(),
+        startup_message.parameters,
+    )?;
+
+    connection.send_authentication_ok().await?;
+
+    Ok(config)
+}
+
 /// Backend server configuration options.
 pub struct Config {
     /// Absolute path to PGDATA directory.
@@ -154,24 +734,65 @@ pub struct Config {
 
     /// Size of buffer pool.
     pub buffer_pool_size: usize,
+
+    /// Maximum number of concurrent client connections.
+    pub max_connections: usize,
+
+    /// Maximum time to complete the startup handshake of a new connection.
+    pub authentication_timeout: Duration,
 }
 
 /// Start the tinydb backend server.
 ///
 /// Accepts connections from the supplied listener. For each inbound connection,
 /// a task is spawned to handle that connection. The server runs until the
-/// `shutdown` future completes, at which point the server shuts down
-/// gracefully.
+/// `shutdown` future completes or a SIGTERM signal is received, at which point
+/// the server shuts down gracefully.
 ///
 /// `tokio::signal::ctrl_c()` can be used as the `shutdown` argument. This will
 /// listen for a SIGINT signal.
-pub async fn start(config: &Config, listener: TcpListener, shutdown: impl Future) {
+///
+/// The host-based authentication rules are loaded from the data directory before
+/// accepting connections and are reloaded when a SIGHUP signal is received.
+///
+/// If unix_listener is supplied, connections are also accepted from the unix-domain socket.
+///
+/// The cluster is marked as in production on the control file only after the server is ready to
+/// accept connections, so an error while starting leaves the control file unchanged. It is
+/// marked as shut down when the server stops. If the previous server was not shut down cleanly,
+/// the state that is kept on memory is recovered from the catalogs before accepting connections.
+pub async fn start(
+    config: &Config,
+    listener: TcpListener,
+    unix_listener: Option<UnixListener>,
+    shutdown: impl Future,
+) -> Result<()> {
+    start_oid_counter(&config.data_dir)?;
+
+    let hba = Arc::new(RwLock::new(HbaRules::load(&config.data_dir)?));
+
     let buffer = BufferPool::new(
         config.buffer_pool_size,
         StorageManager::new(&config.data_dir),
     );
 
-    let backend = Backend::new(listener, buffer.clone());
+    // Types created with CREATE TYPE are kept on the type registry while the server is running.
+    catalog::load_user_defined_types(&buffer)?;
+
+    if control::startup(&config.data_dir)? == ClusterState::InProduction {
+        log::warn!(
+            "database system was not properly shut down; changes made since the last shutdown may be lost"
+        );
+        recover(&buffer)?;
+    }
+
+    task::spawn(reload_hba_on_sighup(config.data_dir.clone(), hba.clone()));
+
+    let backend = Backend::new(listener, unix_listener, buffer.clone(), hba, config);
+
+    // SIGTERM is the signal sent by service managers to stop the server, so it shuts down
+    // cleanly like the given shutdown future does.
+    let mut sigterm = signal(SignalKind::terminate())?;
 
     tokio::select! {
         res = backend.start() => {
@@ -187,13 +808,65 @@ pub async fn start(config: &Config, listener: TcpListener, shutdown: impl Future
         }
         _ = shutdown => {
             // Shutdown signal has been received.
-            //
-            // The buffer pool will be droped at this point that will force all
-            // in memory dirty pages to be written on disk.
-            drop(buffer);
+        }
+        _ = sigterm.recv() => {
+            log::info!("received SIGTERM signal");
+        }
+    }
+
+    // All in memory dirty pages are written on disk before marking the cluster as shut down on
+    // the control file.
+    if let Err(err) = sequence::write_sequences(&buffer) {
+        log::error!("failed to write sequences: {}", err);
+    }
+    let checkpoint = buffer
+        .flush_all_buffers()
+        .and_then(|_| write_oid_counter())
+        .and_then(|_| control::shutdown(&config.data_dir));
+    if let Err(err) = checkpoint {
+        log::error!("failed to shut down cleanly: {}", err);
+    }
+    drop(buffer);
+
+    log::info!("shutting down");
+
+    Ok(())
+}
 
-            log::info!("shutting down");
+/// Recover the state of a server that was not shut down cleanly.
+///
+/// OIDs and sequence values are reserved on disk before they are used, so none of them is
+/// assigned twice. But the pages that were only on memory are lost, so the OID counter is moved
+/// after the OIDs found on the catalogs and the sequences that lost their tuple are restored.
+fn recover(buffer: &BufferPool) -> Result<()> {
+    advance_oid_counter(catalog::max_object_id(buffer)?);
+    sequence::recover_sequences(buffer)?;
+    buffer.flush_all_buffers()
+}
+
+/// Reload the host-based authentication rules every time that a SIGHUP signal is received.
+///
+/// If the new file contains errors the current rules are kept.
+async fn reload_hba_on_sighup(data_dir: PathBuf, hba: SharedHbaRules) {
+    let mut sighup = match signal(SignalKind::hangup()) {
+        Ok(sighup) => sighup,
+        Err(err) => {
+            log::error!("failed to listen for SIGHUP signal: {}", err);
+            return;
         }
+    };
+
+    while sighup.recv().await.is_some() {
+        log::info!("received SIGHUP, reloading configuration files");
+        reload_hba(&data_dir, &hba);
+    }
+}
+
+/// Replace the current hba rules with the rules from tinydb_hba.conf.
+fn reload_hba(data_dir: &Path, hba: &SharedHbaRules) {
+    match HbaRules::load(data_dir) {
+        Ok(rules) => *hba.write().unwrap() = rules,
+        Err(err) => log::error!("{} was not reloaded: {}", hba::HBA_FILE_NAME, err),
     }
 }
 
@@ -202,16 +875,47 @@ pub async fn start(config: &Config, listener: TcpListener, shutdown: impl Future
 /// The database name is read from the given parameters and the respective OID is searched on
 /// database catalog, an error is returned if the database don't exists.
 ///
+/// The connection is also checked against the hba rules, an error is returned if the rules
+/// reject the connection.
+///
+/// The session time zone is read from the TimeZone parameter if the client sends it, e.g
+/// from the PGTZ environment variable, otherwise it is UTC. An invalid time zone is logged
+/// and UTC is used instead.
+///
 // TODO: Make this HashMap of connection parameters into a struct.
 fn executor_config_from_startup_parameters(
     buffer_pool: &BufferPool,
+    hba: &SharedHbaRules,
+    conn_type: ConnectionType,
+    addr: Option<&IpAddr>,
     parameters: HashMap<String, String>,
 ) -> Result<ExecutorConfig> {
     let dbname = parameters
         .get("database")
         .ok_or_else(|| anyhow!("database name does not exists on connection parameters",))?;
 
+    let user = parameters
+        .get("user")
+        .ok_or_else(|| anyhow!("no user name specified in startup packet"))?;
+
+    hba.read().unwrap().check(conn_type, dbname, user, addr)?;
+
     let dboid = get_datase_oid(buffer_pool, dbname)?;
 
-    Ok(ExecutorConfig { database: dboid })
+    let timezone = match parameters
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("timezone"))
+    {
+        // An unknown time zone does not prevent the client from connecting.
+        Some((_, value)) => TimeZone::parse(value).unwrap_or_else(|err| {
+            log::warn!("{}, using UTC instead", err);
+            TimeZone::utc()
+        }),
+        None => TimeZone::utc(),
+    };
+
+    Ok(ExecutorConfig {
+        database: dboid,
+        timezone,
+    })
 }
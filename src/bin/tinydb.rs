@@ -1,13 +1,19 @@
-use std::env;
+use std::{env, fs, time::Duration};
 
 use structopt::StructOpt;
 use tinydb::{
-    backend,
+    backend::{
+        self,
+        lockfile::{DataDirLock, SocketLock},
+    },
     cli::Flags,
     initdb::init_database,
     storage::{smgr::StorageManager, BufferPool},
 };
-use tokio::{net::TcpListener, signal};
+use tokio::{
+    net::{TcpListener, UnixListener},
+    signal,
+};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -23,6 +29,22 @@ async fn main() -> anyhow::Result<()> {
 
     let cwd = env::current_dir().expect("Failed to get current working directory");
     let data_dir = cwd.join(&flags.data_dir);
+    let socket_path = if flags.unix_socket_directory.is_empty() {
+        None
+    } else {
+        Some(backend::unix_socket_path(
+            &cwd.join(&flags.unix_socket_directory),
+            flags.port,
+        ))
+    };
+
+    if flags.init {
+        fs::create_dir_all(&data_dir)?;
+    }
+
+    // Hold the lock file until the server exits, so that no other server can use the same
+    // data directory.
+    let _lock = DataDirLock::acquire(&data_dir, flags.port)?;
 
     if flags.init {
         log::info!("initializing database directory");
@@ -35,9 +57,22 @@ async fn main() -> anyhow::Result<()> {
 
     let listener = TcpListener::bind(format!("{}:{}", flags.hostname, flags.port)).await?;
 
+    // Hold the socket lock file until the server exits, the socket file is removed with it.
+    let socket_lock = match &socket_path {
+        Some(socket_path) => Some(SocketLock::acquire(socket_path, &data_dir)?),
+        None => None,
+    };
+
+    let unix_listener = match &socket_path {
+        Some(socket_path) => Some(UnixListener::bind(socket_path)?),
+        None => None,
+    };
+
     let config = backend::Config {
         data_dir,
         buffer_pool_size: 120,
+        max_connections: flags.max_connections,
+        authentication_timeout: Duration::from_secs(flags.authentication_timeout),
     };
 
     log::info!("starting tinydb server");
@@ -46,7 +81,12 @@ async fn main() -> anyhow::Result<()> {
         flags.hostname,
         flags.port
     );
-    backend::start(&config, listener, signal::ctrl_c()).await;
+    if let Some(socket_path) = &socket_path {
+        log::info!("listening on unix socket {}", socket_path.display());
+    }
+    backend::start(&config, listener, unix_listener, signal::ctrl_c()).await?;
+
+    drop(socket_lock);
     log::info!("database system is shut down");
 
     Ok(())
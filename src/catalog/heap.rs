@@ -5,13 +5,16 @@ use crate::{
         heaptuple::{HeapTuple, TupleDesc},
     },
     relation::Relation,
-    storage::{page::PageHeader, BufferPool, PageWriter},
+    storage::{freespace, BufferPool},
     Oid,
 };
 use anyhow::Result;
 use log::debug;
 
-use super::{pg_class::PgClass, pg_tablespace::GLOBALTABLESPACE_OID};
+use super::{
+    pg_class::{PgClass, RELKIND_COMPOSITE_TYPE, RELKIND_RELATION, RELKIND_SEQUENCE},
+    pg_tablespace::{DEFAULTTABLESPACE_OID, GLOBALTABLESPACE_OID},
+};
 
 /// Create a new cataloged heap relation.
 pub fn heap_create(
@@ -21,6 +24,47 @@ pub fn heap_create(
     rel_name: &str,
     new_rel_oid: Oid,
     tupledesc: &TupleDesc,
+) -> Result<Relation> {
+    heap_create_with_kind(
+        buffer,
+        tablespace,
+        db_oid,
+        rel_name,
+        new_rel_oid,
+        tupledesc,
+        RELKIND_RELATION,
+    )
+}
+
+/// Create a new cataloged sequence relation. The relation is created empty, the caller should
+/// insert the sequence tuple.
+pub fn sequence_create(
+    buffer: &BufferPool,
+    db_oid: &Oid,
+    rel_name: &str,
+    new_rel_oid: Oid,
+    tupledesc: &TupleDesc,
+) -> Result<Relation> {
+    heap_create_with_kind(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        rel_name,
+        new_rel_oid,
+        tupledesc,
+        RELKIND_SEQUENCE,
+    )
+}
+
+/// Create a new cataloged relation of the given kind that stores heap tuples.
+fn heap_create_with_kind(
+    buffer: &BufferPool,
+    tablespace: Oid,
+    db_oid: &Oid,
+    rel_name: &str,
+    new_rel_oid: Oid,
+    tupledesc: &TupleDesc,
+    relkind: &str,
 ) -> Result<Relation> {
     // Create a new relation object for the new heap relation.
     let new_rel = access::open_relation(new_rel_oid, tablespace, db_oid, rel_name);
@@ -32,7 +76,7 @@ pub fn heap_create(
     let pg_class = access::open_pg_class_relation(db_oid);
 
     // Now create an entry in pg_class for the relation.
-    add_new_relation_tuple(buffer, &pg_class, &new_rel)?;
+    add_new_relation_tuple(buffer, &pg_class, &new_rel, relkind)?;
 
     // Now that the new relation is already stored on pg_class, initialize the default page header
     // data
@@ -41,6 +85,25 @@ pub fn heap_create(
     Ok(new_rel)
 }
 
+/// Catalog the attributes of a new composite type on pg_class and pg_attribute. The relation
+/// has no storage, it only describes the attributes of the type.
+pub fn composite_type_create(
+    buffer: &BufferPool,
+    db_oid: &Oid,
+    type_name: &str,
+    new_rel_oid: Oid,
+    tupledesc: &TupleDesc,
+) -> Result<()> {
+    let new_rel = access::open_relation(new_rel_oid, DEFAULTTABLESPACE_OID, db_oid, type_name);
+
+    add_new_attribute_tuples(buffer, &new_rel, tupledesc)?;
+
+    let pg_class = access::open_pg_class_relation(db_oid);
+    add_new_relation_tuple(buffer, &pg_class, &new_rel, RELKIND_COMPOSITE_TYPE)?;
+
+    Ok(())
+}
+
 /// Registers the new relation's schema by adding tuples to pg_attribute.
 fn add_new_attribute_tuples(
     buffer: &BufferPool,
@@ -69,6 +132,7 @@ fn add_new_relation_tuple(
     buffer: &BufferPool,
     pg_class: &Relation,
     new_rel: &Relation,
+    relkind: &str,
 ) -> Result<()> {
     // Initialize default page header of pg_class relation if needed.
     if buffer.size_of_relation(pg_class)? == 0 {
@@ -84,6 +148,7 @@ fn add_new_relation_tuple(
             relname: new_rel.rel_name.clone(),
             reltablespace: new_rel.locator.tablespace,
             relisshared: new_rel.locator.tablespace == GLOBALTABLESPACE_OID,
+            relkind: relkind.to_string(),
         })?,
     )?;
 
@@ -98,10 +163,7 @@ pub fn initialize_default_page_header(buffer_pool: &BufferPool, rel: &Relation)
         return Ok(());
     }
 
-    let buffer = buffer_pool.alloc_buffer(rel)?;
-
-    let mut page_writer = PageWriter::new(&buffer.page);
-    bincode::serialize_into(&mut page_writer, &PageHeader::default())?;
+    let buffer = freespace::extend_relation(buffer_pool, rel)?;
 
     // Force a write to make sure that future fetches of this page see the page header correctly.
     buffer_pool.flush_buffer(&buffer)?;
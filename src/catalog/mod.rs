@@ -1,18 +1,42 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+
+use std::collections::HashMap;
 
 use crate::{
-    access::{self, heap::HeapScanner, heaptuple::TupleDesc},
+    access::{
+        self,
+        heap::{heap_insert, HeapScanner},
+        heaptuple::{HeapTuple, TupleDesc},
+    },
     new_object_id,
+    relation::Relation,
     storage::{relation_locator::relation_path, BufferPool},
-    Oid,
+    types::{
+        self, array::ArrayType, composite::CompositeType, enumerated::EnumType, Type, TypeKind,
+    },
+    Oid, INVALID_OID,
 };
 
-use self::{pg_attribute::PgAttribute, pg_class::PgClass, pg_database::PgDatabase};
+use self::{
+    pg_attrdef::PgAttrdef,
+    pg_attribute::PgAttribute,
+    pg_class::{PgClass, RELKIND_RELATION},
+    pg_database::PgDatabase,
+    pg_enum::PgEnum,
+    pg_sequence::PgSequence,
+    pg_type::PgType,
+};
 
 pub mod heap;
+pub mod pg_attrdef;
 pub mod pg_attribute;
+pub mod pg_cast;
 pub mod pg_class;
 pub mod pg_database;
+pub mod pg_enum;
+pub mod pg_proc;
+pub mod pg_sequence;
 pub mod pg_tablespace;
 pub mod pg_type;
 
@@ -24,6 +48,32 @@ pub enum Error {
 
     #[error("database {0} does not exist")]
     DatabaseNotFound(String),
+
+    #[error("type \"{0}\" does not exist")]
+    TypeNotFound(String),
+
+    #[error("type \"{0}\" already exists")]
+    DuplicateType(String),
+
+    #[error("enum label \"{0}\" used more than once")]
+    DuplicateEnumLabel(String),
+
+    #[error("relation \"{0}\" already exists")]
+    DuplicateRelation(String),
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::RelationNotFound(_) => "42P01",
+            Error::DatabaseNotFound(_) => "3D000",
+            Error::TypeNotFound(_) => "42704",
+            Error::DuplicateType(_) => "42710",
+            Error::DuplicateEnumLabel(_) => "42710",
+            Error::DuplicateRelation(_) => "42P07",
+        }
+    }
 }
 
 /// Return the tuple description of the given relation name.
@@ -49,31 +99,60 @@ pub fn tuple_desc_from_relation(
     Ok(TupleDesc { attrs: attributes })
 }
 
-/// Return the pg class tuple from the given relation name.
+/// Return the pg class tuple of the table with the given relation name.
 pub fn get_pg_class_relation(
     buffer_pool: &BufferPool,
     db_oid: &Oid,
     rel_name: &str,
 ) -> Result<PgClass> {
-    let pg_class_rel = access::open_pg_class_relation(db_oid);
+    find_pg_class(buffer_pool, db_oid, |pg_class| {
+        pg_class.relname == rel_name && pg_class.relkind == RELKIND_RELATION
+    })?
+    .ok_or_else(|| anyhow!(Error::RelationNotFound(rel_name.to_string())))
+}
 
-    let mut pg_class_tuple = None;
+/// Return the pg class tuple of the relation of any kind with the given name, if exists.
+pub fn find_pg_class_by_name(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    rel_name: &str,
+) -> Result<Option<PgClass>> {
+    find_pg_class(buffer_pool, db_oid, |pg_class| pg_class.relname == rel_name)
+}
+
+/// Return the pg class tuple of the relation of any kind with the given oid, if exists.
+pub fn find_pg_class_by_oid(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    rel_oid: Oid,
+) -> Result<Option<PgClass>> {
+    find_pg_class(buffer_pool, db_oid, |pg_class| pg_class.oid == rel_oid)
+}
+
+/// Return an error if a relation of any kind with the given name already exists.
+pub fn check_relation_name(buffer_pool: &BufferPool, db_oid: &Oid, rel_name: &str) -> Result<()> {
+    if find_pg_class_by_name(buffer_pool, db_oid, rel_name)?.is_some() {
+        bail!(Error::DuplicateRelation(rel_name.to_string()));
+    }
+    Ok(())
+}
+
+/// Return the first pg_class tuple that matches the given predicate.
+fn find_pg_class<P>(buffer_pool: &BufferPool, db_oid: &Oid, predicate: P) -> Result<Option<PgClass>>
+where
+    P: Fn(&PgClass) -> bool,
+{
+    let pg_class_rel = access::open_pg_class_relation(db_oid);
 
     let mut heap = HeapScanner::new(buffer_pool, &pg_class_rel)?;
     while let Some(tuple) = heap.next_tuple()? {
-        // Do nothing if the oid is already founded.
-        if pg_class_tuple.is_none() {
-            let pg_class = bincode::deserialize::<PgClass>(&tuple.data)?;
-            if pg_class.relname == rel_name {
-                pg_class_tuple = Some(pg_class);
-            }
+        let pg_class = bincode::deserialize::<PgClass>(&tuple.data)?;
+        if predicate(&pg_class) {
+            return Ok(Some(pg_class));
         }
     }
 
-    match pg_class_tuple {
-        Some(tuple) => Ok(tuple),
-        None => bail!(Error::RelationNotFound(rel_name.to_string())),
-    }
+    Ok(None)
 }
 
 /// Return the database oid for the given database name.
@@ -91,15 +170,338 @@ pub fn get_datase_oid(buffer_pool: &BufferPool, dbname: &str) -> Result<Oid> {
     bail!(Error::DatabaseNotFound(dbname.to_string()))
 }
 
+/// Return the pg_type tuple of the given type oid.
+pub fn get_pg_type(buffer_pool: &BufferPool, db_oid: &Oid, oid: Oid) -> Result<PgType> {
+    find_pg_type(buffer_pool, db_oid, |typ| typ.oid == oid)?
+        .ok_or_else(|| anyhow!(Error::TypeNotFound(oid.to_string())))
+}
+
+/// Return the pg_type tuple of the given type name.
+pub fn get_pg_type_by_name(buffer_pool: &BufferPool, db_oid: &Oid, name: &str) -> Result<PgType> {
+    find_pg_type(buffer_pool, db_oid, |typ| typ.typname == name)?
+        .ok_or_else(|| anyhow!(Error::TypeNotFound(name.to_string())))
+}
+
+/// Return the first pg_type tuple that matches the given predicate.
+fn find_pg_type<P>(buffer_pool: &BufferPool, db_oid: &Oid, predicate: P) -> Result<Option<PgType>>
+where
+    P: Fn(&PgType) -> bool,
+{
+    let pg_type_rel = access::open_pg_type_relation(db_oid);
+
+    let mut heap = HeapScanner::new(buffer_pool, &pg_type_rel)?;
+    while let Some(tuple) = heap.next_tuple()? {
+        let pg_type = bincode::deserialize::<PgType>(&tuple.data)?;
+        if predicate(&pg_type) {
+            return Ok(Some(pg_type));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Return the pg_sequence tuple of the given sequence relation.
+pub fn get_pg_sequence(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    seqrelid: Oid,
+) -> Result<PgSequence> {
+    let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_sequence_relation(db_oid))?;
+    while let Some(tuple) = heap.next_tuple()? {
+        let pg_sequence = bincode::deserialize::<PgSequence>(&tuple.data)?;
+        if pg_sequence.seqrelid == seqrelid {
+            return Ok(pg_sequence);
+        }
+    }
+
+    bail!("sequence {} not found on pg_sequence", seqrelid)
+}
+
+/// Return the default values of the attributes of the given relation, ordered by attribute
+/// number.
+pub fn get_attr_defaults(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    relid: Oid,
+) -> Result<Vec<PgAttrdef>> {
+    let mut defaults = Vec::new();
+    let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_attrdef_relation(db_oid))?;
+    while let Some(tuple) = heap.next_tuple()? {
+        let pg_attrdef = bincode::deserialize::<PgAttrdef>(&tuple.data)?;
+        if pg_attrdef.adrelid == relid {
+            defaults.push(pg_attrdef);
+        }
+    }
+
+    defaults.sort_by_key(|pg_attrdef| pg_attrdef.adnum);
+    Ok(defaults)
+}
+
+/// Store the default value of the given attribute on pg_attrdef.
+pub fn store_attr_default(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    adrelid: Oid,
+    adnum: usize,
+    adbin: String,
+) -> Result<()> {
+    heap_insert(
+        buffer_pool,
+        &access::open_pg_attrdef_relation(db_oid),
+        &HeapTuple::with_default_header(&PgAttrdef {
+            oid: new_object_id()?,
+            adrelid,
+            adnum,
+            adbin,
+        })?,
+    )
+}
+
 /// Genereate a new relation oid that is unique to the given the database.
 ///
+/// After a wraparound of the OID counter the oid may be in use, so it is checked against the
+/// relation files and pg_class, since composite types have a pg_class entry but no file.
+///
 /// Note that the current working directory is expected to be the data directory.
-pub fn new_relation_oid(tablespace: &Oid, db_oid: &Oid) -> Result<Oid> {
+pub fn new_relation_oid(buffer_pool: &BufferPool, tablespace: &Oid, db_oid: &Oid) -> Result<Oid> {
     loop {
-        let rel_oid = new_object_id();
+        let rel_oid = new_object_id()?;
         let relpath = relation_path(tablespace, db_oid, &rel_oid)?;
-        if !relpath.exists() {
+        if !relpath.exists() && find_pg_class_by_oid(buffer_pool, db_oid, rel_oid)?.is_none() {
             return Ok(rel_oid);
         }
     }
 }
+
+/// Generate a new type oid that is not used by any type.
+///
+/// The registry of types has the builtin types and the user defined types of all databases, so
+/// the oid is checked against it instead of the pg_type of a single database.
+fn new_type_oid() -> Result<Oid> {
+    loop {
+        let typ_oid = new_object_id()?;
+        if types::lookup_type(typ_oid).is_err() {
+            return Ok(typ_oid);
+        }
+    }
+}
+
+/// Generate a new enum label oid that is not used by any label stored on pg_enum of the given
+/// database.
+fn new_enum_label_oid(buffer_pool: &BufferPool, db_oid: &Oid) -> Result<Oid> {
+    let pg_enum_rel = access::open_pg_enum_relation(db_oid);
+    'next_oid: loop {
+        let label_oid = new_object_id()?;
+        let mut heap = HeapScanner::new(buffer_pool, &pg_enum_rel)?;
+        while let Some(tuple) = heap.next_tuple()? {
+            if bincode::deserialize::<PgEnum>(&tuple.data)?.oid == label_oid {
+                continue 'next_oid;
+            }
+        }
+        return Ok(label_oid);
+    }
+}
+
+/// Create a new enum type with the given labels, storing the labels on pg_enum in declaration
+/// order. An array type of the new type is also created.
+pub fn create_enum_type(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    name: &str,
+    labels: &[String],
+) -> Result<()> {
+    check_type_name(buffer_pool, db_oid, name)?;
+    for (i, label) in labels.iter().enumerate() {
+        if labels[..i].contains(label) {
+            bail!(Error::DuplicateEnumLabel(label.clone()));
+        }
+    }
+
+    let pg_enum_rel = access::open_pg_enum_relation(db_oid);
+    let typ_oid = new_type_oid()?;
+
+    let mut enum_labels = Vec::with_capacity(labels.len());
+    for (i, label) in labels.iter().enumerate() {
+        let pg_enum = PgEnum {
+            oid: new_enum_label_oid(buffer_pool, db_oid)?,
+            enumtypid: typ_oid,
+            enumsortorder: (i + 1) as f32,
+            enumlabel: label.clone(),
+        };
+        heap_insert(
+            buffer_pool,
+            &pg_enum_rel,
+            &HeapTuple::with_default_header(&pg_enum)?,
+        )?;
+        enum_labels.push((pg_enum.oid, pg_enum.enumlabel));
+    }
+
+    let typ = types::register_type(Box::new(EnumType::new(typ_oid, name, enum_labels)));
+    add_new_type_tuples(buffer_pool, db_oid, typ)
+}
+
+/// Create a new composite type with the given attributes, which are stored on pg_attribute for
+/// the pg_class entry of the type. An array type of the new type is also created.
+pub fn create_composite_type(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    name: &str,
+    typrelid: Oid,
+    tupledesc: &TupleDesc,
+) -> Result<()> {
+    check_type_name(buffer_pool, db_oid, name)?;
+
+    heap::composite_type_create(buffer_pool, db_oid, name, typrelid, tupledesc)?;
+
+    let typ = types::register_type(Box::new(CompositeType::new(
+        new_type_oid()?,
+        name,
+        typrelid,
+        tupledesc.attrs.clone(),
+    )));
+    add_new_type_tuples(buffer_pool, db_oid, typ)
+}
+
+/// Return an error if a type with the given name already exists.
+pub fn check_type_name(buffer_pool: &BufferPool, db_oid: &Oid, name: &str) -> Result<()> {
+    if find_pg_type(buffer_pool, db_oid, |typ| typ.typname == name)?.is_some() {
+        bail!(Error::DuplicateType(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Register the array type of a new user defined type and insert both types on pg_type.
+fn add_new_type_tuples(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    typ: &'static dyn Type,
+) -> Result<()> {
+    let array = types::register_type(Box::new(ArrayType::new(new_type_oid()?, typ)));
+
+    let pg_type_rel = access::open_pg_type_relation(db_oid);
+    for typ in [typ, array] {
+        heap_insert(
+            buffer_pool,
+            &pg_type_rel,
+            &HeapTuple::with_default_header(PgType::from(typ))?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Register the user defined types stored on the catalogs of all databases, so that they can be
+/// found on the type registry.
+pub fn load_user_defined_types(buffer_pool: &BufferPool) -> Result<()> {
+    for db_oid in &databases(buffer_pool)? {
+        let mut user_types = Vec::new();
+        let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_type_relation(db_oid))?;
+        while let Some(tuple) = heap.next_tuple()? {
+            let pg_type = bincode::deserialize::<PgType>(&tuple.data)?;
+            if types::lookup_type(pg_type.oid).is_err() {
+                user_types.push(pg_type);
+            }
+        }
+
+        let mut labels = HashMap::<Oid, Vec<PgEnum>>::new();
+        let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_enum_relation(db_oid))?;
+        while let Some(tuple) = heap.next_tuple()? {
+            let pg_enum = bincode::deserialize::<PgEnum>(&tuple.data)?;
+            labels.entry(pg_enum.enumtypid).or_default().push(pg_enum);
+        }
+
+        let mut attributes = HashMap::<Oid, Vec<PgAttribute>>::new();
+        let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_attribute_relation(db_oid))?;
+        while let Some(tuple) = heap.next_tuple()? {
+            let attr = bincode::deserialize::<PgAttribute>(&tuple.data)?;
+            attributes.entry(attr.attrelid).or_default().push(attr);
+        }
+
+        // Array types are registered after their element types.
+        let (arrays, user_types): (Vec<_>, Vec<_>) = user_types
+            .into_iter()
+            .partition(|typ| typ.typelem != INVALID_OID);
+
+        for pg_type in user_types {
+            let typ: Box<dyn Type> = if pg_type.typtype.starts_with(TypeKind::Enum.as_char()) {
+                let mut labels = labels.remove(&pg_type.oid).unwrap_or_default();
+                labels.sort_by(|a, b| a.enumsortorder.total_cmp(&b.enumsortorder));
+                let labels = labels
+                    .into_iter()
+                    .map(|label| (label.oid, label.enumlabel))
+                    .collect();
+                Box::new(EnumType::new(pg_type.oid, &pg_type.typname, labels))
+            } else {
+                let mut attrs = attributes.remove(&pg_type.typrelid).unwrap_or_default();
+                attrs.sort_by_key(|attr| attr.attnum);
+                Box::new(CompositeType::new(
+                    pg_type.oid,
+                    &pg_type.typname,
+                    pg_type.typrelid,
+                    attrs,
+                ))
+            };
+            types::register_type(typ);
+        }
+
+        for pg_type in arrays {
+            let elem = types::lookup_type(pg_type.typelem)?;
+            types::register_type(Box::new(ArrayType::new(pg_type.oid, elem)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the oids of all databases stored on pg_database.
+pub fn databases(buffer_pool: &BufferPool) -> Result<Vec<Oid>> {
+    let mut databases = Vec::new();
+    let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_database_relation())?;
+    while let Some(tuple) = heap.next_tuple()? {
+        databases.push(bincode::deserialize::<PgDatabase>(&tuple.data)?.oid);
+    }
+    Ok(databases)
+}
+
+/// Return the greatest oid used by the objects stored on the catalogs of all databases.
+pub fn max_object_id(buffer_pool: &BufferPool) -> Result<Oid> {
+    let mut max = INVALID_OID;
+    for db_oid in databases(buffer_pool)? {
+        max = max
+            .max(db_oid)
+            .max(max_catalog_oid(
+                buffer_pool,
+                &access::open_pg_class_relation(&db_oid),
+                |pg_class: PgClass| pg_class.oid,
+            )?)
+            .max(max_catalog_oid(
+                buffer_pool,
+                &access::open_pg_type_relation(&db_oid),
+                |pg_type: PgType| pg_type.oid,
+            )?)
+            .max(max_catalog_oid(
+                buffer_pool,
+                &access::open_pg_enum_relation(&db_oid),
+                |pg_enum: PgEnum| pg_enum.oid,
+            )?)
+            .max(max_catalog_oid(
+                buffer_pool,
+                &access::open_pg_attrdef_relation(&db_oid),
+                |pg_attrdef: PgAttrdef| pg_attrdef.oid,
+            )?);
+    }
+    Ok(max)
+}
+
+/// Return the greatest oid of the tuples of the given catalog relation.
+fn max_catalog_oid<T, F>(buffer_pool: &BufferPool, rel: &Relation, oid: F) -> Result<Oid>
+where
+    T: DeserializeOwned,
+    F: Fn(T) -> Oid,
+{
+    let mut max = INVALID_OID;
+    let mut heap = HeapScanner::new(buffer_pool, rel)?;
+    while let Some(tuple) = heap.next_tuple()? {
+        max = max.max(oid(bincode::deserialize::<T>(&tuple.data)?));
+    }
+    Ok(max)
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::heaptuple::TupleDesc,
+    sql::encode::{varlena_deserializer, varlena_serializer},
+    Oid,
+};
+
+use super::{pg_attribute::PgAttribute, pg_type};
+
+/// Fixed oid of pg_attrdef relation.
+pub const RELATION_OID: Oid = 2604;
+
+pub const RELATION_NAME: &str = "pg_attrdef";
+
+/// The catalog pg_attrdef stores column default values. Only columns for which a default value
+/// has been explicitly set have an entry, including serial and identity columns whose default
+/// is the next value of their sequence.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PgAttrdef {
+    /// Oid of the default value.
+    pub oid: Oid,
+
+    /// The table this column belongs to.
+    pub adrelid: Oid,
+
+    /// The number of the column.
+    pub adnum: usize,
+
+    /// The default value expression, as SQL text that is parsed when the default is used.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub adbin: String,
+}
+
+impl PgAttrdef {
+    /// Return the tuple description from pg_attrdef system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("oid"),
+                    attnum: 1,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("adrelid"),
+                    attnum: 2,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("adnum"),
+                    attnum: 3,
+                    attlen: 8,
+                    atttypid: pg_type::INT_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("adbin"),
+                    attnum: 4,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
@@ -12,6 +12,12 @@ pub const RELATION_OID: Oid = 1249;
 
 pub const RELATION_NAME: &'static str = "pg_attribute";
 
+/// Identity of a column declared as GENERATED ALWAYS AS IDENTITY.
+pub const ATTRIBUTE_IDENTITY_ALWAYS: &str = "a";
+
+/// Identity of a column declared as GENERATED BY DEFAULT AS IDENTITY.
+pub const ATTRIBUTE_IDENTITY_BY_DEFAULT: &str = "d";
+
 /// The catalog pg_attribute stores information about table columns. There will be exactly one pg_attribute row for
 /// every column in every table in the database.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +38,17 @@ pub struct PgAttribute {
 
     /// The data type of this column
     pub atttypid: Oid,
+
+    /// Type-specific data supplied at table creation time, e.g the precision and scale of a
+    /// numeric(p, s) column or the max length of a varchar(n) column. It is passed to the type
+    /// input functions and is -1 for types that do not need it.
+    pub atttypmod: i32,
+
+    /// If a zero byte (''), then not an identity column. Otherwise, a = generated always, d =
+    /// generated by default.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub attidentity: String,
 }
 
 impl PgAttribute {
@@ -44,7 +61,9 @@ impl PgAttribute {
                     attname: String::from("attrelid"),
                     attnum: 1,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -52,6 +71,8 @@ impl PgAttribute {
                     attnum: 2,
                     attlen: -1,
                     atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -59,6 +80,8 @@ impl PgAttribute {
                     attnum: 3,
                     attlen: 8,
                     atttypid: pg_type::INT_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -66,6 +89,35 @@ impl PgAttribute {
                     attnum: 4,
                     attlen: 8,
                     atttypid: pg_type::INT_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("atttypid"),
+                    attnum: 5,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("atttypmod"),
+                    attnum: 6,
+                    attlen: 4,
+                    atttypid: pg_type::INT_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("attidentity"),
+                    attnum: 7,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
             ],
         }
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::heaptuple::TupleDesc,
+    sql::encode::{varlena_deserializer, varlena_serializer},
+    Oid,
+};
+
+use super::{pg_attribute::PgAttribute, pg_type};
+
+/// Fixed oid of pg_cast relation.
+pub const RELATION_OID: Oid = 2605;
+
+pub const RELATION_NAME: &str = "pg_cast";
+
+/// The catalog pg_cast stores the conversions between builtin data types and the context in
+/// which each conversion can be invoked.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PgCast {
+    /// Oid of the source data type.
+    pub castsource: Oid,
+
+    /// Oid of the target data type.
+    pub casttarget: Oid,
+
+    /// Indicates what contexts the cast can be invoked in: e means only as an explicit cast, a
+    /// means implicitly in assignment to a target column, as well as explicitly, and i means
+    /// implicitly in expressions, as well as the other cases.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub castcontext: String,
+}
+
+impl PgCast {
+    /// Return the tuple description from pg_cast system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("castsource"),
+                    attnum: 1,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("casttarget"),
+                    attnum: 2,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("castcontext"),
+                    attnum: 3,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
@@ -13,6 +13,15 @@ pub const RELATION_OID: Oid = 1259;
 
 pub const RELATION_NAME: &'static str = "pg_class";
 
+/// Kind of an ordinary table.
+pub const RELKIND_RELATION: &str = "r";
+
+/// Kind of a sequence relation.
+pub const RELKIND_SEQUENCE: &str = "S";
+
+/// Kind of the relation that holds the attributes of a composite type.
+pub const RELKIND_COMPOSITE_TYPE: &str = "c";
+
 /// The catalog pg_class catalogs tables and most everything else that has columns or is otherwise similar to a table.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PgClass {
@@ -30,6 +39,12 @@ pub struct PgClass {
     /// True if this table is shared across all databases in the cluster. Only certain system
     /// catalogs (such as pg_database) are shared.
     pub relisshared: bool,
+
+    /// Kind of relation: r for an ordinary table, S for a sequence or c for the attributes of a
+    /// composite type.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub relkind: String,
 }
 
 impl PgClass {
@@ -42,7 +57,9 @@ impl PgClass {
                     attname: String::from("oid"),
                     attnum: 1,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -50,13 +67,17 @@ impl PgClass {
                     attnum: 2,
                     attlen: -1,
                     atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
                     attname: String::from("reltablespace"),
                     attnum: 3,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -64,6 +85,17 @@ impl PgClass {
                     attnum: 4,
                     attlen: 1,
                     atttypid: pg_type::BOOL_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("relkind"),
+                    attnum: 5,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
             ],
         }
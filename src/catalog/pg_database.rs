@@ -39,7 +39,9 @@ impl PgDatabase {
                     attname: String::from("oid"),
                     attnum: 1,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -47,13 +49,17 @@ impl PgDatabase {
                     attnum: 2,
                     attlen: -1,
                     atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
                     attname: String::from("dattablespace"),
                     attnum: 3,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
             ],
         }
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::heaptuple::TupleDesc,
+    sql::encode::{varlena_deserializer, varlena_serializer},
+    Oid,
+};
+
+use super::{pg_attribute::PgAttribute, pg_type};
+
+/// Fixed oid of pg_enum relation.
+pub const RELATION_OID: Oid = 3501;
+
+pub const RELATION_NAME: &str = "pg_enum";
+
+/// The catalog pg_enum contains entries showing the values and labels for each enum type. The
+/// internal representation of a given enum value is actually the oid of its associated row in
+/// pg_enum.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PgEnum {
+    /// Oid of the enum label.
+    pub oid: Oid,
+
+    /// The oid of the pg_type entry owning this enum value.
+    pub enumtypid: Oid,
+
+    /// The sort position of this enum value within its enum type.
+    pub enumsortorder: f32,
+
+    /// The textual label for this enum value.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub enumlabel: String,
+}
+
+impl PgEnum {
+    /// Return the tuple description from pg_enum system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("oid"),
+                    attnum: 1,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("enumtypid"),
+                    attnum: 2,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("enumsortorder"),
+                    attnum: 3,
+                    attlen: 4,
+                    atttypid: pg_type::FLOAT4_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("enumlabel"),
+                    attnum: 4,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
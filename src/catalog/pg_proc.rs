@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::heaptuple::TupleDesc,
+    planner::function::BuiltinFunction,
+    sql::encode::{varlena_deserializer, varlena_serializer},
+    Oid, INVALID_OID,
+};
+
+use super::{pg_attribute::PgAttribute, pg_type};
+
+/// Fixed oid of pg_proc relation.
+pub const RELATION_OID: Oid = 1255;
+
+pub const RELATION_NAME: &str = "pg_proc";
+
+/// The catalog pg_proc stores information about the builtin functions, one row for each
+/// variant of an overloaded function.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PgProc {
+    /// Name of the function.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub proname: String,
+
+    /// Number of declared arguments.
+    pub pronargs: i16,
+
+    /// Oids of the types of the declared arguments separated by spaces.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub proargtypes: String,
+
+    /// Oid of the type of the variadic arguments, which is also the type of the last declared
+    /// argument, or 0 if the function is not variadic.
+    pub provariadic: Oid,
+
+    /// Oid of the type of the result.
+    pub prorettype: Oid,
+
+    /// True if the function returns NULL when any argument is NULL, without being called.
+    pub proisstrict: bool,
+}
+
+impl From<&BuiltinFunction> for PgProc {
+    fn from(function: &BuiltinFunction) -> Self {
+        Self {
+            proname: function.name.to_string(),
+            pronargs: function.args.len() as i16,
+            proargtypes: function
+                .args
+                .iter()
+                .map(|oid| oid.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            provariadic: match function.args.last() {
+                Some(typ) if function.variadic => *typ,
+                _ => INVALID_OID,
+            },
+            prorettype: function.rettype,
+            proisstrict: function.strict,
+        }
+    }
+}
+
+impl PgProc {
+    /// Return the tuple description from pg_proc system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("proname"),
+                    attnum: 1,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("pronargs"),
+                    attnum: 2,
+                    attlen: 2,
+                    atttypid: pg_type::INT2_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("proargtypes"),
+                    attnum: 3,
+                    attlen: -1,
+                    atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("provariadic"),
+                    attnum: 4,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("prorettype"),
+                    attnum: 5,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("proisstrict"),
+                    attnum: 6,
+                    attlen: 1,
+                    atttypid: pg_type::BOOL_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
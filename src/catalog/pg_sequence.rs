@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{access::heaptuple::TupleDesc, Oid};
+
+use super::{pg_attribute::PgAttribute, pg_type};
+
+/// Fixed oid of pg_sequence relation.
+pub const RELATION_OID: Oid = 2224;
+
+pub const RELATION_NAME: &str = "pg_sequence";
+
+/// The catalog pg_sequence contains information about sequences. Some of the information about
+/// sequences, such as the name, is in pg_class, and the current value of a sequence is stored
+/// on the sequence relation itself.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PgSequence {
+    /// The oid of the pg_class entry for this sequence.
+    pub seqrelid: Oid,
+
+    /// Data type of the sequence.
+    pub seqtypid: Oid,
+
+    /// Start value of the sequence.
+    pub seqstart: i64,
+
+    /// Increment value of the sequence.
+    pub seqincrement: i64,
+
+    /// Maximum value of the sequence.
+    pub seqmax: i64,
+
+    /// Minimum value of the sequence.
+    pub seqmin: i64,
+
+    /// Cache size of the sequence.
+    pub seqcache: i64,
+
+    /// Whether the sequence cycles.
+    pub seqcycle: bool,
+}
+
+impl PgSequence {
+    /// Return the tuple description from pg_sequence system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqrelid"),
+                    attnum: 1,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqtypid"),
+                    attnum: 2,
+                    attlen: 8,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqstart"),
+                    attnum: 3,
+                    attlen: 8,
+                    atttypid: pg_type::INT8_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqincrement"),
+                    attnum: 4,
+                    attlen: 8,
+                    atttypid: pg_type::INT8_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqmax"),
+                    attnum: 5,
+                    attlen: 8,
+                    atttypid: pg_type::INT8_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqmin"),
+                    attnum: 6,
+                    attlen: 8,
+                    atttypid: pg_type::INT8_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqcache"),
+                    attnum: 7,
+                    attlen: 8,
+                    atttypid: pg_type::INT8_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("seqcycle"),
+                    attnum: 8,
+                    attlen: 1,
+                    atttypid: pg_type::BOOL_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
@@ -41,7 +41,9 @@ impl PgTablespace {
                     attname: String::from("oid"),
                     attnum: 1,
                     attlen: 8,
-                    atttypid: pg_type::INT_OID,
+                    atttypid: pg_type::OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
                 PgAttribute {
                     attrelid: RELATION_OID,
@@ -49,6 +51,8 @@ impl PgTablespace {
                     attnum: 2,
                     attlen: -1,
                     atttypid: pg_type::VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
                 },
             ],
         }
@@ -1,8 +1,239 @@
-use crate::Oid;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access::heaptuple::TupleDesc,
+    sql::encode::{varlena_deserializer, varlena_serializer},
+    types::{self, Type},
+    Oid, INVALID_OID,
+};
+
+use super::pg_attribute::PgAttribute;
+
+/// Fixed oid of pg_type relation.
+pub const RELATION_OID: Oid = 1247;
+
+pub const RELATION_NAME: &str = "pg_type";
 
 // List of Oids for each type that tinydb supports
 // Copy and pasted from src/backend/catalog/pg_type_d.h
 
+pub const BOOL_OID: Oid = 16;
+pub const BYTEA_OID: Oid = 17;
+pub const INT8_OID: Oid = 20;
+pub const INT2_OID: Oid = 21;
 pub const INT_OID: Oid = 23;
+pub const TEXT_OID: Oid = 25;
+pub const OID_OID: Oid = 26;
+pub const JSON_OID: Oid = 114;
+pub const FLOAT4_OID: Oid = 700;
+pub const FLOAT8_OID: Oid = 701;
+pub const UNKNOWN_OID: Oid = 705;
+pub const BPCHAR_OID: Oid = 1042;
 pub const VARCHAR_OID: Oid = 1043;
-pub const BOOL_OID: Oid = 16;
+pub const DATE_OID: Oid = 1082;
+pub const TIME_OID: Oid = 1083;
+pub const TIMESTAMP_OID: Oid = 1114;
+pub const TIMESTAMPTZ_OID: Oid = 1184;
+pub const INTERVAL_OID: Oid = 1186;
+pub const NUMERIC_OID: Oid = 1700;
+pub const UUID_OID: Oid = 2950;
+pub const JSONB_OID: Oid = 3802;
+pub const ANY_OID: Oid = 2276;
+pub const RECORD_OID: Oid = 2249;
+
+pub const JSON_ARRAY_OID: Oid = 199;
+pub const BOOL_ARRAY_OID: Oid = 1000;
+pub const BYTEA_ARRAY_OID: Oid = 1001;
+pub const INT2_ARRAY_OID: Oid = 1005;
+pub const INT4_ARRAY_OID: Oid = 1007;
+pub const TEXT_ARRAY_OID: Oid = 1009;
+pub const BPCHAR_ARRAY_OID: Oid = 1014;
+pub const VARCHAR_ARRAY_OID: Oid = 1015;
+pub const INT8_ARRAY_OID: Oid = 1016;
+pub const FLOAT4_ARRAY_OID: Oid = 1021;
+pub const FLOAT8_ARRAY_OID: Oid = 1022;
+pub const OID_ARRAY_OID: Oid = 1028;
+pub const TIMESTAMP_ARRAY_OID: Oid = 1115;
+pub const DATE_ARRAY_OID: Oid = 1182;
+pub const TIME_ARRAY_OID: Oid = 1183;
+pub const TIMESTAMPTZ_ARRAY_OID: Oid = 1185;
+pub const INTERVAL_ARRAY_OID: Oid = 1187;
+pub const NUMERIC_ARRAY_OID: Oid = 1231;
+pub const UUID_ARRAY_OID: Oid = 2951;
+pub const JSONB_ARRAY_OID: Oid = 3807;
+
+/// The catalog pg_type stores information about data types. There will be exactly one pg_type
+/// row for every builtin data type in the database.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PgType {
+    /// Oid of type.
+    pub oid: Oid,
+
+    /// Data type name.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub typname: String,
+
+    /// Number of bytes of the internal representation of the type, -1 for variable-length
+    /// types.
+    pub typlen: i32,
+
+    /// True if values of this type are passed by value.
+    pub typbyval: bool,
+
+    /// Alignment required when storing a value of this type.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub typalign: String,
+
+    /// Category of type, used to decide which implicit conversions are allowed.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub typcategory: String,
+
+    /// Oid of the element type if this is an array type, 0 otherwise.
+    pub typelem: Oid,
+
+    /// Oid of the array type whose elements are of this type, 0 if there is none.
+    pub typarray: Oid,
+
+    /// Kind of type: b for a base type, c for a composite type, e for an enum type or p for a
+    /// pseudo-type.
+    #[serde(deserialize_with = "varlena_deserializer")]
+    #[serde(serialize_with = "varlena_serializer")]
+    pub typtype: String,
+
+    /// Oid of the pg_class entry that describes the attributes of a composite type, 0 for any
+    /// other kind of type.
+    pub typrelid: Oid,
+
+    /// True if the type is the preferred type of its category.
+    pub typispreferred: bool,
+}
+
+impl From<&dyn Type> for PgType {
+    fn from(typ: &dyn Type) -> Self {
+        Self {
+            oid: typ.oid(),
+            typname: typ.typname().to_string(),
+            typlen: typ.typlen(),
+            typbyval: typ.typbyval(),
+            typalign: typ.typalign().as_char().to_string(),
+            typcategory: typ.typcategory().as_char().to_string(),
+            typelem: typ.typelem(),
+            typarray: types::array_type_of(typ.oid()).map_or(INVALID_OID, |array| array.oid()),
+            typtype: typ.typtype().as_char().to_string(),
+            typrelid: typ.typrelid(),
+            typispreferred: typ.typispreferred(),
+        }
+    }
+}
+
+impl PgType {
+    /// Return the tuple description from pg_type system relation.
+    pub fn tuple_desc() -> TupleDesc {
+        TupleDesc {
+            attrs: vec![
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("oid"),
+                    attnum: 1,
+                    attlen: 8,
+                    atttypid: OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typname"),
+                    attnum: 2,
+                    attlen: -1,
+                    atttypid: VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typlen"),
+                    attnum: 3,
+                    attlen: 4,
+                    atttypid: INT_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typbyval"),
+                    attnum: 4,
+                    attlen: 1,
+                    atttypid: BOOL_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typalign"),
+                    attnum: 5,
+                    attlen: -1,
+                    atttypid: VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typcategory"),
+                    attnum: 6,
+                    attlen: -1,
+                    atttypid: VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typelem"),
+                    attnum: 7,
+                    attlen: 8,
+                    atttypid: OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typarray"),
+                    attnum: 8,
+                    attlen: 8,
+                    atttypid: OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typtype"),
+                    attnum: 9,
+                    attlen: -1,
+                    atttypid: VARCHAR_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typrelid"),
+                    attnum: 10,
+                    attlen: 8,
+                    atttypid: OID_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+                PgAttribute {
+                    attrelid: RELATION_OID,
+                    attname: String::from("typispreferred"),
+                    attnum: 11,
+                    attlen: 1,
+                    atttypid: BOOL_OID,
+                    atttypmod: -1,
+                    attidentity: String::new(),
+                },
+            ],
+        }
+    }
+}
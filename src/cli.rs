@@ -27,4 +27,17 @@ pub struct Flags {
     /// Database server port.
     #[structopt(short = "p", long = "port", default_value = "6379")]
     pub port: u32,
+
+    /// Directory where the unix-domain socket is created. An empty value disables the
+    /// unix-domain socket.
+    #[structopt(long = "unix-socket-directory", default_value = "/tmp")]
+    pub unix_socket_directory: String,
+
+    /// Maximum number of concurrent connections.
+    #[structopt(long = "max-connections", default_value = "100")]
+    pub max_connections: usize,
+
+    /// Maximum time in seconds to complete the client authentication.
+    #[structopt(long = "authentication-timeout", default_value = "60")]
+    pub authentication_timeout: u64,
 }
@@ -0,0 +1,245 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{storage::PAGE_SIZE, Oid, FIRST_NORMAL_OBJECT_ID};
+
+/// Path of the control file relative to the data directory.
+pub const CONTROL_FILE_PATH: &str = "global/tinydb_control";
+
+/// Version of the on-disk format of the data directory, in the YYYYMMDDN format.
+///
+/// It must be changed every time that the layout of the control file, pages or system catalogs
+/// changes, so that a server never reads a data directory that it does not understand.
+pub const DATA_FORMAT_VERSION: u32 = 202610181;
+
+/// Serialize the read-modify-write cycles of the control file.
+static CONTROL_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Errors related with the control file.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("database files are incompatible with server: the data directory was initialized with format version {0}, but the server was compiled with format version {DATA_FORMAT_VERSION}")]
+    IncompatibleVersion(u32),
+
+    #[error("database files are incompatible with server: the data directory was initialized with page size {0}, but the server was compiled with page size {PAGE_SIZE}")]
+    IncompatiblePageSize(u32),
+}
+
+/// State of the database cluster stored on the control file.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ClusterState {
+    /// The server was shut down cleanly and all data was written to disk.
+    ShutDown,
+
+    /// The server is running, or was running when it crashed.
+    InProduction,
+}
+
+/// Cluster wide state that must survive restarts.
+///
+/// The format version must be the first field, so that it can be read even if the rest of the
+/// file has a different layout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ControlFile {
+    /// Version of the on-disk format of the data directory.
+    pub format_version: u32,
+
+    /// Size of the pages on relation files.
+    pub page_size: u32,
+
+    /// State of the cluster.
+    pub state: ClusterState,
+
+    /// Location of the last checkpoint on the write-ahead log. There is no write-ahead log yet,
+    /// so it is always 0.
+    pub checkpoint: u64,
+
+    /// Time, in seconds since the unix epoch, of the last checkpoint. Dirty pages are only
+    /// guaranteed to be on disk after a clean shutdown, so this is the time of the last one.
+    pub checkpoint_time: u64,
+
+    /// Upper bound of the OIDs that may have been assigned. The OID counter starts from
+    /// this value after a restart.
+    pub next_oid: Oid,
+}
+
+impl Default for ControlFile {
+    fn default() -> Self {
+        Self {
+            format_version: DATA_FORMAT_VERSION,
+            page_size: PAGE_SIZE as u32,
+            state: ClusterState::ShutDown,
+            checkpoint: 0,
+            checkpoint_time: now(),
+            next_oid: FIRST_NORMAL_OBJECT_ID,
+        }
+    }
+}
+
+impl ControlFile {
+    /// Read the control file of the given data directory, checking that its content is
+    /// compatible with this server.
+    pub fn read(data_dir: &Path) -> Result<Self> {
+        let path = data_dir.join(CONTROL_FILE_PATH);
+        let data = fs::read(&path)
+            .with_context(|| format!("could not open control file \"{}\"", path.display()))?;
+
+        let format_version: u32 = bincode::deserialize(&data)
+            .with_context(|| format!("invalid control file \"{}\"", path.display()))?;
+        if format_version != DATA_FORMAT_VERSION {
+            bail!(Error::IncompatibleVersion(format_version));
+        }
+
+        let control: Self = bincode::deserialize(&data)
+            .with_context(|| format!("invalid control file \"{}\"", path.display()))?;
+        if control.page_size as usize != PAGE_SIZE {
+            bail!(Error::IncompatiblePageSize(control.page_size));
+        }
+
+        Ok(control)
+    }
+
+    /// Write the control file of the given data directory.
+    ///
+    /// The new content is written on a temporary file that replaces the current control file
+    /// only after it has been synced to disk, so a crash never leaves a partially written
+    /// control file behind.
+    pub fn write(&self, data_dir: &Path) -> Result<()> {
+        let path = data_dir.join(CONTROL_FILE_PATH);
+        let tmp_path = path.with_extension("tmp");
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bincode::serialize(self)?)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp_path, &path)?;
+        if let Some(dir) = path.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Read the control file of the given data directory, apply the given change and write
+    /// it back.
+    pub fn update(data_dir: &Path, f: impl FnOnce(&mut Self)) -> Result<()> {
+        let _guard = CONTROL_FILE_LOCK.lock().unwrap();
+        let mut control = Self::read(data_dir)?;
+        f(&mut control);
+        control.write(data_dir)
+    }
+}
+
+/// Check that the given data directory can be used by this server and mark the cluster as in
+/// production. Return the previous state of the cluster.
+///
+/// A cluster that is already in production was not shut down cleanly, so the dirty pages
+/// that were only in memory are lost and the state kept on memory must be recovered from the
+/// catalogs.
+pub fn startup(data_dir: &Path) -> Result<ClusterState> {
+    let mut previous = ClusterState::ShutDown;
+    ControlFile::update(data_dir, |control| {
+        previous = control.state;
+        control.state = ClusterState::InProduction;
+    })?;
+    Ok(previous)
+}
+
+/// Mark the cluster as shut down. Must be called after all dirty pages were written to disk.
+pub fn shutdown(data_dir: &Path) -> Result<()> {
+    ControlFile::update(data_dir, |control| {
+        control.state = ClusterState::ShutDown;
+        control.checkpoint_time = now();
+    })
+}
+
+/// Return the number of seconds since the unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_test_data_dir() -> Result<tempfile::TempDir> {
+        let data_dir = tempfile::tempdir()?;
+        fs::create_dir(data_dir.path().join("global"))?;
+        Ok(data_dir)
+    }
+
+    #[test]
+    fn test_read_write_control_file() -> Result<()> {
+        let data_dir = new_test_data_dir()?;
+
+        assert!(ControlFile::read(data_dir.path()).is_err());
+
+        let control = ControlFile {
+            next_oid: 42,
+            ..Default::default()
+        };
+        control.write(data_dir.path())?;
+        assert_eq!(control, ControlFile::read(data_dir.path())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_incompatible_control_file() -> Result<()> {
+        let data_dir = new_test_data_dir()?;
+
+        ControlFile {
+            format_version: 1,
+            ..Default::default()
+        }
+        .write(data_dir.path())?;
+        let err = ControlFile::read(data_dir.path()).unwrap_err();
+        assert_eq!(
+            Some(&Error::IncompatibleVersion(1)),
+            err.downcast_ref::<Error>()
+        );
+
+        ControlFile {
+            page_size: 4096,
+            ..Default::default()
+        }
+        .write(data_dir.path())?;
+        let err = ControlFile::read(data_dir.path()).unwrap_err();
+        assert_eq!(
+            Some(&Error::IncompatiblePageSize(4096)),
+            err.downcast_ref::<Error>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_startup_shutdown() -> Result<()> {
+        let data_dir = new_test_data_dir()?;
+        ControlFile::default().write(data_dir.path())?;
+
+        assert_eq!(ClusterState::ShutDown, startup(data_dir.path())?);
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(ClusterState::InProduction, control.state);
+
+        // The server was not shut down cleanly.
+        assert_eq!(ClusterState::InProduction, startup(data_dir.path())?);
+
+        shutdown(data_dir.path())?;
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(ClusterState::ShutDown, control.state);
+
+        Ok(())
+    }
+}
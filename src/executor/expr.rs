@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use super::function::eval_func;
+use crate::{
+    catalog::pg_type,
+    planner::expr::{BoolOp, Expr, MinMaxOp},
+    types::{self, array::ArrayValue, cast, composite::Record},
+    Datum,
+};
+
+impl Expr {
+    /// Evaluate the expression over the attributes of the input tuple, returning None if the
+    /// result is NULL. The row is empty when the expression does not reference any column.
+    ///
+    /// Operators return NULL if any of their operands is NULL. A comparison with the elements
+    /// of an array follows the SQL rules: x = ANY(array) is true if any comparison is true, NULL
+    /// if none is true but some is NULL, and false otherwise.
+    pub fn eval(&self, row: &[Option<Datum>]) -> Result<Option<Datum>> {
+        match self {
+            Expr::Const { value, .. } => Ok(value.clone()),
+            Expr::Column { attnum, .. } => Ok(row[attnum - 1].clone()),
+            Expr::Arith {
+                op, left, right, ..
+            } => match (left.eval(row)?, right.eval(row)?) {
+                (Some(a), Some(b)) => Ok(Some(types::lookup_type(left.typ())?.arith(
+                    *op,
+                    &a,
+                    right.typ(),
+                    &b,
+                )?)),
+                _ => Ok(None),
+            },
+            Expr::Negate { typ, expr } => match expr.eval(row)? {
+                Some(datum) => Ok(Some(types::lookup_type(*typ)?.negate(&datum)?)),
+                None => Ok(None),
+            },
+            Expr::Cast {
+                typ,
+                typmod,
+                explicit,
+                expr,
+            } => match expr.eval(row)? {
+                Some(datum) => Ok(Some(cast::cast_datum(
+                    &datum,
+                    expr.typ(),
+                    *typ,
+                    *typmod,
+                    *explicit,
+                )?)),
+                None => Ok(None),
+            },
+            Expr::Func {
+                func,
+                typ,
+                strict,
+                args,
+            } => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.eval(row))
+                    .collect::<Result<Vec<_>>>()?;
+                if *strict && values.iter().any(Option::is_none) {
+                    return Ok(None);
+                }
+                eval_func(*func, *typ, args, &values)
+            }
+            Expr::Array {
+                elemtype,
+                multidim,
+                elements,
+                ..
+            } => {
+                let values = elements
+                    .iter()
+                    .map(|element| element.eval(row))
+                    .collect::<Result<Vec<_>>>()?;
+                let array = if *multidim {
+                    let arrays = values
+                        .iter()
+                        .map(|value| match value {
+                            Some(datum) => ArrayValue::from_datum(datum),
+                            None => Ok(ArrayValue::new(*elemtype, Vec::new())),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    ArrayValue::stack(*elemtype, arrays)?
+                } else {
+                    ArrayValue::new(*elemtype, values)
+                };
+                Ok(Some(array.to_datum()?))
+            }
+            Expr::Subscript {
+                array, subscripts, ..
+            } => {
+                let array = match array.eval(row)? {
+                    Some(datum) => ArrayValue::from_datum(&datum)?,
+                    None => return Ok(None),
+                };
+
+                let mut indexes = Vec::with_capacity(subscripts.len());
+                for subscript in subscripts {
+                    match subscript.eval(row)? {
+                        Some(datum) => indexes.push(bincode::deserialize::<i32>(&datum)?),
+                        None => return Ok(None),
+                    }
+                }
+                Ok(array.element(&indexes).cloned())
+            }
+            Expr::Compare { op, left, right } => match (left.eval(row)?, right.eval(row)?) {
+                (Some(a), Some(b)) => {
+                    let ordering = types::lookup_type(left.typ())?.compare(&a, &b)?;
+                    Ok(Some(bincode::serialize(&op.matches(ordering))?))
+                }
+                _ => Ok(None),
+            },
+            Expr::ArrayCompare {
+                op,
+                any,
+                left,
+                right,
+            } => {
+                let array = match right.eval(row)? {
+                    Some(datum) => ArrayValue::from_datum(&datum)?,
+                    None => return Ok(None),
+                };
+                if array.elements.is_empty() {
+                    return Ok(Some(bincode::serialize(&!*any)?));
+                }
+                let value = match left.eval(row)? {
+                    Some(value) => value,
+                    None => return Ok(None),
+                };
+
+                let typ = types::lookup_type(left.typ())?;
+                let mut has_nulls = false;
+                for element in &array.elements {
+                    match element {
+                        Some(element) => {
+                            if op.matches(typ.compare(&value, element)?) == *any {
+                                return Ok(Some(bincode::serialize(any)?));
+                            }
+                        }
+                        None => has_nulls = true,
+                    }
+                }
+
+                if has_nulls {
+                    Ok(None)
+                } else {
+                    Ok(Some(bincode::serialize(&!*any)?))
+                }
+            }
+            Expr::Bool { op, args } => {
+                // AND returns false if any argument is false and OR returns true if any
+                // argument is true, even if other arguments are NULL.
+                let short_circuit = *op == BoolOp::Or;
+                let mut has_nulls = false;
+                for arg in args {
+                    match arg.eval(row)? {
+                        Some(datum) => {
+                            let value = bincode::deserialize::<bool>(&datum)?;
+                            if *op == BoolOp::Not {
+                                return Ok(Some(bincode::serialize(&!value)?));
+                            }
+                            if value == short_circuit {
+                                return Ok(Some(datum));
+                            }
+                        }
+                        None => has_nulls = true,
+                    }
+                }
+
+                if has_nulls {
+                    Ok(None)
+                } else {
+                    Ok(Some(bincode::serialize(&!short_circuit)?))
+                }
+            }
+            Expr::NullTest { negated, expr } => {
+                let is_null = expr.eval(row)?.is_none();
+                Ok(Some(bincode::serialize(&(is_null != *negated))?))
+            }
+            Expr::Case { whens, default, .. } => {
+                for (condition, result) in whens {
+                    if let Some(datum) = condition.eval(row)? {
+                        if bincode::deserialize::<bool>(&datum)? {
+                            return result.eval(row);
+                        }
+                    }
+                }
+                default.eval(row)
+            }
+            Expr::Coalesce { args, .. } => {
+                for arg in args {
+                    if let Some(datum) = arg.eval(row)? {
+                        return Ok(Some(datum));
+                    }
+                }
+                Ok(None)
+            }
+            Expr::NullIf { left, right } => {
+                let value = left.eval(row)?;
+                if let (Some(a), Some(b)) = (&value, right.eval(row)?) {
+                    if types::lookup_type(left.typ())?.compare(a, &b)? == Ordering::Equal {
+                        return Ok(None);
+                    }
+                }
+                Ok(value)
+            }
+            Expr::Row { typ, args } => {
+                let values = args
+                    .iter()
+                    .map(|arg| arg.eval(row))
+                    .collect::<Result<Vec<_>>>()?;
+                if *typ != pg_type::RECORD_OID {
+                    return Ok(Some(bincode::serialize(&values)?));
+                }
+                let fields = args.iter().map(Expr::typ).zip(values).collect::<Vec<_>>();
+                Ok(Some(Record::to_datum(&fields)?))
+            }
+            Expr::FieldSelect { expr, fieldnum, .. } => match expr.eval(row)? {
+                Some(datum) => Ok(bincode::deserialize::<Vec<Option<Datum>>>(&datum)?
+                    .into_iter()
+                    .nth(*fieldnum)
+                    .flatten()),
+                None => Ok(None),
+            },
+            Expr::MinMax { op, typ, args } => {
+                let typ = types::lookup_type(*typ)?;
+                let wanted = match op {
+                    MinMaxOp::Greatest => Ordering::Greater,
+                    MinMaxOp::Least => Ordering::Less,
+                };
+
+                let mut result: Option<Datum> = None;
+                for arg in args {
+                    if let Some(datum) = arg.eval(row)? {
+                        result = match result {
+                            Some(current) if typ.compare(&datum, &current)? != wanted => {
+                                Some(current)
+                            }
+                            _ => Some(datum),
+                        };
+                    }
+                }
+                Ok(result)
+            }
+        }
+    }
+}
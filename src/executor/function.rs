@@ -0,0 +1,325 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::pattern::{self, RegexFlags};
+use crate::{
+    catalog::pg_type,
+    planner::{expr::Expr, function::Func, SetFunc},
+    sql::sequence,
+    types::{self, array::ArrayValue, jsonb::JsonbValue, numeric::NumericVar, ArithOp},
+    Datum, Oid,
+};
+
+/// Call a builtin function with the evaluated arguments, typ is the type of the result.
+///
+/// Strict functions are not called if any argument is NULL, so only concat and
+/// jsonb_build_object receive NULL values: concat ignores them and jsonb_build_object converts
+/// them to JSON nulls.
+pub fn eval_func(
+    func: Func,
+    typ: Oid,
+    args: &[Expr],
+    values: &[Option<Datum>],
+) -> Result<Option<Datum>> {
+    match func {
+        Func::Concat => {
+            let mut result = String::new();
+            for (arg, value) in args.iter().zip(values) {
+                if let Some(value) = value {
+                    result.push_str(&types::lookup_type(arg.typ())?.output(value)?);
+                }
+            }
+            return Ok(Some(bincode::serialize(&result)?));
+        }
+        Func::JsonbBuildObject => {
+            let args = args
+                .iter()
+                .zip(values)
+                .map(|(arg, value)| (arg.typ(), value.as_deref()))
+                .collect::<Vec<_>>();
+            return Ok(Some(JsonbValue::build_object(&args)?.to_datum()?));
+        }
+        _ => {}
+    }
+
+    let values = values
+        .iter()
+        .map(|value| value.as_deref().expect("NULL argument of strict function"))
+        .collect::<Vec<_>>();
+    let text = |i: usize| bincode::deserialize::<String>(values[i]);
+    let int4 = |i: usize| bincode::deserialize::<i32>(values[i]);
+
+    let datum = match func {
+        Func::Lower => bincode::serialize(&text(0)?.to_lowercase())?,
+        Func::Upper => bincode::serialize(&text(0)?.to_uppercase())?,
+        Func::TextLen => bincode::serialize(&(text(0)?.chars().count() as i32))?,
+        Func::ByteaLen => {
+            bincode::serialize(&(bincode::deserialize::<Vec<u8>>(values[0])?.len() as i32))?
+        }
+        Func::TextSubstr => {
+            let count = match values.get(2) {
+                Some(_) => Some(int4(2)?),
+                None => None,
+            };
+            bincode::serialize(&substring(&text(0)?, int4(1)?, count)?)?
+        }
+        Func::Btrim | Func::Ltrim | Func::Rtrim => {
+            let characters = match values.get(1) {
+                Some(_) => text(1)?,
+                None => String::from(" "),
+            };
+            let string = text(0)?;
+            let matches = |c: char| characters.contains(c);
+            let trimmed = match func {
+                Func::Ltrim => string.trim_start_matches(matches),
+                Func::Rtrim => string.trim_end_matches(matches),
+                _ => string.trim_matches(matches),
+            };
+            bincode::serialize(trimmed)?
+        }
+        Func::TextCat => {
+            let mut result = String::new();
+            for (arg, value) in args.iter().zip(&values) {
+                result.push_str(&types::lookup_type(arg.typ())?.output(value)?);
+            }
+            bincode::serialize(&result)?
+        }
+        Func::Replace => {
+            let (string, from) = (text(0)?, text(1)?);
+            if from.is_empty() {
+                bincode::serialize(&string)?
+            } else {
+                bincode::serialize(&string.replace(&from, &text(2)?))?
+            }
+        }
+        Func::Strpos => {
+            let (string, substring) = (text(0)?, text(1)?);
+            let position = match string.find(&substring) {
+                Some(offset) => string[..offset].chars().count() as i32 + 1,
+                None => 0,
+            };
+            bincode::serialize(&position)?
+        }
+        Func::Like | Func::NotLike | Func::ILike | Func::NotILike => {
+            let case_insensitive = matches!(func, Func::ILike | Func::NotILike);
+            let matches = pattern::like(&text(0)?, &text(1)?, case_insensitive)?;
+            bincode::serialize(&(matches == matches!(func, Func::Like | Func::ILike)))?
+        }
+        Func::LikeEscape => bincode::serialize(&pattern::like_escape(&text(0)?, &text(1)?)?)?,
+        Func::SimilarToEscape => {
+            let escape = match values.get(1) {
+                Some(_) => text(1)?,
+                None => String::from("\\"),
+            };
+            bincode::serialize(&pattern::similar_to_escape(&text(0)?, &escape)?)?
+        }
+        Func::RegexMatch | Func::RegexNotMatch | Func::RegexIMatch | Func::RegexNotIMatch => {
+            let flags = match func {
+                Func::RegexIMatch | Func::RegexNotIMatch => RegexFlags::case_insensitive(),
+                _ => RegexFlags::default(),
+            };
+            let matches = pattern::regex_match(&text(0)?, &text(1)?, flags)?;
+            bincode::serialize(&(matches == matches!(func, Func::RegexMatch | Func::RegexIMatch)))?
+        }
+        Func::RegexpReplace => {
+            let flags = match values.get(3) {
+                Some(_) => RegexFlags::parse(&text(3)?)?,
+                None => RegexFlags::default(),
+            };
+            bincode::serialize(&pattern::regexp_replace(
+                &text(0)?,
+                &text(1)?,
+                &text(2)?,
+                flags,
+            )?)?
+        }
+        Func::RegexpMatch => {
+            let flags = match values.get(2) {
+                Some(_) => RegexFlags::parse(&text(2)?)?,
+                None => RegexFlags::default(),
+            };
+            if flags.global {
+                bail!(types::Error::RegexpGlobalOption("regexp_match"));
+            }
+            return match pattern::regexp_matches(&text(0)?, &text(1)?, flags)?.pop() {
+                Some(captures) => Ok(Some(text_array(captures)?)),
+                None => Ok(None),
+            };
+        }
+        Func::Abs => {
+            let typ = types::lookup_type(typ)?;
+            if typ.compare(values[0], &typ.input("0", -1)?)? == Ordering::Less {
+                typ.negate(values[0])?
+            } else {
+                values[0].to_vec()
+            }
+        }
+        Func::Mod => types::lookup_type(typ)?.arith(ArithOp::Mod, values[0], typ, values[1])?,
+        Func::Round | Func::Floor | Func::Ceil | Func::Power if typ == pg_type::FLOAT8_OID => {
+            let value = bincode::deserialize::<f64>(values[0])?;
+            let result = match func {
+                Func::Round => value.round_ties_even(),
+                Func::Floor => value.floor(),
+                Func::Ceil => value.ceil(),
+                _ => float_power(value, bincode::deserialize::<f64>(values[1])?)?,
+            };
+            bincode::serialize(&result)?
+        }
+        Func::Round | Func::Floor | Func::Ceil | Func::Power => {
+            let value = NumericVar::from_datum(values[0])?;
+            let result = match func {
+                Func::Round if values.len() == 2 => value.round_to(int4(1)? as i64),
+                Func::Round => value.round_to(0),
+                Func::Floor => value.floor(),
+                Func::Ceil => value.ceil(),
+                _ => value.pow(&NumericVar::from_datum(values[1])?)?,
+            };
+            result.to_datum()?
+        }
+        Func::Nextval => bincode::serialize(&sequence::nextval(&text(0)?)?)?,
+        Func::Currval => bincode::serialize(&sequence::currval(&text(0)?)?)?,
+        Func::Setval => {
+            let is_called = match values.get(2) {
+                Some(value) => bincode::deserialize::<bool>(value)?,
+                None => true,
+            };
+            let value = bincode::deserialize::<i64>(values[1])?;
+            bincode::serialize(&sequence::setval(&text(0)?, value, is_called)?)?
+        }
+        Func::Lastval => bincode::serialize(&sequence::lastval()?)?,
+        _ => return eval_jsonb_func(func, &values),
+    };
+    Ok(Some(datum))
+}
+
+/// Call a set returning function with the evaluated arguments, returning the value of each row.
+pub fn eval_set_func(func: SetFunc, values: &[Option<Datum>]) -> Result<Vec<Option<Datum>>> {
+    match func {
+        SetFunc::Unnest => match &values[0] {
+            Some(datum) => Ok(ArrayValue::from_datum(datum)?.elements),
+            None => Ok(Vec::new()),
+        },
+        SetFunc::RegexpMatches => {
+            let values = match values
+                .iter()
+                .map(Option::as_deref)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(values) => values,
+                None => return Ok(Vec::new()),
+            };
+            let text = |i: usize| bincode::deserialize::<String>(values[i]);
+            let flags = match values.get(2) {
+                Some(_) => RegexFlags::parse(&text(2)?)?,
+                None => RegexFlags::default(),
+            };
+            pattern::regexp_matches(&text(0)?, &text(1)?, flags)?
+                .into_iter()
+                .map(|captures| Ok(Some(text_array(captures)?)))
+                .collect()
+        }
+    }
+}
+
+/// Return a text array with the given strings.
+fn text_array(strings: Vec<Option<String>>) -> Result<Datum> {
+    let elements = strings
+        .iter()
+        .map(|string| string.as_ref().map(bincode::serialize).transpose())
+        .collect::<bincode::Result<Vec<_>>>()?;
+    ArrayValue::new(pg_type::TEXT_OID, elements).to_datum()
+}
+
+/// Return the characters of the string starting at the given position, counted from 1, up to
+/// the given number of characters or until the end of the string. Positions before the start
+/// of the string are counted but return no characters, e.g substring('abc', 0, 2) is 'a'.
+fn substring(string: &str, start: i32, count: Option<i32>) -> Result<String> {
+    let end = match count {
+        Some(count) if count < 0 => bail!(types::Error::NegativeSubstringLength),
+        Some(count) => start as i64 + count as i64,
+        None => i64::MAX,
+    };
+    let skip = (start as i64 - 1).max(0);
+    let take = (end - 1 - skip).max(0);
+    Ok(string
+        .chars()
+        .skip(skip as usize)
+        .take(take as usize)
+        .collect())
+}
+
+/// Raise a double precision value to the power of exp.
+fn float_power(value: f64, exp: f64) -> Result<f64> {
+    if value == 0.0 && exp < 0.0 {
+        bail!(types::Error::ZeroToNegativePower);
+    }
+    if value < 0.0 && exp.fract() != 0.0 {
+        bail!(types::Error::ComplexPower);
+    }
+
+    let result = value.powf(exp);
+    if result.is_infinite() && value.is_finite() && exp.is_finite() {
+        bail!(types::Error::OutOfRange("double precision"));
+    }
+    Ok(result)
+}
+
+/// Call one of the functions that implement the jsonb operators.
+fn eval_jsonb_func(func: Func, values: &[&[u8]]) -> Result<Option<Datum>> {
+    let json = JsonbValue::from_datum(values[0])?;
+    let (value, as_text) = match func {
+        Func::JsonbContains => {
+            let contains = json.contains(&JsonbValue::from_datum(values[1])?);
+            return Ok(Some(bincode::serialize(&contains)?));
+        }
+        Func::JsonbExists => {
+            let exists = json.exists(&bincode::deserialize::<String>(values[1])?);
+            return Ok(Some(bincode::serialize(&exists)?));
+        }
+        Func::JsonbObjectField | Func::JsonbObjectFieldText => (
+            json.object_field(&bincode::deserialize::<String>(values[1])?),
+            func == Func::JsonbObjectFieldText,
+        ),
+        Func::JsonbArrayElement | Func::JsonbArrayElementText => (
+            json.array_element(bincode::deserialize::<i32>(values[1])?),
+            func == Func::JsonbArrayElementText,
+        ),
+        _ => unreachable!("{:?} is not a jsonb function", func),
+    };
+
+    match value {
+        Some(value) if as_text => match value.to_text() {
+            Some(text) => Ok(Some(bincode::serialize(&text)?)),
+            None => Ok(None),
+        },
+        Some(value) => Ok(Some(value.to_datum()?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substring() -> Result<()> {
+        assert_eq!(substring("tinydb", 2, Some(3))?, "iny");
+        assert_eq!(substring("tinydb", 0, Some(2))?, "t");
+        assert_eq!(substring("tinydb", -5, Some(3))?, "");
+        assert_eq!(substring("tinydb", 5, None)?, "db");
+        assert_eq!(substring("ação", 2, Some(2))?, "çã");
+        assert!(substring("tinydb", 1, Some(-1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_float_power() -> Result<()> {
+        assert_eq!(float_power(2.0, 10.0)?, 1024.0);
+        assert_eq!(float_power(-2.0, 3.0)?, -8.0);
+        assert!(float_power(0.0, -1.0).is_err());
+        assert!(float_power(-2.0, 0.5).is_err());
+        assert!(float_power(10.0, 400.0).is_err());
+        Ok(())
+    }
+}
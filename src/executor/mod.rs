@@ -4,10 +4,15 @@ use anyhow::{bail, Result};
 
 use crate::{
     access::heaptuple::{HeapTuple, TupleDesc},
-    planner::{Plan, PlanNodeType},
-    Datums,
+    planner::{AggFunc, Plan, PlanNodeType},
+    types::{self, array::ArrayValue, jsonb::JsonbValue, TypeCategory},
+    Datum, Datums, Oid,
 };
 
+mod expr;
+mod function;
+mod pattern;
+
 /// A plan tree executor. Contains function to execute each type of PlanNodeType.
 pub struct Executor;
 
@@ -30,37 +35,146 @@ impl Executor {
                     values: Vec::new(),
                 };
 
-                loop {
-                    match self.fetch_next_tuple(&mut state.child)? {
-                        Some(tuple) => {
-                            let mut slot = Datums::default();
-
-                            for attr in &tuple_table.tuple_desc.attrs {
-                                // Use the tuple descriptor from projection state since
-                                // it is in the same order that is stored on disk page.
-                                let datum = tuple.get_attr(attr.attnum, &state.tuple_desc)?;
-                                slot.push(datum);
-                            }
-                            tuple_table.values.push(slot);
-                        }
-                        None => break,
+                while let Some(row) = self.fetch_next_tuple(&mut state.child)? {
+                    let mut slot = Datums::default();
+                    for target in &state.targets {
+                        slot.push(target.eval(&row)?);
                     }
+                    tuple_table.values.push(slot);
                 }
 
                 Ok(tuple_table)
             }
 
+            PlanNodeType::Aggregate { state } => {
+                let mut inputs = vec![Vec::new(); state.aggregates.len()];
+                while let Some(row) = self.fetch_next_tuple(&mut state.child)? {
+                    for (aggregate, values) in state.aggregates.iter().zip(inputs.iter_mut()) {
+                        values.push(aggregate.arg.eval(&row)?);
+                    }
+                }
+
+                let mut slot = Datums::default();
+                for (aggregate, values) in state.aggregates.iter().zip(inputs) {
+                    slot.push(eval_aggregate(aggregate.func, aggregate.arg.typ(), values)?);
+                }
+
+                Ok(TupleTable {
+                    tuple_desc: Arc::new(TupleDesc {
+                        attrs: state.output.clone(),
+                    }),
+                    values: vec![slot],
+                })
+            }
+
             _ => bail!("Unexpected root plan node of type {}", node.node_type),
         }
     }
 
-    fn fetch_next_tuple(&self, node: &mut Plan) -> Result<Option<HeapTuple>> {
-        match &mut node.node_type {
-            PlanNodeType::SeqScan { ref mut state } => state.heap_scanner.next_tuple(),
-            _ => bail!(
-                "Unsupported plan node type {} to fetch next page",
-                node.node_type
-            ),
+    /// Return the attribute values of the next tuple of a scan node for which the WHERE
+    /// condition is true, or None if there are no more tuples.
+    fn fetch_next_tuple(&self, node: &mut Plan) -> Result<Option<Vec<Option<Datum>>>> {
+        loop {
+            let (row, qual) = match &mut node.node_type {
+                PlanNodeType::SeqScan { ref mut state } => match state.heap_scanner.next_tuple()? {
+                    Some(tuple) => (deform_tuple(&tuple, &state.tuple_desc)?, &state.qual),
+                    None => return Ok(None),
+                },
+                PlanNodeType::FunctionScan { ref mut state } => {
+                    if state.rows.is_none() {
+                        let values = state
+                            .args
+                            .iter()
+                            .map(|arg| arg.eval(&[]))
+                            .collect::<Result<Vec<_>>>()?;
+                        state.rows =
+                            Some(function::eval_set_func(state.func, &values)?.into_iter());
+                    }
+
+                    match state.rows.as_mut().and_then(Iterator::next) {
+                        Some(value) => (vec![value], &state.qual),
+                        None => return Ok(None),
+                    }
+                }
+                PlanNodeType::ValuesScan { ref mut state } => match state.rows.get(state.next) {
+                    Some(values) => {
+                        let row = values
+                            .iter()
+                            .map(|value| value.eval(&[]))
+                            .collect::<Result<Vec<_>>>()?;
+                        state.next += 1;
+                        (row, &state.qual)
+                    }
+                    None => return Ok(None),
+                },
+                _ => bail!(
+                    "Unsupported plan node type {} to fetch next page",
+                    node.node_type
+                ),
+            };
+
+            let matches = match qual {
+                Some(qual) => match qual.eval(&row)? {
+                    Some(datum) => bincode::deserialize::<bool>(&datum)?,
+                    None => false,
+                },
+                None => true,
+            };
+            if matches {
+                return Ok(Some(row));
+            }
+        }
+    }
+}
+
+/// Extract all attribute values of a heap tuple, in the order of the tuple descriptor.
+fn deform_tuple(tuple: &HeapTuple, tuple_desc: &TupleDesc) -> Result<Vec<Option<Datum>>> {
+    (1..=tuple_desc.attrs.len())
+        .map(|attnum| tuple.get_attr(attnum, tuple_desc))
+        .collect()
+}
+
+/// Compute an aggregate function over the values of an attribute of the given type, returning
+/// None if the aggregate result is NULL.
+fn eval_aggregate(func: AggFunc, typ: Oid, values: Vec<Option<Datum>>) -> Result<Option<Datum>> {
+    match func {
+        AggFunc::JsonbAgg => {
+            if values.is_empty() {
+                return Ok(None);
+            }
+            let items = values
+                .iter()
+                .map(|value| JsonbValue::from_sql(value.as_deref(), typ))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Some(JsonbValue::Array(items).to_datum()?))
+        }
+        AggFunc::ArrayAgg => {
+            if values.is_empty() {
+                return Ok(None);
+            }
+            let typ = types::lookup_type(typ)?;
+            if typ.typcategory() != TypeCategory::Array {
+                return Ok(Some(ArrayValue::new(typ.oid(), values).to_datum()?));
+            }
+
+            // Arrays are accumulated into an array with one more dimension, so all of them must
+            // have the same dimensions.
+            let arrays = values
+                .iter()
+                .map(|value| {
+                    let array = ArrayValue::from_datum(
+                        value.as_ref().ok_or(types::Error::NullArrayAccumulation)?,
+                    )?;
+                    if array.dims.is_empty() {
+                        bail!(types::Error::EmptyArrayAccumulation);
+                    }
+                    Ok(array)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if arrays.iter().any(|array| array.dims != arrays[0].dims) {
+                bail!(types::Error::ArrayAccumulationMismatch);
+            }
+            Ok(Some(ArrayValue::stack(typ.typelem(), arrays)?.to_datum()?))
         }
     }
 }
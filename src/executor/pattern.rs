@@ -0,0 +1,441 @@
+//! Pattern matching with LIKE patterns and POSIX regular expressions.
+//!
+//! LIKE patterns are matched as in PostgreSQL, where % matches any sequence of characters, _
+//! matches a single character and a backslash escapes the next character. Regular expressions
+//! are compiled with the regex crate, which accepts most of the PostgreSQL syntax except
+//! back references and lookaround.
+use std::cell::RefCell;
+
+use anyhow::{bail, Result};
+use regex::{Captures, Regex, RegexBuilder};
+
+use crate::types::Error;
+
+/// Escape character of LIKE patterns.
+const LIKE_ESCAPE: char = '\\';
+
+/// Maximum number of compiled regular expressions kept by each thread.
+const REGEX_CACHE_SIZE: usize = 32;
+
+thread_local! {
+    /// Regular expressions compiled recently, since the same pattern is usually matched against
+    /// every tuple of a query. The most recently used are at the end.
+    static REGEX_CACHE: RefCell<Vec<(String, RegexFlags, Regex)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Return true if the text matches the LIKE pattern, ignoring case if case_insensitive.
+pub fn like(text: &str, pattern: &str, case_insensitive: bool) -> Result<bool> {
+    let (text, pattern): (Vec<char>, Vec<char>) = if case_insensitive {
+        (
+            text.to_lowercase().chars().collect(),
+            pattern.to_lowercase().chars().collect(),
+        )
+    } else {
+        (text.chars().collect(), pattern.chars().collect())
+    };
+    Ok(match_like(&text, &pattern)? == LikeMatch::True)
+}
+
+/// Result of matching a LIKE pattern, Abort means that the pattern can not match any suffix of
+/// the text either, so there is no need to try them.
+#[derive(Debug, PartialEq)]
+enum LikeMatch {
+    True,
+    False,
+    Abort,
+}
+
+/// Match the text with the pattern, following the algorithm of PostgreSQL MatchText.
+fn match_like(text: &[char], pattern: &[char]) -> Result<LikeMatch> {
+    let (mut t, mut p) = (0, 0);
+    while t < text.len() && p < pattern.len() {
+        match pattern[p] {
+            LIKE_ESCAPE => {
+                p += 1;
+                match pattern.get(p) {
+                    Some(c) if *c == text[t] => {}
+                    Some(_) => return Ok(LikeMatch::False),
+                    None => bail!(Error::LikePatternEndsWithEscape),
+                }
+            }
+            '%' => {
+                // Consume the wildcards that follow, each _ must match one character.
+                while p < pattern.len() && (pattern[p] == '%' || pattern[p] == '_') {
+                    if pattern[p] == '_' {
+                        if t >= text.len() {
+                            return Ok(LikeMatch::Abort);
+                        }
+                        t += 1;
+                    }
+                    p += 1;
+                }
+                if p == pattern.len() {
+                    return Ok(LikeMatch::True);
+                }
+
+                // Try to match the rest of the pattern on each suffix that starts with the
+                // next literal character of the pattern.
+                let first = match pattern[p] {
+                    LIKE_ESCAPE => match pattern.get(p + 1) {
+                        Some(c) => *c,
+                        None => bail!(Error::LikePatternEndsWithEscape),
+                    },
+                    c => c,
+                };
+                while t < text.len() {
+                    if text[t] == first {
+                        match match_like(&text[t..], &pattern[p..])? {
+                            LikeMatch::False => {}
+                            result => return Ok(result),
+                        }
+                    }
+                    t += 1;
+                }
+                return Ok(LikeMatch::Abort);
+            }
+            '_' => {}
+            c if c != text[t] => return Ok(LikeMatch::False),
+            _ => {}
+        }
+        t += 1;
+        p += 1;
+    }
+
+    if t < text.len() {
+        return Ok(LikeMatch::False);
+    }
+    while p < pattern.len() && pattern[p] == '%' {
+        p += 1;
+    }
+    if p == pattern.len() {
+        Ok(LikeMatch::True)
+    } else {
+        Ok(LikeMatch::Abort)
+    }
+}
+
+/// Convert a LIKE pattern that uses the given escape character into a pattern that uses the
+/// default escape character, as done for LIKE ... ESCAPE. An empty escape disables escaping.
+pub fn like_escape(pattern: &str, escape: &str) -> Result<String> {
+    let mut chars = escape.chars();
+    let escape = match (chars.next(), chars.next()) {
+        (None, _) => return Ok(pattern.replace(LIKE_ESCAPE, "\\\\")),
+        (Some(LIKE_ESCAPE), None) => return Ok(pattern.to_string()),
+        (Some(escape), None) => escape,
+        _ => bail!(Error::InvalidEscapeString),
+    };
+
+    let mut result = String::with_capacity(pattern.len());
+    let mut after_escape = false;
+    for c in pattern.chars() {
+        if c == escape && !after_escape {
+            result.push(LIKE_ESCAPE);
+            after_escape = true;
+        } else {
+            if c == LIKE_ESCAPE && !after_escape {
+                result.push(LIKE_ESCAPE);
+            }
+            result.push(c);
+            after_escape = false;
+        }
+    }
+    Ok(result)
+}
+
+/// Convert a SQL regular expression, used by SIMILAR TO, into a POSIX regular expression that
+/// matches the whole text. An empty escape disables escaping.
+pub fn similar_to_escape(pattern: &str, escape: &str) -> Result<String> {
+    let mut chars = escape.chars();
+    let escape = match (chars.next(), chars.next()) {
+        (None, _) => None,
+        (Some(escape), None) => Some(escape),
+        _ => bail!(Error::InvalidEscapeString),
+    };
+
+    let mut result = String::from("^(?:");
+    let mut after_escape = false;
+    let mut in_bracket = false;
+    for c in pattern.chars() {
+        if after_escape {
+            result.push('\\');
+            result.push(c);
+            after_escape = false;
+        } else if Some(c) == escape {
+            after_escape = true;
+        } else if in_bracket {
+            if c == '\\' {
+                result.push('\\');
+            }
+            result.push(c);
+            in_bracket = c != ']';
+        } else {
+            match c {
+                '[' => {
+                    result.push(c);
+                    in_bracket = true;
+                }
+                '%' => result.push_str(".*"),
+                '_' => result.push('.'),
+                '(' => result.push_str("(?:"),
+                '\\' | '.' | '^' | '$' => {
+                    result.push('\\');
+                    result.push(c);
+                }
+                c => result.push(c),
+            }
+        }
+    }
+    result.push_str(")$");
+    Ok(result)
+}
+
+/// Options of a regular expression, given as a string of flag characters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RegexFlags {
+    case_insensitive: bool,
+
+    /// Dot and negated brackets do not match newlines.
+    newline_sensitive_dot: bool,
+
+    /// ^ and $ match at the start and end of each line.
+    newline_sensitive_anchors: bool,
+
+    /// Whitespace and comments are ignored.
+    expanded: bool,
+
+    /// Match all occurrences instead of the first one.
+    pub global: bool,
+}
+
+impl RegexFlags {
+    /// Parse the flags of a regular expression function.
+    pub fn parse(flags: &str) -> Result<Self> {
+        let mut result = Self::default();
+        for flag in flags.chars() {
+            match flag {
+                'i' => result.case_insensitive = true,
+                'c' => result.case_insensitive = false,
+                'n' | 'm' => {
+                    result.newline_sensitive_dot = true;
+                    result.newline_sensitive_anchors = true;
+                }
+                'p' => {
+                    result.newline_sensitive_dot = true;
+                    result.newline_sensitive_anchors = false;
+                }
+                'w' => {
+                    result.newline_sensitive_dot = false;
+                    result.newline_sensitive_anchors = true;
+                }
+                's' => {
+                    result.newline_sensitive_dot = false;
+                    result.newline_sensitive_anchors = false;
+                }
+                'x' => result.expanded = true,
+                't' => result.expanded = false,
+                'g' => result.global = true,
+                _ => bail!(Error::InvalidRegexpOption(flag)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Return the flags with case insensitive matching.
+    pub fn case_insensitive() -> Self {
+        Self {
+            case_insensitive: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Return the compiled regular expression, compiling it if it is not on the cache.
+fn compile_regex(pattern: &str, flags: RegexFlags) -> Result<Regex> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(index) = cache
+            .iter()
+            .position(|(p, f, _)| p == pattern && *f == flags)
+        {
+            let entry = cache.remove(index);
+            let regex = entry.2.clone();
+            cache.push(entry);
+            return Ok(regex);
+        }
+
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(flags.case_insensitive)
+            .dot_matches_new_line(!flags.newline_sensitive_dot)
+            .multi_line(flags.newline_sensitive_anchors)
+            .ignore_whitespace(flags.expanded)
+            .build()
+            .map_err(|err| {
+                // Keep only the description of the error, without the pattern.
+                let message = err.to_string();
+                let message = message.lines().last().unwrap_or_default();
+                Error::InvalidRegularExpression(message.trim_start_matches("error: ").to_string())
+            })?;
+
+        if cache.len() >= REGEX_CACHE_SIZE {
+            cache.remove(0);
+        }
+        cache.push((pattern.to_string(), flags, regex.clone()));
+        Ok(regex)
+    })
+}
+
+/// Return true if the regular expression matches any part of the text.
+pub fn regex_match(text: &str, pattern: &str, flags: RegexFlags) -> Result<bool> {
+    Ok(compile_regex(pattern, flags)?.is_match(text))
+}
+
+/// Replace the first match of the regular expression, or all matches with the global flag.
+///
+/// The replacement can refer to the text matched by the n-th parenthesized subexpression with
+/// \n, for n from 1 to 9, and to the whole match with \&.
+pub fn regexp_replace(
+    text: &str,
+    pattern: &str,
+    replacement: &str,
+    flags: RegexFlags,
+) -> Result<String> {
+    let regex = compile_regex(pattern, flags)?;
+    let limit = if flags.global { 0 } else { 1 };
+    Ok(regex
+        .replacen(text, limit, |captures: &Captures| {
+            expand_replacement(captures, replacement)
+        })
+        .into_owned())
+}
+
+/// Expand the references to the captured subexpressions of the replacement text.
+fn expand_replacement(captures: &Captures, replacement: &str) -> String {
+    let mut result = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(n @ '1'..='9') => {
+                let group = n.to_digit(10).unwrap_or_default() as usize;
+                if let Some(matched) = captures.get(group) {
+                    result.push_str(matched.as_str());
+                }
+            }
+            Some('&') => result.push_str(&captures[0]),
+            Some('\\') => result.push('\\'),
+            Some(c) => {
+                result.push('\\');
+                result.push(c);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Return the substrings captured by each match of the regular expression, only the first match
+/// unless the global flag is given. A match of an expression without parenthesized
+/// subexpressions captures the whole match, and subexpressions that did not participate on the
+/// match capture None.
+pub fn regexp_matches(
+    text: &str,
+    pattern: &str,
+    flags: RegexFlags,
+) -> Result<Vec<Vec<Option<String>>>> {
+    let regex = compile_regex(pattern, flags)?;
+    let limit = if flags.global { usize::MAX } else { 1 };
+    Ok(regex
+        .captures_iter(text)
+        .take(limit)
+        .map(|captures| {
+            if captures.len() == 1 {
+                vec![Some(captures[0].to_string())]
+            } else {
+                captures
+                    .iter()
+                    .skip(1)
+                    .map(|group| group.map(|group| group.as_str().to_string()))
+                    .collect()
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like() -> Result<()> {
+        assert!(like("tinydb", "tiny%", false)?);
+        assert!(like("tinydb", "%db", false)?);
+        assert!(like("tinydb", "t_nydb", false)?);
+        assert!(like("tinydb", "%n%_b", false)?);
+        assert!(like("", "%", false)?);
+        assert!(like("100%", "100\\%", false)?);
+        assert!(like("TinyDB", "tiny%", true)?);
+        assert!(!like("tinydb", "tiny", false)?);
+        assert!(!like("tinydb", "_", false)?);
+        assert!(!like("1000", "100\\%", false)?);
+        assert!(!like("TinyDB", "tiny%", false)?);
+        assert!(like("tinydb", "tiny\\", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_like_escape() -> Result<()> {
+        assert_eq!(like_escape("100#%", "#")?, "100\\%");
+        assert_eq!(like_escape("a\\b##", "#")?, "a\\\\b\\#");
+        assert_eq!(like_escape("a\\%", "")?, "a\\\\%");
+        assert_eq!(like_escape("a\\%", "\\")?, "a\\%");
+        assert!(like_escape("a", "##").is_err());
+        assert!(like("a\\b", &like_escape("a\\_", "")?, false)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similar_to_escape() -> Result<()> {
+        let pattern = similar_to_escape("%(b|d)%", "\\")?;
+        assert_eq!(pattern, "^(?:.*(?:b|d).*)$");
+        assert!(regex_match("abc", &pattern, RegexFlags::default())?);
+        assert!(!regex_match("ace", &pattern, RegexFlags::default())?);
+        assert_eq!(similar_to_escape("a.#_[%_]", "#")?, "^(?:a\\.\\_[%_])$");
+        Ok(())
+    }
+
+    #[test]
+    fn test_regex() -> Result<()> {
+        let flags = RegexFlags::parse("g")?;
+        assert!(regex_match(
+            "TinyDB",
+            "^tiny",
+            RegexFlags::case_insensitive()
+        )?);
+        assert!(!regex_match("TinyDB", "^tiny", RegexFlags::default())?);
+        assert_eq!(
+            regexp_replace("a1b22", "(\\d+)", "<\\1\\&>", flags)?,
+            "a<11>b<2222>"
+        );
+        assert_eq!(
+            regexp_replace("a1b22", "\\d", "x", RegexFlags::default())?,
+            "axb22"
+        );
+        assert_eq!(
+            regexp_matches("a1b22", "([a-z])(\\d)(x)?", flags)?,
+            vec![
+                vec![Some("a".to_string()), Some("1".to_string()), None],
+                vec![Some("b".to_string()), Some("2".to_string()), None]
+            ]
+        );
+        assert_eq!(
+            regexp_matches("a1b22", "\\d+", RegexFlags::default())?,
+            vec![vec![Some("1".to_string())]]
+        );
+        assert!(RegexFlags::parse("z").is_err());
+        assert!(regex_match("a", "(", RegexFlags::default()).is_err());
+        Ok(())
+    }
+}
@@ -1,17 +1,30 @@
-use std::{fs::create_dir_all, path::Path};
+use std::{
+    fs::{create_dir_all, write},
+    path::Path,
+};
 
 use anyhow::Result;
 
 use crate::{
     access::{self, heap::heap_insert, heaptuple::HeapTuple},
+    backend::hba,
     catalog::{
         heap::{self, heap_create},
+        pg_attrdef::{self, PgAttrdef},
         pg_attribute::{self, PgAttribute},
+        pg_cast::{self, PgCast},
         pg_class::{self, PgClass},
         pg_database::{self, PgDatabase, TINYDB_OID},
+        pg_enum::{self, PgEnum},
+        pg_proc::{self, PgProc},
+        pg_sequence::{self, PgSequence},
         pg_tablespace::{self, PgTablespace, DEFAULTTABLESPACE_OID, GLOBALTABLESPACE_OID},
+        pg_type::{self, PgType},
     },
+    control::{ControlFile, CONTROL_FILE_PATH},
+    planner::function,
     storage::BufferPool,
+    types::{self, cast},
     Oid,
 };
 
@@ -33,11 +46,26 @@ pub fn init_database(buffer: &BufferPool, data_dir: &Path) -> Result<()> {
     // Init per database relations
     init_pg_attribute(buffer, &pg_database::TINYDB_OID)?;
     init_pg_class(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_type(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_enum(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_cast(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_proc(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_sequence(buffer, &pg_database::TINYDB_OID)?;
+    init_pg_attrdef(buffer, &pg_database::TINYDB_OID)?;
 
     // Init global relations
     init_pg_tablespace(buffer, &pg_database::TINYDB_OID)?;
     init_pg_database(buffer, &pg_database::TINYDB_OID)?;
 
+    // Write the control file with the format version of this server. An existing control
+    // file is kept so that OIDs already assigned are not reused.
+    if !data_dir.join(CONTROL_FILE_PATH).exists() {
+        ControlFile::default().write(data_dir)?;
+    }
+
+    // Write the default client authentication configuration file.
+    write(data_dir.join(hba::HBA_FILE_NAME), hba::DEFAULT_HBA_FILE)?;
+
     Ok(())
 }
 
@@ -110,6 +138,130 @@ fn init_pg_class(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
     Ok(())
 }
 
+/// Initialize pg_type relation and insert all builtin data types.
+fn init_pg_type(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_type");
+
+    let pg_type = heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_type::RELATION_NAME,
+        pg_type::RELATION_OID,
+        &PgType::tuple_desc(),
+    )?;
+
+    for typ in types::BUILTIN_TYPES {
+        heap_insert(
+            buffer,
+            &pg_type,
+            &HeapTuple::with_default_header(PgType::from(*typ))?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Initialize the empty pg_enum relation, labels are inserted by CREATE TYPE.
+fn init_pg_enum(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_enum");
+
+    heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_enum::RELATION_NAME,
+        pg_enum::RELATION_OID,
+        &PgEnum::tuple_desc(),
+    )?;
+
+    Ok(())
+}
+
+/// Initialize pg_cast relation and insert the conversions between builtin data types.
+fn init_pg_cast(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_cast");
+
+    let pg_cast = heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_cast::RELATION_NAME,
+        pg_cast::RELATION_OID,
+        &PgCast::tuple_desc(),
+    )?;
+
+    for (castsource, casttarget, context) in cast::BUILTIN_CASTS {
+        heap_insert(
+            buffer,
+            &pg_cast,
+            &HeapTuple::with_default_header(&PgCast {
+                castsource: *castsource,
+                casttarget: *casttarget,
+                castcontext: context.as_char().to_string(),
+            })?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Initialize pg_proc relation and insert all builtin functions.
+fn init_pg_proc(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_proc");
+
+    let pg_proc = heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_proc::RELATION_NAME,
+        pg_proc::RELATION_OID,
+        &PgProc::tuple_desc(),
+    )?;
+
+    for function in function::BUILTIN_FUNCTIONS {
+        heap_insert(
+            buffer,
+            &pg_proc,
+            &HeapTuple::with_default_header(PgProc::from(function))?,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Initialize the empty pg_sequence relation, sequences are inserted by CREATE SEQUENCE.
+fn init_pg_sequence(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_sequence");
+
+    heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_sequence::RELATION_NAME,
+        pg_sequence::RELATION_OID,
+        &PgSequence::tuple_desc(),
+    )?;
+
+    Ok(())
+}
+
+/// Initialize the empty pg_attrdef relation, default values are inserted by CREATE TABLE.
+fn init_pg_attrdef(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
+    log::info!("initializing pg_attrdef");
+
+    heap_create(
+        buffer,
+        DEFAULTTABLESPACE_OID,
+        db_oid,
+        pg_attrdef::RELATION_NAME,
+        pg_attrdef::RELATION_OID,
+        &PgAttrdef::tuple_desc(),
+    )?;
+
+    Ok(())
+}
+
 /// Initialize pg_tablespace relation and insert default tablespace.
 fn init_pg_tablespace(buffer: &BufferPool, db_oid: &Oid) -> Result<()> {
     log::info!("initializing pg_tablespace");
@@ -1,11 +1,17 @@
 use std::ops::Index;
+use std::path::{Path, PathBuf};
 use std::slice::Iter;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+
+use crate::control::ControlFile;
 
 pub mod access;
 pub mod backend;
 pub mod catalog;
 pub mod cli;
+pub mod control;
 pub mod executor;
 pub mod initdb;
 pub mod lru;
@@ -14,9 +20,17 @@ pub mod postgres_protocol;
 pub mod relation;
 pub mod sql;
 pub mod storage;
+pub mod types;
 
 /// First object id to assign when creating a new database cluster.
-const FIRST_NORMAL_OBJECT_ID: u64 = 10000;
+pub const FIRST_NORMAL_OBJECT_ID: u64 = 10000;
+
+/// Largest object id to assign before wrapping around to [FIRST_NORMAL_OBJECT_ID]. OIDs are
+/// sent as 32-bit integers on the wire, so larger values can not be used.
+const MAX_OBJECT_ID: u64 = u32::MAX as u64;
+
+/// Number of OIDs to reserve on the control file each time that the reserved ones run out.
+const OID_PREFETCH: u64 = 8192;
 
 /// Objecct identifier.
 pub type Oid = u64;
@@ -26,16 +40,109 @@ pub const INVALID_OID: Oid = 0;
 /// A slice of bytes that represents a value of inside a tuple.
 pub type Datum = Vec<u8>;
 
-/// Atomic counter to increment when allocating new OIDs.
-static OID_COUNTER: AtomicU64 = AtomicU64::new(FIRST_NORMAL_OBJECT_ID);
+/// Counter used to allocate new OIDs.
+static OID_COUNTER: Mutex<OidCounter> = Mutex::new(OidCounter::new(FIRST_NORMAL_OBJECT_ID, None));
 
 /// Allocate a new OID.
 ///
-/// Duplicate OIDs is (and shouldn't) not handled here.
+/// Duplicate OIDs is (and shouldn't) not handled here. After a wraparound the same OID can be
+/// returned again, so callers that need unique OIDs must check if the OID is already in use, like
+/// the allocation of relation, type and enum label OIDs on the catalog module.
+pub fn new_object_id() -> Result<Oid> {
+    OID_COUNTER.lock().unwrap().next()
+}
+
+/// Start allocating OIDs from the counter persisted on the control file of the given data
+/// directory.
+pub fn start_oid_counter(data_dir: &Path) -> Result<()> {
+    let control = ControlFile::read(data_dir)?;
+    *OID_COUNTER.lock().unwrap() = OidCounter::new(control.next_oid, Some(data_dir.into()));
+    Ok(())
+}
+
+/// Move the OID counter after the given OID, if it is not already after it.
 ///
-/// TODO: Deal carefully with wraparround.
-pub fn new_object_id() -> Oid {
-    OID_COUNTER.fetch_add(1, Ordering::SeqCst)
+/// After a wraparound the given OID may have been assigned before the OIDs that the counter is
+/// assigning now, so some OIDs are skipped. This is harmless, since the callers that need unique
+/// OIDs check if they are already in use.
+pub fn advance_oid_counter(oid: Oid) {
+    OID_COUNTER.lock().unwrap().advance(oid)
+}
+
+/// Persist the exact position of the OID counter, so that the OIDs reserved but not used are
+/// not skipped after a clean restart.
+pub fn write_oid_counter() -> Result<()> {
+    OID_COUNTER.lock().unwrap().write()
+}
+
+/// Counter of OIDs persisted on the control file.
+///
+/// OIDs are reserved in batches of [OID_PREFETCH]: the end of the batch is written on the
+/// control file before any OID of the batch is returned, so an OID is never reused after a
+/// crash.
+struct OidCounter {
+    /// Next OID to assign.
+    next_oid: Oid,
+
+    /// Number of OIDs that can still be assigned before reserving a new batch.
+    oid_count: u64,
+
+    /// Data directory of the control file. If None the counter is only kept in memory.
+    data_dir: Option<PathBuf>,
+}
+
+impl OidCounter {
+    const fn new(next_oid: Oid, data_dir: Option<PathBuf>) -> Self {
+        Self {
+            next_oid,
+            oid_count: 0,
+            data_dir,
+        }
+    }
+
+    /// Return the next OID, reserving a new batch of OIDs on the control file if needed.
+    fn next(&mut self) -> Result<Oid> {
+        if self.next_oid < FIRST_NORMAL_OBJECT_ID || self.next_oid > MAX_OBJECT_ID {
+            // Wraparound, start again from the first OID that is not used by the system
+            // catalogs. The batch is reserved again because the control file points after
+            // the maximum OID.
+            self.next_oid = FIRST_NORMAL_OBJECT_ID;
+            self.oid_count = 0;
+        }
+
+        if self.oid_count == 0 {
+            self.write_control_file(self.next_oid + OID_PREFETCH)?;
+            self.oid_count = OID_PREFETCH;
+        }
+
+        let oid = self.next_oid;
+        self.next_oid += 1;
+        self.oid_count -= 1;
+        Ok(oid)
+    }
+
+    /// Assign the OIDs after the given OID from now on, if the counter is not already after it.
+    fn advance(&mut self, oid: Oid) {
+        if self.next_oid <= oid {
+            self.next_oid = oid + 1;
+            // The reserved batch ends before the new position, so a new one is reserved.
+            self.oid_count = 0;
+        }
+    }
+
+    /// Write the next OID to assign on the control file and release the reserved batch.
+    fn write(&mut self) -> Result<()> {
+        self.write_control_file(self.next_oid)?;
+        self.oid_count = 0;
+        Ok(())
+    }
+
+    fn write_control_file(&self, next_oid: Oid) -> Result<()> {
+        match &self.data_dir {
+            Some(data_dir) => ControlFile::update(data_dir, |control| control.next_oid = next_oid),
+            None => Ok(()),
+        }
+    }
 }
 
 /// An array of nullable Datums.
@@ -60,6 +167,12 @@ impl Datums {
     }
 }
 
+impl From<Vec<Option<Datum>>> for Datums {
+    fn from(datums: Vec<Option<Datum>>) -> Self {
+        Self(datums)
+    }
+}
+
 impl Index<usize> for Datums {
     type Output = Option<Datum>;
 
@@ -78,3 +191,77 @@ impl std::io::Write for Datums {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_test_counter(next_oid: Oid) -> Result<(tempfile::TempDir, OidCounter)> {
+        let data_dir = tempfile::tempdir()?;
+        std::fs::create_dir(data_dir.path().join("global"))?;
+        ControlFile::default().write(data_dir.path())?;
+        let counter = OidCounter::new(next_oid, Some(data_dir.path().into()));
+        Ok((data_dir, counter))
+    }
+
+    #[test]
+    fn test_oid_counter_prefetch() -> Result<()> {
+        let (data_dir, mut counter) = new_test_counter(FIRST_NORMAL_OBJECT_ID)?;
+
+        assert_eq!(FIRST_NORMAL_OBJECT_ID, counter.next()?);
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + 1, counter.next()?);
+
+        // A restart after a crash must continue after the reserved batch.
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + OID_PREFETCH, control.next_oid);
+
+        for _ in 2..OID_PREFETCH {
+            counter.next()?;
+        }
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + OID_PREFETCH, counter.next()?);
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + 2 * OID_PREFETCH, control.next_oid);
+
+        // A clean shutdown writes the exact position of the counter.
+        counter.write()?;
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + OID_PREFETCH + 1, control.next_oid);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oid_counter_wraparound() -> Result<()> {
+        let (data_dir, mut counter) = new_test_counter(MAX_OBJECT_ID)?;
+
+        assert_eq!(MAX_OBJECT_ID, counter.next()?);
+        assert_eq!(FIRST_NORMAL_OBJECT_ID, counter.next()?);
+
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + OID_PREFETCH, control.next_oid);
+
+        // A control file pointing after the maximum OID also wraps around.
+        let mut counter = OidCounter::new(MAX_OBJECT_ID + OID_PREFETCH, None);
+        assert_eq!(FIRST_NORMAL_OBJECT_ID, counter.next()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_oid_counter_advance() -> Result<()> {
+        let (data_dir, mut counter) = new_test_counter(FIRST_NORMAL_OBJECT_ID)?;
+        assert_eq!(FIRST_NORMAL_OBJECT_ID, counter.next()?);
+
+        // The counter is never moved backwards.
+        counter.advance(FIRST_NORMAL_OBJECT_ID);
+        assert_eq!(FIRST_NORMAL_OBJECT_ID + 1, counter.next()?);
+
+        let oid = FIRST_NORMAL_OBJECT_ID + 2 * OID_PREFETCH;
+        counter.advance(oid);
+        assert_eq!(oid + 1, counter.next()?);
+        let control = ControlFile::read(data_dir.path())?;
+        assert_eq!(oid + 1 + OID_PREFETCH, control.next_oid);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1769 @@
+//! Typed expressions.
+//!
+//! The expressions parsed from a SQL statement are transformed into a tree of typed expressions
+//! where the data type of each node is already resolved, adding implicit conversions when
+//! operands of different types are mixed.
+use anyhow::{bail, Result};
+use sqlparser::ast;
+
+use super::{
+    function::{self, Func},
+    SetFunc,
+};
+use crate::{
+    access::heaptuple::TupleDesc,
+    catalog::{self, pg_type},
+    sql::{
+        parser::{self, ExtOperator},
+        session, SQLError,
+    },
+    types::{
+        self,
+        cast::{self, CoercionContext},
+        datetime, ArithOp, CmpOp, Type, TypeCategory,
+    },
+    Datum, Oid, INVALID_OID,
+};
+
+/// A typed expression.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    /// A constant value, None represents a NULL value.
+    Const { typ: Oid, value: Option<Datum> },
+
+    /// The value of a column of the input tuple, attnum is the position of the column on the
+    /// input tuple starting at 1.
+    Column {
+        typ: Oid,
+        typmod: i32,
+        attnum: usize,
+    },
+
+    /// An arithmetic operator applied on two operands, typ is the type of the result.
+    Arith {
+        op: ArithOp,
+        typ: Oid,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+
+    /// A negated value.
+    Negate { typ: Oid, expr: Box<Expr> },
+
+    /// A conversion of a value to another type, explicit if requested with CAST or ::.
+    Cast {
+        typ: Oid,
+        typmod: i32,
+        explicit: bool,
+        expr: Box<Expr>,
+    },
+
+    /// A call to a builtin function, typ is the type of the result. Strict functions are not
+    /// called if any argument is NULL.
+    Func {
+        func: Func,
+        typ: Oid,
+        strict: bool,
+        args: Vec<Expr>,
+    },
+
+    /// An array constructor, e.g ARRAY[1, 2]. The elements of a multidimensional array are the
+    /// sub-arrays of its first dimension.
+    Array {
+        typ: Oid,
+        elemtype: Oid,
+        multidim: bool,
+        elements: Vec<Expr>,
+    },
+
+    /// An element of an array, which is NULL if the subscripts are out of the array bounds.
+    Subscript {
+        typ: Oid,
+        array: Box<Expr>,
+        subscripts: Vec<Expr>,
+    },
+
+    /// A comparison of two values of the same type.
+    Compare {
+        op: CmpOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+
+    /// A comparison of a value with the elements of an array, e.g x = ANY(array), which is true
+    /// if the comparison is true for any element, or for all elements if any is false.
+    ArrayCompare {
+        op: CmpOp,
+        any: bool,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+
+    /// AND, OR or NOT of boolean values, using the SQL three-valued logic.
+    Bool { op: BoolOp, args: Vec<Expr> },
+
+    /// IS NULL, or IS NOT NULL if negated.
+    NullTest { negated: bool, expr: Box<Expr> },
+
+    /// CASE WHEN condition THEN result ... ELSE default END, returning the result of the first
+    /// condition that is true.
+    Case {
+        typ: Oid,
+        whens: Vec<(Expr, Expr)>,
+        default: Box<Expr>,
+    },
+
+    /// COALESCE(args), returning the first argument that is not NULL.
+    Coalesce { typ: Oid, args: Vec<Expr> },
+
+    /// NULLIF(left, right), returning NULL if both values are equal and left otherwise.
+    NullIf { left: Box<Expr>, right: Box<Expr> },
+
+    /// GREATEST(args) or LEAST(args), ignoring NULL arguments.
+    MinMax {
+        op: MinMaxOp,
+        typ: Oid,
+        args: Vec<Expr>,
+    },
+
+    /// A row constructor, e.g ROW(1, 'a'), whose type is a composite type or the record
+    /// pseudo-type if the row was not converted to a composite type.
+    Row { typ: Oid, args: Vec<Expr> },
+
+    /// An attribute of a composite value, e.g (p).a, where fieldnum is the position of the
+    /// attribute starting at 0.
+    FieldSelect {
+        typ: Oid,
+        typmod: i32,
+        expr: Box<Expr>,
+        fieldnum: usize,
+    },
+}
+
+/// Boolean operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoolOp {
+    And,
+    Or,
+    Not,
+}
+
+/// Operators that select the greatest or the least of a list of values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinMaxOp {
+    Greatest,
+    Least,
+}
+
+impl Expr {
+    /// Return the oid of the type of the expression result.
+    pub fn typ(&self) -> Oid {
+        match self {
+            Expr::Const { typ, .. }
+            | Expr::Column { typ, .. }
+            | Expr::Arith { typ, .. }
+            | Expr::Negate { typ, .. }
+            | Expr::Cast { typ, .. }
+            | Expr::Func { typ, .. }
+            | Expr::Array { typ, .. }
+            | Expr::Subscript { typ, .. }
+            | Expr::Case { typ, .. }
+            | Expr::Coalesce { typ, .. }
+            | Expr::MinMax { typ, .. }
+            | Expr::Row { typ, .. }
+            | Expr::FieldSelect { typ, .. } => *typ,
+            Expr::NullIf { left, .. } => left.typ(),
+            Expr::Compare { .. }
+            | Expr::ArrayCompare { .. }
+            | Expr::Bool { .. }
+            | Expr::NullTest { .. } => pg_type::BOOL_OID,
+        }
+    }
+
+    /// Return the type modifier of the expression result, or -1 if it is not known.
+    pub fn typmod(&self) -> i32 {
+        match self {
+            Expr::Column { typmod, .. }
+            | Expr::Cast { typmod, .. }
+            | Expr::FieldSelect { typmod, .. } => *typmod,
+            _ => -1,
+        }
+    }
+}
+
+/// The columns that can be referenced by an expression, which are the columns of the relation
+/// on FROM.
+pub struct Scope<'a> {
+    /// Name of the relation, which can be used to qualify the column names.
+    pub name: &'a str,
+
+    /// Columns of the relation.
+    pub tuple_desc: &'a TupleDesc,
+}
+
+/// Transform a parsed expression into a typed expression. Column references are resolved on
+/// the given scope, and are rejected if there is none.
+pub fn transform_expr(expr: &ast::Expr, scope: Option<&Scope>) -> Result<Expr> {
+    match expr {
+        ast::Expr::Value(value) => transform_value(value),
+        ast::Expr::Identifier(ident) => transform_column_ref(None, ident, scope),
+        ast::Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+            [relation, column] => transform_column_ref(Some(relation), column, scope),
+            _ => bail!(SQLError::Unsupported(expr.to_string())),
+        },
+        ast::Expr::Nested(expr) => transform_expr(expr, scope),
+        ast::Expr::Tuple(exprs) => transform_row(exprs, scope),
+        ast::Expr::CompositeAccess { expr, key } => {
+            transform_field_select(transform_expr(expr, scope)?, key)
+        }
+        ast::Expr::UnaryOp {
+            op: ast::UnaryOperator::Not,
+            expr,
+        } => transform_bool_expr(BoolOp::Not, &[expr], scope),
+        ast::Expr::UnaryOp { op, expr } => {
+            let expr = transform_expr(expr, scope)?;
+            match op {
+                ast::UnaryOperator::Plus => Ok(expr),
+                ast::UnaryOperator::Minus => {
+                    let typ = types::lookup_type(expr.typ())?;
+                    if !matches!(
+                        typ.typcategory(),
+                        TypeCategory::Numeric | TypeCategory::Timespan
+                    ) {
+                        bail!(types::Error::UndefinedOperator(format!(
+                            "- {}",
+                            typ.format_type()
+                        )));
+                    }
+                    Ok(Expr::Negate {
+                        typ: expr.typ(),
+                        expr: Box::new(expr),
+                    })
+                }
+                _ => bail!(types::Error::UndefinedOperator(format!(
+                    "{} {}",
+                    op,
+                    types::lookup_type(expr.typ())?.format_type()
+                ))),
+            }
+        }
+        ast::Expr::BinaryOp { left, op, right } => {
+            if let Some((op, left, right)) = parser::ext_operator(expr) {
+                return transform_ext_operator(op, left, right, scope);
+            }
+
+            if let Some(op) = comparison_operator(op) {
+                return match right.as_ref() {
+                    ast::Expr::AnyOp(right) => transform_array_compare(
+                        op,
+                        true,
+                        transform_expr(left, scope)?,
+                        transform_expr(right, scope)?,
+                    ),
+                    ast::Expr::AllOp(right) => transform_array_compare(
+                        op,
+                        false,
+                        transform_expr(left, scope)?,
+                        transform_expr(right, scope)?,
+                    ),
+                    right => transform_compare(
+                        op,
+                        transform_expr(left, scope)?,
+                        transform_expr(right, scope)?,
+                    ),
+                };
+            }
+
+            if let Some(func) = pattern_match_function(op) {
+                let like = matches!(
+                    op,
+                    ast::BinaryOperator::Like
+                        | ast::BinaryOperator::NotLike
+                        | ast::BinaryOperator::ILike
+                        | ast::BinaryOperator::NotILike
+                );
+                let right = match parser::ext_operator(right) {
+                    Some((ExtOperator::Escape, pattern, escape)) if like => make_function_call(
+                        "like_escape",
+                        vec![
+                            transform_expr(pattern, scope)?,
+                            transform_expr(escape, scope)?,
+                        ],
+                    )?,
+                    _ => transform_expr(right, scope)?,
+                };
+                return transform_operator_call(func, transform_expr(left, scope)?, right);
+            }
+
+            if let ast::BinaryOperator::StringConcat = op {
+                return transform_string_concat(
+                    expr,
+                    transform_expr(left, scope)?,
+                    transform_expr(right, scope)?,
+                );
+            }
+
+            let op = match op {
+                ast::BinaryOperator::And => {
+                    return transform_bool_expr(BoolOp::And, &[left, right], scope)
+                }
+                ast::BinaryOperator::Or => {
+                    return transform_bool_expr(BoolOp::Or, &[left, right], scope)
+                }
+                ast::BinaryOperator::Plus => ArithOp::Add,
+                ast::BinaryOperator::Minus => ArithOp::Sub,
+                ast::BinaryOperator::Multiply => ArithOp::Mul,
+                ast::BinaryOperator::Divide => ArithOp::Div,
+                ast::BinaryOperator::Modulo => ArithOp::Mod,
+                _ => bail!(SQLError::Unsupported(expr.to_string())),
+            };
+            transform_arith(
+                op,
+                transform_expr(left, scope)?,
+                transform_expr(right, scope)?,
+            )
+        }
+        ast::Expr::IsNull(expr) => Ok(Expr::NullTest {
+            negated: false,
+            expr: Box::new(transform_expr(expr, scope)?),
+        }),
+        ast::Expr::IsNotNull(expr) => Ok(Expr::NullTest {
+            negated: true,
+            expr: Box::new(transform_expr(expr, scope)?),
+        }),
+        ast::Expr::TypedString { data_type, value } => {
+            let (typ, typmod) = lookup_data_type(data_type)?;
+            Ok(Expr::Const {
+                typ: typ.oid(),
+                value: Some(typ.input(value, typmod)?),
+            })
+        }
+        ast::Expr::Cast { expr, data_type } => {
+            let (target, typmod) = lookup_data_type(data_type)?;
+            // A parameter of unknown type takes the type that it is cast to, e.g $1::int[].
+            if let ast::Expr::Value(ast::Value::Placeholder(name)) = expr.as_ref() {
+                if let Some(number) = param_number(name) {
+                    session::infer_param_type(number, target.oid());
+                }
+            }
+            let expr = transform_expr(expr, scope)?;
+            let source = types::lookup_type(expr.typ())?;
+            match coerce_to_target_type(expr, target, typmod, CoercionContext::Explicit)? {
+                Some(expr) => Ok(expr),
+                None => bail!(types::Error::CannotCoerce(
+                    source.format_type(),
+                    target.format_type()
+                )),
+            }
+        }
+        ast::Expr::Function(function) => transform_function(function, scope),
+        ast::Expr::Case {
+            operand,
+            conditions,
+            results,
+            else_result,
+        } => transform_case(
+            operand.as_deref(),
+            conditions,
+            results,
+            else_result.as_deref(),
+            scope,
+        ),
+        ast::Expr::Substring {
+            expr,
+            substring_from,
+            substring_for,
+        } => {
+            // SUBSTRING(s FOR n) starts at the first character.
+            let mut args = vec![transform_expr(expr, scope)?];
+            match substring_from {
+                Some(from) => args.push(transform_expr(from, scope)?),
+                None if substring_for.is_some() => args.push(transform_value(
+                    &ast::Value::Number(String::from("1"), false),
+                )?),
+                None => {}
+            }
+            if let Some(count) = substring_for {
+                args.push(transform_expr(count, scope)?);
+            }
+            make_function_call("substring", args)
+        }
+        ast::Expr::Trim { expr, trim_where } => {
+            let (name, characters) = match trim_where {
+                Some((ast::TrimWhereField::Both, characters)) => ("btrim", Some(characters)),
+                Some((ast::TrimWhereField::Leading, characters)) => ("ltrim", Some(characters)),
+                Some((ast::TrimWhereField::Trailing, characters)) => ("rtrim", Some(characters)),
+                None => ("btrim", None),
+            };
+            let mut args = vec![transform_expr(expr, scope)?];
+            if let Some(characters) = characters {
+                args.push(transform_expr(characters, scope)?);
+            }
+            make_function_call(name, args)
+        }
+        // POSITION(substring IN string) is a call to position(string, substring).
+        ast::Expr::Position { expr, r#in } => make_function_call(
+            "position",
+            vec![transform_expr(r#in, scope)?, transform_expr(expr, scope)?],
+        ),
+        ast::Expr::JsonAccess {
+            left,
+            operator,
+            right,
+        } => {
+            // The parser reads a chain of operators such as a -> 'b' ->> 'c' from right to
+            // left, so the chain is rebuilt to be applied from left to right.
+            let mut left = transform_expr(left, scope)?;
+            let mut operator = operator;
+            let mut right = right.as_ref();
+            while let ast::Expr::JsonAccess {
+                left: next,
+                operator: next_operator,
+                right: rest,
+            } = right
+            {
+                left = transform_json_access(left, transform_expr(next, scope)?, operator)?;
+                operator = next_operator;
+                right = rest;
+            }
+            transform_json_access(left, transform_expr(right, scope)?, operator)
+        }
+        ast::Expr::Array(array) if array.named => transform_array(array, scope),
+        ast::Expr::ArrayIndex { obj, indexs } => {
+            transform_subscript(transform_expr(obj, scope)?, indexs, scope)
+        }
+        _ => bail!(SQLError::Unsupported(expr.to_string())),
+    }
+}
+
+/// Return the name of the output column of an expression of the select list without alias,
+/// following PostgreSQL FigureColname: a column reference is named after the column, a
+/// function call after the function and a cast after the type. Expressions without a name, like
+/// operators, are named ?column?.
+pub fn figure_colname(expr: &ast::Expr) -> String {
+    match figure_colname_internal(expr) {
+        Some((name, _)) => name,
+        None => String::from("?column?"),
+    }
+}
+
+/// Return the name figured for an expression and its strength, which is 2 for the names of
+/// columns and functions and 1 for names that are only used if no better name is found inside
+/// the expression, like the name of the type of a cast or "case".
+fn figure_colname_internal(expr: &ast::Expr) -> Option<(String, u8)> {
+    let name = match expr {
+        ast::Expr::Identifier(ident) => ident.value.clone(),
+        ast::Expr::CompoundIdentifier(idents) => idents.last()?.value.clone(),
+        ast::Expr::Function(function) => function.name.0.last()?.value.to_lowercase(),
+        ast::Expr::Nested(expr) => return figure_colname_internal(expr),
+        ast::Expr::ArrayIndex { obj, .. } => return figure_colname_internal(obj),
+        ast::Expr::Cast { expr, data_type } => match figure_colname_internal(expr) {
+            Some((name, 2)) => return Some((name, 2)),
+            _ => return Some((data_type_name(data_type)?, 1)),
+        },
+        ast::Expr::TypedString { data_type, .. } => return Some((data_type_name(data_type)?, 1)),
+        ast::Expr::Value(ast::Value::Boolean(_)) => return Some((String::from("bool"), 1)),
+        ast::Expr::Value(ast::Value::Interval { .. }) => {
+            return Some((String::from("interval"), 1))
+        }
+        ast::Expr::Case { else_result, .. } => {
+            return match else_result.as_deref().and_then(figure_colname_internal) {
+                Some((name, 2)) => Some((name, 2)),
+                _ => Some((String::from("case"), 1)),
+            }
+        }
+        ast::Expr::Array(_) => String::from("array"),
+        ast::Expr::Tuple(_) => String::from("row"),
+        ast::Expr::CompositeAccess { key, .. } => key.value.clone(),
+        ast::Expr::Substring { .. } => String::from("substring"),
+        ast::Expr::Position { .. } => String::from("position"),
+        ast::Expr::Trim { trim_where, .. } => String::from(match trim_where {
+            Some((ast::TrimWhereField::Leading, _)) => "ltrim",
+            Some((ast::TrimWhereField::Trailing, _)) => "rtrim",
+            _ => "btrim",
+        }),
+        _ => return None,
+    };
+    Some((name, 2))
+}
+
+/// Return the name of a data type used to name a cast, the name of the element type for arrays.
+fn data_type_name(data_type: &ast::DataType) -> Option<String> {
+    match data_type {
+        ast::DataType::Array(data_type) => data_type_name(data_type),
+        data_type => Some(lookup_data_type(data_type).ok()?.0.typname().to_string()),
+    }
+}
+
+/// Transform a reference to a column of the relation on scope, optionally qualified with the
+/// relation name.
+fn transform_column_ref(
+    relation: Option<&ast::Ident>,
+    column: &ast::Ident,
+    scope: Option<&Scope>,
+) -> Result<Expr> {
+    if let Some(relation) = relation {
+        if scope.is_none_or(|scope| scope.name != relation.value) {
+            bail!(SQLError::MissingFromEntry(relation.value.clone()));
+        }
+    }
+
+    let attr = scope.and_then(|scope| {
+        scope
+            .tuple_desc
+            .attrs
+            .iter()
+            .find(|attr| attr.attname == column.value)
+    });
+    match attr {
+        Some(attr) => Ok(Expr::Column {
+            typ: attr.atttypid,
+            typmod: attr.atttypmod,
+            attnum: attr.attnum,
+        }),
+        None => bail!(SQLError::UndefinedColumnReference(match relation {
+            Some(relation) => format!("{}.{}", relation.value, column.value),
+            None => column.value.clone(),
+        })),
+    }
+}
+
+/// Convert an expression to boolean, as required by the argument of the given clause, e.g the
+/// condition of WHERE.
+pub fn coerce_to_boolean(expr: Expr, context: &'static str) -> Result<Expr> {
+    let typ = types::lookup_type(expr.typ())?;
+    let bool_type = types::lookup_type(pg_type::BOOL_OID)?;
+    match coerce_to_target_type(expr, bool_type, -1, CoercionContext::Implicit)? {
+        Some(expr) => Ok(expr),
+        None => bail!(types::Error::NonBooleanArgument(context, typ.format_type())),
+    }
+}
+
+/// Transform AND, OR or NOT, whose arguments must be boolean.
+fn transform_bool_expr(op: BoolOp, args: &[&ast::Expr], scope: Option<&Scope>) -> Result<Expr> {
+    let context = match op {
+        BoolOp::And => "AND",
+        BoolOp::Or => "OR",
+        BoolOp::Not => "NOT",
+    };
+    let args = args
+        .iter()
+        .map(|arg| coerce_to_boolean(transform_expr(arg, scope)?, context))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Expr::Bool { op, args })
+}
+
+/// Transform a CASE expression, converting all results to their common type. The conditions of
+/// a CASE with an operand are comparisons of the operand with each condition value.
+fn transform_case(
+    operand: Option<&ast::Expr>,
+    conditions: &[ast::Expr],
+    results: &[ast::Expr],
+    else_result: Option<&ast::Expr>,
+    scope: Option<&Scope>,
+) -> Result<Expr> {
+    let operand = operand
+        .map(|operand| transform_expr(operand, scope))
+        .transpose()?;
+
+    let conditions = conditions
+        .iter()
+        .map(|condition| {
+            let condition = transform_expr(condition, scope)?;
+            match &operand {
+                Some(operand) => transform_compare(CmpOp::Eq, operand.clone(), condition),
+                None => coerce_to_boolean(condition, "CASE/WHEN"),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut results = results
+        .iter()
+        .map(|result| transform_expr(result, scope))
+        .collect::<Result<Vec<_>>>()?;
+    results.push(match else_result {
+        Some(default) => transform_expr(default, scope)?,
+        None => Expr::Const {
+            typ: pg_type::UNKNOWN_OID,
+            value: None,
+        },
+    });
+
+    let typ = select_common_type(&results, "CASE")?;
+    let mut results = coerce_to_common_type(results, typ, "CASE")?;
+    let default = results.pop().expect("CASE without default result");
+
+    Ok(Expr::Case {
+        typ,
+        whens: conditions.into_iter().zip(results).collect(),
+        default: Box::new(default),
+    })
+}
+
+/// Transform the functions that are not builtin functions but conditional expressions, which
+/// may not evaluate all their arguments, or None if the function is not one of them.
+fn transform_conditional_function(name: &str, args: Vec<Expr>) -> Option<Result<Expr>> {
+    let expr = match name {
+        "coalesce" => select_common_type(&args, "COALESCE").and_then(|typ| {
+            Ok(Expr::Coalesce {
+                typ,
+                args: coerce_to_common_type(args, typ, "COALESCE")?,
+            })
+        }),
+        "greatest" | "least" => {
+            let (op, context) = if name == "greatest" {
+                (MinMaxOp::Greatest, "GREATEST")
+            } else {
+                (MinMaxOp::Least, "LEAST")
+            };
+            select_common_type(&args, context).and_then(|typ| {
+                Ok(Expr::MinMax {
+                    op,
+                    typ,
+                    args: coerce_to_common_type(args, typ, context)?,
+                })
+            })
+        }
+        "nullif" => {
+            let mut args = args.into_iter();
+            match (args.next(), args.next(), args.next()) {
+                (Some(left), Some(right), None) => {
+                    transform_compare(CmpOp::Eq, left, right).map(|compare| match compare {
+                        Expr::Compare { left, right, .. } => Expr::NullIf { left, right },
+                        _ => unreachable!("comparison transformed into {:?}", compare),
+                    })
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(expr)
+}
+
+/// Return the type and the type modifier of the given data type.
+fn lookup_data_type(data_type: &ast::DataType) -> Result<(&'static dyn Type, i32)> {
+    let (name, modifiers) = type_name(data_type)?;
+    let typ = types::lookup_type_by_name(&name)
+        .ok_or_else(|| catalog::Error::TypeNotFound(name.clone()))?;
+    let typmod = if modifiers.is_empty() {
+        -1
+    } else {
+        typ.typmod_in(&modifiers)?
+    };
+    Ok((typ, typmod))
+}
+
+/// Return the comparison operator of a binary operator, if it is one.
+fn comparison_operator(op: &ast::BinaryOperator) -> Option<CmpOp> {
+    match op {
+        ast::BinaryOperator::Eq => Some(CmpOp::Eq),
+        ast::BinaryOperator::NotEq => Some(CmpOp::NotEq),
+        ast::BinaryOperator::Lt => Some(CmpOp::Lt),
+        ast::BinaryOperator::LtEq => Some(CmpOp::LtEq),
+        ast::BinaryOperator::Gt => Some(CmpOp::Gt),
+        ast::BinaryOperator::GtEq => Some(CmpOp::GtEq),
+        _ => None,
+    }
+}
+
+/// Return the type that values of types a and b can be implicitly converted to, or None if
+/// there is no such type. Values of unknown type are resolved as the other type, and when both
+/// types can be converted to each other text is preferred, e.g varchar and text are resolved as
+/// text.
+fn common_type(a: Oid, b: Oid) -> Option<Oid> {
+    if a == pg_type::UNKNOWN_OID {
+        return Some(b);
+    }
+    if b == pg_type::UNKNOWN_OID || a == b {
+        return Some(a);
+    }
+
+    match (
+        cast::can_coerce(a, b, CoercionContext::Implicit),
+        cast::can_coerce(b, a, CoercionContext::Implicit),
+    ) {
+        (true, false) => Some(b),
+        (false, true) => Some(a),
+        (true, true) if b == pg_type::TEXT_OID => Some(b),
+        (true, true) => Some(a),
+        (false, false) => None,
+    }
+}
+
+/// Transform a comparison, converting both operands to their common type. Operands of unknown
+/// type are compared as text.
+fn transform_compare(op: CmpOp, left: Expr, right: Expr) -> Result<Expr> {
+    let left_type = types::lookup_type(left.typ())?;
+    let right_type = types::lookup_type(right.typ())?;
+    let undefined_operator = || {
+        types::Error::UndefinedOperator(format!(
+            "{} {} {}",
+            left_type.format_type(),
+            op,
+            right_type.format_type()
+        ))
+    };
+
+    let typ = match common_type(left_type.oid(), right_type.oid()) {
+        Some(pg_type::UNKNOWN_OID) => pg_type::TEXT_OID,
+        Some(typ) => typ,
+        None => bail!(undefined_operator()),
+    };
+
+    let target = types::lookup_type(typ)?;
+    let left = coerce_to_target_type(left, target, -1, CoercionContext::Implicit)?;
+    let right = coerce_to_target_type(right, target, -1, CoercionContext::Implicit)?;
+    match (left, right) {
+        (Some(left), Some(right)) => Ok(Expr::Compare {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }),
+        _ => bail!(undefined_operator()),
+    }
+}
+
+/// Transform a comparison of a value with the elements of an array. A right operand of unknown
+/// type is read as an array of the type of the left operand, e.g 1 = ANY('{1,2}').
+fn transform_array_compare(op: CmpOp, any: bool, left: Expr, right: Expr) -> Result<Expr> {
+    let right = if right.typ() == pg_type::UNKNOWN_OID {
+        let elem = match left.typ() {
+            pg_type::UNKNOWN_OID => pg_type::TEXT_OID,
+            typ => typ,
+        };
+        let array = array_type_of(elem)?;
+        coerce_to_target_type(right, array, -1, CoercionContext::Implicit)?
+            .ok_or(types::Error::ArrayRequired)?
+    } else {
+        right
+    };
+
+    let elem = types::lookup_type(right.typ())?.typelem();
+    if elem == INVALID_OID {
+        bail!(types::Error::ArrayRequired);
+    }
+
+    let left_type = types::lookup_type(left.typ())?;
+    let elem_type = types::lookup_type(elem)?;
+    let undefined_operator = || {
+        types::Error::UndefinedOperator(format!(
+            "{} {} {}",
+            left_type.format_type(),
+            op,
+            elem_type.format_type()
+        ))
+    };
+
+    let typ = common_type(left_type.oid(), elem).ok_or_else(undefined_operator)?;
+    let left = coerce_to_target_type(
+        left,
+        types::lookup_type(typ)?,
+        -1,
+        CoercionContext::Implicit,
+    )?;
+    let right = coerce_to_target_type(right, array_type_of(typ)?, -1, CoercionContext::Implicit)?;
+    match (left, right) {
+        (Some(left), Some(right)) => Ok(Expr::ArrayCompare {
+            op,
+            any,
+            left: Box::new(left),
+            right: Box::new(right),
+        }),
+        _ => bail!(undefined_operator()),
+    }
+}
+
+/// Return the array type whose elements are of the given type.
+fn array_type_of(elem: Oid) -> Result<&'static dyn Type> {
+    match types::array_type_of(elem) {
+        Some(array) => Ok(array),
+        None => bail!(types::Error::UndefinedArrayType(
+            types::lookup_type(elem)?.format_type()
+        )),
+    }
+}
+
+/// Return the type that all expressions can be implicitly converted to, as required by the
+/// given construct, e.g CASE. Expressions of unknown type are resolved as text.
+pub fn select_common_type(exprs: &[Expr], context: &'static str) -> Result<Oid> {
+    let mut typ = pg_type::UNKNOWN_OID;
+    for expr in exprs {
+        typ = match common_type(typ, expr.typ()) {
+            Some(typ) => typ,
+            None => bail!(types::Error::CannotMatchTypes(
+                context,
+                types::lookup_type(typ)?.format_type(),
+                types::lookup_type(expr.typ())?.format_type()
+            )),
+        };
+    }
+
+    if typ == pg_type::UNKNOWN_OID {
+        return Ok(pg_type::TEXT_OID);
+    }
+    Ok(typ)
+}
+
+/// Convert all expressions to the type returned by [select_common_type].
+pub fn coerce_to_common_type(
+    exprs: Vec<Expr>,
+    typ: Oid,
+    context: &'static str,
+) -> Result<Vec<Expr>> {
+    let target = types::lookup_type(typ)?;
+    exprs
+        .into_iter()
+        .map(|expr| {
+            let expr_type = types::lookup_type(expr.typ())?.format_type();
+            coerce_to_target_type(expr, target, -1, CoercionContext::Implicit)?.ok_or_else(|| {
+                types::Error::CannotMatchTypes(context, target.format_type(), expr_type).into()
+            })
+        })
+        .collect()
+}
+
+/// Transform an array constructor, converting all elements to their common type. Elements of
+/// unknown type are read as text, and elements that are arrays, including the sub-arrays
+/// written without the ARRAY keyword, build a multidimensional array.
+fn transform_array(array: &ast::Array, scope: Option<&Scope>) -> Result<Expr> {
+    let elements = array
+        .elem
+        .iter()
+        .map(|elem| match elem {
+            ast::Expr::Array(array) => transform_array(array, scope),
+            elem => transform_expr(elem, scope),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if elements.is_empty() {
+        bail!(types::Error::IndeterminateEmptyArray);
+    }
+
+    let mut multidim = false;
+    for element in &elements {
+        multidim |= types::lookup_type(element.typ())?.typelem() != INVALID_OID;
+    }
+    let typ = select_common_type(&elements, "ARRAY")?;
+
+    let (array_type, elem_type) = if multidim {
+        let array_type = types::lookup_type(typ)?;
+        if array_type.typelem() == INVALID_OID {
+            bail!(types::Error::UndefinedArrayType(array_type.format_type()));
+        }
+        (array_type, array_type.typelem())
+    } else {
+        (array_type_of(typ)?, typ)
+    };
+
+    Ok(Expr::Array {
+        typ: array_type.oid(),
+        elemtype: elem_type,
+        multidim,
+        elements: coerce_to_common_type(elements, typ, "ARRAY")?,
+    })
+}
+
+/// Transform the subscripts of an array, e.g array[1][2]. Subscripts are converted to integer
+/// as when assigning a value to an integer column.
+fn transform_subscript(array: Expr, indexes: &[ast::Expr], scope: Option<&Scope>) -> Result<Expr> {
+    let array_type = types::lookup_type(array.typ())?;
+    if array_type.typelem() == INVALID_OID {
+        bail!(types::Error::CannotSubscript(array_type.format_type()));
+    }
+
+    let int4 = types::lookup_type(pg_type::INT_OID)?;
+    let subscripts = indexes
+        .iter()
+        .map(|index| {
+            coerce_to_target_type(
+                transform_expr(index, scope)?,
+                int4,
+                -1,
+                CoercionContext::Assignment,
+            )?
+            .ok_or_else(|| types::Error::SubscriptType.into())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Expr::Subscript {
+        typ: array_type.typelem(),
+        array: Box::new(array),
+        subscripts,
+    })
+}
+
+/// Transform a function call into a call of a builtin function or a conditional expression.
+fn transform_function(function: &ast::Function, scope: Option<&Scope>) -> Result<Expr> {
+    let name = function.name.to_string().to_lowercase();
+    if name == "row" {
+        let args = function
+            .args
+            .iter()
+            .map(|arg| match arg {
+                ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr)) => Ok(expr.clone()),
+                _ => bail!(SQLError::Unsupported(function.to_string())),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        return transform_row(&args, scope);
+    }
+    if function.args.is_empty() {
+        if let Some(expr) = transform_time_function(&name) {
+            return expr;
+        }
+    }
+
+    let args = function
+        .args
+        .iter()
+        .map(|arg| match arg {
+            ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(expr)) => {
+                transform_expr(expr, scope)
+            }
+            _ => bail!(SQLError::Unsupported(function.to_string())),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(expr) = transform_conditional_function(&name, args.clone()) {
+        return expr;
+    }
+    // Set returning functions are only called by a function scan, which returns a row for each
+    // value, so they can not be used where a single value is expected, e.g the select list.
+    if SetFunc::from_name(&name).is_some() {
+        bail!(SQLError::SetFunctionOutsideFrom(name));
+    }
+    make_function_call(&name, args)
+}
+
+/// Transform a row constructor, ROW(args) or (arg1, arg2, ...), into a value of the record
+/// pseudo-type, which is converted to a composite type when assigned to a column or cast.
+fn transform_row(args: &[ast::Expr], scope: Option<&Scope>) -> Result<Expr> {
+    Ok(Expr::Row {
+        typ: pg_type::RECORD_OID,
+        args: args
+            .iter()
+            .map(|arg| transform_expr(arg, scope))
+            .collect::<Result<Vec<_>>>()?,
+    })
+}
+
+/// Transform the selection of an attribute of a composite value, e.g (p).a.
+fn transform_field_select(expr: Expr, field: &ast::Ident) -> Result<Expr> {
+    let typ = types::lookup_type(expr.typ())?;
+    if typ.typcategory() != TypeCategory::Composite {
+        bail!(types::Error::NotCompositeType(
+            field.value.clone(),
+            typ.format_type()
+        ));
+    }
+    match typ
+        .attributes()
+        .iter()
+        .position(|attr| attr.attname == field.value)
+    {
+        Some(fieldnum) => {
+            let attr = &typ.attributes()[fieldnum];
+            Ok(Expr::FieldSelect {
+                typ: attr.atttypid,
+                typmod: attr.atttypmod,
+                expr: Box::new(expr),
+                fieldnum,
+            })
+        }
+        None => bail!(types::Error::UndefinedField(
+            field.value.clone(),
+            typ.format_type()
+        )),
+    }
+}
+
+/// Build a call to the builtin function with the given name that best matches the arguments,
+/// converting the arguments to the types of the function arguments.
+fn make_function_call(name: &str, args: Vec<Expr>) -> Result<Expr> {
+    match resolve_function(name, args.clone())? {
+        Some(expr) => Ok(expr),
+        None => {
+            let arg_types = args
+                .iter()
+                .map(|arg| Ok(types::lookup_type(arg.typ())?.format_type()))
+                .collect::<Result<Vec<_>>>()?;
+            bail!(types::Error::UndefinedFunction(format!(
+                "{}({})",
+                name,
+                arg_types.join(", ")
+            )))
+        }
+    }
+}
+
+/// Search a builtin function with the given name that accepts the given arguments, returning
+/// None if there is no such function.
+///
+/// Arguments declared as "any" are passed as they are, except the arguments of unknown type
+/// which are converted to text.
+fn resolve_function(name: &str, args: Vec<Expr>) -> Result<Option<Expr>> {
+    let arg_types = args.iter().map(Expr::typ).collect::<Vec<_>>();
+    let function = match function::select_function(name, &arg_types)? {
+        Some(function) => function,
+        None => return Ok(None),
+    };
+
+    if function.func == Func::JsonbBuildObject && !args.len().is_multiple_of(2) {
+        bail!(types::Error::OddNumberOfArguments);
+    }
+
+    let mut coerced = Vec::with_capacity(args.len());
+    for (i, arg) in args.into_iter().enumerate() {
+        let target = match function.arg_type(i) {
+            pg_type::ANY_OID if arg.typ() != pg_type::UNKNOWN_OID => {
+                coerced.push(arg);
+                continue;
+            }
+            pg_type::ANY_OID => pg_type::TEXT_OID,
+            typ => typ,
+        };
+        match coerce_to_target_type(
+            arg,
+            types::lookup_type(target)?,
+            -1,
+            CoercionContext::Implicit,
+        )? {
+            Some(arg) => coerced.push(arg),
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(Expr::Func {
+        func: function.func,
+        typ: function.rettype,
+        strict: function.strict,
+        args: coerced,
+    }))
+}
+
+/// Return the name of the function that implements a pattern matching operator, or None if the
+/// operator is not a pattern matching operator.
+fn pattern_match_function(op: &ast::BinaryOperator) -> Option<(&'static str, &'static str)> {
+    match op {
+        ast::BinaryOperator::Like => Some(("like", "~~")),
+        ast::BinaryOperator::NotLike => Some(("notlike", "!~~")),
+        ast::BinaryOperator::ILike => Some(("ilike", "~~*")),
+        ast::BinaryOperator::NotILike => Some(("notilike", "!~~*")),
+        ast::BinaryOperator::PGRegexMatch => Some(("textregexeq", "~")),
+        ast::BinaryOperator::PGRegexNotMatch => Some(("textregexne", "!~")),
+        ast::BinaryOperator::PGRegexIMatch => Some(("texticregexeq", "~*")),
+        ast::BinaryOperator::PGRegexNotIMatch => Some(("texticregexne", "!~*")),
+        _ => None,
+    }
+}
+
+/// Transform the || operator into a call of textcat when both operands can be converted to
+/// text, or of anytextcat or textanycat when only one of them can, which convert the other
+/// operand to text, e.g 1 || 'a' is 1a. Arrays are rejected, since || concatenates arrays on
+/// PostgreSQL, which is not supported yet.
+fn transform_string_concat(expr: &ast::Expr, left: Expr, right: Expr) -> Result<Expr> {
+    let is_array = |expr: &Expr| {
+        types::lookup_type(expr.typ()).map(|typ| typ.typcategory() == TypeCategory::Array)
+    };
+    if is_array(&left)? || is_array(&right)? {
+        bail!(SQLError::Unsupported(expr.to_string()));
+    }
+
+    for func in &["textcat", "anytextcat", "textanycat"] {
+        if let Some(expr) = resolve_function(func, vec![left.clone(), right.clone()])? {
+            return Ok(expr);
+        }
+    }
+    bail!(types::Error::UndefinedOperator(format!(
+        "{} || {}",
+        types::lookup_type(left.typ())?.format_type(),
+        types::lookup_type(right.typ())?.format_type()
+    )))
+}
+
+/// Transform an operator not supported by the SQL parser into a call of the function that
+/// implements it. SIMILAR TO converts the pattern to a POSIX regular expression with
+/// similar_to_escape, like PostgreSQL does, and matches it with the ~ operator.
+fn transform_ext_operator(
+    op: ExtOperator,
+    left: &ast::Expr,
+    right: &ast::Expr,
+    scope: Option<&Scope>,
+) -> Result<Expr> {
+    let func = match op {
+        ExtOperator::JsonbContains => ("jsonb_contains", op.name()),
+        ExtOperator::JsonbExists => ("jsonb_exists", op.name()),
+        ExtOperator::SimilarTo => ("textregexeq", "~"),
+        ExtOperator::NotSimilarTo => ("textregexne", "!~"),
+        ExtOperator::Escape => bail!(SQLError::MisplacedEscape),
+    };
+
+    let right = match op {
+        ExtOperator::SimilarTo | ExtOperator::NotSimilarTo => {
+            let args = match parser::ext_operator(right) {
+                Some((ExtOperator::Escape, pattern, escape)) => vec![pattern, escape],
+                _ => vec![right],
+            };
+            let args = args
+                .into_iter()
+                .map(|arg| transform_expr(arg, scope))
+                .collect::<Result<Vec<_>>>()?;
+            make_function_call("similar_to_escape", args)?
+        }
+        _ => transform_expr(right, scope)?,
+    };
+    transform_operator_call(func, transform_expr(left, scope)?, right)
+}
+
+/// Transform an operator into a call of the function that implements it, e.g the LIKE, ILIKE
+/// and POSIX regular expression operators. The function is given as a pair of the function name
+/// and the operator name used by PostgreSQL, e.g ~~ for LIKE.
+fn transform_operator_call(
+    (func, operator): (&'static str, &'static str),
+    left: Expr,
+    right: Expr,
+) -> Result<Expr> {
+    let left_type = types::lookup_type(left.typ())?.format_type();
+    let right_type = types::lookup_type(right.typ())?.format_type();
+    match resolve_function(func, vec![left, right])? {
+        Some(expr) => Ok(expr),
+        None => bail!(types::Error::UndefinedOperator(format!(
+            "{} {} {}",
+            left_type, operator, right_type
+        ))),
+    }
+}
+
+/// Transform the -> and ->> operators, which return the value of an object key when the right
+/// operand is a string or an array element when the right operand is an integer. The ->>
+/// operator returns the value as text.
+fn transform_json_access(left: Expr, right: Expr, operator: &ast::JsonOperator) -> Result<Expr> {
+    let as_text = match operator {
+        ast::JsonOperator::Arrow => false,
+        ast::JsonOperator::LongArrow => true,
+        _ => bail!(SQLError::Unsupported(operator.to_string())),
+    };
+
+    let func = match right.typ() {
+        pg_type::INT2_OID | pg_type::INT_OID => "jsonb_array_element",
+        _ => "jsonb_object_field",
+    };
+    let func = if as_text {
+        format!("{}_text", func)
+    } else {
+        func.to_string()
+    };
+
+    let left_type = types::lookup_type(left.typ())?.format_type();
+    let right_type = types::lookup_type(right.typ())?.format_type();
+    match resolve_function(&func, vec![left, right])? {
+        Some(expr) => Ok(expr),
+        None => bail!(types::Error::UndefinedOperator(format!(
+            "{} {} {}",
+            left_type, operator, right_type
+        ))),
+    }
+}
+
+/// Transform a function that returns the current date or time, which are constant during a
+/// transaction, or None if the function is not one of them.
+fn transform_time_function(name: &str) -> Option<Result<Expr>> {
+    let (typ, value) = match name {
+        "now" | "current_timestamp" | "transaction_timestamp" => (
+            pg_type::TIMESTAMPTZ_OID,
+            bincode::serialize(&session::transaction_timestamp()),
+        ),
+        "current_date" => (
+            pg_type::DATE_OID,
+            bincode::serialize(&(datetime::local_today() as i32)),
+        ),
+        _ => return None,
+    };
+    Some(value.map_err(Into::into).map(|value| Expr::Const {
+        typ,
+        value: Some(value),
+    }))
+}
+
+/// Return the name of the given data type on pg_type and the modifiers declared with the type,
+/// e.g the precision and scale of numeric(p, s).
+pub fn type_name(typ: &ast::DataType) -> Result<(String, Vec<u64>)> {
+    let mut modifiers = Vec::new();
+    let name = match typ {
+        ast::DataType::SmallInt(_) => "int2",
+        ast::DataType::Int(_) => "int4",
+        ast::DataType::BigInt(_) => "int8",
+        ast::DataType::Real => "float4",
+        ast::DataType::Double => "float8",
+        ast::DataType::Float(precision) => match precision {
+            Some(precision) if *precision <= 24 => "float4",
+            _ => "float8",
+        },
+        ast::DataType::Decimal(precision, scale) => {
+            modifiers.extend(precision.iter().chain(scale.iter()));
+            "numeric"
+        }
+        ast::DataType::Varchar(len) => {
+            modifiers.extend(len.iter());
+            "varchar"
+        }
+        // A character column without length holds a single character.
+        ast::DataType::Char(len) => {
+            modifiers.push(len.unwrap_or(1));
+            "bpchar"
+        }
+        ast::DataType::Text => "text",
+        ast::DataType::Bytea => "bytea",
+        ast::DataType::Uuid => "uuid",
+        ast::DataType::Boolean => "bool",
+        ast::DataType::Date => "date",
+        ast::DataType::Time => "time",
+        // The parser discards WITH TIME ZONE, so a timestamp with time zone has to be declared
+        // as timestamptz.
+        ast::DataType::Timestamp => "timestamp",
+        ast::DataType::Interval => "interval",
+        ast::DataType::Custom(name) => match name.0.last() {
+            Some(ident) => return Ok((ident.value.to_lowercase(), modifiers)),
+            None => bail!("Not supported data type: {}", typ),
+        },
+        // Like PostgreSQL, the number of dimensions is not part of the type, so text[][] is
+        // the same type as text[].
+        ast::DataType::Array(elem) if matches!(elem.as_ref(), ast::DataType::Array(_)) => {
+            return type_name(elem)
+        }
+        // Array types are named after their element type with a leading underscore, and keep
+        // the modifiers of the element type, e.g varchar(10)[].
+        ast::DataType::Array(elem) => {
+            let (name, modifiers) = type_name(elem)?;
+            if name.starts_with('_') {
+                bail!("Not supported data type: {}", typ);
+            }
+            return Ok((format!("_{}", name), modifiers));
+        }
+        _ => bail!("Not supported data type: {}", typ),
+    };
+    Ok((name.to_string(), modifiers))
+}
+
+/// Return the number of a parameter placeholder, e.g 1 for $1.
+pub fn param_number(name: &str) -> Option<usize> {
+    name.strip_prefix('$')?
+        .parse()
+        .ok()
+        .filter(|number| *number > 0)
+}
+
+/// Transform a literal value into a constant expression.
+fn transform_value(value: &ast::Value) -> Result<Expr> {
+    let (typ, value) = match value {
+        ast::Value::Number(value, _) => {
+            // Integer literals are typed as the smallest integer type that fits the value, any
+            // other number literal is a numeric value.
+            let typ = if value.parse::<i32>().is_ok() {
+                pg_type::INT_OID
+            } else if value.parse::<i64>().is_ok() {
+                pg_type::INT8_OID
+            } else {
+                pg_type::NUMERIC_OID
+            };
+            (typ, Some(types::lookup_type(typ)?.input(value, -1)?))
+        }
+        ast::Value::SingleQuotedString(value) => (
+            pg_type::UNKNOWN_OID,
+            Some(types::lookup_type(pg_type::UNKNOWN_OID)?.input(value, -1)?),
+        ),
+        ast::Value::Boolean(value) => (
+            pg_type::BOOL_OID,
+            Some(types::lookup_type(pg_type::BOOL_OID)?.input(&value.to_string(), -1)?),
+        ),
+        ast::Value::Null => (pg_type::UNKNOWN_OID, None),
+        ast::Value::Placeholder(name) => match param_number(name).and_then(session::param) {
+            Some(param) => (param.typ, param.value),
+            None => bail!(SQLError::UndefinedParameter(name.clone())),
+        },
+        ast::Value::Interval {
+            value,
+            leading_field,
+            ..
+        } => {
+            // A number followed by a field, e.g INTERVAL '2' HOUR, is a quantity of that field.
+            let value = match leading_field {
+                Some(field) if value.trim().parse::<f64>().is_ok() => {
+                    format!("{} {}", value, field)
+                }
+                _ => value.clone(),
+            };
+            (
+                pg_type::INTERVAL_OID,
+                Some(types::lookup_type(pg_type::INTERVAL_OID)?.input(&value, -1)?),
+            )
+        }
+        _ => bail!(SQLError::Unsupported(value.to_string())),
+    };
+    Ok(Expr::Const { typ, value })
+}
+
+/// Transform an arithmetic operation, converting the operands to the types of the operator.
+///
+/// The operator is searched on the type of the left operand, trying the types that each operand
+/// can be implicitly converted to, e.g smallint + bigint is resolved as bigint + bigint. Operands
+/// of unknown type (string literals and NULL) are first resolved as the type of the other
+/// operand, and then as any other type, e.g timestamp + '1 day' is resolved as timestamp +
+/// interval. Commutative operators are also searched on the type of the right operand, so
+/// interval + date is resolved as date + interval.
+fn transform_arith(op: ArithOp, left: Expr, right: Expr) -> Result<Expr> {
+    let left_type = types::lookup_type(left.typ())?;
+    let right_type = types::lookup_type(right.typ())?;
+
+    let undefined_operator = || {
+        types::Error::UndefinedOperator(format!(
+            "{} {} {}",
+            left_type.format_type(),
+            op,
+            right_type.format_type()
+        ))
+    };
+
+    if left_type.oid() == pg_type::UNKNOWN_OID && right_type.oid() == pg_type::UNKNOWN_OID {
+        bail!(undefined_operator());
+    }
+
+    let left_candidates = operand_candidates(left_type.oid(), right_type.oid());
+    let right_candidates = operand_candidates(right_type.oid(), left_type.oid());
+
+    for &left_oid in &left_candidates {
+        for &right_oid in &right_candidates {
+            if let Some(expr) = make_arith(op, &left, left_oid, &right, right_oid)? {
+                return Ok(expr);
+            }
+        }
+    }
+
+    if op == ArithOp::Add || op == ArithOp::Mul {
+        for &right_oid in &right_candidates {
+            for &left_oid in &left_candidates {
+                if let Some(expr) = make_arith(op, &right, right_oid, &left, left_oid)? {
+                    return Ok(expr);
+                }
+            }
+        }
+    }
+
+    bail!(undefined_operator())
+}
+
+/// Return the types that an operand of type typ can be converted to when searching for an
+/// operator, in order of preference. The other type is the type of the other operand.
+fn operand_candidates(typ: Oid, other: Oid) -> Vec<Oid> {
+    if typ == pg_type::UNKNOWN_OID {
+        return std::iter::once(other)
+            .chain(types::BUILTIN_TYPES.iter().map(|typ| typ.oid()))
+            .filter(|oid| *oid != pg_type::UNKNOWN_OID)
+            .collect();
+    }
+
+    match cast::numeric_precedence(typ) {
+        Some(_) => cast::numeric_supertypes(typ).to_vec(),
+        None => vec![typ],
+    }
+}
+
+/// Build an arithmetic expression if the operator is defined for the given types and both
+/// operands can be implicitly converted to them.
+fn make_arith(
+    op: ArithOp,
+    left: &Expr,
+    left_oid: Oid,
+    right: &Expr,
+    right_oid: Oid,
+) -> Result<Option<Expr>> {
+    let left_type = types::lookup_type(left_oid)?;
+    let typ = match left_type.arith_result_type(op, right_oid) {
+        Some(typ) => typ,
+        None => return Ok(None),
+    };
+
+    let left = coerce_to_target_type(left.clone(), left_type, -1, CoercionContext::Implicit)?;
+    let right = coerce_to_target_type(
+        right.clone(),
+        types::lookup_type(right_oid)?,
+        -1,
+        CoercionContext::Implicit,
+    )?;
+
+    match (left, right) {
+        (Some(left), Some(right)) => Ok(Some(Expr::Arith {
+            op,
+            typ,
+            left: Box::new(left),
+            right: Box::new(right),
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Convert an expression to the target type, returning None if there is no conversion from the
+/// expression type to the target type on the given context.
+///
+/// Constants of unknown type are converted when transforming the expression, so an invalid
+/// literal is reported before executing the statement.
+pub fn coerce_to_target_type(
+    expr: Expr,
+    target: &dyn Type,
+    typmod: i32,
+    context: CoercionContext,
+) -> Result<Option<Expr>> {
+    let source = types::lookup_type(expr.typ())?;
+    let explicit = context == CoercionContext::Explicit;
+
+    if source.oid() == pg_type::UNKNOWN_OID {
+        if let Expr::Const { value, .. } = expr {
+            let value = match value {
+                Some(value) => Some(cast::input_value(
+                    target,
+                    &source.output(&value)?,
+                    typmod,
+                    explicit,
+                )?),
+                None => None,
+            };
+            return Ok(Some(Expr::Const {
+                typ: target.oid(),
+                value,
+            }));
+        }
+    }
+
+    // A row constructor is converted to a composite type by converting each attribute to the
+    // type of the attribute of the composite type.
+    if source.oid() == pg_type::RECORD_OID && target.typcategory() == TypeCategory::Composite {
+        let args = match expr {
+            Expr::Row { args, .. } if args.len() == target.attributes().len() => args,
+            _ => return Ok(None),
+        };
+        let mut coerced = Vec::with_capacity(args.len());
+        for (arg, attr) in args.into_iter().zip(target.attributes()) {
+            let typ = types::lookup_type(attr.atttypid)?;
+            match coerce_to_target_type(arg, typ, attr.atttypmod, context)? {
+                Some(arg) => coerced.push(arg),
+                None => return Ok(None),
+            }
+        }
+        return Ok(Some(Expr::Row {
+            typ: target.oid(),
+            args: coerced,
+        }));
+    }
+
+    if !cast::can_coerce(source.oid(), target.oid(), context) {
+        return Ok(None);
+    }
+
+    // Variable-length values only need to be converted if the target has a type modifier that
+    // may reject the value, e.g the max length of a varchar(n).
+    if source.oid() == target.oid() && (typmod < 0 || target.typlen() >= 0) {
+        return Ok(Some(expr));
+    }
+
+    Ok(Some(Expr::Cast {
+        typ: target.oid(),
+        typmod,
+        explicit,
+        expr: Box::new(expr),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::pg_attribute::PgAttribute;
+    use sqlparser::dialect::PostgreSqlDialect;
+
+    fn transform(sql: &str) -> Result<Expr> {
+        transform_expr(&parser::parse_expr(&PostgreSqlDialect {}, sql)?, None)
+    }
+
+    #[test]
+    fn test_literal_types() -> Result<()> {
+        assert_eq!(transform("42")?.typ(), pg_type::INT_OID);
+        assert_eq!(transform("3000000000")?.typ(), pg_type::INT8_OID);
+        assert_eq!(transform("1.5")?.typ(), pg_type::NUMERIC_OID);
+        assert_eq!(
+            transform("9223372036854775808")?.typ(),
+            pg_type::NUMERIC_OID
+        );
+        assert_eq!(transform("'a'")?.typ(), pg_type::UNKNOWN_OID);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith_promotion() -> Result<()> {
+        assert_eq!(transform("1 + 3000000000")?.typ(), pg_type::INT8_OID);
+        assert_eq!(transform("2 * 1.5")?.typ(), pg_type::NUMERIC_OID);
+        assert_eq!(transform("1.5 * '2'")?.typ(), pg_type::NUMERIC_OID);
+        assert_eq!(transform("'2' + 1")?.typ(), pg_type::INT_OID);
+        assert!(transform("true + 1").is_err());
+        assert!(transform("'a' + 1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_datetime_operators() -> Result<()> {
+        assert_eq!(transform("DATE '2024-01-01' + 1")?.typ(), pg_type::DATE_OID);
+        assert_eq!(
+            transform("DATE '2024-01-01' - '2023-01-01'")?.typ(),
+            pg_type::INT_OID
+        );
+        assert_eq!(
+            transform("TIMESTAMP '2024-01-01' + '1 day'")?.typ(),
+            pg_type::TIMESTAMP_OID
+        );
+        assert_eq!(
+            transform("INTERVAL '1 day' + DATE '2024-01-01'")?.typ(),
+            pg_type::TIMESTAMP_OID
+        );
+        assert_eq!(
+            transform("2 * INTERVAL '1' HOUR")?.typ(),
+            pg_type::INTERVAL_OID
+        );
+        assert_eq!(transform("-INTERVAL '1 day'")?.typ(), pg_type::INTERVAL_OID);
+        assert_eq!(transform("now()")?.typ(), pg_type::TIMESTAMPTZ_OID);
+        assert_eq!(transform("CURRENT_DATE")?.typ(), pg_type::DATE_OID);
+        assert!(transform("DATE '2024-01-01' + DATE '2024-01-01'").is_err());
+        assert!(transform("TIMESTAMP '2024-01-01' + 1").is_err());
+        assert!(transform("-DATE '2024-01-01'").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_jsonb_functions() -> Result<()> {
+        assert_eq!(
+            transform(r#"'{"a": [1]}' -> 'a' ->> 0"#)?.typ(),
+            pg_type::TEXT_OID
+        );
+        assert_eq!(
+            transform("jsonb_build_object('a', 1, 'b', now())")?.typ(),
+            pg_type::JSONB_OID
+        );
+        assert_eq!(
+            transform(r#"jsonb_contains('{"a": 1}', '{}')"#)?.typ(),
+            pg_type::BOOL_OID
+        );
+        assert_eq!(
+            transform(r#"'{"a": 1}'::jsonb @> '{}' AND '["a"]'::jsonb ? 'a'"#)?.typ(),
+            pg_type::BOOL_OID
+        );
+        assert!(transform(r#"'{"a": 1}'::jsonb @> 1"#).is_err());
+        assert!(transform("1 ? 'a'").is_err());
+        assert!(transform("jsonb_build_object('a')").is_err());
+        assert!(transform("jsonb_exists('{}', 1)").is_err());
+        assert!(transform("1 -> 'a'").is_err());
+        assert!(transform("unknown_function(1)").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_expressions() -> Result<()> {
+        assert_eq!(
+            transform("ARRAY[1, 2.5]")?.typ(),
+            pg_type::NUMERIC_ARRAY_OID
+        );
+        assert_eq!(
+            transform("ARRAY['a', NULL]")?.typ(),
+            pg_type::TEXT_ARRAY_OID
+        );
+        assert_eq!(transform("ARRAY[[1], [2]]")?.typ(), pg_type::INT4_ARRAY_OID);
+        assert_eq!(
+            transform("'{{a}}'::text[][]")?.typ(),
+            pg_type::TEXT_ARRAY_OID
+        );
+        assert_eq!(transform("ARRAY[1, 2][1]")?.typ(), pg_type::INT_OID);
+        assert_eq!(transform("1 = ANY('{1,2}')")?.typ(), pg_type::BOOL_OID);
+        assert_eq!(
+            transform("1.5 > ALL(ARRAY[1, 2])")?.typ(),
+            pg_type::BOOL_OID
+        );
+        assert!(transform("ARRAY[]").is_err());
+        assert!(transform("ARRAY[1, true]").is_err());
+        assert!(transform("ARRAY[1]['a']").is_err());
+        assert!(transform("1[1]").is_err());
+        assert!(transform("1 = ANY(1)").is_err());
+        assert!(transform("true = ANY(ARRAY[1])").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_function_calls() -> Result<()> {
+        assert_eq!(transform("length('abc')")?.typ(), pg_type::INT_OID);
+        assert_eq!(transform("upper('a')")?.typ(), pg_type::TEXT_OID);
+        assert_eq!(transform("round(1)")?.typ(), pg_type::FLOAT8_OID);
+        assert_eq!(transform("round(1.5, 1)")?.typ(), pg_type::NUMERIC_OID);
+        assert_eq!(transform("mod(1, 3000000000)")?.typ(), pg_type::INT8_OID);
+        assert_eq!(transform("power(2, 3)")?.typ(), pg_type::FLOAT8_OID);
+        assert_eq!(transform("concat(1, true, NULL)")?.typ(), pg_type::TEXT_OID);
+        assert_eq!(transform("'a' || 'b'")?.typ(), pg_type::TEXT_OID);
+        assert_eq!(transform("1 || 'a'")?.typ(), pg_type::TEXT_OID);
+        assert!(transform("1 || 2").is_err());
+        assert_eq!(
+            transform("SUBSTRING('abc' FROM 2)")?.typ(),
+            pg_type::TEXT_OID
+        );
+        assert_eq!(transform("POSITION('b' IN 'abc')")?.typ(), pg_type::INT_OID);
+        assert!(transform("length(1)").is_err());
+        assert!(transform("abs(true)").is_err());
+
+        assert_eq!(
+            transform("TRIM(LEADING 'x' FROM 'xxabcxx')")?.eval(&[])?,
+            Some(bincode::serialize("abcxx")?)
+        );
+        assert_eq!(
+            transform("SUBSTRING('tinydb' FOR 4)")?.eval(&[])?,
+            Some(bincode::serialize("tiny")?)
+        );
+        assert_eq!(transform("upper(NULL)")?.eval(&[])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pattern_match() -> Result<()> {
+        assert_eq!(transform("'abc' LIKE 'a%'")?.typ(), pg_type::BOOL_OID);
+        assert_eq!(transform("'abc' !~* 'B'")?.typ(), pg_type::BOOL_OID);
+        assert_eq!(
+            transform("regexp_match('abc', 'b')")?.typ(),
+            pg_type::TEXT_ARRAY_OID
+        );
+        assert!(transform("1 LIKE '1'").is_err());
+        assert!(transform("'a' ~ 1").is_err());
+
+        let eval = |sql: &str| -> Result<Option<Datum>> { transform(sql)?.eval(&[]) };
+        let bool_datum = |value: bool| Some(bincode::serialize(&value).unwrap());
+        assert_eq!(eval("'TinyDB' ILIKE 'tiny%'")?, bool_datum(true));
+        assert_eq!(eval("'TinyDB' NOT LIKE 'tiny%'")?, bool_datum(true));
+        assert_eq!(
+            eval("'10%' LIKE like_escape('10#%', '#')")?,
+            bool_datum(true)
+        );
+        assert_eq!(
+            eval("'abc' ~ similar_to_escape('a(b|x)c')")?,
+            bool_datum(true)
+        );
+        assert_eq!(eval("'10%' LIKE '10#%' ESCAPE '#'")?, bool_datum(true));
+        assert_eq!(eval("'10x' NOT ILIKE '10#%' ESCAPE '#'")?, bool_datum(true));
+        assert_eq!(eval("'abc' SIMILAR TO 'a(b|x)c'")?, bool_datum(true));
+        assert_eq!(eval("'abc' NOT SIMILAR TO 'a_'")?, bool_datum(true));
+        assert_eq!(eval("'a%' SIMILAR TO 'a!%' ESCAPE '!'")?, bool_datum(true));
+        assert!(transform("'a' ~ 'a' ESCAPE '!'").is_err());
+        assert!(transform("1 SIMILAR TO 'a'").is_err());
+        assert_eq!(eval("NULL LIKE 'a'")?, None);
+        assert_eq!(
+            eval("regexp_replace('a1b2', '[0-9]', '#', 'g')")?,
+            Some(bincode::serialize("a#b#")?)
+        );
+        assert!(eval("regexp_match('a', 'a', 'g')").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_figure_colname() -> Result<()> {
+        let colname = |sql: &str| -> Result<String> {
+            Ok(figure_colname(&parser::parse_expr(
+                &PostgreSqlDialect {},
+                sql,
+            )?))
+        };
+        assert_eq!(colname("a")?, "a");
+        assert_eq!(colname("t.a")?, "a");
+        assert_eq!(colname("(a)")?, "a");
+        assert_eq!(colname("a[1]")?, "a");
+        assert_eq!(colname("((p).a)")?, "a");
+        assert_eq!(colname("(1, 2)")?, "row");
+        assert_eq!(colname("ROW(1)")?, "row");
+        assert_eq!(colname("UPPER(a)")?, "upper");
+        assert_eq!(colname("a + 1")?, "?column?");
+        assert_eq!(colname("'a'")?, "?column?");
+        assert_eq!(colname("a::text")?, "a");
+        assert_eq!(colname("1::integer")?, "int4");
+        assert_eq!(colname("'{1}'::int[]")?, "int4");
+        assert_eq!(colname("DATE '2024-01-01'")?, "date");
+        assert_eq!(colname("true")?, "bool");
+        assert_eq!(colname("CASE WHEN true THEN 1 ELSE a END")?, "a");
+        assert_eq!(colname("CASE WHEN true THEN 1 ELSE 2::int END")?, "case");
+        assert_eq!(colname("TRIM(TRAILING 'x' FROM a)")?, "rtrim");
+        assert_eq!(colname("ARRAY[1]")?, "array");
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditional_expressions() -> Result<()> {
+        assert_eq!(
+            transform("CASE WHEN true THEN 1 ELSE 2.5 END")?.typ(),
+            pg_type::NUMERIC_OID
+        );
+        assert_eq!(
+            transform("CASE 1 WHEN 2 THEN 'a' END")?.typ(),
+            pg_type::TEXT_OID
+        );
+        assert_eq!(transform("COALESCE(NULL, 1, 2)")?.typ(), pg_type::INT_OID);
+        assert_eq!(transform("NULLIF(1, 2.5)")?.typ(), pg_type::NUMERIC_OID);
+        assert_eq!(transform("GREATEST(1, 2.5)")?.typ(), pg_type::NUMERIC_OID);
+        assert!(transform("CASE WHEN 1 THEN 1 END").is_err());
+        assert!(transform("COALESCE(1, true)").is_err());
+        assert!(transform("1 AND true").is_err());
+
+        assert_eq!(
+            transform("CASE 2 WHEN 1 THEN 'a' WHEN 2 THEN 'b' END")?.eval(&[])?,
+            Some(bincode::serialize("b")?)
+        );
+        assert_eq!(
+            transform("COALESCE(NULL, 2, 3)")?.eval(&[])?,
+            Some(bincode::serialize(&2)?)
+        );
+        assert_eq!(transform("NULLIF(2, 2)")?.eval(&[])?, None);
+        assert_eq!(
+            transform("LEAST(3, NULL, 1)")?.eval(&[])?,
+            Some(bincode::serialize(&1)?)
+        );
+        assert_eq!(
+            transform("NULL AND false")?.eval(&[])?,
+            Some(bincode::serialize(&false)?)
+        );
+        assert_eq!(transform("NULL OR false")?.eval(&[])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_column_references() -> Result<()> {
+        let tuple_desc = TupleDesc {
+            attrs: vec![PgAttribute {
+                attrelid: INVALID_OID,
+                attname: String::from("a"),
+                attnum: 1,
+                attlen: 4,
+                atttypid: pg_type::INT_OID,
+                atttypmod: -1,
+                attidentity: String::new(),
+            }],
+        };
+        let scope = Scope {
+            name: "t",
+            tuple_desc: &tuple_desc,
+        };
+        let parse = |sql: &str| -> Result<Expr> {
+            transform_expr(
+                &parser::parse_expr(&PostgreSqlDialect {}, sql)?,
+                Some(&scope),
+            )
+        };
+
+        let expr = parse("abs(t.a) IS NOT NULL")?;
+        assert_eq!(expr.typ(), pg_type::BOOL_OID);
+        assert_eq!(
+            parse("a * 2")?.eval(&[Some(bincode::serialize(&-3)?)])?,
+            Some(bincode::serialize(&-6)?)
+        );
+        assert!(parse("b").is_err());
+        assert!(parse("u.a").is_err());
+        assert!(transform("a").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,512 @@
+//! Builtin functions.
+//!
+//! Every function that can be called on SQL statements is registered on [BUILTIN_FUNCTIONS],
+//! which is also used to fill the pg_proc catalog relation when a new database is created.
+//! Overloaded functions have one entry for each list of argument types, and a call is resolved
+//! to one of them with [select_function].
+use anyhow::{bail, Result};
+
+use crate::{
+    catalog::pg_type::{
+        ANY_OID, BOOL_OID, BYTEA_OID, FLOAT4_OID, FLOAT8_OID, INT2_OID, INT8_OID, INT_OID,
+        JSONB_OID, NUMERIC_OID, TEXT_ARRAY_OID, TEXT_OID, UNKNOWN_OID,
+    },
+    types::{
+        self,
+        cast::{self, CoercionContext},
+        TypeCategory,
+    },
+    Oid,
+};
+
+/// Implementations of the builtin functions, named after the PostgreSQL functions that
+/// implement them.
+///
+/// Operators that are not arithmetic operators, like the jsonb operators, are transformed into
+/// calls of the function that implements the operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Func {
+    Lower,
+    Upper,
+    TextLen,
+    ByteaLen,
+    TextSubstr,
+    Btrim,
+    Ltrim,
+    Rtrim,
+    Concat,
+    TextCat,
+    Replace,
+    Strpos,
+    Like,
+    NotLike,
+    ILike,
+    NotILike,
+    LikeEscape,
+    SimilarToEscape,
+    RegexMatch,
+    RegexNotMatch,
+    RegexIMatch,
+    RegexNotIMatch,
+    RegexpReplace,
+    RegexpMatch,
+    Abs,
+    Round,
+    Floor,
+    Ceil,
+    Mod,
+    Power,
+    JsonbObjectField,
+    JsonbObjectFieldText,
+    JsonbArrayElement,
+    JsonbArrayElementText,
+    JsonbContains,
+    JsonbExists,
+    JsonbBuildObject,
+    Nextval,
+    Currval,
+    Setval,
+    Lastval,
+}
+
+/// A builtin function with a fixed list of argument types.
+#[derive(Debug)]
+pub struct BuiltinFunction {
+    /// Name used to call the function.
+    pub name: &'static str,
+
+    pub func: Func,
+
+    /// Types of the declared arguments.
+    pub args: &'static [Oid],
+
+    /// True if the last argument can be repeated any number of times.
+    pub variadic: bool,
+
+    /// Type of the result.
+    pub rettype: Oid,
+
+    /// True if the function returns NULL when any argument is NULL, without being called.
+    pub strict: bool,
+}
+
+impl BuiltinFunction {
+    /// Declare a strict function that is not variadic.
+    const fn new(name: &'static str, func: Func, args: &'static [Oid], rettype: Oid) -> Self {
+        Self {
+            name,
+            func,
+            args,
+            variadic: false,
+            rettype,
+            strict: true,
+        }
+    }
+
+    /// Declare a function whose last argument accepts any number of values of any type, which
+    /// is called even if some arguments are NULL.
+    const fn variadic_any(name: &'static str, func: Func, rettype: Oid) -> Self {
+        Self {
+            name,
+            func,
+            args: &[ANY_OID],
+            variadic: true,
+            rettype,
+            strict: false,
+        }
+    }
+
+    /// Return true if the function accepts the given number of arguments.
+    fn accepts_nargs(&self, nargs: usize) -> bool {
+        nargs == self.args.len() || (self.variadic && nargs >= self.args.len())
+    }
+
+    /// Return the declared type of the argument at the given position, the function must
+    /// accept an argument on that position.
+    pub fn arg_type(&self, position: usize) -> Oid {
+        self.args[position.min(self.args.len() - 1)]
+    }
+}
+
+/// All builtin functions.
+pub static BUILTIN_FUNCTIONS: &[BuiltinFunction] = &[
+    BuiltinFunction::new("lower", Func::Lower, &[TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("upper", Func::Upper, &[TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("length", Func::TextLen, &[TEXT_OID], INT_OID),
+    BuiltinFunction::new("length", Func::ByteaLen, &[BYTEA_OID], INT_OID),
+    BuiltinFunction::new("char_length", Func::TextLen, &[TEXT_OID], INT_OID),
+    BuiltinFunction::new("character_length", Func::TextLen, &[TEXT_OID], INT_OID),
+    BuiltinFunction::new(
+        "substring",
+        Func::TextSubstr,
+        &[TEXT_OID, INT_OID, INT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "substring",
+        Func::TextSubstr,
+        &[TEXT_OID, INT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "substr",
+        Func::TextSubstr,
+        &[TEXT_OID, INT_OID, INT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new("substr", Func::TextSubstr, &[TEXT_OID, INT_OID], TEXT_OID),
+    BuiltinFunction::new("btrim", Func::Btrim, &[TEXT_OID, TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("btrim", Func::Btrim, &[TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("ltrim", Func::Ltrim, &[TEXT_OID, TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("ltrim", Func::Ltrim, &[TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("rtrim", Func::Rtrim, &[TEXT_OID, TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("rtrim", Func::Rtrim, &[TEXT_OID], TEXT_OID),
+    BuiltinFunction::variadic_any("concat", Func::Concat, TEXT_OID),
+    BuiltinFunction::new("textcat", Func::TextCat, &[TEXT_OID, TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("anytextcat", Func::TextCat, &[ANY_OID, TEXT_OID], TEXT_OID),
+    BuiltinFunction::new("textanycat", Func::TextCat, &[TEXT_OID, ANY_OID], TEXT_OID),
+    BuiltinFunction::new(
+        "replace",
+        Func::Replace,
+        &[TEXT_OID, TEXT_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new("strpos", Func::Strpos, &[TEXT_OID, TEXT_OID], INT_OID),
+    BuiltinFunction::new("position", Func::Strpos, &[TEXT_OID, TEXT_OID], INT_OID),
+    BuiltinFunction::new("like", Func::Like, &[TEXT_OID, TEXT_OID], BOOL_OID),
+    BuiltinFunction::new("notlike", Func::NotLike, &[TEXT_OID, TEXT_OID], BOOL_OID),
+    BuiltinFunction::new("ilike", Func::ILike, &[TEXT_OID, TEXT_OID], BOOL_OID),
+    BuiltinFunction::new("notilike", Func::NotILike, &[TEXT_OID, TEXT_OID], BOOL_OID),
+    BuiltinFunction::new(
+        "like_escape",
+        Func::LikeEscape,
+        &[TEXT_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "similar_to_escape",
+        Func::SimilarToEscape,
+        &[TEXT_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "similar_to_escape",
+        Func::SimilarToEscape,
+        &[TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "textregexeq",
+        Func::RegexMatch,
+        &[TEXT_OID, TEXT_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new(
+        "textregexne",
+        Func::RegexNotMatch,
+        &[TEXT_OID, TEXT_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new(
+        "texticregexeq",
+        Func::RegexIMatch,
+        &[TEXT_OID, TEXT_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new(
+        "texticregexne",
+        Func::RegexNotIMatch,
+        &[TEXT_OID, TEXT_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new(
+        "regexp_replace",
+        Func::RegexpReplace,
+        &[TEXT_OID, TEXT_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "regexp_replace",
+        Func::RegexpReplace,
+        &[TEXT_OID, TEXT_OID, TEXT_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "regexp_match",
+        Func::RegexpMatch,
+        &[TEXT_OID, TEXT_OID],
+        TEXT_ARRAY_OID,
+    ),
+    BuiltinFunction::new(
+        "regexp_match",
+        Func::RegexpMatch,
+        &[TEXT_OID, TEXT_OID, TEXT_OID],
+        TEXT_ARRAY_OID,
+    ),
+    BuiltinFunction::new("abs", Func::Abs, &[INT2_OID], INT2_OID),
+    BuiltinFunction::new("abs", Func::Abs, &[INT_OID], INT_OID),
+    BuiltinFunction::new("abs", Func::Abs, &[INT8_OID], INT8_OID),
+    BuiltinFunction::new("abs", Func::Abs, &[FLOAT4_OID], FLOAT4_OID),
+    BuiltinFunction::new("abs", Func::Abs, &[FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("abs", Func::Abs, &[NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("round", Func::Round, &[FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("round", Func::Round, &[NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("round", Func::Round, &[NUMERIC_OID, INT_OID], NUMERIC_OID),
+    BuiltinFunction::new("floor", Func::Floor, &[FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("floor", Func::Floor, &[NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("ceil", Func::Ceil, &[FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("ceil", Func::Ceil, &[NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("ceiling", Func::Ceil, &[FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("ceiling", Func::Ceil, &[NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("mod", Func::Mod, &[INT2_OID, INT2_OID], INT2_OID),
+    BuiltinFunction::new("mod", Func::Mod, &[INT_OID, INT_OID], INT_OID),
+    BuiltinFunction::new("mod", Func::Mod, &[INT8_OID, INT8_OID], INT8_OID),
+    BuiltinFunction::new("mod", Func::Mod, &[NUMERIC_OID, NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new("power", Func::Power, &[FLOAT8_OID, FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new(
+        "power",
+        Func::Power,
+        &[NUMERIC_OID, NUMERIC_OID],
+        NUMERIC_OID,
+    ),
+    BuiltinFunction::new("pow", Func::Power, &[FLOAT8_OID, FLOAT8_OID], FLOAT8_OID),
+    BuiltinFunction::new("pow", Func::Power, &[NUMERIC_OID, NUMERIC_OID], NUMERIC_OID),
+    BuiltinFunction::new(
+        "jsonb_object_field",
+        Func::JsonbObjectField,
+        &[JSONB_OID, TEXT_OID],
+        JSONB_OID,
+    ),
+    BuiltinFunction::new(
+        "jsonb_object_field_text",
+        Func::JsonbObjectFieldText,
+        &[JSONB_OID, TEXT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "jsonb_array_element",
+        Func::JsonbArrayElement,
+        &[JSONB_OID, INT_OID],
+        JSONB_OID,
+    ),
+    BuiltinFunction::new(
+        "jsonb_array_element_text",
+        Func::JsonbArrayElementText,
+        &[JSONB_OID, INT_OID],
+        TEXT_OID,
+    ),
+    BuiltinFunction::new(
+        "jsonb_contains",
+        Func::JsonbContains,
+        &[JSONB_OID, JSONB_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new(
+        "jsonb_exists",
+        Func::JsonbExists,
+        &[JSONB_OID, TEXT_OID],
+        BOOL_OID,
+    ),
+    BuiltinFunction::new("jsonb_build_object", Func::JsonbBuildObject, &[], JSONB_OID),
+    BuiltinFunction::variadic_any("jsonb_build_object", Func::JsonbBuildObject, JSONB_OID),
+    BuiltinFunction::new("nextval", Func::Nextval, &[TEXT_OID], INT8_OID),
+    BuiltinFunction::new("currval", Func::Currval, &[TEXT_OID], INT8_OID),
+    BuiltinFunction::new("setval", Func::Setval, &[TEXT_OID, INT8_OID], INT8_OID),
+    BuiltinFunction::new(
+        "setval",
+        Func::Setval,
+        &[TEXT_OID, INT8_OID, BOOL_OID],
+        INT8_OID,
+    ),
+    BuiltinFunction::new("lastval", Func::Lastval, &[], INT8_OID),
+];
+
+/// Select the builtin function with the given name that best matches the types of the
+/// arguments of a call, or None if no function accepts them.
+///
+/// The candidates are the functions whose arguments can be implicitly converted from the given
+/// types. Like PostgreSQL, when there is more than one candidate the ones with the most exact
+/// type matches are kept, then the ones that convert the most arguments to the preferred type of
+/// their category, e.g round(integer) is resolved as round(double precision). Arguments of
+/// unknown type are resolved as the string category if some candidate accepts it, or as the
+/// preferred type of the category shared by all candidates, e.g length('a') is resolved as
+/// length(text) and abs('-1') as abs(double precision).
+pub fn select_function(name: &str, arg_types: &[Oid]) -> Result<Option<&'static BuiltinFunction>> {
+    let mut candidates = Vec::new();
+    for function in BUILTIN_FUNCTIONS {
+        if function.name != name || !function.accepts_nargs(arg_types.len()) {
+            continue;
+        }
+
+        let mut viable = true;
+        for (i, arg_type) in arg_types.iter().enumerate() {
+            let target = function.arg_type(i);
+            viable &= target == ANY_OID
+                || *arg_type == UNKNOWN_OID
+                || cast::can_coerce(*arg_type, target, CoercionContext::Implicit);
+        }
+        if viable {
+            candidates.push(function);
+        }
+    }
+
+    if candidates.len() > 1 {
+        candidates = keep_best(candidates, |function| {
+            Ok(arg_types
+                .iter()
+                .enumerate()
+                .filter(|(i, typ)| function.arg_type(*i) == **typ)
+                .count())
+        })?;
+    }
+
+    if candidates.len() > 1 {
+        candidates = keep_best(candidates, |function| {
+            let mut count = 0;
+            for (i, arg_type) in arg_types.iter().enumerate() {
+                let target = function.arg_type(i);
+                if *arg_type == UNKNOWN_OID || *arg_type == target {
+                    continue;
+                }
+                let target = types::lookup_type(target)?;
+                if target.typispreferred()
+                    && target.typcategory() == types::lookup_type(*arg_type)?.typcategory()
+                {
+                    count += 1;
+                }
+            }
+            Ok(count)
+        })?;
+    }
+
+    for (i, _) in arg_types
+        .iter()
+        .enumerate()
+        .filter(|(_, typ)| **typ == UNKNOWN_OID)
+    {
+        if candidates.len() <= 1 {
+            break;
+        }
+
+        let targets = candidates
+            .iter()
+            .map(|function| types::lookup_type(function.arg_type(i)))
+            .collect::<Result<Vec<_>>>()?;
+        let category = if targets
+            .iter()
+            .any(|typ| typ.typcategory() == TypeCategory::String)
+        {
+            TypeCategory::String
+        } else if targets
+            .iter()
+            .all(|typ| typ.typcategory() == targets[0].typcategory())
+        {
+            targets[0].typcategory()
+        } else {
+            continue;
+        };
+        let has_preferred = targets
+            .iter()
+            .any(|typ| typ.typcategory() == category && typ.typispreferred());
+
+        candidates = candidates
+            .into_iter()
+            .zip(targets)
+            .filter(|(_, typ)| {
+                typ.typcategory() == category && (!has_preferred || typ.typispreferred())
+            })
+            .map(|(function, _)| function)
+            .collect();
+    }
+
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [function] => Ok(Some(function)),
+        _ => {
+            let arg_types = arg_types
+                .iter()
+                .map(|typ| Ok(types::lookup_type(*typ)?.format_type()))
+                .collect::<Result<Vec<_>>>()?;
+            bail!(types::Error::AmbiguousFunction(format!(
+                "{}({})",
+                name,
+                arg_types.join(", ")
+            )))
+        }
+    }
+}
+
+/// Keep the candidates with the highest score.
+fn keep_best<F>(
+    candidates: Vec<&'static BuiltinFunction>,
+    score: F,
+) -> Result<Vec<&'static BuiltinFunction>>
+where
+    F: Fn(&BuiltinFunction) -> Result<usize>,
+{
+    let scores = candidates
+        .iter()
+        .map(|function| score(function))
+        .collect::<Result<Vec<_>>>()?;
+    let best = scores.iter().copied().max().unwrap_or_default();
+    Ok(candidates
+        .into_iter()
+        .zip(scores)
+        .filter(|(_, score)| *score == best)
+        .map(|(function, _)| function)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn select(name: &str, arg_types: &[Oid]) -> Result<Option<(Func, Vec<Oid>)>> {
+        Ok(select_function(name, arg_types)?.map(|function| {
+            let args = (0..arg_types.len()).map(|i| function.arg_type(i));
+            (function.func, args.collect())
+        }))
+    }
+
+    #[test]
+    fn test_select_function() -> Result<()> {
+        assert_eq!(
+            select("abs", &[INT2_OID])?,
+            Some((Func::Abs, vec![INT2_OID]))
+        );
+        assert_eq!(
+            select("round", &[INT_OID])?,
+            Some((Func::Round, vec![FLOAT8_OID]))
+        );
+        assert_eq!(
+            select("mod", &[INT_OID, NUMERIC_OID])?,
+            Some((Func::Mod, vec![NUMERIC_OID, NUMERIC_OID]))
+        );
+        assert_eq!(
+            select("length", &[UNKNOWN_OID])?,
+            Some((Func::TextLen, vec![TEXT_OID]))
+        );
+        assert_eq!(
+            select("abs", &[UNKNOWN_OID])?,
+            Some((Func::Abs, vec![FLOAT8_OID]))
+        );
+        assert_eq!(
+            select("concat", &[INT_OID, UNKNOWN_OID, BOOL_OID])?,
+            Some((Func::Concat, vec![ANY_OID, ANY_OID, ANY_OID]))
+        );
+        assert_eq!(select("concat", &[])?, None);
+        assert_eq!(select("lower", &[INT_OID])?, None);
+        assert_eq!(select("lower", &[TEXT_OID, TEXT_OID])?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_builtin_function_types_exist() -> Result<()> {
+        for function in BUILTIN_FUNCTIONS {
+            for typ in function.args.iter().chain([&function.rettype]) {
+                types::lookup_type(*typ)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -5,13 +5,22 @@ use std::sync::Arc;
 
 use crate::{
     access::{self, heap::HeapScanner, heaptuple::TupleDesc},
-    catalog::{self, pg_attribute::PgAttribute, pg_class::PgClass},
+    catalog::{
+        self,
+        pg_attribute::PgAttribute,
+        pg_class::{PgClass, RELKIND_COMPOSITE_TYPE, RELKIND_SEQUENCE},
+        pg_type,
+    },
     relation::Relation,
-    sql::SQLError,
+    sql::{sequence, SQLError},
     storage::BufferPool,
-    Oid, INVALID_OID,
+    types::{self, cast::CoercionContext, TypeCategory},
+    Datum, Oid, INVALID_OID,
 };
 
+pub mod expr;
+pub mod function;
+
 /// Information needed to project a query output.
 pub struct ProjectionState {
     /// Projection output attributes of query.
@@ -21,6 +30,9 @@ pub struct ProjectionState {
     /// tuple_desc_ field if the attributes order on page is required.
     pub projection: Vec<PgAttribute>,
 
+    /// Expressions that compute each output attribute from the attributes of a child tuple.
+    pub targets: Vec<expr::Expr>,
+
     /// Tuple descriptor from a relation heap tuple. The tuple descriptor
     /// attributes is in the same order that is stored on page tuple.
     pub tuple_desc: Arc<TupleDesc>,
@@ -28,6 +40,74 @@ pub struct ProjectionState {
     pub child: Plan,
 }
 
+/// Aggregate functions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggFunc {
+    /// Collect all values into a jsonb array.
+    JsonbAgg,
+
+    /// Collect all values into an array.
+    ArrayAgg,
+}
+
+impl AggFunc {
+    /// Return the aggregate function with the given name, if exists.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "jsonb_agg" => Some(AggFunc::JsonbAgg),
+            "array_agg" => Some(AggFunc::ArrayAgg),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            AggFunc::JsonbAgg => "jsonb_agg",
+            AggFunc::ArrayAgg => "array_agg",
+        }
+    }
+
+    /// Return the type of the aggregate result for an argument of the given type. The
+    /// array_agg of arrays returns an array with one more dimension, which has the same type.
+    fn result_type(&self, arg: Oid) -> Result<Oid> {
+        match self {
+            AggFunc::JsonbAgg => Ok(pg_type::JSONB_OID),
+            AggFunc::ArrayAgg if types::lookup_type(arg)?.typcategory() == TypeCategory::Array => {
+                Ok(arg)
+            }
+            AggFunc::ArrayAgg => match types::array_type_of(arg) {
+                Some(array) => Ok(array.oid()),
+                None => bail!(types::Error::UndefinedArrayType(
+                    types::lookup_type(arg)?.format_type()
+                )),
+            },
+        }
+    }
+}
+
+/// An aggregate function computed over an expression of all tuples of a relation.
+pub struct Aggregate {
+    pub func: AggFunc,
+
+    /// Expression passed as argument to the aggregate function, computed for each tuple.
+    pub arg: expr::Expr,
+}
+
+/// Information needed to compute the aggregates of a query without GROUP BY, which return a
+/// single row.
+pub struct AggregateState {
+    /// Aggregates computed, one for each output attribute.
+    pub aggregates: Vec<Aggregate>,
+
+    /// Output attributes of query.
+    pub output: Vec<PgAttribute>,
+
+    /// Tuple descriptor from a relation heap tuple.
+    pub tuple_desc: Arc<TupleDesc>,
+
+    pub child: Plan,
+}
+
 /// Sequential scan information needed by executor.
 pub struct SeqScanState {
     /// Tuple description of relation being used by planner executor.
@@ -38,6 +118,64 @@ pub struct SeqScanState {
 
     /// Iterator used to fetch tuples.
     pub heap_scanner: HeapScanner,
+
+    /// Condition of WHERE clause, only tuples for which it is true are returned.
+    pub qual: Option<expr::Expr>,
+}
+
+/// Set returning functions, which can only be called on FROM.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetFunc {
+    /// Return each element of an array.
+    Unnest,
+
+    /// Return the substrings captured by each match of a regular expression.
+    RegexpMatches,
+}
+
+impl SetFunc {
+    /// Return the set returning function with the given name, if exists.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unnest" => Some(SetFunc::Unnest),
+            "regexp_matches" => Some(SetFunc::RegexpMatches),
+            _ => None,
+        }
+    }
+}
+
+/// Function scan information needed by executor, which returns a tuple for each row returned
+/// by a set returning function.
+pub struct FunctionScanState {
+    /// Tuple description of the function output, which has a single attribute.
+    pub tuple_desc: Arc<TupleDesc>,
+
+    pub func: SetFunc,
+
+    /// Arguments passed to the function.
+    pub args: Vec<expr::Expr>,
+
+    /// Rows that were not returned yet, None until the function is called.
+    pub rows: Option<std::vec::IntoIter<Option<Datum>>>,
+
+    /// Condition of WHERE clause, only tuples for which it is true are returned.
+    pub qual: Option<expr::Expr>,
+}
+
+/// Values scan information needed by executor, which returns a tuple for each list of
+/// expressions of a VALUES clause. A SELECT without FROM scans a single empty list.
+pub struct ValuesScanState {
+    /// Tuple description of the values, with an attribute for each column.
+    pub tuple_desc: Arc<TupleDesc>,
+
+    /// Expressions of each row, converted to the type of their column.
+    pub rows: Vec<Vec<expr::Expr>>,
+
+    /// Index of the next row to be returned.
+    pub next: usize,
+
+    /// Condition of WHERE clause, only tuples for which it is true are returned.
+    pub qual: Option<expr::Expr>,
 }
 
 /// Types of a plan node on plan tree.
@@ -45,8 +183,17 @@ pub enum PlanNodeType {
     /// Projection plan node.
     Projection { state: Box<ProjectionState> },
 
+    /// Aggregate plan node.
+    Aggregate { state: Box<AggregateState> },
+
     /// Sequential scan plan node.
     SeqScan { state: SeqScanState },
+
+    /// Function scan plan node.
+    FunctionScan { state: FunctionScanState },
+
+    /// Values scan plan node.
+    ValuesScan { state: ValuesScanState },
 }
 
 /// A tree of nodes to be executed.
@@ -57,13 +204,23 @@ pub struct Plan {
 
 impl Plan {
     /// Create a new plan for the given parsed query.
-    pub fn create(buffer_pool: &BufferPool, db_oid: &Oid, query: &Box<ast::Query>) -> Result<Plan> {
+    pub fn create(buffer_pool: &BufferPool, db_oid: &Oid, query: &ast::Query) -> Result<Plan> {
         let plan = match &query.body {
             SetExpr::Select(select) => create_plan_from_select(buffer_pool, db_oid, &select)?,
+            SetExpr::Values(values) => create_plan_from_values(values)?,
             _ => bail!(SQLError::Unsupported(query.body.to_string())),
         };
         Ok(plan)
     }
+
+    /// Return the attributes of the tuples returned by the plan.
+    pub fn output(&self) -> Result<&[PgAttribute]> {
+        match &self.node_type {
+            PlanNodeType::Projection { state } => Ok(&state.projection),
+            PlanNodeType::Aggregate { state } => Ok(&state.output),
+            _ => bail!("Unexpected root plan node of type {}", self.node_type),
+        }
+    }
 }
 
 fn create_plan_from_select(
@@ -71,71 +228,504 @@ fn create_plan_from_select(
     db_oid: &Oid,
     select: &ast::Select,
 ) -> Result<Plan> {
-    if select.from.len() > 1 {
-        bail!(SQLError::Unsupported(
+    let from = match select.from.as_slice() {
+        [] => None,
+        [from] => Some(from),
+        _ => bail!(SQLError::Unsupported(
             "Can not use multiple expressions on FROM".to_string(),
+        )),
+    };
+
+    let (rel_name, tuple_desc, mut child) = match from.map(|from| &from.relation) {
+        None => create_values_scan(vec![Vec::new()], String::new(), &[])?,
+        Some(TableFactor::Table {
+            name, alias, args, ..
+        }) if !args.is_empty() => create_function_scan(name, alias.as_ref(), args)?,
+        Some(TableFactor::Table { name, alias, .. }) => {
+            let rel_name = name.0[0].to_string();
+            let pg_class = catalog::find_pg_class_by_name(buffer_pool, db_oid, &rel_name)?
+                .filter(|pg_class| pg_class.relkind != RELKIND_COMPOSITE_TYPE)
+                .ok_or_else(|| catalog::Error::RelationNotFound(rel_name.clone()))?;
+
+            // A sequence is scanned from its current position, which is not always the tuple
+            // stored on disk.
+            let (mut tuple_desc, sequence_row) = if pg_class.relkind == RELKIND_SEQUENCE {
+                let (tuple_desc, row) = sequence::scan_sequence(buffer_pool, db_oid, &pg_class)?;
+                (tuple_desc, Some(row))
+            } else {
+                let tuple_desc = catalog::tuple_desc_from_relation(buffer_pool, db_oid, &rel_name)?;
+                (tuple_desc, None)
+            };
+
+            // The columns are only referenced by name, so the aliases of the columns rename the
+            // attributes of the tuple descriptor.
+            if let Some(alias) = alias {
+                rename_attributes(&mut tuple_desc, &alias.name.value, &alias.columns)?;
+            }
+            let tuple_desc = Arc::new(tuple_desc);
+
+            let child = match sequence_row {
+                Some(row) => create_sequence_scan(row, tuple_desc.clone()),
+                None => create_seq_scan(
+                    buffer_pool,
+                    db_oid,
+                    &rel_name,
+                    &pg_class,
+                    tuple_desc.clone(),
+                )?,
+            };
+
+            // Like PostgreSQL, a relation with an alias can only be referenced by the alias.
+            match alias {
+                Some(alias) => (alias.name.value.clone(), tuple_desc, child),
+                None => (rel_name, tuple_desc, child),
+            }
+        }
+        Some(TableFactor::Derived {
+            subquery, alias, ..
+        }) => match &subquery.body {
+            SetExpr::Values(values) => {
+                let rel_name = match alias {
+                    Some(alias) => alias.name.value.clone(),
+                    None => String::from("*VALUES*"),
+                };
+                let aliases = alias.as_ref().map_or(&[][..], |alias| &alias.columns);
+                create_values_scan(transform_values(values)?, rel_name, aliases)?
+            }
+            _ => bail!(SQLError::Unsupported(subquery.to_string())),
+        },
+        Some(relation) => bail!(SQLError::Unsupported(relation.to_string())),
+    };
+
+    let scope = expr::Scope {
+        name: &rel_name,
+        tuple_desc: &tuple_desc,
+    };
+
+    if let Some(selection) = &select.selection {
+        let qual =
+            expr::coerce_to_boolean(expr::transform_expr(selection, Some(&scope))?, "WHERE")?;
+        match &mut child.node_type {
+            PlanNodeType::SeqScan { state } => state.qual = Some(qual),
+            PlanNodeType::FunctionScan { state } => state.qual = Some(qual),
+            PlanNodeType::ValuesScan { state } => state.qual = Some(qual),
+            _ => bail!("Unexpected scan plan node of type {}", child.node_type),
+        }
+    }
+
+    // Grouping is not implemented yet, so a grouped query is rejected instead of computing the
+    // select list over all tuples.
+    if !select.group_by.is_empty() {
+        let exprs: Vec<String> = select.group_by.iter().map(|e| e.to_string()).collect();
+        bail!(SQLError::Unsupported(format!(
+            "GROUP BY {}",
+            exprs.join(", ")
+        )));
+    }
+    if let Some(having) = &select.having {
+        bail!(SQLError::Unsupported(format!("HAVING {}", having)));
+    }
+
+    if select.projection.iter().any(is_aggregate) {
+        return create_aggregate_plan(select, &scope, tuple_desc.clone(), child);
+    }
+
+    if select.from.is_empty() && select.projection.contains(&ast::SelectItem::Wildcard) {
+        bail!(SQLError::WildcardWithoutTables);
+    }
+    let (projection, targets) = transform_target_list(&select.projection, &scope)?;
+
+    Ok(Plan {
+        node_type: PlanNodeType::Projection {
+            state: Box::new(ProjectionState {
+                projection,
+                targets,
+                tuple_desc,
+                child,
+            }),
+        },
+    })
+}
+
+/// Transform the items of a select list, or of a RETURNING list, into the expressions that
+/// compute each output attribute from the columns of the given scope. Return the output
+/// attributes and their expressions.
+pub fn transform_target_list(
+    items: &[ast::SelectItem],
+    scope: &expr::Scope,
+) -> Result<(Vec<PgAttribute>, Vec<expr::Expr>)> {
+    let mut projection = Vec::with_capacity(items.len());
+    let mut targets = Vec::with_capacity(items.len());
+
+    for item in items {
+        let (expr, attname) = match item {
+            ast::SelectItem::UnnamedExpr(expr) => (expr, expr::figure_colname(expr)),
+            ast::SelectItem::ExprWithAlias { expr, alias } => (expr, alias.value.clone()),
+            ast::SelectItem::QualifiedWildcard(name) if name.to_string() != scope.name => {
+                bail!(SQLError::MissingFromEntry(name.to_string()))
+            }
+            ast::SelectItem::Wildcard | ast::SelectItem::QualifiedWildcard(_) => {
+                for attr in &scope.tuple_desc.attrs {
+                    projection.push(attr.clone());
+                    targets.push(expr::Expr::Column {
+                        typ: attr.atttypid,
+                        typmod: attr.atttypmod,
+                        attnum: attr.attnum,
+                    });
+                }
+                continue;
+            }
+        };
+
+        let target = resolve_unknown_type(expr::transform_expr(expr, Some(scope))?, item)?;
+
+        // Columns keep the relation and attribute number, which are sent to the client.
+        let attr = match target {
+            expr::Expr::Column { attnum, .. } => PgAttribute {
+                attname,
+                ..scope.tuple_desc.attrs[attnum - 1].clone()
+            },
+            _ => PgAttribute {
+                attrelid: INVALID_OID,
+                attname,
+                attnum: projection.len() + 1,
+                attlen: types::lookup_type(target.typ())?.typlen() as i64,
+                atttypid: target.typ(),
+                atttypmod: target.typmod(),
+                attidentity: String::new(),
+            },
+        };
+        projection.push(attr);
+        targets.push(target);
+    }
+
+    Ok((projection, targets))
+}
+
+/// Like PostgreSQL, values of unknown type, e.g SELECT 'a', are returned as text. The item is
+/// the select item of the value, used on the error message.
+fn resolve_unknown_type(target: expr::Expr, item: &ast::SelectItem) -> Result<expr::Expr> {
+    if target.typ() != pg_type::UNKNOWN_OID {
+        return Ok(target);
+    }
+    let text_type = types::lookup_type(pg_type::TEXT_OID)?;
+    Ok(
+        expr::coerce_to_target_type(target, text_type, -1, CoercionContext::Implicit)?
+            .ok_or_else(|| SQLError::Unsupported(item.to_string()))?,
+    )
+}
+
+/// Create a plan for a VALUES query, which returns all columns of the values.
+fn create_plan_from_values(values: &ast::Values) -> Result<Plan> {
+    let (_, tuple_desc, child) =
+        create_values_scan(transform_values(values)?, String::from("*VALUES*"), &[])?;
+    let targets = tuple_desc
+        .attrs
+        .iter()
+        .map(|attr| expr::Expr::Column {
+            typ: attr.atttypid,
+            typmod: attr.atttypmod,
+            attnum: attr.attnum,
+        })
+        .collect();
+
+    Ok(Plan {
+        node_type: PlanNodeType::Projection {
+            state: Box::new(ProjectionState {
+                projection: tuple_desc.attrs.clone(),
+                targets,
+                tuple_desc,
+                child,
+            }),
+        },
+    })
+}
+
+/// Transform the expressions of each list of a VALUES clause. Like PostgreSQL, the expressions
+/// of each column are converted to their common type, and columns of unknown type are text.
+fn transform_values(values: &ast::Values) -> Result<Vec<Vec<expr::Expr>>> {
+    let ncolumns = values.0.first().map_or(0, Vec::len);
+    if values.0.iter().any(|row| row.len() != ncolumns) {
+        bail!(SQLError::ValuesListsLength);
+    }
+
+    let mut columns = vec![Vec::with_capacity(values.0.len()); ncolumns];
+    for row in &values.0 {
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(expr::transform_expr(value, None)?);
+        }
+    }
+
+    let mut rows = vec![Vec::with_capacity(ncolumns); values.0.len()];
+    for column in columns {
+        let typ = expr::select_common_type(&column, "VALUES")?;
+        for (row, value) in rows
+            .iter_mut()
+            .zip(expr::coerce_to_common_type(column, typ, "VALUES")?)
+        {
+            row.push(value);
+        }
+    }
+    Ok(rows)
+}
+
+/// Rename the first attributes of a relation with the column aliases given on FROM, e.g
+/// FROM t AS alias(a, b).
+fn rename_attributes(
+    tuple_desc: &mut TupleDesc,
+    alias: &str,
+    columns: &[ast::Ident],
+) -> Result<()> {
+    if columns.len() > tuple_desc.attrs.len() {
+        bail!(SQLError::TooManyColumnAliases(
+            alias.to_string(),
+            tuple_desc.attrs.len(),
+            columns.len()
         ));
     }
+    for (attr, column) in tuple_desc.attrs.iter_mut().zip(columns) {
+        attr.attname = column.value.clone();
+    }
+    Ok(())
+}
 
-    let from = &select.from[0];
+/// Create a plan that returns the given rows of expressions, which must have the same types on
+/// every row. Return the name of the values, their tuple descriptor and the plan.
+///
+/// The columns are named after the aliases, the columns without alias are named column1,
+/// column2 and so on.
+fn create_values_scan(
+    rows: Vec<Vec<expr::Expr>>,
+    rel_name: String,
+    aliases: &[ast::Ident],
+) -> Result<(String, Arc<TupleDesc>, Plan)> {
+    let first = &rows[0];
+    if aliases.len() > first.len() {
+        bail!(SQLError::TooManyColumnAliases(
+            rel_name,
+            first.len(),
+            aliases.len()
+        ));
+    }
 
-    match &from.relation {
-        TableFactor::Table { name, .. } => {
-            let rel_name = name.0[0].to_string();
-            let pg_class = catalog::get_pg_class_relation(buffer_pool, db_oid, &rel_name)?;
-
-            let tuple_desc = Arc::new(catalog::tuple_desc_from_relation(
-                buffer_pool,
-                db_oid,
-                &rel_name,
-            )?);
-
-            let mut projection = Vec::with_capacity(select.projection.len());
-
-            for item in &select.projection {
-                match item {
-                    ast::SelectItem::UnnamedExpr(expr) => match expr {
-                        ast::Expr::Identifier(ident) => {
-                            match tuple_desc
-                                .attrs
-                                .iter()
-                                .find(|attr| attr.attname == ident.value)
-                            {
-                                Some(attr) => projection.push(attr.clone()),
-                                None => bail!(
-                                    "Attribute {} does not exists on relation {}",
-                                    ident.value,
-                                    rel_name
-                                ),
-                            }
-                        }
-                        _ => bail!(SQLError::Unsupported(from.relation.to_string())),
-                    },
-                    ast::SelectItem::Wildcard => {
-                        projection.extend_from_slice(&tuple_desc.attrs);
-                    }
-                    _ => bail!(SQLError::Unsupported(from.relation.to_string())),
+    let mut attrs = Vec::with_capacity(first.len());
+    for (i, value) in first.iter().enumerate() {
+        // The type modifier is kept only if it is the same on all rows.
+        let typmod = value.typmod();
+        let typmod = if rows.iter().all(|row| row[i].typmod() == typmod) {
+            typmod
+        } else {
+            -1
+        };
+        attrs.push(PgAttribute {
+            attrelid: INVALID_OID,
+            attname: match aliases.get(i) {
+                Some(alias) => alias.value.clone(),
+                None => format!("column{}", i + 1),
+            },
+            attnum: i + 1,
+            attlen: types::lookup_type(value.typ())?.typlen() as i64,
+            atttypid: value.typ(),
+            atttypmod: typmod,
+            attidentity: String::new(),
+        });
+    }
+
+    let tuple_desc = Arc::new(TupleDesc { attrs });
+    let plan = Plan {
+        node_type: PlanNodeType::ValuesScan {
+            state: ValuesScanState {
+                tuple_desc: tuple_desc.clone(),
+                rows,
+                next: 0,
+                qual: None,
+            },
+        },
+    };
+    Ok((rel_name, tuple_desc, plan))
+}
+
+/// Create a plan that calls a function on FROM, e.g FROM unnest(ARRAY[1, 2]) AS t(x). Return
+/// the name of the function output, its tuple descriptor and the plan.
+///
+/// unnest and regexp_matches are the only functions supported, and return a single column named
+/// after the alias or the function.
+fn create_function_scan(
+    name: &ast::ObjectName,
+    alias: Option<&ast::TableAlias>,
+    args: &[ast::FunctionArg],
+) -> Result<(String, Arc<TupleDesc>, Plan)> {
+    let function = ast::Function {
+        name: name.clone(),
+        args: args.to_vec(),
+        over: None,
+        distinct: false,
+    };
+    let func_name = name.to_string().to_lowercase();
+
+    let args = args
+        .iter()
+        .map(|arg| match arg {
+            ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(arg)) => {
+                expr::transform_expr(arg, None)
+            }
+            _ => bail!(SQLError::Unsupported(function.to_string())),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let arg_types = args
+        .iter()
+        .map(|arg| Ok(types::lookup_type(arg.typ())?.format_type()))
+        .collect::<Result<Vec<_>>>()?;
+    let (func, args, rettype) = match resolve_set_function(&func_name, args)? {
+        Some(resolved) => resolved,
+        None => bail!(types::Error::UndefinedFunction(format!(
+            "{}({})",
+            func_name,
+            arg_types.join(", ")
+        ))),
+    };
+
+    let rel_name = alias.map_or(func_name, |alias| alias.name.value.clone());
+    let attname = match alias.and_then(|alias| alias.columns.first()) {
+        Some(column) => column.value.clone(),
+        None => rel_name.clone(),
+    };
+    let rettype = types::lookup_type(rettype)?;
+    let tuple_desc = Arc::new(TupleDesc {
+        attrs: vec![PgAttribute {
+            attrelid: INVALID_OID,
+            attname,
+            attnum: 1,
+            attlen: rettype.typlen() as i64,
+            atttypid: rettype.oid(),
+            atttypmod: -1,
+            attidentity: String::new(),
+        }],
+    });
+
+    let plan = Plan {
+        node_type: PlanNodeType::FunctionScan {
+            state: FunctionScanState {
+                tuple_desc: tuple_desc.clone(),
+                func,
+                args,
+                rows: None,
+                qual: None,
+            },
+        },
+    };
+    Ok((rel_name, tuple_desc, plan))
+}
+
+/// Resolve a call of a set returning function, returning the function, the arguments coerced
+/// to the declared types and the type of the result, or None if no function accepts them.
+///
+/// unnest accepts any array and returns its elements, regexp_matches accepts a string, a
+/// pattern and optionally flags, which are all text.
+fn resolve_set_function(
+    name: &str,
+    args: Vec<expr::Expr>,
+) -> Result<Option<(SetFunc, Vec<expr::Expr>, Oid)>> {
+    match name {
+        "unnest" if args.len() == 1 => {
+            let elem_type = types::lookup_type(args[0].typ())?.typelem();
+            if elem_type == INVALID_OID {
+                return Ok(None);
+            }
+            Ok(Some((SetFunc::Unnest, args, elem_type)))
+        }
+        "regexp_matches" if args.len() == 2 || args.len() == 3 => {
+            let text_type = types::lookup_type(pg_type::TEXT_OID)?;
+            let mut coerced = Vec::with_capacity(args.len());
+            for arg in args {
+                match expr::coerce_to_target_type(arg, text_type, -1, CoercionContext::Implicit)? {
+                    Some(arg) => coerced.push(arg),
+                    None => return Ok(None),
                 }
             }
+            Ok(Some((
+                SetFunc::RegexpMatches,
+                coerced,
+                pg_type::TEXT_ARRAY_OID,
+            )))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Return true if the select item is a call to an aggregate function.
+fn is_aggregate(item: &ast::SelectItem) -> bool {
+    match item {
+        ast::SelectItem::UnnamedExpr(ast::Expr::Function(function))
+        | ast::SelectItem::ExprWithAlias {
+            expr: ast::Expr::Function(function),
+            ..
+        } => AggFunc::from_name(&function.name.to_string().to_lowercase()).is_some(),
+        _ => false,
+    }
+}
+
+/// Create a plan that computes the aggregates of the select list over all tuples returned by
+/// child. Each item of the select list must be an aggregate function of an expression on the
+/// columns of the scope.
+fn create_aggregate_plan(
+    select: &ast::Select,
+    scope: &expr::Scope,
+    tuple_desc: Arc<TupleDesc>,
+    child: Plan,
+) -> Result<Plan> {
+    let mut aggregates = Vec::with_capacity(select.projection.len());
+    let mut output = Vec::with_capacity(select.projection.len());
+
+    for item in &select.projection {
+        let (function, alias) = match item {
+            ast::SelectItem::UnnamedExpr(ast::Expr::Function(function)) => (function, None),
+            ast::SelectItem::ExprWithAlias {
+                expr: ast::Expr::Function(function),
+                alias,
+            } => (function, Some(alias.value.clone())),
+            ast::SelectItem::UnnamedExpr(ast::Expr::Identifier(ident)) => {
+                bail!(SQLError::GroupingError(ident.value.clone()))
+            }
+            _ => bail!(SQLError::Unsupported(item.to_string())),
+        };
 
-            Ok(Plan {
-                node_type: PlanNodeType::Projection {
-                    state: Box::new(ProjectionState {
-                        projection,
-                        tuple_desc: tuple_desc.clone(),
-                        child: create_seq_scan(
-                            buffer_pool,
-                            db_oid,
-                            &rel_name,
-                            &pg_class,
-                            tuple_desc,
-                        )?,
-                    }),
-                },
-            })
+        let func = AggFunc::from_name(&function.name.to_string().to_lowercase())
+            .ok_or_else(|| SQLError::Unsupported(item.to_string()))?;
+        if function.distinct {
+            bail!(SQLError::Unsupported(item.to_string()));
         }
-        _ => bail!(SQLError::Unsupported(from.relation.to_string())),
+
+        let arg = match function.args.as_slice() {
+            [ast::FunctionArg::Unnamed(ast::FunctionArgExpr::Expr(arg))] => {
+                resolve_unknown_type(expr::transform_expr(arg, Some(scope))?, item)?
+            }
+            _ => bail!(SQLError::Unsupported(item.to_string())),
+        };
+
+        let typ = types::lookup_type(func.result_type(arg.typ())?)?;
+        output.push(PgAttribute {
+            attrelid: INVALID_OID,
+            attname: alias.unwrap_or_else(|| func.name().to_string()),
+            attnum: output.len() + 1,
+            attlen: typ.typlen() as i64,
+            atttypid: typ.oid(),
+            atttypmod: -1,
+            attidentity: String::new(),
+        });
+        aggregates.push(Aggregate { func, arg });
     }
+
+    Ok(Plan {
+        node_type: PlanNodeType::Aggregate {
+            state: Box::new(AggregateState {
+                aggregates,
+                output,
+                tuple_desc,
+                child,
+            }),
+        },
+    })
 }
 
 fn create_seq_scan(
@@ -161,16 +751,44 @@ fn create_seq_scan(
                 tuple_desc,
                 relation: relation.clone(),
                 heap_scanner: HeapScanner::new(buffer_pool, &relation)?,
+                qual: None,
             },
         },
     })
 }
 
+/// Return a plan that scans the only row of a sequence, whose values have the types of the
+/// attributes of the given tuple description.
+fn create_sequence_scan(row: Vec<Option<Datum>>, tuple_desc: Arc<TupleDesc>) -> Plan {
+    let row = row
+        .into_iter()
+        .zip(&tuple_desc.attrs)
+        .map(|(value, attr)| expr::Expr::Const {
+            typ: attr.atttypid,
+            value,
+        })
+        .collect();
+
+    Plan {
+        node_type: PlanNodeType::ValuesScan {
+            state: ValuesScanState {
+                tuple_desc,
+                rows: vec![row],
+                next: 0,
+                qual: None,
+            },
+        },
+    }
+}
+
 impl fmt::Display for PlanNodeType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             PlanNodeType::Projection { .. } => write!(f, "Projection"),
+            PlanNodeType::Aggregate { .. } => write!(f, "Aggregate"),
             PlanNodeType::SeqScan { .. } => write!(f, "SeqScan"),
+            PlanNodeType::FunctionScan { .. } => write!(f, "FunctionScan"),
+            PlanNodeType::ValuesScan { .. } => write!(f, "ValuesScan"),
         }
     }
 }
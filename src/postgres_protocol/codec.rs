@@ -0,0 +1,349 @@
+use std::{io, mem::size_of};
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::Decoder;
+
+/// Maximum length of a startup packet. Startup packets are only a few parameters, so any
+/// bigger packet is considered a protocol violation.
+pub const MAX_STARTUP_PACKET_LENGTH: usize = 10000;
+
+/// Maximum length of a regular frontend message received before the client is
+/// authenticated. Only small messages are expected before authentication, so an
+/// unauthenticated client can not make the server wait for a big message.
+pub const MAX_UNAUTHENTICATED_MESSAGE_LENGTH: usize = 10000;
+
+/// Maximum length of a regular frontend message.
+pub const MAX_MESSAGE_LENGTH: usize = 0x3fffffff; // 1 GB - 1
+
+/// Maximum number of bytes reserved on the read buffer while waiting for the rest of a
+/// message. The buffer grows as the bytes arrive instead of reserving the whole advertised
+/// length up front.
+const MAX_RESERVE_SIZE: usize = 8 * 1024;
+
+/// Size of the length field that prefixes every message body.
+const LENGTH_SIZE: usize = size_of::<u32>();
+
+/// Size of the message type tag of regular messages.
+const TAG_SIZE: usize = size_of::<u8>();
+
+/// Errors related with the framing of frontend messages.
+#[derive(thiserror::Error, Debug)]
+pub enum ProtocolError {
+    #[error("invalid message length {0}")]
+    InvalidMessageLength(usize),
+
+    #[error("invalid startup packet length {0}")]
+    InvalidStartupPacketLength(usize),
+
+    #[error("unexpected EOF within message")]
+    UnexpectedEof,
+
+    #[error("unexpected EOF on client connection")]
+    ConnectionClosed,
+
+    #[error("invalid frontend message type {0}")]
+    InvalidMessageType(u8),
+
+    #[error("invalid message format: {0}")]
+    InvalidMessageFormat(String),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl ProtocolError {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            ProtocolError::Io(_) | ProtocolError::ConnectionClosed => "08006",
+            _ => "08P01",
+        }
+    }
+}
+
+/// A complete frontend message read from the client.
+#[derive(Debug, PartialEq)]
+pub enum Frame {
+    /// A message sent before the connection startup is completed. Startup packets don't
+    /// have a type tag, the body starts with a request code.
+    Startup(BytesMut),
+
+    /// A regular message identified by its type tag.
+    Message { tag: u8, body: BytesMut },
+}
+
+/// Decoder that splits the bytes received from the client into frames.
+///
+/// A frame is only returned when all bytes of the message were received. The advertised
+/// length of each message is validated before waiting for the message body, so a client
+/// can not make the server buffer an arbitrary amount of data.
+#[derive(Debug, Default)]
+pub struct FrontendCodec {
+    /// True after the startup packet is received. Messages are decoded as startup packets
+    /// until then.
+    startup_done: bool,
+
+    /// True after the client is authenticated. Messages bigger than
+    /// MAX_UNAUTHENTICATED_MESSAGE_LENGTH are rejected until then.
+    authenticated: bool,
+}
+
+impl FrontendCodec {
+    /// Create a new codec expecting a startup packet as the first message.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inform the codec that the startup phase is completed and the next frames are
+    /// regular messages.
+    pub fn set_startup_done(&mut self) {
+        self.startup_done = true;
+    }
+
+    /// Inform the codec that the client is authenticated, so messages up to
+    /// MAX_MESSAGE_LENGTH are accepted.
+    pub fn set_authenticated(&mut self) {
+        self.authenticated = true;
+    }
+
+    /// Return the maximum length accepted for a regular message.
+    fn max_message_length(&self) -> usize {
+        if self.authenticated {
+            MAX_MESSAGE_LENGTH
+        } else {
+            MAX_UNAUTHENTICATED_MESSAGE_LENGTH
+        }
+    }
+
+    fn decode_startup(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, ProtocolError> {
+        if src.len() < LENGTH_SIZE {
+            src.reserve(LENGTH_SIZE - src.len());
+            return Ok(None);
+        }
+
+        let len = read_length(src, 0);
+        if !(LENGTH_SIZE..=MAX_STARTUP_PACKET_LENGTH).contains(&len) {
+            return Err(ProtocolError::InvalidStartupPacketLength(len));
+        }
+
+        if src.len() < len {
+            src.reserve(len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(LENGTH_SIZE);
+        Ok(Some(Frame::Startup(src.split_to(len - LENGTH_SIZE))))
+    }
+
+    fn decode_message(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, ProtocolError> {
+        if src.len() < TAG_SIZE + LENGTH_SIZE {
+            src.reserve(TAG_SIZE + LENGTH_SIZE - src.len());
+            return Ok(None);
+        }
+
+        let tag = src[0];
+
+        // The length includes itself but not the message type tag.
+        let len = read_length(src, TAG_SIZE);
+        if !(LENGTH_SIZE..=self.max_message_length()).contains(&len) {
+            return Err(ProtocolError::InvalidMessageLength(len));
+        }
+
+        let frame_len = TAG_SIZE + len;
+        if src.len() < frame_len {
+            src.reserve((frame_len - src.len()).min(MAX_RESERVE_SIZE));
+            return Ok(None);
+        }
+
+        src.advance(TAG_SIZE + LENGTH_SIZE);
+        Ok(Some(Frame::Message {
+            tag,
+            body: src.split_to(len - LENGTH_SIZE),
+        }))
+    }
+}
+
+/// Read the big endian message length starting at the given position.
+fn read_length(src: &BytesMut, pos: usize) -> usize {
+    let mut len = [0; LENGTH_SIZE];
+    len.copy_from_slice(&src[pos..pos + LENGTH_SIZE]);
+    u32::from_be_bytes(len) as usize
+}
+
+impl Decoder for FrontendCodec {
+    type Item = Frame;
+    type Error = ProtocolError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, ProtocolError> {
+        if self.startup_done {
+            self.decode_message(src)
+        } else {
+            self.decode_startup(src)
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Frame>, ProtocolError> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            // The connection was closed in the middle of a message.
+            None => Err(ProtocolError::UnexpectedEof),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut buf = vec![tag];
+        buf.extend_from_slice(&((body.len() + LENGTH_SIZE) as u32).to_be_bytes());
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    #[test]
+    fn test_decode_waits_for_full_frame() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+
+        let msg = message(b'Q', b"select 1\0");
+        let mut src = BytesMut::new();
+
+        // Feed the message one byte at a time, no frame should be returned until the last
+        // byte is received.
+        for b in &msg[..msg.len() - 1] {
+            src.extend_from_slice(&[*b]);
+            assert_eq!(codec.decode(&mut src)?, None);
+        }
+
+        src.extend_from_slice(&msg[msg.len() - 1..]);
+        assert_eq!(
+            codec.decode(&mut src)?,
+            Some(Frame::Message {
+                tag: b'Q',
+                body: BytesMut::from(&b"select 1\0"[..]),
+            })
+        );
+        assert!(src.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_multiple_frames() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&message(b'Q', b"select 1\0"));
+        src.extend_from_slice(&message(b'X', b""));
+
+        assert!(matches!(
+            codec.decode(&mut src)?,
+            Some(Frame::Message { tag: b'Q', .. })
+        ));
+        assert!(matches!(
+            codec.decode(&mut src)?,
+            Some(Frame::Message { tag: b'X', .. })
+        ));
+        assert_eq!(codec.decode(&mut src)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_startup_packet() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+
+        let mut src = BytesMut::new();
+        src.extend_from_slice(&8u32.to_be_bytes());
+        src.extend_from_slice(&80877103u32.to_be_bytes());
+
+        assert_eq!(
+            codec.decode(&mut src)?,
+            Some(Frame::Startup(BytesMut::from(
+                &80877103u32.to_be_bytes()[..]
+            )))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_lengths() {
+        let mut codec = FrontendCodec::new();
+        let mut src = BytesMut::from(&100000u32.to_be_bytes()[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(ProtocolError::InvalidStartupPacketLength(100000))
+        ));
+
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+        codec.set_authenticated();
+
+        let mut src = BytesMut::from(&[b'Q', 0xff, 0xff, 0xff, 0xff][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(ProtocolError::InvalidMessageLength(_))
+        ));
+
+        let mut src = BytesMut::from(&[b'Q', 0, 0, 0, 2][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(ProtocolError::InvalidMessageLength(2))
+        ));
+    }
+
+    #[test]
+    fn test_truncated_frame_on_eof() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+
+        let msg = message(b'Q', b"select 1\0");
+        let mut src = BytesMut::from(&msg[..6]);
+        assert!(matches!(
+            codec.decode_eof(&mut src),
+            Err(ProtocolError::UnexpectedEof)
+        ));
+
+        let mut src = BytesMut::new();
+        assert_eq!(codec.decode_eof(&mut src)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_message_length_before_authentication() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+
+        let len = (MAX_UNAUTHENTICATED_MESSAGE_LENGTH + 1) as u32;
+        let mut header = vec![b'p'];
+        header.extend_from_slice(&len.to_be_bytes());
+
+        let mut src = BytesMut::from(&header[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(ProtocolError::InvalidMessageLength(_))
+        ));
+
+        codec.set_authenticated();
+        let mut src = BytesMut::from(&header[..]);
+        assert_eq!(codec.decode(&mut src)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_do_not_reserve_advertised_length() -> Result<(), ProtocolError> {
+        let mut codec = FrontendCodec::new();
+        codec.set_startup_done();
+        codec.set_authenticated();
+
+        let mut header = vec![b'Q'];
+        header.extend_from_slice(&(MAX_MESSAGE_LENGTH as u32).to_be_bytes());
+
+        let mut src = BytesMut::from(&header[..]);
+        assert_eq!(codec.decode(&mut src)?, None);
+        assert!(src.capacity() < 2 * MAX_RESERVE_SIZE);
+        Ok(())
+    }
+}
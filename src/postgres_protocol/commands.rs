@@ -2,16 +2,21 @@
 
 use std::{
     collections::HashMap,
+    fmt,
     io::{BufRead, Cursor},
 };
 
 use anyhow::{bail, Error};
 use byteorder::{BigEndian, ByteOrder};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use sqlparser::parser::ParserError;
+use tokio::io::AsyncWriteExt;
 
+use super::codec::{Frame, ProtocolError};
 use crate::{
-    sql::{encode, PGResult, RowDescriptor},
-    Oid,
+    backend::{self, extended_query, hba},
+    catalog,
+    sql::{copy, sequence, session::Param, PGResult, RowDescriptor, SQLError},
+    types, Oid,
 };
 
 pub const AUTH_TYPE_OK: u32 = 0;
@@ -55,6 +60,58 @@ pub enum Message {
     ParameterStatus(ParameterStatus),
     DataRow(PGResult),
     ErrorResponse(ErrorResponse),
+
+    /// Start of a COPY FROM STDIN with the number of columns of each row.
+    CopyInResponse(usize),
+
+    /// Start of a COPY TO STDOUT with the number of columns of each row.
+    CopyOutResponse(usize),
+
+    /// Chunk of data of a COPY in any direction.
+    CopyData(Vec<u8>),
+
+    /// End of data of a COPY in any direction.
+    CopyDone,
+
+    /// The client aborted the COPY FROM STDIN with the given error message.
+    CopyFail(String),
+
+    /// Prepare a statement of the extended query protocol.
+    Parse(Parse),
+
+    /// Create a portal from a prepared statement and the values of its parameters.
+    Bind(Bind),
+
+    /// Describe a prepared statement or a portal.
+    Describe(Target),
+
+    /// Execute a portal.
+    Execute(Execute),
+
+    /// Close a prepared statement or a portal.
+    Close(Target),
+
+    /// End of the messages of the extended query protocol of a transaction.
+    Sync,
+
+    /// Send the buffered messages to the client.
+    Flush,
+
+    ParseComplete,
+    BindComplete,
+    CloseComplete,
+
+    /// The types of the parameters of a prepared statement.
+    ParameterDescription(Vec<Oid>),
+
+    /// The statement or portal being described returns no rows.
+    NoData,
+
+    /// The portal was not completely executed, since it reached the row limit.
+    PortalSuspended,
+
+    /// The query string of the statement being executed was empty.
+    EmptyQueryResponse,
 }
 
 #[derive(Debug)]
@@ -62,27 +119,336 @@ pub struct Query {
     pub query: String,
 }
 
-pub async fn decode<R>(decode_from: &mut R) -> anyhow::Result<Message>
-where
-    R: AsyncReadExt + std::marker::Unpin,
-{
-    let msg_type = decode_from.read_u8().await?;
+/// Format of a value sent or received on the extended query protocol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Binary,
+}
 
-    match msg_type {
-        b'Q' => {
-            let msg_len = decode_from.read_u32().await?;
+impl Format {
+    /// Return the format of the given format code.
+    fn from_code(code: i16) -> Result<Self, ProtocolError> {
+        match code {
+            0 => Ok(Format::Text),
+            1 => Ok(Format::Binary),
+            _ => Err(ProtocolError::InvalidMessageFormat(format!(
+                "unsupported format code: {}",
+                code
+            ))),
+        }
+    }
 
-            // Exclude the msg_len when reading
-            let mut msg_body = vec![0; (msg_len as usize) - 4];
-            decode_from.read(&mut msg_body).await?;
+    /// Return the format code sent on the protocol messages.
+    pub fn code(self) -> i16 {
+        match self {
+            Format::Text => 0,
+            Format::Binary => 1,
+        }
+    }
+}
+
+/// A prepared statement or a portal, identified by its name. The empty name is the unnamed
+/// statement or portal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Statement(String),
+    Portal(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Parse {
+    /// Name of the prepared statement.
+    pub name: String,
+
+    pub query: String,
+
+    /// Types of the parameters, where 0 is a parameter whose type should be inferred. The
+    /// statement can have more parameters than types.
+    pub param_types: Vec<Oid>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Bind {
+    /// Name of the portal being created.
+    pub portal: String,
 
-            // Exclude the \0 at the end when parsing.
-            let _ = msg_body.pop();
-            let query = String::from_utf8(msg_body)?;
-            Ok(Message::Query(Query { query }))
+    /// Name of the prepared statement.
+    pub statement: String,
+
+    /// Format of the parameter values, which is empty when all values are text, has a single
+    /// format used by all values or a format for each value.
+    pub param_formats: Vec<Format>,
+
+    /// Values of the parameters, None for NULL.
+    pub params: Vec<Option<Vec<u8>>>,
+
+    /// Format of the result columns, with the same rules of param_formats.
+    pub result_formats: Vec<Format>,
+}
+
+impl Bind {
+    /// Return the value of each parameter, converted to the given parameter types with the
+    /// type input function of text values and the type receive function of binary values.
+    ///
+    /// Values of unknown type are kept as the received string.
+    pub fn param_values(&self, param_types: &[Oid]) -> anyhow::Result<Vec<Param>> {
+        if self.params.len() != param_types.len() {
+            bail!(ProtocolError::InvalidMessageFormat(format!(
+                "bind message supplies {} parameters, but prepared statement \"{}\" requires {}",
+                self.params.len(),
+                self.statement,
+                param_types.len()
+            )));
         }
-        b'X' => Ok(Message::Terminate),
-        _ => anyhow::bail!("Message type {} not supported", msg_type),
+        let formats = match column_formats(&self.param_formats, self.params.len()) {
+            Some(formats) => formats,
+            None => bail!(ProtocolError::InvalidMessageFormat(format!(
+                "bind message has {} parameter formats but {} parameters",
+                self.param_formats.len(),
+                self.params.len()
+            ))),
+        };
+
+        let mut values = Vec::with_capacity(self.params.len());
+        for ((value, typ), format) in self.params.iter().zip(param_types).zip(formats) {
+            let value = match value {
+                Some(value) => {
+                    let typ = types::lookup_type(*typ)?;
+                    Some(match format {
+                        Format::Text => match std::str::from_utf8(value) {
+                            Ok(value) => typ.input(value, -1)?,
+                            Err(_) => bail!(ProtocolError::InvalidMessageFormat(String::from(
+                                "invalid UTF-8 string"
+                            ))),
+                        },
+                        Format::Binary => typ.recv(value, -1)?,
+                    })
+                }
+                None => None,
+            };
+            values.push(Param { typ: *typ, value });
+        }
+        Ok(values)
+    }
+
+    /// Return the format of each column of a result with the given number of columns.
+    pub fn result_formats(&self, ncolumns: usize) -> Result<Vec<Format>, ProtocolError> {
+        column_formats(&self.result_formats, ncolumns).ok_or_else(|| {
+            ProtocolError::InvalidMessageFormat(format!(
+                "bind message has {} result formats but query has {} columns",
+                self.result_formats.len(),
+                ncolumns
+            ))
+        })
+    }
+}
+
+/// Return the format of each of the given number of values, from a list of formats that is
+/// empty when all values are text, has a single format used by all values or a format for
+/// each value. Return None if the number of formats is not valid.
+fn column_formats(formats: &[Format], nvalues: usize) -> Option<Vec<Format>> {
+    match formats {
+        [] => Some(vec![Format::Text; nvalues]),
+        [format] => Some(vec![*format; nvalues]),
+        formats if formats.len() == nvalues => Some(formats.to_vec()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Execute {
+    /// Name of the portal.
+    pub portal: String,
+
+    /// Maximum number of rows to return, 0 for no limit.
+    pub max_rows: usize,
+}
+
+/// Reader of the fields of a message body.
+struct MessageReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> MessageReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], ProtocolError> {
+        if self.buf.len() < len {
+            return Err(ProtocolError::InvalidMessageFormat(String::from(
+                "insufficient data left in message",
+            )));
+        }
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn read_i16(&mut self) -> Result<i16, ProtocolError> {
+        Ok(BigEndian::read_i16(self.read_bytes(2)?))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, ProtocolError> {
+        Ok(BigEndian::read_i32(self.read_bytes(4)?))
+    }
+
+    /// Read a count of the following fields, which can not be negative.
+    fn read_count(&mut self) -> Result<usize, ProtocolError> {
+        let count = self.read_i16()?;
+        if count < 0 {
+            return Err(ProtocolError::InvalidMessageFormat(format!(
+                "invalid count {}",
+                count
+            )));
+        }
+        Ok(count as usize)
+    }
+
+    fn read_cstr(&mut self) -> Result<String, ProtocolError> {
+        match self.buf.iter().position(|b| *b == 0) {
+            Some(len) => {
+                let value = read_cstr(self.read_bytes(len + 1)?)?;
+                Ok(value)
+            }
+            None => Err(ProtocolError::InvalidMessageFormat(String::from(
+                "invalid string in message",
+            ))),
+        }
+    }
+
+    fn read_formats(&mut self) -> Result<Vec<Format>, ProtocolError> {
+        let count = self.read_count()?;
+        (0..count)
+            .map(|_| Format::from_code(self.read_i16()?))
+            .collect()
+    }
+
+    fn read_target(&mut self) -> Result<Target, ProtocolError> {
+        match self.read_bytes(1)?[0] {
+            b'S' => Ok(Target::Statement(self.read_cstr()?)),
+            b'P' => Ok(Target::Portal(self.read_cstr()?)),
+            kind => Err(ProtocolError::InvalidMessageFormat(format!(
+                "invalid DESCRIBE or CLOSE message subtype {}",
+                kind
+            ))),
+        }
+    }
+
+    /// Check that all the fields of the message were read.
+    fn finish(self) -> Result<(), ProtocolError> {
+        if !self.buf.is_empty() {
+            return Err(ProtocolError::InvalidMessageFormat(String::from(
+                "invalid message length",
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn decode_parse(body: &[u8]) -> Result<Parse, ProtocolError> {
+    let mut reader = MessageReader::new(body);
+    let name = reader.read_cstr()?;
+    let query = reader.read_cstr()?;
+    let count = reader.read_count()?;
+    let param_types = (0..count)
+        .map(|_| Ok(reader.read_i32()? as u32 as Oid))
+        .collect::<Result<Vec<_>, ProtocolError>>()?;
+    reader.finish()?;
+    Ok(Parse {
+        name,
+        query,
+        param_types,
+    })
+}
+
+fn decode_bind(body: &[u8]) -> Result<Bind, ProtocolError> {
+    let mut reader = MessageReader::new(body);
+    let portal = reader.read_cstr()?;
+    let statement = reader.read_cstr()?;
+    let param_formats = reader.read_formats()?;
+
+    let count = reader.read_count()?;
+    let mut params = Vec::with_capacity(count);
+    for _ in 0..count {
+        // NULL is sent as a length of -1.
+        let len = reader.read_i32()?;
+        params.push(match len {
+            -1 => None,
+            len if len < 0 => {
+                return Err(ProtocolError::InvalidMessageFormat(format!(
+                    "invalid parameter length {}",
+                    len
+                )))
+            }
+            len => Some(reader.read_bytes(len as usize)?.to_vec()),
+        });
+    }
+
+    let result_formats = reader.read_formats()?;
+    reader.finish()?;
+    Ok(Bind {
+        portal,
+        statement,
+        param_formats,
+        params,
+        result_formats,
+    })
+}
+
+fn decode_execute(body: &[u8]) -> Result<Execute, ProtocolError> {
+    let mut reader = MessageReader::new(body);
+    let portal = reader.read_cstr()?;
+    // A limit that is not positive means no limit.
+    let max_rows = reader.read_i32()?.max(0) as usize;
+    reader.finish()?;
+    Ok(Execute { portal, max_rows })
+}
+
+fn decode_target(body: &[u8]) -> Result<Target, ProtocolError> {
+    let mut reader = MessageReader::new(body);
+    let target = reader.read_target()?;
+    reader.finish()?;
+    Ok(target)
+}
+
+/// Decode a complete frontend message frame.
+///
+/// Return a protocol violation error if the message body is malformed.
+pub fn decode(frame: Frame) -> Result<Message, ProtocolError> {
+    match frame {
+        Frame::Message { tag, body } => match tag {
+            b'Q' => Ok(Message::Query(Query {
+                query: read_cstr(&body)?,
+            })),
+            b'X' => Ok(Message::Terminate),
+            COPY_DATA_TAG => Ok(Message::CopyData(body.to_vec())),
+            COPY_DONE_TAG => Ok(Message::CopyDone),
+            b'f' => Ok(Message::CopyFail(read_cstr(&body)?)),
+            b'P' => Ok(Message::Parse(decode_parse(&body)?)),
+            b'B' => Ok(Message::Bind(decode_bind(&body)?)),
+            b'D' => Ok(Message::Describe(decode_target(&body)?)),
+            b'E' => Ok(Message::Execute(decode_execute(&body)?)),
+            b'C' => Ok(Message::Close(decode_target(&body)?)),
+            b'S' => Ok(Message::Sync),
+            b'H' => Ok(Message::Flush),
+            _ => Err(ProtocolError::InvalidMessageType(tag)),
+        },
+        Frame::Startup(_) => Err(ProtocolError::InvalidMessageFormat(String::from(
+            "unexpected startup packet",
+        ))),
+    }
+}
+
+/// Read a null terminated string that fills the entire given buffer.
+fn read_cstr(buf: &[u8]) -> Result<String, ProtocolError> {
+    match buf.split_last() {
+        Some((0, value)) if !value.contains(&0) => String::from_utf8(value.to_vec())
+            .map_err(|_| ProtocolError::InvalidMessageFormat(String::from("invalid UTF-8 string"))),
+        _ => Err(ProtocolError::InvalidMessageFormat(String::from(
+            "invalid string in message",
+        ))),
     }
 }
 
@@ -93,14 +459,14 @@ where
     match message {
         Message::ReadyForQuery => {
             encode_to
-                .write(&[READY_FOR_QUERY_TAG, 0, 0, 0, 5, EMPTY_QUERY_RESPONSE_TAG])
+                .write_all(&[READY_FOR_QUERY_TAG, 0, 0, 0, 5, EMPTY_QUERY_RESPONSE_TAG])
                 .await?;
             Ok(())
         }
         Message::CommandComplete(tag) => {
             encode_to.write_u8(COMMAND_COMPLETE_TAG).await?;
             encode_to.write_i32((tag.len() as i32) + 5).await?;
-            encode_to.write(&tag.as_bytes()).await?;
+            encode_to.write_all(tag.as_bytes()).await?;
             encode_to.write_u8(0).await?;
             Ok(())
         }
@@ -109,7 +475,7 @@ where
 
             field_values.write_u16(desc.fields.len() as u16).await?;
             for field in &desc.fields {
-                field_values.write(&field.name).await?;
+                field_values.write_all(&field.name).await?;
                 field_values.write_u8(0).await?;
 
                 field_values.write_u32(field.table_oid).await?;
@@ -122,11 +488,11 @@ where
 
             encode_to.write_u8(ROW_DESCRIPTION_TAG).await?;
             encode_to.write_i32((field_values.len() as i32) + 4).await?;
-            encode_to.write(&field_values).await?;
+            encode_to.write_all(&field_values).await?;
             Ok(())
         }
         Message::AuthenticationOk => {
-            encode_to.write(&[AUTHENTICATION_TAG]).await?;
+            encode_to.write_u8(AUTHENTICATION_TAG).await?;
             encode_to.write_i32(8).await?;
             encode_to.write_u32(AUTH_TYPE_OK).await?;
             Ok(())
@@ -143,14 +509,14 @@ where
         }
         Message::ParameterStatus(status) => {
             let mut buf = Vec::new();
-            buf.write(status.key.as_bytes()).await?;
+            buf.write_all(status.key.as_bytes()).await?;
             buf.write_u8(0).await?;
-            buf.write(status.value.as_bytes()).await?;
+            buf.write_all(status.value.as_bytes()).await?;
             buf.write_u8(0).await?;
 
             encode_to.write_u8(PARAMETER_STATUS_TAG).await?;
             encode_to.write_i32((buf.len() as i32) + 4).await?;
-            encode_to.write(&buf).await?;
+            encode_to.write_all(&buf).await?;
             Ok(())
         }
         Message::DataRow(result) => {
@@ -165,10 +531,14 @@ where
                     match datum {
                         Some(datum) => match &result.desc.fields.get(attnum) {
                             Some(att_desc) => {
-                                let datum = encode::decode(datum, att_desc.data_type_oid as Oid)?;
-                                let datum = datum.as_bytes();
+                                let typ = types::lookup_type(att_desc.data_type_oid as Oid)?;
+                                let datum = if att_desc.format == Format::Binary.code() {
+                                    typ.send(datum)?
+                                } else {
+                                    typ.output(datum)?.into_bytes()
+                                };
                                 buf_row.write_u32(datum.len() as u32).await?;
-                                buf_row.write(datum).await?;
+                                buf_row.write_all(&datum).await?;
                             }
                             None => {
                                 bail!("Can not find field desc for attnum {}", attnum)
@@ -182,29 +552,124 @@ where
                 }
                 data_rows.write_u8(DATA_ROW_TAG).await?;
                 data_rows.write_i32((buf_row.len() as i32) + 4).await?;
-                data_rows.write(&buf_row).await?;
+                data_rows.write_all(&buf_row).await?;
             }
 
-            encode_to.write(&data_rows).await?;
+            encode_to.write_all(&data_rows).await?;
 
             Ok(())
         }
-        Message::StartupMessage(_) | Message::Query(_) => {
+        Message::CopyInResponse(ncolumns) | Message::CopyOutResponse(ncolumns) => {
+            let tag = match message {
+                Message::CopyInResponse(_) => COPY_IN_RESPONSE_TAG,
+                _ => COPY_OUT_RESPONSE_TAG,
+            };
+
+            encode_to.write_u8(tag).await?;
+            encode_to.write_i32(4 + 1 + 2 + 2 * ncolumns as i32).await?;
+            // Overall format is textual, so all columns also use the text format.
+            encode_to.write_i8(0).await?;
+            encode_to.write_i16(ncolumns as i16).await?;
+            for _ in 0..ncolumns {
+                encode_to.write_i16(0).await?;
+            }
+            Ok(())
+        }
+        Message::CopyData(data) => {
+            encode_to.write_u8(COPY_DATA_TAG).await?;
+            encode_to.write_i32((data.len() as i32) + 4).await?;
+            encode_to.write_all(&data).await?;
+            Ok(())
+        }
+        Message::CopyDone => {
+            encode_to.write_all(&[COPY_DONE_TAG, 0, 0, 0, 4]).await?;
+            Ok(())
+        }
+        Message::ParseComplete => {
+            encode_to
+                .write_all(&[PARSE_COMPLETE_TAG, 0, 0, 0, 4])
+                .await?;
+            Ok(())
+        }
+        Message::BindComplete => {
+            encode_to
+                .write_all(&[BIND_COMPLETE_TAG, 0, 0, 0, 4])
+                .await?;
+            Ok(())
+        }
+        Message::CloseComplete => {
+            encode_to
+                .write_all(&[CLOSE_COMPLETE_TAG, 0, 0, 0, 4])
+                .await?;
+            Ok(())
+        }
+        Message::NoData => {
+            encode_to.write_all(&[NO_DATA_TAG, 0, 0, 0, 4]).await?;
+            Ok(())
+        }
+        Message::PortalSuspended => {
+            encode_to
+                .write_all(&[PORTAL_SUSPENDED_TAG, 0, 0, 0, 4])
+                .await?;
+            Ok(())
+        }
+        Message::EmptyQueryResponse => {
+            encode_to
+                .write_all(&[EMPTY_QUERY_RESPONSE_TAG, 0, 0, 0, 4])
+                .await?;
+            Ok(())
+        }
+        Message::ParameterDescription(param_types) => {
+            encode_to.write_u8(PARAMETER_DESCRIPTION_TAG).await?;
+            encode_to
+                .write_i32(4 + 2 + 4 * param_types.len() as i32)
+                .await?;
+            encode_to.write_i16(param_types.len() as i16).await?;
+            for typ in param_types {
+                encode_to.write_u32(typ as u32).await?;
+            }
+            Ok(())
+        }
+        Message::StartupMessage(_)
+        | Message::Query(_)
+        | Message::CopyFail(_)
+        | Message::Parse(_)
+        | Message::Bind(_)
+        | Message::Describe(_)
+        | Message::Execute(_)
+        | Message::Close(_)
+        | Message::Sync
+        | Message::Flush => {
             bail!("can not encode message {:?}", message)
         }
         Message::ErrorResponse(err) => {
             encode_to.write_u8(ERROR_RESPONSE_TAG).await?;
             let mut buf = Vec::new();
 
+            let severity = err.severity.to_string();
+
+            buf.write_u8(b'S').await?;
+            buf.write_all(severity.as_bytes()).await?;
+            buf.write_u8(0).await?;
+
+            // Non-localized severity.
+            buf.write_u8(b'V').await?;
+            buf.write_all(severity.as_bytes()).await?;
+            buf.write_u8(0).await?;
+
+            buf.write_u8(b'C').await?;
+            buf.write_all(err.sqlstate().as_bytes()).await?;
+            buf.write_u8(0).await?;
+
             buf.write_u8(b'M').await?;
-            buf.write(&err.error.to_string().as_bytes()).await?;
+            buf.write_all(err.error.to_string().as_bytes()).await?;
             buf.write_u8(0).await?;
 
             // Mark the the end of error response.
             buf.write_u8(0).await?;
 
             encode_to.write_u32((buf.len() + 4) as u32).await?;
-            encode_to.write(&buf).await?;
+            encode_to.write_all(&buf).await?;
             Ok(())
         }
         Message::Terminate => Ok(()),
@@ -217,11 +682,74 @@ pub struct ParameterStatus {
     pub value: String,
 }
 
+/// Severity of an error response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The error aborted the current command.
+    Error,
+
+    /// The error aborted the current session.
+    Fatal,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "ERROR"),
+            Severity::Fatal => write!(f, "FATAL"),
+        }
+    }
+}
+
+/// SQLSTATE code used for errors that don't have a more specific code.
+pub const INTERNAL_ERROR_SQLSTATE: &str = "XX000";
+
 #[derive(Debug)]
 pub struct ErrorResponse {
+    pub severity: Severity,
     pub error: Error,
 }
 
+impl ErrorResponse {
+    /// Return the SQLSTATE code of the error.
+    ///
+    /// The code is taken from the underlying error type if it is known, otherwise
+    /// internal_error is returned.
+    pub fn sqlstate(&self) -> &'static str {
+        if let Some(err) = self.error.downcast_ref::<ProtocolError>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<backend::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<hba::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<extended_query::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<catalog::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<SQLError>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<copy::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<types::Error>() {
+            return err.sqlstate();
+        }
+        if let Some(err) = self.error.downcast_ref::<sequence::Error>() {
+            return err.sqlstate();
+        }
+        if self.error.downcast_ref::<ParserError>().is_some() {
+            return "42601";
+        }
+        INTERNAL_ERROR_SQLSTATE
+    }
+}
+
 #[derive(Debug)]
 pub struct StartupMessage {
     pub protocol_version: u32,
@@ -263,3 +791,132 @@ impl StartupMessage {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::pg_type;
+
+    /// Return the binary format of a one dimensional int4[] without NULLs.
+    fn int4_array(values: &[i32]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for field in [1, 0, pg_type::INT_OID as i32, values.len() as i32, 1] {
+            buf.extend_from_slice(&field.to_be_bytes());
+        }
+        for value in values {
+            buf.extend_from_slice(&4i32.to_be_bytes());
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+        buf
+    }
+
+    #[test]
+    fn test_decode_bind() -> anyhow::Result<()> {
+        let array = int4_array(&[1, 2]);
+        let mut body = b"portal\0stmt\0".to_vec();
+        body.extend_from_slice(&[0, 2, 0, 1, 0, 0]);
+        body.extend_from_slice(&[0, 3]);
+        body.extend_from_slice(&(array.len() as i32).to_be_bytes());
+        body.extend_from_slice(&array);
+        body.extend_from_slice(&[0, 0, 0, 2, b'4', b'2']);
+        body.extend_from_slice(&(-1i32).to_be_bytes());
+        body.extend_from_slice(&[0, 1, 0, 1]);
+
+        let bind = match decode(Frame::Message {
+            tag: b'B',
+            body: body.as_slice().into(),
+        })? {
+            Message::Bind(bind) => bind,
+            msg => panic!("unexpected message {:?}", msg),
+        };
+        assert_eq!(bind.portal, "portal");
+        assert_eq!(bind.statement, "stmt");
+        assert_eq!(bind.param_formats, vec![Format::Binary, Format::Text]);
+        assert_eq!(bind.params, vec![Some(array), Some(b"42".to_vec()), None]);
+        assert_eq!(bind.result_formats(3)?, vec![Format::Binary; 3]);
+
+        // The number of formats is neither 1 nor the number of parameters.
+        assert!(bind
+            .param_values(&[pg_type::INT4_ARRAY_OID, pg_type::INT_OID, pg_type::INT_OID])
+            .is_err());
+
+        body.truncate(body.len() - 1);
+        assert!(decode(Frame::Message {
+            tag: b'B',
+            body: body.as_slice().into(),
+        })
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_param_values() -> anyhow::Result<()> {
+        let bind = Bind {
+            portal: String::new(),
+            statement: String::new(),
+            param_formats: vec![Format::Binary, Format::Text, Format::Text],
+            params: vec![Some(int4_array(&[1, 2])), Some(b"42".to_vec()), None],
+            result_formats: Vec::new(),
+        };
+        let array_type = types::lookup_type(pg_type::INT4_ARRAY_OID)?;
+        let int_type = types::lookup_type(pg_type::INT_OID)?;
+
+        let values = bind.param_values(&[
+            pg_type::INT4_ARRAY_OID,
+            pg_type::INT_OID,
+            pg_type::UNKNOWN_OID,
+        ])?;
+        assert_eq!(
+            values,
+            vec![
+                Param {
+                    typ: pg_type::INT4_ARRAY_OID,
+                    value: Some(array_type.input("{1,2}", -1)?),
+                },
+                Param {
+                    typ: pg_type::INT_OID,
+                    value: Some(int_type.input("42", -1)?),
+                },
+                Param {
+                    typ: pg_type::UNKNOWN_OID,
+                    value: None,
+                },
+            ]
+        );
+        assert_eq!(
+            array_type.send(values[0].value.as_ref().unwrap())?,
+            int4_array(&[1, 2])
+        );
+
+        assert!(bind.param_values(&[pg_type::INT_OID]).is_err());
+        assert!(bind.result_formats(1).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_execute_and_describe() -> Result<(), ProtocolError> {
+        let mut body = b"p\0".to_vec();
+        body.extend_from_slice(&10i32.to_be_bytes());
+        assert!(matches!(
+            decode(Frame::Message {
+                tag: b'E',
+                body: body.as_slice().into(),
+            })?,
+            Message::Execute(Execute { max_rows: 10, .. })
+        ));
+
+        assert!(matches!(
+            decode(Frame::Message {
+                tag: b'D',
+                body: b"Sname\0"[..].into(),
+            })?,
+            Message::Describe(Target::Statement(name)) if name == "name"
+        ));
+        assert!(decode(Frame::Message {
+            tag: b'D',
+            body: b"Xname\0"[..].into(),
+        })
+        .is_err());
+        Ok(())
+    }
+}
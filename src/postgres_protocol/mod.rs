@@ -1,19 +1,51 @@
+pub mod codec;
 pub mod commands;
 
-use std::{io, net::SocketAddr};
+use std::{fmt, net::SocketAddr};
 
-use async_recursion::async_recursion;
 use byteorder::{BigEndian, ByteOrder};
-use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio_util::codec::Decoder;
+
+use anyhow::{bail, Error, Result};
+
+use crate::{
+    sql::{PGResult, RowDescriptor},
+    Oid,
+};
+
+use self::{
+    codec::{Frame, FrontendCodec, ProtocolError},
+    commands::{
+        Message, Severity, StartupMessage, CANCEL_REQUEST_CODE, GSS_ENC_REQ_NUMBER,
+        PROTOCOL_VERSION_NUMBER, SSL_REQUEST_NUMBER,
+    },
 };
 
-use anyhow::{Error, Result};
+/// A socket that a client is connected through, either a TCP or a Unix-domain socket.
+pub trait Socket: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug {}
 
-use crate::sql::PGResult;
+impl<T> Socket for T where T: AsyncRead + AsyncWrite + Unpin + Send + fmt::Debug {}
 
-use self::commands::{Message, StartupMessage, PROTOCOL_VERSION_NUMBER, SSL_REQUEST_NUMBER};
+/// Address of a connected client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientAddr {
+    /// Client connected through a TCP socket.
+    Tcp(SocketAddr),
+
+    /// Client connected through a Unix-domain socket.
+    Local,
+}
+
+impl fmt::Display for ClientAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientAddr::Tcp(addr) => write!(f, "{}", addr),
+            ClientAddr::Local => write!(f, "[local]"),
+        }
+    }
+}
 
 /// Connection implements the Postgres wire protocol (version 3 of the protocol, implemented
 /// by Postgres 7.4 an later). receive() reads protocol messages, and return a Message type
@@ -23,16 +55,29 @@ use self::commands::{Message, StartupMessage, PROTOCOL_VERSION_NUMBER, SSL_REQUE
 /// connection to send the appropriate messages back to the client.
 #[derive(Debug)]
 pub struct Connection {
-    // The `TcpStream` used to read and write data back and from the client.
-    stream: BufReader<TcpStream>,
+    // The socket used to read and write data back and from the client. Writes are
+    // buffered and flushed when the server is waiting for a new message from the client.
+    stream: BufWriter<Box<dyn Socket>>,
+
+    // Address of the client connected on the other side of the socket.
+    peer_addr: ClientAddr,
+
+    // Bytes received from the client that were not decoded into a frame yet.
+    buffer: BytesMut,
+
+    // Decoder used to split the received bytes into message frames.
+    codec: FrontendCodec,
 }
 
 impl Connection {
     /// Create a new `Connection`, backed by `socket`. Read and write buffers
     /// are initialized.
-    pub fn new(socket: TcpStream) -> Connection {
+    pub fn new(socket: Box<dyn Socket>, peer_addr: ClientAddr) -> Connection {
         Connection {
-            stream: BufReader::new(socket),
+            stream: BufWriter::new(socket),
+            peer_addr,
+            buffer: BytesMut::with_capacity(4 * 1024),
+            codec: FrontendCodec::new(),
         }
     }
 
@@ -40,21 +85,101 @@ impl Connection {
     ///
     /// The function waits until it has retrieved enough data to parse a message.
     pub async fn receive(&mut self) -> Result<Message> {
-        let message = commands::decode(&mut self.stream).await?;
-        Ok(message)
+        let frame = self.read_frame().await?;
+        Ok(commands::decode(frame)?)
     }
 
-    /// Send a query result back to the client.
-    pub async fn send_result(&mut self, result: PGResult) -> Result<()> {
-        let rows = result.tuples.len();
+    /// Read a single complete frame from the stream.
+    ///
+    /// Return an error if the connection is closed, if the connection is closed in the
+    /// middle of a frame a protocol violation error is returned.
+    async fn read_frame(&mut self) -> Result<Frame, ProtocolError> {
+        loop {
+            if let Some(frame) = self.codec.decode(&mut self.buffer)? {
+                return Ok(frame);
+            }
+
+            // There is not enough buffered data to decode a frame, read more data from the
+            // socket. A read of 0 bytes means that the client closed the connection.
+            if 0 == self.stream.read_buf(&mut self.buffer).await? {
+                return match self.codec.decode_eof(&mut self.buffer)? {
+                    Some(frame) => Ok(frame),
+                    None => Err(ProtocolError::ConnectionClosed),
+                };
+            }
+        }
+    }
 
+    /// Send the rows of a command result back to the client, followed by the command tag.
+    pub async fn send_result(&mut self, result: PGResult, tag: &str) -> Result<()> {
         commands::encode(
             &mut self.stream,
             Message::RowDescriptor(result.desc.clone()),
         )
         .await?;
         commands::encode(&mut self.stream, Message::DataRow(result)).await?;
-        self.command_complete(&format!("SELECT {}", rows)).await?;
+        self.command_complete(tag).await?;
+        Ok(())
+    }
+
+    /// Send the description of the rows returned by a statement or portal, or NoData if it
+    /// returns no rows.
+    pub async fn send_row_description(&mut self, desc: Option<RowDescriptor>) -> Result<()> {
+        let message = match desc {
+            Some(desc) => Message::RowDescriptor(desc),
+            None => Message::NoData,
+        };
+        commands::encode(&mut self.stream, message).await?;
+        Ok(())
+    }
+
+    /// Send the rows of a result, without the row description, which was already sent when the
+    /// portal was described.
+    pub async fn send_rows(&mut self, result: PGResult) -> Result<()> {
+        commands::encode(&mut self.stream, Message::DataRow(result)).await?;
+        Ok(())
+    }
+
+    /// Send the types of the parameters of a prepared statement.
+    pub async fn send_parameter_description(&mut self, param_types: Vec<Oid>) -> Result<()> {
+        commands::encode(&mut self.stream, Message::ParameterDescription(param_types)).await?;
+        Ok(())
+    }
+
+    /// Send a message of the extended query protocol that has no body, e.g ParseComplete.
+    pub async fn send_status(&mut self, message: Message) -> Result<()> {
+        commands::encode(&mut self.stream, message).await?;
+        Ok(())
+    }
+
+    /// Send all buffered messages to the client.
+    pub async fn flush(&mut self) -> Result<()> {
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Start a COPY FROM STDIN, informing the client that it can send the data.
+    pub async fn start_copy_in(&mut self, ncolumns: usize) -> Result<()> {
+        commands::encode(&mut self.stream, Message::CopyInResponse(ncolumns)).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    /// Start a COPY TO STDOUT, informing the client that the rows will be sent.
+    pub async fn start_copy_out(&mut self, ncolumns: usize) -> Result<()> {
+        commands::encode(&mut self.stream, Message::CopyOutResponse(ncolumns)).await?;
+        Ok(())
+    }
+
+    /// Send a single row of a COPY TO STDOUT to the client.
+    pub async fn send_copy_data(&mut self, row: Vec<u8>) -> Result<()> {
+        commands::encode(&mut self.stream, Message::CopyData(row)).await?;
+        Ok(())
+    }
+
+    /// Finish a COPY TO STDOUT, informing the client that all rows were sent.
+    pub async fn finish_copy_out(&mut self) -> Result<()> {
+        commands::encode(&mut self.stream, Message::CopyDone).await?;
         Ok(())
     }
 
@@ -62,57 +187,101 @@ impl Connection {
     pub async fn send_error(&mut self, err: Error) -> Result<()> {
         commands::encode(
             &mut self.stream,
-            Message::ErrorResponse(commands::ErrorResponse { error: err }),
+            Message::ErrorResponse(commands::ErrorResponse {
+                severity: Severity::Error,
+                error: err,
+            }),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Send the given error back to the client with FATAL severity, which informs that the
+    /// connection will be closed by the server.
+    pub async fn send_fatal_error(&mut self, err: Error) -> Result<()> {
+        commands::encode(
+            &mut self.stream,
+            Message::ErrorResponse(commands::ErrorResponse {
+                severity: Severity::Fatal,
+                error: err,
+            }),
         )
         .await?;
+        self.stream.flush().await?;
         Ok(())
     }
 
-    /// Send to the client that the command returned by receive() is completed.
+    /// Send to the client that a SQL command is completed.
+    ///
+    /// A query message may have multiple commands, so the client is only ready to send a new
+    /// query after ready_for_query is called.
     pub async fn command_complete(&mut self, tag: &str) -> Result<()> {
         commands::encode(
             &mut self.stream,
             Message::CommandComplete(String::from(tag)),
         )
         .await?;
-        self.ready_for_query().await?;
         Ok(())
     }
 
     /// Send a ReadyForQuery to the client.
+    ///
+    /// The client waits for a ReadyForQuery before sending a new message, so all buffered
+    /// messages are flushed to the socket.
     pub async fn ready_for_query(&mut self) -> Result<()> {
         commands::encode(&mut self.stream, Message::ReadyForQuery).await?;
+        self.stream.flush().await?;
         Ok(())
     }
 
     /// Return the startup message from the client.
-    #[async_recursion]
+    ///
+    /// SSL and GSSAPI encryption requests are declined and the client is expected to send
+    /// the startup message in plain text.
     pub async fn startup_message(&mut self) -> Result<StartupMessage> {
-        let msg_size = self.stream.read_u32().await? - 4;
+        loop {
+            let body = match self.read_frame().await? {
+                Frame::Startup(body) => body,
+                Frame::Message { tag, .. } => bail!(ProtocolError::InvalidMessageType(tag)),
+            };
 
-        let mut buf = vec![0; msg_size as usize];
-        self.stream.read(&mut buf).await?;
-        let code = BigEndian::read_u32(&buf);
+            if body.len() < 4 {
+                bail!(ProtocolError::InvalidMessageFormat(String::from(
+                    "startup packet too short"
+                )));
+            }
+            let code = BigEndian::read_u32(&body);
 
-        match code {
-            PROTOCOL_VERSION_NUMBER => StartupMessage::decode(&buf),
-            SSL_REQUEST_NUMBER => {
-                self.stream.write(&"N".as_bytes()).await?;
-                self.startup_message().await
+            match code {
+                PROTOCOL_VERSION_NUMBER => {
+                    let startup_message = StartupMessage::decode(&body)?;
+                    self.codec.set_startup_done();
+                    return Ok(startup_message);
+                }
+                SSL_REQUEST_NUMBER | GSS_ENC_REQ_NUMBER => {
+                    self.stream.write_u8(b'N').await?;
+                    self.stream.flush().await?;
+                }
+                CANCEL_REQUEST_CODE => bail!("cancel requests are not supported"),
+                _ => bail!(ProtocolError::InvalidMessageFormat(format!(
+                    "unsupported frontend protocol {}.{}",
+                    code >> 16,
+                    code & 0xffff
+                ))),
             }
-            _ => anyhow::bail!("Unexpected startup code: {}", code),
         }
     }
 
     /// Send an AuthenticationOk with a ReadForQuery command back to the client.
     pub async fn send_authentication_ok(&mut self) -> Result<()> {
         commands::encode(&mut self.stream, Message::AuthenticationOk).await?;
+        self.codec.set_authenticated();
         self.ready_for_query().await?;
         Ok(())
     }
 
     /// Returns the remote address that this stream is connected to.
-    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        self.stream.get_ref().peer_addr()
+    pub fn peer_addr(&self) -> ClientAddr {
+        self.peer_addr
     }
 }
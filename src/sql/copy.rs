@@ -0,0 +1,832 @@
+use anyhow::{bail, Result};
+use sqlparser::ast;
+
+use crate::{
+    access::{
+        heap::{heap_clear_pending, heap_multi_insert, HeapScanner, ItemRange},
+        heaptuple::{HeapTuple, TupleDesc, HEAP_TUPLE_HEADER_SIZE},
+    },
+    planner::expr::Expr,
+    relation::Relation,
+    sql::{encode::encode_text, SQLError},
+    storage::{BufferPool, PAGE_SIZE},
+    types, Datums,
+};
+
+/// Marker used on text and csv formats to inform the end of data.
+const END_OF_DATA_MARKER: &str = "\\.";
+
+/// Errors related with COPY commands.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The received data does not match the expected format.
+    #[error("{0}")]
+    BadFormat(String),
+
+    /// An invalid option was used on COPY statement.
+    #[error("{0}")]
+    InvalidOption(String),
+
+    /// The client aborted the COPY FROM.
+    #[error("COPY from stdin failed: {0}")]
+    Failed(String),
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::BadFormat(_) => "22P04",
+            Error::InvalidOption(_) => "22023",
+            Error::Failed(_) => "57014",
+        }
+    }
+}
+
+/// Formats of data supported by COPY.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyFormat {
+    Text,
+    Csv,
+}
+
+/// Options of a COPY command.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+    /// Format of data read or written.
+    pub format: CopyFormat,
+
+    /// True if the first line contains the column names.
+    pub header: bool,
+
+    /// Character that separates columns within each row.
+    pub delimiter: char,
+
+    /// String that represents a NULL value.
+    pub null: String,
+
+    /// Quoting character used when a value is quoted. CSV only.
+    pub quote: char,
+
+    /// Character that should appear before a data character that matches the quote value.
+    /// CSV only.
+    pub escape: char,
+}
+
+impl CopyOptions {
+    /// Create the COPY options from the options of a COPY statement.
+    ///
+    /// Both the current syntax and the syntax used before PostgreSQL 9.0 are accepted.
+    pub fn new(
+        options: &[ast::CopyOption],
+        legacy_options: &[ast::CopyLegacyOption],
+    ) -> Result<Self> {
+        let mut format = CopyFormat::Text;
+        let mut header = false;
+        let mut delimiter = None;
+        let mut null = None;
+        let mut quote = None;
+        let mut escape = None;
+
+        for option in options {
+            match option {
+                ast::CopyOption::Format(name) => match name.value.to_lowercase().as_str() {
+                    "text" => format = CopyFormat::Text,
+                    "csv" => format = CopyFormat::Csv,
+                    _ => bail!(Error::InvalidOption(format!(
+                        "COPY format \"{}\" not recognized",
+                        name.value
+                    ))),
+                },
+                ast::CopyOption::Header(value) => header = *value,
+                ast::CopyOption::Delimiter(value) => delimiter = Some(*value),
+                ast::CopyOption::Null(value) => null = Some(value.clone()),
+                ast::CopyOption::Quote(value) => quote = Some(*value),
+                ast::CopyOption::Escape(value) => escape = Some(*value),
+                _ => bail!(SQLError::Unsupported(option.to_string())),
+            }
+        }
+
+        for option in legacy_options {
+            match option {
+                ast::CopyLegacyOption::Delimiter(value) => delimiter = Some(*value),
+                ast::CopyLegacyOption::Null(value) => null = Some(value.clone()),
+                ast::CopyLegacyOption::Csv(csv_options) => {
+                    format = CopyFormat::Csv;
+                    for option in csv_options {
+                        match option {
+                            ast::CopyLegacyCsvOption::Header => header = true,
+                            ast::CopyLegacyCsvOption::Quote(value) => quote = Some(*value),
+                            ast::CopyLegacyCsvOption::Escape(value) => escape = Some(*value),
+                            _ => bail!(SQLError::Unsupported(option.to_string())),
+                        }
+                    }
+                }
+                ast::CopyLegacyOption::Binary => bail!(SQLError::Unsupported(option.to_string())),
+            }
+        }
+
+        if format != CopyFormat::Csv && quote.is_some() {
+            bail!(Error::InvalidOption(String::from(
+                "COPY quote available only in CSV mode"
+            )));
+        }
+        if format != CopyFormat::Csv && escape.is_some() {
+            bail!(Error::InvalidOption(String::from(
+                "COPY escape available only in CSV mode"
+            )));
+        }
+
+        let (default_delimiter, default_null) = match format {
+            CopyFormat::Text => ('\t', "\\N"),
+            CopyFormat::Csv => (',', ""),
+        };
+        let quote = quote.unwrap_or('"');
+
+        let options = Self {
+            format,
+            header,
+            delimiter: delimiter.unwrap_or(default_delimiter),
+            null: null.unwrap_or_else(|| String::from(default_null)),
+            quote,
+            escape: escape.unwrap_or(quote),
+        };
+        options.validate()?;
+
+        Ok(options)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.delimiter.is_ascii() {
+            bail!(Error::InvalidOption(String::from(
+                "COPY delimiter must be a single one-byte character"
+            )));
+        }
+        if self.delimiter == '\r' || self.delimiter == '\n' {
+            bail!(Error::InvalidOption(String::from(
+                "COPY delimiter cannot be newline or carriage return"
+            )));
+        }
+        if self.null.contains('\r') || self.null.contains('\n') {
+            bail!(Error::InvalidOption(String::from(
+                "COPY null representation cannot use newline or carriage return"
+            )));
+        }
+        if self.null.contains(self.delimiter) {
+            bail!(Error::InvalidOption(String::from(
+                "COPY delimiter must not appear in the NULL specification"
+            )));
+        }
+        if self.format == CopyFormat::Csv {
+            if !self.quote.is_ascii() {
+                bail!(Error::InvalidOption(String::from(
+                    "COPY quote must be a single one-byte character"
+                )));
+            }
+            if !self.escape.is_ascii() {
+                bail!(Error::InvalidOption(String::from(
+                    "COPY escape must be a single one-byte character"
+                )));
+            }
+            if self.delimiter == self.quote {
+                bail!(Error::InvalidOption(String::from(
+                    "COPY delimiter and quote must be different"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return the indexes on tuple desc attributes of the given columns. All attributes are returned
+/// if columns is empty.
+pub fn column_indexes(
+    tuple_desc: &TupleDesc,
+    rel_name: &str,
+    columns: &[ast::Ident],
+) -> Result<Vec<usize>> {
+    if columns.is_empty() {
+        return Ok((0..tuple_desc.attrs.len()).collect());
+    }
+
+    let mut indexes = Vec::with_capacity(columns.len());
+    for column in columns {
+        match tuple_desc
+            .attrs
+            .iter()
+            .position(|attr| attr.attname == column.value)
+        {
+            Some(index) => indexes.push(index),
+            None => bail!(SQLError::UndefinedColumn(
+                column.value.clone(),
+                rel_name.to_string()
+            )),
+        }
+    }
+    Ok(indexes)
+}
+
+/// State of a COPY FROM in progress.
+///
+/// The data received from the client is not aligned with rows, so the bytes of an incomplete
+/// row are kept until the rest of row is received.
+///
+/// Tuples are inserted on relation in batches of about a page, but are written as pending, so
+/// they are not visible until the end of data is processed without errors. Like an INSERT, a
+/// COPY FROM that fails does not insert any row, and the pending tuples written before the
+/// error are never made visible.
+pub struct CopyFrom {
+    /// Buffer pool used to write the new tuples.
+    buffer_pool: BufferPool,
+
+    /// Relation that tuples are inserted.
+    rel: Relation,
+
+    /// Tuple descriptor of relation.
+    tuple_desc: TupleDesc,
+
+    /// Index on tuple descriptor of each column on a row.
+    columns: Vec<usize>,
+
+    /// Default value of each attribute, used for the attributes that are not columns.
+    defaults: Vec<Option<Expr>>,
+
+    /// Options of COPY command.
+    options: CopyOptions,
+
+    /// Received bytes that don't form a complete row yet.
+    buffer: Vec<u8>,
+
+    /// Tuples waiting to be inserted on relation.
+    tuples: Vec<HeapTuple>,
+
+    /// Size in bytes of the tuples waiting to be inserted.
+    tuples_size: usize,
+
+    /// Items of the pending tuples already inserted on relation.
+    inserted: Vec<ItemRange>,
+
+    /// True if the header line was already skipped.
+    header_skipped: bool,
+
+    /// True if the end of data marker was received.
+    done: bool,
+
+    /// Total of rows processed.
+    processed: u64,
+}
+
+impl CopyFrom {
+    /// Create a new COPY FROM state to insert rows into the given relation.
+    pub fn new(
+        buffer_pool: &BufferPool,
+        rel: Relation,
+        tuple_desc: TupleDesc,
+        columns: Vec<usize>,
+        defaults: Vec<Option<Expr>>,
+        options: CopyOptions,
+    ) -> Self {
+        Self {
+            buffer_pool: buffer_pool.clone(),
+            rel,
+            tuple_desc,
+            columns,
+            defaults,
+            options,
+            buffer: Vec::new(),
+            tuples: Vec::new(),
+            tuples_size: 0,
+            inserted: Vec::new(),
+            header_skipped: false,
+            done: false,
+            processed: 0,
+        }
+    }
+
+    /// Return the number of columns of each row.
+    pub fn ncolumns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Process a chunk of data received from client. All complete rows on data are parsed.
+    pub fn process(&mut self, data: &[u8]) -> Result<()> {
+        if self.done {
+            return Ok(());
+        }
+        self.buffer.extend_from_slice(data);
+        self.process_rows(false)
+    }
+
+    /// Process any remaining data, insert the tuples waiting to be inserted and make all
+    /// inserted tuples visible. Return the total of rows inserted.
+    pub fn finish(mut self) -> Result<u64> {
+        self.process_rows(true)?;
+        self.flush_tuples()?;
+        heap_clear_pending(&self.buffer_pool, &self.rel, &self.inserted)?;
+        Ok(self.processed)
+    }
+
+    /// Insert the tuples waiting to be inserted on relation as pending tuples.
+    fn flush_tuples(&mut self) -> Result<()> {
+        let ranges = heap_multi_insert(&self.buffer_pool, &self.rel, &self.tuples)?;
+        self.inserted.extend(ranges);
+        self.tuples.clear();
+        self.tuples_size = 0;
+        Ok(())
+    }
+
+    fn process_rows(&mut self, eof: bool) -> Result<()> {
+        while !self.done {
+            let line = match self.next_line(eof) {
+                Some(line) => line,
+                None => break,
+            };
+
+            let line = String::from_utf8(line).map_err(|_| {
+                Error::BadFormat(String::from("invalid byte sequence for encoding \"UTF8\""))
+            })?;
+
+            if line == END_OF_DATA_MARKER {
+                self.done = true;
+                break;
+            }
+
+            if self.options.header && !self.header_skipped {
+                self.header_skipped = true;
+                continue;
+            }
+
+            let fields = match self.options.format {
+                CopyFormat::Text => parse_text_line(&line, &self.options)?,
+                CopyFormat::Csv => parse_csv_line(&line, &self.options)?,
+            };
+            self.insert_row(fields)?;
+        }
+        Ok(())
+    }
+
+    /// Remove and return the next complete line from buffer without the line terminator. If eof
+    /// is true the remaining data is returned even without a line terminator.
+    fn next_line(&mut self, eof: bool) -> Option<Vec<u8>> {
+        let end = match self.options.format {
+            CopyFormat::Text => self.buffer.iter().position(|b| *b == b'\n'),
+            CopyFormat::Csv => self.csv_line_end(eof),
+        };
+
+        let mut line = match end {
+            Some(end) => {
+                let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+                line.pop();
+                line
+            }
+            None if eof && !self.buffer.is_empty() => self.buffer.drain(..).collect(),
+            None => return None,
+        };
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Some(line)
+    }
+
+    /// Return the position of the newline that terminates the first csv row on buffer. Newlines
+    /// inside quoted values don't terminate a row.
+    fn csv_line_end(&self, eof: bool) -> Option<usize> {
+        let quote = self.options.quote as u8;
+        let escape = self.options.escape as u8;
+
+        let mut in_quotes = false;
+        let mut i = 0;
+        while i < self.buffer.len() {
+            let b = self.buffer[i];
+            if in_quotes {
+                if b == escape && i + 1 < self.buffer.len() {
+                    let next = self.buffer[i + 1];
+                    if next == quote || next == escape {
+                        i += 2;
+                        continue;
+                    }
+                } else if b == escape && !eof {
+                    // Wait for more data to know if the character is escaped.
+                    return None;
+                }
+                if b == quote {
+                    in_quotes = false;
+                }
+            } else if b == quote {
+                in_quotes = true;
+            } else if b == b'\n' {
+                return Some(i);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// Convert the fields of a row into a tuple and add to pending tuples.
+    fn insert_row(&mut self, fields: Vec<Option<String>>) -> Result<()> {
+        if fields.len() > self.columns.len() {
+            bail!(Error::BadFormat(String::from(
+                "extra data after last expected column"
+            )));
+        }
+        if fields.len() < self.columns.len() {
+            let attr = &self.tuple_desc.attrs[self.columns[fields.len()]];
+            bail!(Error::BadFormat(format!(
+                "missing data for column \"{}\"",
+                attr.attname
+            )));
+        }
+
+        // Attributes that are not present on columns are filled with their default value.
+        let mut values = Datums::default();
+        for (index, attr) in self.tuple_desc.attrs.iter().enumerate() {
+            let pos = self.columns.iter().position(|column| *column == index);
+
+            match (pos, &self.defaults[index]) {
+                (Some(pos), _) => match &fields[pos] {
+                    Some(value) => encode_text(&mut values, value, attr)?,
+                    None => values.push(None),
+                },
+                (None, Some(default)) => values.push(default.eval(&[])?),
+                (None, None) => values.push(None),
+            }
+        }
+
+        let mut tuple = HeapTuple::from_datums(values, &self.tuple_desc)?;
+        tuple.header.set_pending(true);
+        self.tuples_size += HEAP_TUPLE_HEADER_SIZE + tuple.data.len();
+        self.tuples.push(tuple);
+        self.processed += 1;
+
+        // Keep at most about a page of tuples in memory.
+        if self.tuples_size >= PAGE_SIZE {
+            self.flush_tuples()?;
+        }
+        Ok(())
+    }
+}
+
+/// Split a line on text format into fields, decoding backslash escape sequences.
+fn parse_text_line(line: &str, options: &CopyOptions) -> Result<Vec<Option<String>>> {
+    let mut fields = Vec::new();
+
+    // The NULL representation is compared against the raw field, before escape sequences
+    // are decoded.
+    let mut raw = String::new();
+    let mut value = Vec::new();
+
+    let mut chars = line.chars().peekable();
+    loop {
+        match chars.next() {
+            None => {
+                fields.push(text_field(&raw, value, options)?);
+                break;
+            }
+            Some(c) if c == options.delimiter => {
+                fields.push(text_field(&raw, value, options)?);
+                raw.clear();
+                value = Vec::new();
+            }
+            Some('\\') => {
+                raw.push('\\');
+                let c = match chars.next() {
+                    Some(c) => c,
+                    None => {
+                        value.push(b'\\');
+                        continue;
+                    }
+                };
+                raw.push(c);
+                match c {
+                    'b' => value.push(0x08),
+                    'f' => value.push(0x0c),
+                    'n' => value.push(b'\n'),
+                    'r' => value.push(b'\r'),
+                    't' => value.push(b'\t'),
+                    'v' => value.push(0x0b),
+                    '0'..='7' => {
+                        let mut byte = c.to_digit(8).unwrap_or_default();
+                        for _ in 0..2 {
+                            match chars.peek().and_then(|c| c.to_digit(8)) {
+                                Some(digit) => {
+                                    byte = (byte << 3) + digit;
+                                    raw.push(chars.next().unwrap_or_default());
+                                }
+                                None => break,
+                            }
+                        }
+                        value.push(byte as u8);
+                    }
+                    'x' if chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) => {
+                        let mut byte = 0;
+                        for _ in 0..2 {
+                            match chars.peek().and_then(|c| c.to_digit(16)) {
+                                Some(digit) => {
+                                    byte = (byte << 4) + digit;
+                                    raw.push(chars.next().unwrap_or_default());
+                                }
+                                None => break,
+                            }
+                        }
+                        value.push(byte as u8);
+                    }
+                    c => push_char(&mut value, c),
+                }
+            }
+            Some(c) => {
+                raw.push(c);
+                push_char(&mut value, c);
+            }
+        }
+    }
+
+    Ok(fields)
+}
+
+fn text_field(raw: &str, value: Vec<u8>, options: &CopyOptions) -> Result<Option<String>> {
+    if raw == options.null {
+        return Ok(None);
+    }
+    let value = String::from_utf8(value).map_err(|_| {
+        Error::BadFormat(String::from("invalid byte sequence for encoding \"UTF8\""))
+    })?;
+    Ok(Some(value))
+}
+
+fn push_char(buf: &mut Vec<u8>, c: char) {
+    let mut tmp = [0; 4];
+    buf.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+}
+
+/// Split a line on csv format into fields, removing the quotes of quoted values.
+///
+/// Unquoted values that match the NULL representation are NULL, a quoted value is never NULL.
+fn parse_csv_line(line: &str, options: &CopyOptions) -> Result<Vec<Option<String>>> {
+    let mut fields = Vec::new();
+
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+
+    let mut chars = line.chars().peekable();
+    loop {
+        match chars.next() {
+            None if in_quotes => bail!(Error::BadFormat(String::from(
+                "unterminated CSV quoted field"
+            ))),
+            None => {
+                fields.push(csv_field(value, quoted, options));
+                break;
+            }
+            Some(c) if in_quotes => {
+                let escaped = c == options.escape
+                    && chars
+                        .peek()
+                        .is_some_and(|next| *next == options.quote || *next == options.escape);
+
+                if escaped {
+                    value.push(chars.next().unwrap_or_default());
+                } else if c == options.quote {
+                    in_quotes = false;
+                } else {
+                    value.push(c);
+                }
+            }
+            Some(c) if c == options.delimiter => {
+                fields.push(csv_field(value, quoted, options));
+                value = String::new();
+                quoted = false;
+            }
+            Some(c) if c == options.quote => {
+                in_quotes = true;
+                quoted = true;
+            }
+            Some(c) => value.push(c),
+        }
+    }
+
+    Ok(fields)
+}
+
+fn csv_field(value: String, quoted: bool, options: &CopyOptions) -> Option<String> {
+    if !quoted && value == options.null {
+        return None;
+    }
+    Some(value)
+}
+
+/// State of a COPY TO in progress.
+///
+/// The rows are formatted while the relation is scanned, so they can be sent to the client
+/// without keeping the whole relation in memory.
+pub struct CopyTo {
+    /// Scan over the relation being copied.
+    heap: HeapScanner,
+
+    /// Tuple descriptor of relation.
+    tuple_desc: TupleDesc,
+
+    /// Index on tuple descriptor of each column on a row.
+    columns: Vec<usize>,
+
+    /// Options of COPY command.
+    options: CopyOptions,
+
+    /// True if the header line should be returned before the first row.
+    header_pending: bool,
+
+    /// Total of rows returned, without the header line.
+    processed: u64,
+}
+
+impl CopyTo {
+    /// Start a COPY TO over the given relation.
+    pub fn new(
+        buffer_pool: &BufferPool,
+        rel: &Relation,
+        tuple_desc: TupleDesc,
+        columns: Vec<usize>,
+        options: CopyOptions,
+    ) -> Result<Self> {
+        Ok(Self {
+            heap: HeapScanner::new(buffer_pool, rel)?,
+            tuple_desc,
+            columns,
+            header_pending: options.header,
+            options,
+            processed: 0,
+        })
+    }
+
+    /// Return the number of columns of each row.
+    pub fn ncolumns(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Return the total of rows returned so far, the header line is not counted.
+    pub fn processed(&self) -> u64 {
+        self.processed
+    }
+
+    /// Return the next line formatted as COPY TO output, terminated by a newline. None is
+    /// returned when all rows are returned.
+    pub fn next_row(&mut self) -> Result<Option<Vec<u8>>> {
+        if self.header_pending {
+            self.header_pending = false;
+            let names = self
+                .columns
+                .iter()
+                .map(|column| Some(self.tuple_desc.attrs[*column].attname.clone()))
+                .collect::<Vec<_>>();
+            return Ok(Some(format_row(&names, &self.options)));
+        }
+
+        let tuple = match self.heap.next_tuple()? {
+            Some(tuple) => tuple,
+            None => return Ok(None),
+        };
+
+        let mut values = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            let attr = &self.tuple_desc.attrs[*column];
+            match tuple.get_attr(attr.attnum, &self.tuple_desc)? {
+                Some(datum) => {
+                    values.push(Some(types::lookup_type(attr.atttypid)?.output(&datum)?))
+                }
+                None => values.push(None),
+            }
+        }
+
+        self.processed += 1;
+        Ok(Some(format_row(&values, &self.options)))
+    }
+}
+
+/// Format a single row of values using the given options.
+fn format_row(values: &[Option<String>], options: &CopyOptions) -> Vec<u8> {
+    let mut row = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            row.push(options.delimiter);
+        }
+        match value {
+            Some(value) => match options.format {
+                CopyFormat::Text => format_text_value(&mut row, value, options),
+                CopyFormat::Csv => format_csv_value(&mut row, value, options),
+            },
+            None => row.push_str(&options.null),
+        }
+    }
+    row.push('\n');
+    row.into_bytes()
+}
+
+fn format_text_value(row: &mut String, value: &str, options: &CopyOptions) {
+    for c in value.chars() {
+        match c {
+            '\\' => row.push_str("\\\\"),
+            '\n' => row.push_str("\\n"),
+            '\r' => row.push_str("\\r"),
+            '\t' if options.delimiter == '\t' => row.push_str("\\t"),
+            c if c == options.delimiter => {
+                row.push('\\');
+                row.push(c);
+            }
+            c => row.push(c),
+        }
+    }
+}
+
+fn format_csv_value(row: &mut String, value: &str, options: &CopyOptions) {
+    let needs_quote = value == options.null
+        || value == END_OF_DATA_MARKER
+        || value
+            .chars()
+            .any(|c| c == options.delimiter || c == options.quote || c == '\r' || c == '\n');
+
+    if !needs_quote {
+        row.push_str(value);
+        return;
+    }
+
+    row.push(options.quote);
+    for c in value.chars() {
+        if c == options.quote || c == options.escape {
+            row.push(options.escape);
+        }
+        row.push(c);
+    }
+    row.push(options.quote);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv_options() -> CopyOptions {
+        CopyOptions::new(&[], &[ast::CopyLegacyOption::Csv(vec![])]).unwrap()
+    }
+
+    #[test]
+    fn test_parse_text_line() -> Result<()> {
+        let options = CopyOptions::new(&[], &[])?;
+        assert_eq!(
+            parse_text_line("1\t\\N\ta\\tb\\\\c\\101", &options)?,
+            vec![
+                Some(String::from("1")),
+                None,
+                Some(String::from("a\tb\\cA")),
+            ]
+        );
+        assert_eq!(parse_text_line("", &options)?, vec![Some(String::new())]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_csv_line() -> Result<()> {
+        let options = csv_options();
+        assert_eq!(
+            parse_csv_line("1,,\"\",\"a,\"\"b\"\"\nc\"", &options)?,
+            vec![
+                Some(String::from("1")),
+                None,
+                Some(String::new()),
+                Some(String::from("a,\"b\"\nc")),
+            ]
+        );
+        assert!(parse_csv_line("1,\"abc", &options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_row() {
+        let values = vec![
+            Some(String::from("a\tb")),
+            None,
+            Some(String::new()),
+            Some(String::from("x,\"y\"")),
+        ];
+        let options = CopyOptions::new(&[], &[]).unwrap();
+        assert_eq!(format_row(&values, &options), b"a\\tb\t\\N\t\tx,\"y\"\n");
+
+        let options = csv_options();
+        assert_eq!(
+            format_row(&values, &options),
+            b"a\tb,,\"\",\"x,\"\"y\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(CopyOptions::new(&[ast::CopyOption::Quote('\'')], &[]).is_err());
+        assert!(CopyOptions::new(
+            &[
+                ast::CopyOption::Format(ast::Ident::new("csv")),
+                ast::CopyOption::Delimiter('"'),
+            ],
+            &[]
+        )
+        .is_err());
+    }
+}
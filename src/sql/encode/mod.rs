@@ -1,58 +1,21 @@
 use std::{convert::TryFrom, mem::size_of};
 
-use anyhow::{bail, Result};
+use anyhow::Result;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use sqlparser::ast;
 
-use crate::{
-    catalog::{pg_attribute::PgAttribute, pg_type},
-    Datum, Datums, Oid,
-};
+use crate::{catalog::pg_attribute::PgAttribute, types, Datums};
 
-/// Encode the ast value to a Datum representation.
-pub fn encode(encode_to: &mut Datums, value: &ast::Value, attr: &PgAttribute) -> Result<()> {
-    match value {
-        ast::Value::Number(value, _) => {
-            let value = value.parse::<i32>()?;
-            bincode::serialize_into(encode_to, &value)?;
-        }
-        ast::Value::SingleQuotedString(s) => {
-            if attr.attlen >= 0 && (s.len() > attr.attlen as usize) {
-                bail!("value too long for type character varying({})", attr.attlen);
-            }
-            let varlena = Varlena::try_from(s)?;
+pub mod numeric;
 
-            // TODO: Try to understand why I can't use bincode::serialize_into(encode_to, &varlena)
-            // here.
-            // Seems that using serialize_into the datums that are appended is splited between
-            // multiple vectors, which cause erros when reading.
-            let data = bincode::serialize(&varlena)?;
-            encode_to.push(Some(data));
-        }
-        ast::Value::Null => {
-            encode_to.push(None);
-        }
-        ast::Value::Boolean(value) => {
-            bincode::serialize_into(encode_to, value)?;
-        }
-        _ => bail!("Unsupported value {}", value.to_string()),
-    };
+/// Encode the external text representation of a value to a Datum representation.
+///
+/// This is used when the values are not SQL expressions, e.g. the values of a COPY FROM.
+pub fn encode_text(encode_to: &mut Datums, value: &str, attr: &PgAttribute) -> Result<()> {
+    let typ = types::lookup_type(attr.atttypid)?;
+    encode_to.push(Some(typ.input(value, attr.atttypmod)?));
     Ok(())
 }
 
-/// Decode a raw tuple to a SQL value.
-//
-// TODO: Change the return type to a more generic type
-// that represents a SQL value.
-pub fn decode(datum: &Datum, typ: Oid) -> Result<String> {
-    match typ {
-        pg_type::INT_OID => Ok(bincode::deserialize::<i32>(&datum)?.to_string()),
-        pg_type::VARCHAR_OID => Ok(bincode::deserialize::<String>(&datum)?),
-        pg_type::BOOL_OID => Ok(bincode::deserialize::<bool>(&datum)?.to_string()),
-        _ => bail!("decode: Unsupported type to decode"),
-    }
-}
-
 /// Variable-length datatypes all share the 'struct varlena' header.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Varlena {
@@ -71,15 +34,19 @@ impl TryFrom<&String> for Varlena {
 
     /// Create a new varlena from a string.
     fn try_from(value: &String) -> Result<Self, Self::Error> {
-        let data = bincode::serialize(&value)?;
-        Ok(Self {
-            v_len: bincode::serialize(&data)?.len() as u32,
-            v_data: data,
-        })
+        Self::new(&bincode::serialize(&value)?)
     }
 }
 
 impl Varlena {
+    /// Create a new varlena with the given data contents.
+    pub fn new(data: &[u8]) -> Result<Self, bincode::Error> {
+        Ok(Self {
+            v_len: bincode::serialized_size(data)? as u32,
+            v_data: data.to_vec(),
+        })
+    }
+
     /// Compute the total length of varlena value.
     pub fn len(&self) -> usize {
         size_of::<u32>() + self.v_len as usize
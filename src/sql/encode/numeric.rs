@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Base of the digits of a numeric value.
+pub const NBASE: i32 = 10000;
+
+/// Number of decimal digits per NBASE digit.
+pub const DEC_DIGITS: usize = 4;
+
+/// Sign of a positive numeric value.
+pub const NUMERIC_POS: u16 = 0x0000;
+
+/// Sign of a negative numeric value.
+pub const NUMERIC_NEG: u16 = 0x4000;
+
+/// Sign of a numeric NaN value.
+pub const NUMERIC_NAN: u16 = 0xC000;
+
+/// On-disk format of a numeric value, stored on the data of a varlena.
+///
+/// Like PostgreSQL, the value is stored as a sequence of base NBASE digits with the weight of
+/// the first digit, so the value is digits[0] * NBASE^weight + digits[1] * NBASE^(weight - 1)
+/// and so on. Leading and trailing zero digits are not stored, so zero has no digits at all.
+///
+/// This is also the layout of the binary wire format of numeric values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NumericData {
+    /// One of NUMERIC_POS, NUMERIC_NEG or NUMERIC_NAN.
+    pub sign: u16,
+
+    /// Weight of the first digit.
+    pub weight: i16,
+
+    /// Number of decimal digits after the decimal point that are displayed.
+    pub dscale: u16,
+
+    /// Base NBASE digits, each one between 0 and NBASE - 1.
+    pub digits: Vec<i16>,
+}
@@ -1,37 +1,165 @@
 use crate::{
     access::{
         self,
-        heap::heap_insert,
+        heap::heap_multi_insert,
         heaptuple::{HeapTuple, TupleDesc},
     },
     catalog::{
-        self, heap::heap_create, pg_attribute::PgAttribute, pg_tablespace::DEFAULTTABLESPACE_OID,
+        self,
+        heap::heap_create,
+        pg_attribute::{PgAttribute, ATTRIBUTE_IDENTITY_ALWAYS, ATTRIBUTE_IDENTITY_BY_DEFAULT},
+        pg_tablespace::DEFAULTTABLESPACE_OID,
         pg_type,
     },
     executor::{Executor, TupleTable},
-    planner::Plan,
+    planner::{
+        self,
+        expr::{coerce_to_target_type, param_number, transform_expr, type_name, Expr, Scope},
+        Plan,
+    },
+    relation::Relation,
     storage::BufferPool,
-    Datums, Oid,
+    types::{self, cast::CoercionContext, datetime::TimeZone, TypeKind},
+    Datum, Datums, Oid,
 };
 use anyhow::{bail, Result};
-use encode::encode;
-use sqlparser::ast;
-use std::mem::size_of;
+use copy::{CopyFrom, CopyOptions, CopyTo};
+use parser::{ConflictTarget, Identity, OnConflict, SequenceOptions, TypeRepresentation};
+use sqlparser::{ast, dialect::PostgreSqlDialect};
+use std::sync::Arc;
 
+pub mod copy;
 pub mod encode;
+pub mod parser;
+pub mod sequence;
+pub mod session;
 
 /// Errors related with a SQL command
 #[derive(Debug, thiserror::Error)]
 pub enum SQLError {
     /// Unsupported SQL operation.
-    #[error("unsuported operation {0}")]
+    #[error("unsupported operation {0}")]
     Unsupported(String),
+
+    /// Column does not exist on relation.
+    #[error("column \"{0}\" of relation \"{1}\" does not exist")]
+    UndefinedColumn(String, String),
+
+    /// Column referenced by an expression does not exist.
+    #[error("column \"{0}\" does not exist")]
+    UndefinedColumnReference(String),
+
+    /// Relation used to qualify a column reference is not on FROM.
+    #[error("missing FROM-clause entry for table \"{0}\"")]
+    MissingFromEntry(String),
+
+    /// Value can not be converted to the type of a column.
+    #[error("column \"{0}\" is of type {1} but expression is of type {2}")]
+    DatatypeMismatch(String, &'static str, &'static str),
+
+    /// Configuration parameter does not exist.
+    #[error("unrecognized configuration parameter \"{0}\"")]
+    UnrecognizedParameter(String),
+
+    /// Column is used outside of an aggregate function on a query with aggregates.
+    #[error(
+        "column \"{0}\" must appear in the GROUP BY clause or be used in an aggregate function"
+    )]
+    GroupingError(String),
+
+    #[error("VALUES lists must all be the same length")]
+    ValuesListsLength,
+
+    #[error("table \"{0}\" has {1} columns available but {2} columns specified")]
+    TooManyColumnAliases(String, usize, usize),
+
+    #[error("SELECT * with no tables specified is not valid")]
+    WildcardWithoutTables,
+
+    #[error("INSERT has more expressions than target columns")]
+    InsertTooManyExpressions,
+
+    #[error("INSERT has more target columns than expressions")]
+    InsertTooManyTargetColumns,
+
+    /// ON CONFLICT names columns of a unique index, which relations do not have yet.
+    #[error("there is no unique or exclusion constraint matching the ON CONFLICT specification")]
+    NoConflictConstraint,
+
+    #[error("constraint \"{0}\" for table \"{1}\" does not exist")]
+    UndefinedConstraint(String, String),
+
+    /// Default value can not be converted to the type of a column.
+    #[error("column \"{0}\" is of type {1} but default expression is of type {2}")]
+    DefaultDatatypeMismatch(String, &'static str, &'static str),
+
+    #[error("multiple default values specified for column \"{0}\" of table \"{1}\"")]
+    MultipleDefaults(String, String),
+
+    #[error("both default and identity specified for column \"{0}\" of table \"{1}\"")]
+    DefaultAndIdentity(String, String),
+
+    #[error("identity column type must be smallint, integer, or bigint")]
+    InvalidIdentityType,
+
+    #[error("column \"{0}\" has pseudo-type {1}")]
+    PseudoTypeColumn(String, &'static str),
+
+    #[error("ESCAPE can only be used with LIKE, ILIKE or SIMILAR TO")]
+    MisplacedEscape,
+
+    /// Value given to a GENERATED ALWAYS identity column.
+    #[error("cannot insert a non-DEFAULT value into column \"{0}\"")]
+    GeneratedAlways(String),
+
+    /// Parameter placeholder without a value bound to it.
+    #[error("there is no parameter {0}")]
+    UndefinedParameter(String),
+
+    /// Set returning function called outside of FROM.
+    #[error("set-returning function {0} is only supported in FROM")]
+    SetFunctionOutsideFrom(String),
+}
+
+impl SQLError {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            SQLError::Unsupported(_) => "0A000",
+            SQLError::UndefinedColumn(..) => "42703",
+            SQLError::UndefinedColumnReference(_) => "42703",
+            SQLError::MissingFromEntry(_) => "42P01",
+            SQLError::DatatypeMismatch(..) => "42804",
+            SQLError::UnrecognizedParameter(_) => "42704",
+            SQLError::GroupingError(_) => "42803",
+            SQLError::ValuesListsLength => "42601",
+            SQLError::TooManyColumnAliases(..) => "42P10",
+            SQLError::WildcardWithoutTables => "42601",
+            SQLError::InsertTooManyExpressions => "42601",
+            SQLError::InsertTooManyTargetColumns => "42601",
+            SQLError::NoConflictConstraint => "42P10",
+            SQLError::UndefinedConstraint(..) => "42704",
+            SQLError::DefaultDatatypeMismatch(..) => "42804",
+            SQLError::MultipleDefaults(..) => "42601",
+            SQLError::DefaultAndIdentity(..) => "42601",
+            SQLError::InvalidIdentityType => "22023",
+            SQLError::PseudoTypeColumn(..) => "42P16",
+            SQLError::MisplacedEscape => "42601",
+            SQLError::GeneratedAlways(_) => "428C9",
+            SQLError::UndefinedParameter(_) => "42P02",
+            SQLError::SetFunctionOutsideFrom(_) => "0A000",
+        }
+    }
 }
 
 /// An executor config holds per connection configuration values.
 pub struct ExecutorConfig {
     /// Oid of database that is connect in.
     pub database: Oid,
+
+    /// Time zone of the session when the connection starts, also used when the time zone is
+    /// set to DEFAULT.
+    pub timezone: TimeZone,
 }
 
 /// A connection executor is in charge of executing queries on a give database connection.
@@ -58,12 +186,240 @@ impl ConnectionExecutor {
         Ok(PGResult::from(tuple_table))
     }
 
+    /// Change the value of a configuration parameter of the session.
+    ///
+    /// TimeZone is the only parameter that can be changed.
+    pub fn exec_set(
+        &self,
+        variable: &ast::ObjectName,
+        values: &[ast::SetVariableValue],
+    ) -> Result<()> {
+        let name = variable.to_string();
+        if !name.eq_ignore_ascii_case("timezone") {
+            bail!(SQLError::UnrecognizedParameter(name));
+        }
+
+        let value = match values {
+            [ast::SetVariableValue::Ident(ident)] => &ident.value,
+            [ast::SetVariableValue::Literal(ast::Value::SingleQuotedString(value))]
+            | [ast::SetVariableValue::Literal(ast::Value::Number(value, _))] => value,
+            _ => bail!(SQLError::Unsupported(format!(
+                "SET {} TO {}",
+                name,
+                values
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        };
+
+        let timezone =
+            if value.eq_ignore_ascii_case("default") || value.eq_ignore_ascii_case("local") {
+                self.config.timezone.clone()
+            } else {
+                TimeZone::parse(value)?
+            };
+        session::set_timezone(timezone);
+        Ok(())
+    }
+
+    /// Return the value of a configuration parameter of the session.
+    pub fn exec_show(&self, variable: &[ast::Ident]) -> Result<PGResult> {
+        let name = variable
+            .iter()
+            .map(|ident| ident.value.clone())
+            .collect::<Vec<_>>()
+            .join(".");
+        if !name.eq_ignore_ascii_case("timezone") {
+            bail!(SQLError::UnrecognizedParameter(name));
+        }
+
+        let mut tuple = Datums::default();
+        tuple.push(Some(bincode::serialize(session::timezone().name())?));
+
+        Ok(PGResult {
+            desc: RowDescriptor {
+                fields: vec![FieldDescription {
+                    name: b"TimeZone".to_vec(),
+                    table_oid: 0,
+                    table_attribute_number: 0,
+                    data_type_oid: pg_type::TEXT_OID as u32,
+                    data_type_size: -1,
+                    type_modifier: -1,
+                    format: 0,
+                }],
+            },
+            tuples: vec![tuple],
+        })
+    }
+
+    /// Insert the rows of a VALUES list or of a query into the given table. Return the number
+    /// of inserted rows and, if there is a RETURNING list, the values it computes for each
+    /// inserted row.
+    ///
+    /// All rows are evaluated before any tuple is written, so an error on any row leaves the
+    /// table unchanged. Without unique indexes no row can conflict, so ON CONFLICT DO NOTHING
+    /// inserts all rows, while a conflict target is rejected since no index can match it.
     pub fn exec_insert(
         &self,
         table_name: &ast::ObjectName,
-        columns: &Vec<ast::Ident>,
+        columns: &[ast::Ident],
         source: &Box<ast::Query>,
-    ) -> Result<()> {
+        on_conflict: Option<&OnConflict>,
+        returning: &[ast::SelectItem],
+    ) -> Result<(usize, Option<PGResult>)> {
+        let (rel, tuple_desc) = self.open_relation(table_name)?;
+        let listed_columns = !columns.is_empty();
+        let columns = copy::column_indexes(&tuple_desc, &rel.rel_name, columns)?;
+
+        match on_conflict.and_then(|on_conflict| on_conflict.target.as_ref()) {
+            Some(ConflictTarget::Columns(_)) => bail!(SQLError::NoConflictConstraint),
+            Some(ConflictTarget::Constraint(name)) => bail!(SQLError::UndefinedConstraint(
+                name.value.clone(),
+                rel.rel_name.clone()
+            )),
+            None => {}
+        }
+
+        let scope = Scope {
+            name: &rel.rel_name,
+            tuple_desc: &tuple_desc,
+        };
+        let (projection, targets) = planner::transform_target_list(returning, &scope)?;
+
+        let rows = match &source.body {
+            ast::SetExpr::Values(values) => transform_insert_rows(values, &tuple_desc, &columns)?,
+            _ => {
+                let mut plan = Plan::create(&self.buffer_pool, &self.config.database, source)?;
+                let tuple_table = Executor::new().exec(&mut plan)?;
+                tuple_table
+                    .values
+                    .iter()
+                    .map(|values| {
+                        values
+                            .iter()
+                            .zip(&tuple_table.tuple_desc.attrs)
+                            .map(|(value, attr)| {
+                                Some(Expr::Const {
+                                    typ: attr.atttypid,
+                                    value: value.clone(),
+                                })
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+        };
+
+        let defaults = self.column_defaults(&rel, &tuple_desc)?;
+
+        let mut tuples = Vec::with_capacity(rows.len());
+        let mut returned = Vec::new();
+        for row in rows {
+            let values = insert_values(&tuple_desc, &columns, listed_columns, row, &defaults)?;
+            if !returning.is_empty() {
+                let mut slot = Datums::default();
+                for target in &targets {
+                    slot.push(target.eval(&values)?);
+                }
+                returned.push(slot);
+            }
+            tuples.push(HeapTuple::from_datums(Datums::from(values), &tuple_desc)?);
+        }
+
+        heap_multi_insert(&self.buffer_pool, &rel, &tuples)?;
+
+        let result = if returning.is_empty() {
+            None
+        } else {
+            Some(PGResult::from(TupleTable {
+                tuple_desc: Arc::new(TupleDesc { attrs: projection }),
+                values: returned,
+            }))
+        };
+        Ok((tuples.len(), result))
+    }
+
+    /// Return the description of the rows returned by the given query, without executing it.
+    pub fn describe_query(&self, query: &ast::Query) -> Result<RowDescriptor> {
+        let plan = Plan::create(&self.buffer_pool, &self.config.database, query)?;
+        Ok(RowDescriptor::from(plan.output()?))
+    }
+
+    /// Return the description of the rows returned by the RETURNING list of an INSERT, or None
+    /// if there is no RETURNING list, without inserting any row.
+    ///
+    /// The values being inserted are also transformed, so parameters of unknown type that are
+    /// inserted on a column take the type of the column.
+    pub fn describe_insert(
+        &self,
+        table_name: &ast::ObjectName,
+        columns: &[ast::Ident],
+        source: &ast::Query,
+        returning: &[ast::SelectItem],
+    ) -> Result<Option<RowDescriptor>> {
+        let (rel, tuple_desc) = self.open_relation(table_name)?;
+        let columns = copy::column_indexes(&tuple_desc, &rel.rel_name, columns)?;
+
+        match &source.body {
+            ast::SetExpr::Values(values) => {
+                transform_insert_rows(values, &tuple_desc, &columns)?;
+            }
+            _ => {
+                self.describe_query(source)?;
+            }
+        }
+
+        if returning.is_empty() {
+            return Ok(None);
+        }
+        let scope = Scope {
+            name: &rel.rel_name,
+            tuple_desc: &tuple_desc,
+        };
+        let (projection, _) = planner::transform_target_list(returning, &scope)?;
+        Ok(Some(RowDescriptor::from(&projection[..])))
+    }
+
+    /// Start a COPY FROM into the given table. The data sent by the client should be passed to
+    /// the returned state.
+    pub fn copy_from(
+        &self,
+        table_name: &ast::ObjectName,
+        columns: &[ast::Ident],
+        options: CopyOptions,
+    ) -> Result<CopyFrom> {
+        let (rel, tuple_desc) = self.open_relation(table_name)?;
+        let columns = copy::column_indexes(&tuple_desc, &rel.rel_name, columns)?;
+        let defaults = self.column_defaults(&rel, &tuple_desc)?;
+
+        Ok(CopyFrom::new(
+            &self.buffer_pool,
+            rel,
+            tuple_desc,
+            columns,
+            defaults,
+            options,
+        ))
+    }
+
+    /// Start a COPY TO of the given table. The formatted rows are read from the returned
+    /// state.
+    pub fn copy_to(
+        &self,
+        table_name: &ast::ObjectName,
+        columns: &[ast::Ident],
+        options: CopyOptions,
+    ) -> Result<CopyTo> {
+        let (rel, tuple_desc) = self.open_relation(table_name)?;
+        let columns = copy::column_indexes(&tuple_desc, &rel.rel_name, columns)?;
+
+        CopyTo::new(&self.buffer_pool, &rel, tuple_desc, columns, options)
+    }
+
+    /// Open the relation of the given table name and return the relation tuple descriptor.
+    fn open_relation(&self, table_name: &ast::ObjectName) -> Result<(Relation, TupleDesc)> {
         let rel_name = table_name.0[0].to_string();
         let pg_class_rel =
             catalog::get_pg_class_relation(&self.buffer_pool, &self.config.database, &rel_name)?;
@@ -75,131 +431,405 @@ impl ConnectionExecutor {
             &rel_name,
         );
 
-        match &source.body {
-            ast::SetExpr::Values(values) => {
-                let tuple_desc = catalog::tuple_desc_from_relation(
-                    &self.buffer_pool,
-                    &self.config.database,
-                    &rel_name,
-                )?;
-
-                let mut heap_values = Datums::default();
-
-                // Iterate over all rows on insert to write new tuples.
-                for row in &values.0 {
-                    // INSERT statement don't specify the columns
-                    if columns.len() == 0 {
-                        for attr in &tuple_desc.attrs {
-                            match row.get(attr.attnum - 1) {
-                                Some(value) => match value {
-                                    ast::Expr::Value(value) => {
-                                        encode(&mut heap_values, &value, attr)?;
-                                    }
-                                    _ => bail!(SQLError::Unsupported(value.to_string())),
-                                },
-                                None => heap_values.push(None),
-                            }
-                        }
-                    } else {
-                        if row.len() != columns.len() {
-                            bail!("INSERT has more expressions than target columns");
-                        }
+        let tuple_desc =
+            catalog::tuple_desc_from_relation(&self.buffer_pool, &self.config.database, &rel_name)?;
 
-                        // Iterate over relation attrs and try to find the value that is being inserted
-                        // for each attr. If the value does not exists a NULL value should be inserted
-                        // on tuple header t_bits array.
-                        for attr in &tuple_desc.attrs {
-                            // TODO: Find a better way to lookup the attr value that is being inserted
-                            let index =
-                                columns.iter().position(|ident| ident.value == attr.attname);
-                            match index {
-                                Some(index) => {
-                                    let value = &row[index];
-                                    match value {
-                                        ast::Expr::Value(value) => {
-                                            encode(&mut heap_values, &value, attr)?;
-                                        }
-                                        _ => bail!(SQLError::Unsupported(value.to_string())),
-                                    }
-                                }
-                                None => {
-                                    heap_values.push(None);
-                                }
-                            }
-                        }
-                    }
-                }
+        Ok((rel, tuple_desc))
+    }
 
-                heap_insert(
-                    &self.buffer_pool,
-                    &rel,
-                    &mut HeapTuple::from_datums(heap_values, &tuple_desc)?,
-                )?;
-            }
-            _ => bail!(SQLError::Unsupported(source.to_string())),
+    /// Return the default value of each attribute of the given relation, converted to the type
+    /// of the attribute, or None if the attribute has no default.
+    fn column_defaults(&self, rel: &Relation, tuple_desc: &TupleDesc) -> Result<Vec<Option<Expr>>> {
+        let mut defaults = vec![None; tuple_desc.attrs.len()];
+        for attrdef in
+            catalog::get_attr_defaults(&self.buffer_pool, &self.config.database, rel.locator.oid)?
+        {
+            let expr = parser::parse_expr(&PostgreSqlDialect {}, &attrdef.adbin)?;
+            let attr = &tuple_desc.attrs[attrdef.adnum - 1];
+            defaults[attrdef.adnum - 1] = Some(coerce_default(transform_expr(&expr, None)?, attr)?);
         }
-
-        Ok(())
+        Ok(defaults)
     }
 
+    /// Create a new table. A new sequence is created for each serial or identity column, and
+    /// the next value of the sequence is the default value of the column.
     pub fn exec_create_table(
         &self,
         name: &ast::ObjectName,
-        columns: &Vec<ast::ColumnDef>,
+        columns: &[ast::ColumnDef],
+        identities: &[(ast::Ident, Identity)],
     ) -> Result<()> {
+        let rel_name = name.0[0].to_string();
+        catalog::check_relation_name(&self.buffer_pool, &self.config.database, &rel_name)?;
+
+        // Tables and types share the same namespace on PostgreSQL, since each table has a row
+        // type with the name of the table.
+        catalog::check_type_name(&self.buffer_pool, &self.config.database, &rel_name)?;
+
         // Create a new unique oid to the new heap relation.
-        let new_oid = catalog::new_relation_oid(&DEFAULTTABLESPACE_OID, &self.config.database)?;
+        let new_oid = catalog::new_relation_oid(
+            &self.buffer_pool,
+            &DEFAULTTABLESPACE_OID,
+            &self.config.database,
+        )?;
 
+        let serial_options = SequenceOptions::default();
         let mut tupledesc = TupleDesc::default();
-        for (i, attr) in columns.iter().enumerate() {
+        let mut defaults = Vec::new();
+        let mut sequences = Vec::new();
+        for (i, column) in columns.iter().enumerate() {
+            let serial_type = serial_type(&column.data_type);
+
             // Attributes numbers start at 1
-            tupledesc
-                .attrs
-                .push(self.new_pg_attribute(new_oid, attr, i + 1)?)
+            let mut attr = self.new_pg_attribute(
+                new_oid,
+                &column.name,
+                serial_type.as_ref().unwrap_or(&column.data_type),
+                i + 1,
+            )?;
+
+            let column_defaults = column
+                .options
+                .iter()
+                .filter_map(|option| match &option.option {
+                    ast::ColumnOption::Default(expr) => Some(expr),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let identity = identities
+                .iter()
+                .find(|(name, _)| name == &column.name)
+                .map(|(_, identity)| identity);
+
+            if column_defaults.len() > 1 || (serial_type.is_some() && !column_defaults.is_empty()) {
+                bail!(SQLError::MultipleDefaults(attr.attname, rel_name));
+            }
+
+            if let Some(identity) = identity {
+                if serial_type.is_some() || !column_defaults.is_empty() {
+                    bail!(SQLError::DefaultAndIdentity(attr.attname, rel_name));
+                }
+                if ![pg_type::INT2_OID, pg_type::INT_OID, pg_type::INT8_OID]
+                    .contains(&attr.atttypid)
+                {
+                    bail!(SQLError::InvalidIdentityType);
+                }
+                attr.attidentity = String::from(if identity.always {
+                    ATTRIBUTE_IDENTITY_ALWAYS
+                } else {
+                    ATTRIBUTE_IDENTITY_BY_DEFAULT
+                });
+                sequences.push((i, &identity.options));
+            } else if serial_type.is_some() {
+                sequences.push((i, &serial_options));
+            } else if let Some(default) = column_defaults.first() {
+                // The default is only checked here, it is evaluated when a row is inserted.
+                coerce_default(transform_expr(default, None)?, &attr)?;
+                defaults.push((attr.attnum, default.to_string()));
+            }
+
+            tupledesc.attrs.push(attr);
         }
 
         heap_create(
             &self.buffer_pool,
             DEFAULTTABLESPACE_OID,
             &self.config.database,
-            &name.0[0].to_string(),
+            &rel_name,
             new_oid,
             &tupledesc,
         )?;
+
+        for (i, options) in sequences {
+            let attr = &tupledesc.attrs[i];
+            let seq_name = self.choose_relation_name(
+                &format!("{}_{}", name.0[0].value, columns[i].name.value),
+                "seq",
+            )?;
+            sequence::define_sequence(
+                &self.buffer_pool,
+                &self.config.database,
+                &seq_name,
+                options,
+                attr.atttypid,
+            )?;
+            defaults.push((attr.attnum, nextval_expr(&seq_name)));
+        }
+
+        for (adnum, adbin) in defaults {
+            catalog::store_attr_default(
+                &self.buffer_pool,
+                &self.config.database,
+                new_oid,
+                adnum,
+                adbin,
+            )?;
+        }
         Ok(())
     }
 
+    /// Create a new sequence. Nothing is done if IF NOT EXISTS is given and a relation with the
+    /// same name already exists.
+    pub fn exec_create_sequence(
+        &self,
+        name: &ast::ObjectName,
+        if_not_exists: bool,
+        options: &SequenceOptions,
+    ) -> Result<()> {
+        let name = &name.0[0].value;
+        if if_not_exists
+            && catalog::find_pg_class_by_name(&self.buffer_pool, &self.config.database, name)?
+                .is_some()
+        {
+            return Ok(());
+        }
+        catalog::check_relation_name(&self.buffer_pool, &self.config.database, name)?;
+
+        sequence::define_sequence(
+            &self.buffer_pool,
+            &self.config.database,
+            name,
+            options,
+            pg_type::INT8_OID,
+        )?;
+        Ok(())
+    }
+
+    /// Return a relation name made of the given name and label that is not used by any
+    /// relation, adding a number after the label if needed, e.g t_a_seq1.
+    fn choose_relation_name(&self, name: &str, label: &str) -> Result<String> {
+        let mut rel_name = format!("{}_{}", name, label);
+        let mut pass = 0;
+        while catalog::find_pg_class_by_name(&self.buffer_pool, &self.config.database, &rel_name)?
+            .is_some()
+        {
+            pass += 1;
+            rel_name = format!("{}_{}{}", name, label, pass);
+        }
+        Ok(rel_name)
+    }
+
+    /// Create a new user defined type, which can be an enum or a composite type.
+    pub fn exec_create_type(
+        &self,
+        name: &ast::ObjectName,
+        representation: &TypeRepresentation,
+    ) -> Result<()> {
+        let (name, _) = type_name(&ast::DataType::Custom(name.clone()))?;
+
+        match representation {
+            TypeRepresentation::Enum(labels) => {
+                catalog::create_enum_type(&self.buffer_pool, &self.config.database, &name, labels)
+            }
+            TypeRepresentation::Composite(attributes) => {
+                let typrelid = catalog::new_relation_oid(
+                    &self.buffer_pool,
+                    &DEFAULTTABLESPACE_OID,
+                    &self.config.database,
+                )?;
+
+                let mut tupledesc = TupleDesc::default();
+                for (i, (attname, data_type)) in attributes.iter().enumerate() {
+                    tupledesc.attrs.push(self.new_pg_attribute(
+                        typrelid,
+                        attname,
+                        data_type,
+                        i + 1,
+                    )?);
+                }
+
+                catalog::create_composite_type(
+                    &self.buffer_pool,
+                    &self.config.database,
+                    &name,
+                    typrelid,
+                    &tupledesc,
+                )
+            }
+        }
+    }
+
     fn new_pg_attribute(
         &self,
         attrelid: Oid,
-        columndef: &ast::ColumnDef,
+        attname: &ast::Ident,
+        data_type: &ast::DataType,
         attnum: usize,
     ) -> Result<PgAttribute> {
-        let (atttypid, attlen) = self.oid_type_and_size(&columndef.data_type)?;
+        let (atttypid, attlen, atttypmod) = self.oid_type_and_size(data_type)?;
+        let typ = types::lookup_type(atttypid)?;
+        if typ.typtype() == TypeKind::Pseudo {
+            bail!(SQLError::PseudoTypeColumn(
+                attname.value.clone(),
+                typ.format_type()
+            ));
+        }
         Ok(PgAttribute {
             attrelid,
-            attname: columndef.name.to_string(),
+            attname: attname.to_string(),
             attnum,
             attlen,
             atttypid,
+            atttypmod,
+            attidentity: String::new(),
         })
     }
 
-    /// Return the oid and the lenght of the given data type.
-    fn oid_type_and_size(&self, typ: &ast::DataType) -> Result<(Oid, i64)> {
-        match typ {
-            ast::DataType::Int(len) => Ok((
-                pg_type::INT_OID,
-                (len.unwrap_or(size_of::<i32>() as u64)) as i64,
-            )),
-            ast::DataType::Varchar(len) => match len {
-                Some(len) => Ok((pg_type::VARCHAR_OID, *len as i64)),
-                None => Ok((pg_type::VARCHAR_OID, -1)),
-            },
-            ast::DataType::Boolean => Ok((pg_type::BOOL_OID, size_of::<bool>() as i64)),
-            _ => bail!("Not supported data type: {}", typ),
+    /// Return the oid, the lenght and the type modifier of the given data type.
+    ///
+    /// The type is searched by name on pg_type catalog.
+    fn oid_type_and_size(&self, typ: &ast::DataType) -> Result<(Oid, i64, i32)> {
+        let (name, modifiers) = type_name(typ)?;
+        let pg_type =
+            catalog::get_pg_type_by_name(&self.buffer_pool, &self.config.database, &name)?;
+
+        let typmod = if modifiers.is_empty() {
+            -1
+        } else {
+            types::lookup_type(pg_type.oid)?.typmod_in(&modifiers)?
+        };
+        Ok((pg_type.oid, pg_type.typlen as i64, typmod))
+    }
+}
+
+/// Transform the rows of the VALUES list of an INSERT on the given target columns, where each
+/// value is None for DEFAULT.
+///
+/// A parameter of unknown type that is a value of a target column takes the type of the column.
+fn transform_insert_rows(
+    values: &ast::Values,
+    tuple_desc: &TupleDesc,
+    columns: &[usize],
+) -> Result<Vec<Vec<Option<Expr>>>> {
+    if values
+        .0
+        .windows(2)
+        .any(|rows| rows[0].len() != rows[1].len())
+    {
+        bail!(SQLError::ValuesListsLength);
+    }
+    values
+        .0
+        .iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, value)| match value {
+                    // DEFAULT is parsed as a column reference.
+                    ast::Expr::Identifier(ident)
+                        if ident.quote_style.is_none()
+                            && ident.value.eq_ignore_ascii_case("default") =>
+                    {
+                        Ok(None)
+                    }
+                    ast::Expr::Value(ast::Value::Placeholder(name)) => {
+                        if let (Some(number), Some(index)) = (param_number(name), columns.get(i)) {
+                            session::infer_param_type(number, tuple_desc.attrs[*index].atttypid);
+                        }
+                        transform_expr(value, None).map(Some)
+                    }
+                    value => transform_expr(value, None).map(Some),
+                })
+                .collect::<Result<Vec<_>>>()
+        })
+        .collect()
+}
+
+/// Return the attribute values of a row being inserted, where each expression is the value of
+/// the target column at the same position, or None for DEFAULT. Attributes that are not target
+/// columns get their default value, which is NULL if the attribute has no default.
+///
+/// When the target columns are not listed on the statement all attributes are targets, and the
+/// row may have less values than attributes.
+fn insert_values(
+    tuple_desc: &TupleDesc,
+    columns: &[usize],
+    listed_columns: bool,
+    row: Vec<Option<Expr>>,
+    defaults: &[Option<Expr>],
+) -> Result<Vec<Option<Datum>>> {
+    if row.len() > columns.len() {
+        bail!(SQLError::InsertTooManyExpressions);
+    }
+    if row.len() < columns.len() && listed_columns {
+        bail!(SQLError::InsertTooManyTargetColumns);
+    }
+
+    let mut exprs = defaults.to_vec();
+    for (index, expr) in columns.iter().zip(row) {
+        if let Some(expr) = expr {
+            let attr = &tuple_desc.attrs[*index];
+            if attr.attidentity == ATTRIBUTE_IDENTITY_ALWAYS {
+                bail!(SQLError::GeneratedAlways(attr.attname.clone()));
+            }
+            exprs[*index] = Some(coerce_insert_value(expr, attr)?);
         }
     }
+
+    // Values are computed in attribute order, so the defaults that call nextval are evaluated
+    // in the order of their columns.
+    exprs
+        .iter()
+        .map(|expr| match expr {
+            Some(expr) => expr.eval(&[]),
+            None => Ok(None),
+        })
+        .collect()
+}
+
+/// Convert an expression being inserted on the given attribute to the attribute type.
+fn coerce_insert_value(expr: Expr, attr: &PgAttribute) -> Result<Expr> {
+    let expr_type = types::lookup_type(expr.typ())?;
+    let attr_type = types::lookup_type(attr.atttypid)?;
+
+    match coerce_to_target_type(expr, attr_type, attr.atttypmod, CoercionContext::Assignment)? {
+        Some(expr) => Ok(expr),
+        None => bail!(SQLError::DatatypeMismatch(
+            attr.attname.clone(),
+            attr_type.format_type(),
+            expr_type.format_type()
+        )),
+    }
+}
+
+/// Convert the default value expression of the given attribute to the attribute type.
+fn coerce_default(expr: Expr, attr: &PgAttribute) -> Result<Expr> {
+    let expr_type = types::lookup_type(expr.typ())?;
+    let attr_type = types::lookup_type(attr.atttypid)?;
+
+    match coerce_to_target_type(expr, attr_type, attr.atttypmod, CoercionContext::Assignment)? {
+        Some(expr) => Ok(expr),
+        None => bail!(SQLError::DefaultDatatypeMismatch(
+            attr.attname.clone(),
+            attr_type.format_type(),
+            expr_type.format_type()
+        )),
+    }
+}
+
+/// Return the integer type of a serial pseudo-type, or None if the type is not serial.
+fn serial_type(data_type: &ast::DataType) -> Option<ast::DataType> {
+    let name = match data_type {
+        ast::DataType::Custom(name) if name.0.len() == 1 => name.0[0].value.to_lowercase(),
+        _ => return None,
+    };
+    match name.as_str() {
+        "smallserial" | "serial2" => Some(ast::DataType::SmallInt(None)),
+        "serial" | "serial4" => Some(ast::DataType::Int(None)),
+        "bigserial" | "serial8" => Some(ast::DataType::BigInt(None)),
+        _ => None,
+    }
+}
+
+/// Return the default value expression of a column that takes the next value of the given
+/// sequence.
+fn nextval_expr(seq_name: &str) -> String {
+    let seq_name = if seq_name.contains('"') {
+        format!("\"{}\"", seq_name.replace('"', "\"\""))
+    } else {
+        seq_name.to_string()
+    };
+    format!("nextval('{}')", seq_name.replace('\'', "''"))
 }
 
 /// Describe an attribute in a row.
@@ -242,24 +872,28 @@ pub struct PGResult {
     pub tuples: Vec<Datums>,
 }
 
-impl From<TupleTable> for PGResult {
-    fn from(table: TupleTable) -> Self {
-        let mut fields = Vec::with_capacity(table.tuple_desc.attrs.len());
-
-        for attr in &table.tuple_desc.attrs {
-            fields.push(FieldDescription {
+impl From<&[PgAttribute]> for RowDescriptor {
+    fn from(attrs: &[PgAttribute]) -> Self {
+        let fields = attrs
+            .iter()
+            .map(|attr| FieldDescription {
                 name: attr.attname.as_bytes().to_vec(),
                 table_oid: attr.attrelid as u32,
                 table_attribute_number: attr.attnum as u16,
                 data_type_oid: attr.atttypid as u32,
                 data_type_size: attr.attlen as i16,
-                type_modifier: -1,
+                type_modifier: attr.atttypmod,
                 format: 0,
             })
-        }
+            .collect();
+        Self { fields }
+    }
+}
 
+impl From<TupleTable> for PGResult {
+    fn from(table: TupleTable) -> Self {
         Self {
-            desc: RowDescriptor { fields },
+            desc: RowDescriptor::from(&table.tuple_desc.attrs[..]),
             tuples: table.values,
         }
     }
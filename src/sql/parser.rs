@@ -0,0 +1,1185 @@
+use sqlparser::{
+    ast,
+    dialect::Dialect,
+    keywords::Keyword,
+    parser::{Parser, ParserError},
+    tokenizer::{Token, Tokenizer, Word},
+};
+
+use crate::planner::expr::param_number;
+
+/// A statement parsed from a query string. Statements that are not supported by the SQL parser
+/// are parsed by tinydb itself.
+#[derive(Debug, Clone)]
+pub enum Statement {
+    /// Any statement supported by the SQL parser.
+    Sql(Box<ast::Statement>),
+
+    /// INSERT INTO table_name (columns) source ON CONFLICT ... RETURNING items.
+    Insert {
+        table_name: ast::ObjectName,
+        columns: Vec<ast::Ident>,
+        source: Box<ast::Query>,
+        on_conflict: Option<OnConflict>,
+
+        /// Items of the RETURNING list, which is empty if there is no RETURNING clause.
+        returning: Vec<ast::SelectItem>,
+    },
+
+    /// CREATE TYPE name AS ENUM (labels) or CREATE TYPE name AS (attributes).
+    CreateType {
+        name: ast::ObjectName,
+        representation: TypeRepresentation,
+    },
+
+    /// CREATE SEQUENCE [IF NOT EXISTS] name options.
+    CreateSequence {
+        name: ast::ObjectName,
+        if_not_exists: bool,
+        options: SequenceOptions,
+    },
+
+    /// CREATE TABLE name (columns). The SQL parser does not support identity columns, so the
+    /// GENERATED AS IDENTITY clauses are removed from the column definitions and returned with
+    /// the name of their column.
+    CreateTable {
+        name: ast::ObjectName,
+        columns: Vec<ast::ColumnDef>,
+        identities: Vec<(ast::Ident, Identity)>,
+    },
+}
+
+/// Options of a sequence, which are None when not given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceOptions {
+    /// AS data_type.
+    pub data_type: Option<ast::DataType>,
+
+    /// INCREMENT [BY] increment.
+    pub increment: Option<i64>,
+
+    /// MINVALUE min_value. NO MINVALUE is the same as not giving the option.
+    pub min_value: Option<i64>,
+
+    /// MAXVALUE max_value. NO MAXVALUE is the same as not giving the option.
+    pub max_value: Option<i64>,
+
+    /// START [WITH] start.
+    pub start: Option<i64>,
+
+    /// CACHE cache.
+    pub cache: Option<i64>,
+
+    /// [NO] CYCLE.
+    pub cycle: Option<bool>,
+}
+
+/// GENERATED { ALWAYS | BY DEFAULT } AS IDENTITY [ ( sequence_options ) ] clause of a column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// True for GENERATED ALWAYS, which does not accept values given by INSERT.
+    pub always: bool,
+
+    /// Options of the sequence that generates the values of the column.
+    pub options: SequenceOptions,
+}
+
+/// Definition of a type created with CREATE TYPE.
+#[derive(Debug, Clone)]
+pub enum TypeRepresentation {
+    /// Labels of an enum type in declaration order.
+    Enum(Vec<String>),
+
+    /// Name and data type of each attribute of a composite type.
+    Composite(Vec<(ast::Ident, ast::DataType)>),
+}
+
+/// ON CONFLICT DO NOTHING clause of an INSERT statement. DO UPDATE is not supported, since it
+/// needs unique indexes to find the conflicting row.
+#[derive(Debug, Clone)]
+pub struct OnConflict {
+    /// Unique index or constraint that is checked for conflicts, or None to check all of them.
+    pub target: Option<ConflictTarget>,
+}
+
+/// How the unique index checked by ON CONFLICT is specified.
+#[derive(Debug, Clone)]
+pub enum ConflictTarget {
+    /// ON CONFLICT (columns), the unique index on the given columns.
+    Columns(Vec<ast::Ident>),
+
+    /// ON CONFLICT ON CONSTRAINT name.
+    Constraint(ast::Ident),
+}
+
+/// PostgreSQL operators that are not supported by the SQL parser.
+///
+/// Before parsing, each operator is replaced by a marker identifier between two infix operators
+/// of the SQL parser, so `a @> b` is parsed as `(a # @>) # b` with the precedence of `#`, which
+/// is the precedence that PostgreSQL gives to any operator that is not a builtin SQL operator.
+/// SIMILAR TO uses `~` instead, which has the precedence of LIKE. The marker is the operator
+/// name as an unquoted identifier, and [`ext_operator`] decodes the expression back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtOperator {
+    /// jsonb @> jsonb, true if the left value contains the right value.
+    JsonbContains,
+
+    /// jsonb ? text, true if the text exists as a key or as an array element of the left value.
+    JsonbExists,
+
+    /// string SIMILAR TO pattern.
+    SimilarTo,
+
+    /// string NOT SIMILAR TO pattern.
+    NotSimilarTo,
+
+    /// pattern ESCAPE escape, which is the right operand of LIKE, ILIKE and SIMILAR TO when the
+    /// pattern uses an escape character other than backslash.
+    Escape,
+}
+
+impl ExtOperator {
+    const ALL: [ExtOperator; 5] = [
+        ExtOperator::JsonbContains,
+        ExtOperator::JsonbExists,
+        ExtOperator::SimilarTo,
+        ExtOperator::NotSimilarTo,
+        ExtOperator::Escape,
+    ];
+
+    /// Return the operator name, which is also the value of its marker identifier.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExtOperator::JsonbContains => "@>",
+            ExtOperator::JsonbExists => "?",
+            ExtOperator::SimilarTo => "similar to",
+            ExtOperator::NotSimilarTo => "not similar to",
+            ExtOperator::Escape => "escape",
+        }
+    }
+
+    /// Return the operator of the SQL parser placed around the marker, which gives the
+    /// precedence of the operator.
+    fn infix(&self) -> (Token, ast::BinaryOperator) {
+        match self {
+            ExtOperator::SimilarTo | ExtOperator::NotSimilarTo => {
+                (Token::Tilde, ast::BinaryOperator::PGRegexMatch)
+            }
+            _ => (Token::Sharp, ast::BinaryOperator::PGBitwiseXor),
+        }
+    }
+
+    /// Return the operator whose marker is the given identifier.
+    fn from_marker(ident: &ast::Ident) -> Option<Self> {
+        if ident.quote_style.is_some() {
+            return None;
+        }
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|op| op.name() == ident.value)
+    }
+
+    /// Return the marker token of the operator.
+    fn marker(&self) -> Token {
+        Token::Word(Word {
+            value: self.name().to_string(),
+            quote_style: None,
+            keyword: Keyword::NoKeyword,
+        })
+    }
+}
+
+/// Return the operator and its operands if the expression is an operator rewritten by
+/// [`rewrite_ext_operators`].
+pub fn ext_operator(expr: &ast::Expr) -> Option<(ExtOperator, &ast::Expr, &ast::Expr)> {
+    let (left, op, right) = match expr {
+        ast::Expr::BinaryOp { left, op, right } => (left, op, right),
+        _ => return None,
+    };
+    let (left, marker) = match left.as_ref() {
+        ast::Expr::BinaryOp {
+            left,
+            op: marker_op,
+            right: marker,
+        } if marker_op == op => (left, marker),
+        _ => return None,
+    };
+    let ext_op = match marker.as_ref() {
+        ast::Expr::Identifier(ident) => ExtOperator::from_marker(ident)?,
+        _ => return None,
+    };
+    if ext_op.infix().1 != *op {
+        return None;
+    }
+    Some((ext_op, left.as_ref(), right.as_ref()))
+}
+
+/// Replace the operators that are not supported by the SQL parser by their marker between two
+/// infix operators, see [`ExtOperator`].
+///
+/// ESCAPE is only replaced after a LIKE, ILIKE or SIMILAR TO on the same parentheses, since it
+/// is also used by other statements, e.g as an option of COPY. A marker that is already between
+/// two infix operators is kept as is, since this is how a parsed expression is displayed, e.g on
+/// the default value of a column that is parsed again.
+fn rewrite_ext_operators(tokens: Vec<Token>) -> Vec<Token> {
+    let mut rewritten = Vec::with_capacity(tokens.len());
+
+    // Number of pattern matching operators not followed by ESCAPE for each level of parentheses.
+    let mut patterns = vec![0];
+    let mut i = 0;
+    while i < tokens.len() {
+        let depth = patterns.len() - 1;
+        let rewrite = match &tokens[i] {
+            Token::AtSign if tokens.get(i + 1) == Some(&Token::Gt) => {
+                Some((ExtOperator::JsonbContains, i + 2))
+            }
+            Token::Placeholder(p) if p == "?" => Some((ExtOperator::JsonbExists, i + 1)),
+            Token::Word(word) if word.keyword == Keyword::SIMILAR => {
+                next_keyword(&tokens, i + 1, Keyword::TO).map(|next| (ExtOperator::SimilarTo, next))
+            }
+            Token::Word(word) if word.keyword == Keyword::NOT => {
+                next_keyword(&tokens, i + 1, Keyword::SIMILAR)
+                    .and_then(|next| next_keyword(&tokens, next, Keyword::TO))
+                    .map(|next| (ExtOperator::NotSimilarTo, next))
+            }
+            Token::Word(word) if word.keyword == Keyword::ESCAPE && patterns[depth] > 0 => {
+                Some((ExtOperator::Escape, i + 1))
+            }
+            _ => None,
+        };
+
+        let (op, next) = match rewrite {
+            Some(rewrite) => rewrite,
+            None => {
+                match &tokens[i] {
+                    Token::LParen => patterns.push(0),
+                    Token::RParen if depth > 0 => {
+                        patterns.pop();
+                    }
+                    Token::SemiColon => patterns = vec![0],
+                    Token::Word(word) if matches!(word.keyword, Keyword::LIKE | Keyword::ILIKE) => {
+                        patterns[depth] += 1
+                    }
+                    _ => {}
+                }
+                rewritten.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        match op {
+            ExtOperator::SimilarTo | ExtOperator::NotSimilarTo => patterns[depth] += 1,
+            ExtOperator::Escape => patterns[depth] -= 1,
+            _ => {}
+        }
+
+        let (infix, _) = op.infix();
+        let is_infix = |token: Option<&Token>| token == Some(&infix);
+        let displayed = is_infix(rewritten.iter().rev().find(|token| !is_whitespace(token)))
+            && is_infix(tokens[next..].iter().find(|token| !is_whitespace(token)));
+        if displayed {
+            rewritten.push(op.marker());
+        } else {
+            rewritten.extend([infix.clone(), op.marker(), infix]);
+        }
+        i = next;
+    }
+    rewritten
+}
+
+/// Return the position after the given keyword if it is the next token that is not a
+/// whitespace, starting at position i.
+fn next_keyword(tokens: &[Token], i: usize, keyword: Keyword) -> Option<usize> {
+    let offset = tokens[i..].iter().position(|token| !is_whitespace(token))?;
+    match &tokens[i + offset] {
+        Token::Word(word) if word.keyword == keyword => Some(i + offset + 1),
+        _ => None,
+    }
+}
+
+/// Return true if the token is a whitespace or a comment.
+fn is_whitespace(token: &Token) -> bool {
+    matches!(token, Token::Whitespace(_))
+}
+
+/// Split a query string into tokens, rewriting the operators not supported by the SQL parser.
+fn tokenize(dialect: &dyn Dialect, sql: &str) -> Result<Vec<Token>, ParserError> {
+    Ok(rewrite_ext_operators(
+        Tokenizer::new(dialect, sql).tokenize()?,
+    ))
+}
+
+/// Return the highest number of the parameter placeholders of a query string, e.g 2 for
+/// `SELECT $2 + 1`, or 0 if there are no parameters.
+pub fn count_params(dialect: &dyn Dialect, sql: &str) -> Result<usize, ParserError> {
+    Ok(tokenize(dialect, sql)?
+        .iter()
+        .filter_map(|token| match token {
+            Token::Placeholder(name) => param_number(name),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0))
+}
+
+/// Parse a query string that may contain multiple statements separated by semicolons.
+pub fn parse_sql(dialect: &dyn Dialect, sql: &str) -> Result<Vec<Statement>, ParserError> {
+    let tokens = tokenize(dialect, sql)?;
+    let mut parser = Parser::new(tokens, dialect);
+    let mut stmts = Vec::new();
+    let mut expecting_statement_delimiter = false;
+    loop {
+        // Ignore empty statements between successive statement delimiters.
+        while parser.consume_token(&Token::SemiColon) {
+            expecting_statement_delimiter = false;
+        }
+
+        if parser.peek_token() == Token::EOF {
+            break;
+        }
+        if expecting_statement_delimiter {
+            return Err(expected("end of statement", parser.peek_token()));
+        }
+
+        let statement = if parser.parse_keywords(&[Keyword::CREATE, Keyword::TYPE]) {
+            parse_create_type(&mut parser)?
+        } else if parser.parse_keywords(&[Keyword::CREATE, Keyword::SEQUENCE]) {
+            parse_create_sequence(&mut parser)?
+        } else if parser.parse_keywords(&[Keyword::CREATE, Keyword::TABLE]) {
+            parse_create_table(dialect, &mut parser)?
+        } else if parser.parse_keyword(Keyword::INSERT) {
+            parse_insert(dialect, &mut parser)?
+        } else if parser.parse_keyword(Keyword::SET) {
+            parse_set(&mut parser)?
+        } else if parser.parse_keyword(Keyword::SHOW) {
+            parse_show(&mut parser)?
+        } else {
+            Statement::Sql(Box::new(parser.parse_statement()?))
+        };
+        stmts.push(statement);
+        expecting_statement_delimiter = true;
+    }
+    Ok(stmts)
+}
+
+/// Parse a single expression, e.g the default value of a column.
+pub fn parse_expr(dialect: &dyn Dialect, sql: &str) -> Result<ast::Expr, ParserError> {
+    let tokens = tokenize(dialect, sql)?;
+    parse_tokens(dialect, tokens, |parser| parser.parse_expr())
+}
+
+/// Parse the remaining of a SET statement, after the SET keyword.
+///
+/// The SQL parser does not accept signed numbers as values, which are used by time zone offsets.
+/// TIME ZONE is the same as the TimeZone parameter, and is followed by the value without TO.
+fn parse_set(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let local =
+        parser.parse_one_of_keywords(&[Keyword::SESSION, Keyword::LOCAL]) == Some(Keyword::LOCAL);
+
+    let variable = if parser.parse_keywords(&[Keyword::TIME, Keyword::ZONE]) {
+        ast::ObjectName(vec![ast::Ident::new("timezone")])
+    } else {
+        let variable = parser.parse_object_name()?;
+        if !parser.consume_token(&Token::Eq) && !parser.parse_keyword(Keyword::TO) {
+            return Err(expected("= or TO", parser.peek_token()));
+        }
+        variable
+    };
+    let value = parser.parse_comma_separated(parse_set_value)?;
+
+    Ok(Statement::Sql(Box::new(ast::Statement::SetVariable {
+        local,
+        hivevar: false,
+        variable,
+        value,
+    })))
+}
+
+/// Parse a value of a SET statement, which may be a signed number.
+fn parse_set_value(parser: &mut Parser) -> Result<ast::SetVariableValue, ParserError> {
+    let sign = match parser.peek_token() {
+        Token::Minus => "-",
+        Token::Plus => "",
+        token => {
+            return match (parser.parse_value(), token) {
+                (Ok(value), _) => Ok(ast::SetVariableValue::Literal(value)),
+                (Err(_), Token::Word(word)) => Ok(ast::SetVariableValue::Ident(word.to_ident())),
+                (Err(_), token) => Err(expected("variable value", token)),
+            }
+        }
+    };
+    parser.next_token();
+
+    match parser.next_token() {
+        Token::Number(number, long) => Ok(ast::SetVariableValue::Literal(ast::Value::Number(
+            format!("{}{}", sign, number),
+            long,
+        ))),
+        token => Err(expected("number", token)),
+    }
+}
+
+/// Parse the remaining of a SHOW statement, after the SHOW keyword.
+///
+/// The SQL parser reads all the remaining tokens as the name of the parameter, even after the
+/// end of the statement. TIME ZONE is the same as the TimeZone parameter.
+fn parse_show(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let variable = if parser.parse_keywords(&[Keyword::TIME, Keyword::ZONE]) {
+        vec![ast::Ident::new("timezone")]
+    } else {
+        parser.parse_object_name()?.0
+    };
+    Ok(Statement::Sql(Box::new(ast::Statement::ShowVariable {
+        variable,
+    })))
+}
+
+/// Parse the remaining of a CREATE TYPE statement, after the CREATE TYPE keywords.
+fn parse_create_type(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let name = parser.parse_object_name()?;
+    parser.expect_keyword(Keyword::AS)?;
+
+    let representation = if parser.parse_keyword(Keyword::ENUM) {
+        parser.expect_token(&Token::LParen)?;
+        // An enum type may be declared without labels.
+        let labels = if parser.consume_token(&Token::RParen) {
+            Vec::new()
+        } else {
+            let labels = parser.parse_comma_separated(parse_label)?;
+            parser.expect_token(&Token::RParen)?;
+            labels
+        };
+        TypeRepresentation::Enum(labels)
+    } else {
+        parser.expect_token(&Token::LParen)?;
+        let attributes = parser.parse_comma_separated(|parser| {
+            Ok((parser.parse_identifier()?, parser.parse_data_type()?))
+        })?;
+        parser.expect_token(&Token::RParen)?;
+        TypeRepresentation::Composite(attributes)
+    };
+
+    Ok(Statement::CreateType {
+        name,
+        representation,
+    })
+}
+
+/// Parse the remaining of a CREATE SEQUENCE statement, after the CREATE SEQUENCE keywords.
+fn parse_create_sequence(parser: &mut Parser) -> Result<Statement, ParserError> {
+    let if_not_exists = parser.parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+    let name = parser.parse_object_name()?;
+    let options = parse_sequence_options(parser)?;
+
+    Ok(Statement::CreateSequence {
+        name,
+        if_not_exists,
+        options,
+    })
+}
+
+/// Parse a list of sequence options, which may be empty. Each option can be given only once.
+fn parse_sequence_options(parser: &mut Parser) -> Result<SequenceOptions, ParserError> {
+    fn set<T>(option: &mut Option<T>, value: T) -> Result<(), ParserError> {
+        if option.replace(value).is_some() {
+            return Err(ParserError::ParserError(String::from(
+                "conflicting or redundant options",
+            )));
+        }
+        Ok(())
+    }
+
+    let mut options = SequenceOptions::default();
+    let mut no_min_value = None;
+    let mut no_max_value = None;
+    loop {
+        if parser.parse_keyword(Keyword::AS) {
+            set(&mut options.data_type, parser.parse_data_type()?)?;
+        } else if parse_word(parser, "increment") {
+            // BY is optional.
+            let _ = parser.parse_keyword(Keyword::BY);
+            set(&mut options.increment, parse_signed_integer(parser)?)?;
+        } else if parse_word(parser, "minvalue") {
+            set(&mut options.min_value, parse_signed_integer(parser)?)?;
+        } else if parse_word(parser, "maxvalue") {
+            set(&mut options.max_value, parse_signed_integer(parser)?)?;
+        } else if parser.parse_keyword(Keyword::START) {
+            // WITH is optional.
+            let _ = parser.parse_keyword(Keyword::WITH);
+            set(&mut options.start, parse_signed_integer(parser)?)?;
+        } else if parser.parse_keyword(Keyword::CACHE) {
+            set(&mut options.cache, parse_signed_integer(parser)?)?;
+        } else if parser.parse_keyword(Keyword::CYCLE) {
+            set(&mut options.cycle, true)?;
+        } else if parser.parse_keyword(Keyword::NO) {
+            if parse_word(parser, "minvalue") {
+                set(&mut no_min_value, ())?;
+            } else if parse_word(parser, "maxvalue") {
+                set(&mut no_max_value, ())?;
+            } else if parser.parse_keyword(Keyword::CYCLE) {
+                set(&mut options.cycle, false)?;
+            } else {
+                return Err(expected("MINVALUE, MAXVALUE or CYCLE", parser.peek_token()));
+            }
+        } else {
+            break;
+        }
+    }
+
+    if (no_min_value.is_some() && options.min_value.is_some())
+        || (no_max_value.is_some() && options.max_value.is_some())
+    {
+        return Err(ParserError::ParserError(String::from(
+            "conflicting or redundant options",
+        )));
+    }
+    Ok(options)
+}
+
+/// Parse an integer literal that may be preceded by a minus sign.
+fn parse_signed_integer(parser: &mut Parser) -> Result<i64, ParserError> {
+    let negative = parser.consume_token(&Token::Minus);
+    match parser.next_token() {
+        Token::Number(number, _) => {
+            let number = if negative {
+                format!("-{}", number)
+            } else {
+                number
+            };
+            number.parse().map_err(|_| {
+                ParserError::ParserError(format!("Expected an integer, found: {}", number))
+            })
+        }
+        token => Err(expected("an integer", token)),
+    }
+}
+
+/// Parse the remaining of a CREATE TABLE statement, after the CREATE TABLE keywords.
+///
+/// The GENERATED AS IDENTITY clauses of the column definitions are parsed by tinydb and
+/// removed from the tokens of the statement, which are then parsed by the SQL parser.
+fn parse_create_table(
+    dialect: &dyn Dialect,
+    parser: &mut Parser,
+) -> Result<Statement, ParserError> {
+    let mut tokens = vec![Token::make_keyword("CREATE"), Token::make_keyword("TABLE")];
+    let mut identities = Vec::new();
+    let mut column = None;
+    let mut depth = 0;
+    loop {
+        let token = parser.next_token();
+        match &token {
+            Token::EOF => break,
+            Token::SemiColon if depth == 0 => {
+                parser.prev_token();
+                break;
+            }
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 1 => column = None,
+            Token::Word(word) if depth == 1 && column.is_none() => {
+                column = Some(ast::Ident {
+                    value: word.value.clone(),
+                    quote_style: word.quote_style,
+                });
+            }
+            Token::Word(_) if depth == 1 && is_word(&token, "generated") => {
+                let name = column.clone().expect("identity clause without column name");
+                identities.push((name, parse_identity(parser)?));
+                continue;
+            }
+            _ => {}
+        }
+        tokens.push(token);
+    }
+
+    match parse_tokens(dialect, tokens, |parser| parser.parse_statement())? {
+        ast::Statement::CreateTable { name, columns, .. } => Ok(Statement::CreateTable {
+            name,
+            columns,
+            identities,
+        }),
+        statement => Err(ParserError::ParserError(format!(
+            "Unsupported statement {}",
+            statement
+        ))),
+    }
+}
+
+/// Parse an identity clause of a column definition, after the GENERATED word.
+fn parse_identity(parser: &mut Parser) -> Result<Identity, ParserError> {
+    let always = if parse_word(parser, "always") {
+        true
+    } else if parser.parse_keywords(&[Keyword::BY, Keyword::DEFAULT]) {
+        false
+    } else {
+        return Err(expected("ALWAYS or BY DEFAULT", parser.peek_token()));
+    };
+    parser.expect_keywords(&[Keyword::AS, Keyword::IDENTITY])?;
+
+    let options = if parser.consume_token(&Token::LParen) {
+        let options = parse_sequence_options(parser)?;
+        parser.expect_token(&Token::RParen)?;
+        options
+    } else {
+        SequenceOptions::default()
+    };
+
+    Ok(Identity { always, options })
+}
+
+/// Parse the remaining of an INSERT statement, after the INSERT keyword.
+///
+/// The SQL parser does not support the ON CONFLICT and RETURNING clauses, so the tokens of the
+/// statement are split on these clauses and each part is parsed on its own.
+fn parse_insert(dialect: &dyn Dialect, parser: &mut Parser) -> Result<Statement, ParserError> {
+    let mut tokens = Vec::new();
+    let mut on_conflict = None;
+    let mut returning = None;
+    let mut depth = 0;
+    loop {
+        let token = parser.next_token();
+        match &token {
+            Token::EOF => break,
+            Token::SemiColon if depth == 0 => {
+                parser.prev_token();
+                break;
+            }
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Word(word)
+                if depth == 0
+                    && on_conflict.is_none()
+                    && returning.is_none()
+                    && word.keyword == Keyword::ON
+                    && parse_word(parser, "conflict") =>
+            {
+                on_conflict = Some(tokens.len());
+                continue;
+            }
+            Token::Word(_) if depth == 0 && returning.is_none() && is_word(&token, "returning") => {
+                returning = Some(tokens.len());
+                continue;
+            }
+            _ => {}
+        }
+        tokens.push(token);
+    }
+
+    let returning = match returning {
+        Some(index) => parse_tokens(dialect, tokens.split_off(index), |parser| {
+            parser.parse_comma_separated(Parser::parse_select_item)
+        })?,
+        None => Vec::new(),
+    };
+    let on_conflict = match on_conflict {
+        Some(index) => Some(parse_tokens(
+            dialect,
+            tokens.split_off(index),
+            parse_on_conflict,
+        )?),
+        None => None,
+    };
+
+    match parse_tokens(dialect, tokens, |parser| parser.parse_insert())? {
+        ast::Statement::Insert {
+            table_name,
+            columns,
+            source,
+            ..
+        } => Ok(Statement::Insert {
+            table_name,
+            columns,
+            source,
+            on_conflict,
+            returning,
+        }),
+        statement => Err(ParserError::ParserError(format!(
+            "Unsupported statement {}",
+            statement
+        ))),
+    }
+}
+
+/// Parse an ON CONFLICT DO NOTHING clause, after the ON CONFLICT keywords.
+fn parse_on_conflict(parser: &mut Parser) -> Result<OnConflict, ParserError> {
+    let target = if parser.consume_token(&Token::LParen) {
+        let columns = parser.parse_comma_separated(Parser::parse_identifier)?;
+        parser.expect_token(&Token::RParen)?;
+        Some(ConflictTarget::Columns(columns))
+    } else if parser.parse_keywords(&[Keyword::ON, Keyword::CONSTRAINT]) {
+        Some(ConflictTarget::Constraint(parser.parse_identifier()?))
+    } else {
+        None
+    };
+
+    if !parse_word(parser, "do") {
+        return Err(expected("DO", parser.peek_token()));
+    }
+    if !parse_word(parser, "nothing") {
+        return Err(expected("NOTHING", parser.peek_token()));
+    }
+
+    Ok(OnConflict { target })
+}
+
+/// Parse all the given tokens with the given function, returning an error if any token is
+/// left.
+fn parse_tokens<T>(
+    dialect: &dyn Dialect,
+    tokens: Vec<Token>,
+    parse: impl FnOnce(&mut Parser) -> Result<T, ParserError>,
+) -> Result<T, ParserError> {
+    let mut parser = Parser::new(tokens, dialect);
+    let value = parse(&mut parser)?;
+    expect_end(&mut parser)?;
+    Ok(value)
+}
+
+/// Return true if the token is the given word, which is not a keyword of the SQL parser.
+fn is_word(token: &Token, expected: &str) -> bool {
+    match token {
+        Token::Word(word) => {
+            word.quote_style.is_none() && word.value.eq_ignore_ascii_case(expected)
+        }
+        _ => false,
+    }
+}
+
+/// Consume the next token if it is the given word, returning true if it was consumed.
+fn parse_word(parser: &mut Parser, expected: &str) -> bool {
+    if is_word(&parser.peek_token(), expected) {
+        parser.next_token();
+        true
+    } else {
+        false
+    }
+}
+
+/// Return an error if the parser has not consumed all tokens.
+fn expect_end(parser: &mut Parser) -> Result<(), ParserError> {
+    match parser.peek_token() {
+        Token::EOF => Ok(()),
+        token => Err(expected("end of statement", token)),
+    }
+}
+
+/// Parse an enum label, which must be a string literal.
+fn parse_label(parser: &mut Parser) -> Result<String, ParserError> {
+    match parser.next_token() {
+        Token::SingleQuotedString(label) => Ok(label),
+        token => Err(expected("a string literal", token)),
+    }
+}
+
+/// Return the error of an unexpected token.
+fn expected(expected: &str, found: Token) -> ParserError {
+    ParserError::ParserError(format!("Expected {}, found: {}", expected, found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::PostgreSqlDialect;
+
+    #[test]
+    fn test_parse_create_type() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "CREATE TYPE mood AS ENUM ('sad', 'ok'); CREATE TYPE pair AS (a int, b mood[]);
+             CREATE TYPE empty AS ENUM (); SELECT 1",
+        )?;
+        assert_eq!(stmts.len(), 4);
+
+        match &stmts[0] {
+            Statement::CreateType {
+                name,
+                representation: TypeRepresentation::Enum(labels),
+            } => {
+                assert_eq!(name.to_string(), "mood");
+                assert_eq!(labels, &["sad", "ok"]);
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        match &stmts[1] {
+            Statement::CreateType {
+                representation: TypeRepresentation::Composite(attributes),
+                ..
+            } => {
+                let attributes = attributes
+                    .iter()
+                    .map(|(name, typ)| format!("{} {}", name, typ))
+                    .collect::<Vec<_>>();
+                assert_eq!(attributes, ["a INT", "b mood[]"]);
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        assert!(matches!(
+            &stmts[2],
+            Statement::CreateType {
+                representation: TypeRepresentation::Enum(labels),
+                ..
+            } if labels.is_empty()
+        ));
+        assert!(
+            matches!(&stmts[3], Statement::Sql(stmt) if matches!(**stmt, ast::Statement::Query(_)))
+        );
+
+        for sql in [
+            "CREATE TYPE mood AS ENUM (sad)",
+            "CREATE TYPE pair (a int)",
+            "CREATE TYPE pair AS ()",
+            "CREATE TYPE mood AS ENUM ('a') SELECT 1",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_insert() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "INSERT INTO t (a, b) VALUES (1, 'returning') RETURNING a, b + 1 AS c;
+             INSERT INTO t SELECT (x) FROM y; INSERT INTO t VALUES (1) RETURNING *",
+        )?;
+        assert_eq!(stmts.len(), 3);
+
+        match &stmts[0] {
+            Statement::Insert {
+                table_name,
+                columns,
+                source,
+                on_conflict: None,
+                returning,
+            } => {
+                assert_eq!(table_name.to_string(), "t");
+                assert_eq!(columns.len(), 2);
+                assert_eq!(source.to_string(), "VALUES (1, 'returning')");
+                let returning = returning
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>();
+                assert_eq!(returning, ["a", "b + 1 AS c"]);
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        assert!(matches!(
+            &stmts[1],
+            Statement::Insert { source, returning, .. }
+                if source.to_string() == "SELECT (x) FROM y" && returning.is_empty()
+        ));
+        assert!(matches!(
+            &stmts[2],
+            Statement::Insert { returning, .. } if returning == &[ast::SelectItem::Wildcard]
+        ));
+
+        for sql in [
+            "INSERT INTO t VALUES (1) RETURNING",
+            "INSERT INTO t VALUES (1) RETURNING a b c",
+            "INSERT INTO t VALUES (1) SELECT 1",
+            "INSERT OVERWRITE DIRECTORY 'x' SELECT 1",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_set_show() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "SET timezone TO -3; SET TimeZone = 'UTC', +5.5; SET TIME ZONE LOCAL;
+             SHOW timezone; SHOW TIME ZONE; SELECT 1",
+        )?;
+        let stmts = stmts
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Sql(stmt) => stmt.to_string(),
+                stmt => panic!("unexpected statement {:?}", stmt),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stmts,
+            vec![
+                "SET timezone = -3",
+                "SET TimeZone = 'UTC', 5.5",
+                "SET timezone = LOCAL",
+                "SHOW timezone",
+                "SHOW timezone",
+                "SELECT 1",
+            ]
+        );
+
+        assert!(parse_sql(&PostgreSqlDialect {}, "SET timezone TO - 'UTC'").is_err());
+        assert!(parse_sql(&PostgreSqlDialect {}, "SET timezone 'UTC'").is_err());
+        assert!(parse_sql(&PostgreSqlDialect {}, "SHOW time zone extra").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_on_conflict() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "INSERT INTO t VALUES (1) ON CONFLICT DO NOTHING RETURNING a;
+             INSERT INTO t SELECT 1 ON CONFLICT (a, b) DO NOTHING;
+             INSERT INTO t VALUES (1) ON CONFLICT ON CONSTRAINT t_pkey DO NOTHING",
+        )?;
+        assert_eq!(stmts.len(), 3);
+
+        assert!(matches!(
+            &stmts[0],
+            Statement::Insert {
+                on_conflict: Some(OnConflict { target: None }),
+                returning,
+                ..
+            } if returning.len() == 1
+        ));
+
+        match &stmts[1] {
+            Statement::Insert {
+                source,
+                on_conflict:
+                    Some(OnConflict {
+                        target: Some(ConflictTarget::Columns(columns)),
+                    }),
+                ..
+            } => {
+                assert_eq!(source.to_string(), "SELECT 1");
+                assert_eq!(columns.len(), 2);
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        assert!(matches!(
+            &stmts[2],
+            Statement::Insert {
+                on_conflict: Some(OnConflict {
+                    target: Some(ConflictTarget::Constraint(name)),
+                }),
+                ..
+            } if name.value == "t_pkey"
+        ));
+
+        for sql in [
+            "INSERT INTO t VALUES (1) ON CONFLICT",
+            "INSERT INTO t VALUES (1) ON CONFLICT (a) DO",
+            "INSERT INTO t VALUES (1) ON CONFLICT (a) DO UPDATE SET b = excluded.b",
+            "INSERT INTO t VALUES (1) RETURNING a ON CONFLICT DO NOTHING",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_create_sequence() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "CREATE SEQUENCE s; CREATE SEQUENCE IF NOT EXISTS s AS int INCREMENT BY -2
+             MINVALUE -10 NO MAXVALUE START WITH -3 CACHE 5 CYCLE",
+        )?;
+        assert_eq!(stmts.len(), 2);
+
+        assert!(matches!(
+            &stmts[0],
+            Statement::CreateSequence { name, if_not_exists: false, options }
+                if name.to_string() == "s" && options == &SequenceOptions::default()
+        ));
+        match &stmts[1] {
+            Statement::CreateSequence {
+                if_not_exists: true,
+                options,
+                ..
+            } => assert_eq!(
+                options,
+                &SequenceOptions {
+                    data_type: Some(ast::DataType::Int(None)),
+                    increment: Some(-2),
+                    min_value: Some(-10),
+                    max_value: None,
+                    start: Some(-3),
+                    cache: Some(5),
+                    cycle: Some(true),
+                }
+            ),
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        for sql in [
+            "CREATE SEQUENCE s START 1 START 2",
+            "CREATE SEQUENCE s MINVALUE 1 NO MINVALUE",
+            "CREATE SEQUENCE s CYCLE NO CYCLE",
+            "CREATE SEQUENCE s INCREMENT BY a",
+            "CREATE SEQUENCE s MAXVALUE 9223372036854775808",
+            "CREATE SEQUENCE s NO START",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_create_table() -> Result<(), ParserError> {
+        let stmts = parse_sql(
+            &PostgreSqlDialect {},
+            "CREATE TABLE t (id int GENERATED ALWAYS AS IDENTITY, generated text DEFAULT 'x',
+             n bigint GENERATED BY DEFAULT AS IDENTITY (START WITH 10 INCREMENT 5), s serial)",
+        )?;
+        assert_eq!(stmts.len(), 1);
+
+        match &stmts[0] {
+            Statement::CreateTable {
+                name,
+                columns,
+                identities,
+            } => {
+                assert_eq!(name.to_string(), "t");
+                let columns = columns
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    columns,
+                    [
+                        "id INT",
+                        "generated TEXT DEFAULT 'x'",
+                        "n BIGINT",
+                        "s serial"
+                    ]
+                );
+                assert_eq!(
+                    identities,
+                    &[
+                        (
+                            ast::Ident::new("id"),
+                            Identity {
+                                always: true,
+                                options: SequenceOptions::default(),
+                            }
+                        ),
+                        (
+                            ast::Ident::new("n"),
+                            Identity {
+                                always: false,
+                                options: SequenceOptions {
+                                    start: Some(10),
+                                    increment: Some(5),
+                                    ..SequenceOptions::default()
+                                },
+                            }
+                        ),
+                    ]
+                );
+            }
+            stmt => panic!("unexpected statement {:?}", stmt),
+        }
+
+        for sql in [
+            "CREATE TABLE t (id int GENERATED AS IDENTITY)",
+            "CREATE TABLE t (id int GENERATED ALWAYS IDENTITY)",
+            "CREATE TABLE t (id int GENERATED ALWAYS AS IDENTITY (START))",
+            "CREATE TABLE t (id int) SELECT 1",
+        ] {
+            assert!(parse_sql(&PostgreSqlDialect {}, sql).is_err(), "{}", sql);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ext_operators() -> Result<(), ParserError> {
+        let dialect = PostgreSqlDialect {};
+        let operator = |sql: &str| -> Result<Option<(ExtOperator, String, String)>, ParserError> {
+            let expr = parse_expr(&dialect, sql)?;
+            Ok(ext_operator(&expr)
+                .map(|(op, left, right)| (op, left.to_string(), right.to_string())))
+        };
+
+        assert_eq!(
+            operator(r#"payload @> '{"a": 1}'"#)?,
+            Some((
+                ExtOperator::JsonbContains,
+                "payload".to_string(),
+                r#"'{"a": 1}'"#.to_string()
+            ))
+        );
+        assert_eq!(
+            operator("(a -> 'b') ? 'c' || 'd'")?,
+            Some((
+                ExtOperator::JsonbExists,
+                "(a -> 'b')".to_string(),
+                "'c' || 'd'".to_string()
+            ))
+        );
+        assert!(parse_expr(&dialect, "a @ > b").is_err());
+        assert_eq!(operator(r#"a # "@>" # b"#)?, None);
+
+        // The operator binds tighter than comparisons and boolean operators.
+        match parse_expr(&dialect, "a ? 'k' = true AND b @> c")? {
+            ast::Expr::BinaryOp {
+                left,
+                op: ast::BinaryOperator::And,
+                right,
+            } => {
+                match left.as_ref() {
+                    ast::Expr::BinaryOp { left, .. } => assert!(ext_operator(left).is_some()),
+                    expr => panic!("unexpected expression {}", expr),
+                }
+                assert!(ext_operator(&right).is_some());
+            }
+            expr => panic!("unexpected expression {}", expr),
+        }
+
+        // A displayed expression is parsed back to the same expression.
+        let expr = parse_expr(&dialect, "a @> b AND c ? 'k'")?;
+        assert_eq!(parse_expr(&dialect, &expr.to_string())?, expr);
+
+        assert_eq!(
+            operator("a NOT SIMILAR TO b || c")?,
+            Some((
+                ExtOperator::NotSimilarTo,
+                "a".to_string(),
+                "b || c".to_string()
+            ))
+        );
+
+        // ESCAPE binds tighter than LIKE, so it is the right operand of LIKE.
+        match parse_expr(&dialect, "a LIKE b ESCAPE '#' = true")? {
+            ast::Expr::BinaryOp {
+                left,
+                op: ast::BinaryOperator::Eq,
+                ..
+            } => match left.as_ref() {
+                ast::Expr::BinaryOp {
+                    op: ast::BinaryOperator::Like,
+                    right,
+                    ..
+                } => assert_eq!(
+                    ext_operator(right).map(|(op, ..)| op),
+                    Some(ExtOperator::Escape)
+                ),
+                expr => panic!("unexpected expression {}", expr),
+            },
+            expr => panic!("unexpected expression {}", expr),
+        }
+        assert!(parse_expr(&dialect, "a ~ b ESCAPE c").is_err());
+        assert!(parse_expr(&dialect, "(a LIKE b) ESCAPE c").is_err());
+
+        // A displayed expression is parsed back to the same expression.
+        let expr = parse_expr(&dialect, "a SIMILAR TO b ESCAPE c OR d ILIKE e ESCAPE f")?;
+        assert_eq!(parse_expr(&dialect, &expr.to_string())?, expr);
+
+        let stmts = parse_sql(
+            &dialect,
+            "SELECT * FROM t WHERE payload ? 'a'; COPY t TO STDOUT (FORMAT csv, ESCAPE '#')",
+        )?;
+        assert_eq!(stmts.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,578 @@
+//! Sequence number generators.
+//!
+//! A sequence is a relation of kind S with a single tuple that holds the last value returned by
+//! nextval. The options of the sequence, such as the increment and the bounds, are stored on
+//! pg_sequence.
+//!
+//! tinydb has no write-ahead log, so the sequence tuple is not updated on every call of nextval.
+//! Like the WAL records of PostgreSQL, the tuple written to disk is always SEQ_LOG_VALS values
+//! ahead of the values already returned, and it is flushed before any of these values is
+//! returned. The current position of the sequences used since the server started is kept in
+//! memory and written back on a clean shutdown, so after a crash some values are skipped but
+//! no value is returned twice.
+use std::collections::{btree_map::Entry, BTreeMap};
+use std::io::SeekFrom;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    access::{
+        self,
+        heap::{heap_insert, HeapScanner},
+        heaptuple::{HeapTuple, TupleDesc},
+    },
+    catalog::{
+        self,
+        heap::sequence_create,
+        pg_attribute::PgAttribute,
+        pg_class::{PgClass, RELKIND_SEQUENCE},
+        pg_sequence::PgSequence,
+        pg_tablespace::DEFAULTTABLESPACE_OID,
+        pg_type::{BOOL_OID, INT2_OID, INT8_OID, INT_OID},
+    },
+    planner::expr::type_name,
+    relation::Relation,
+    storage::{self, BufferPool, PageWriter},
+    types, Datum, Datums, Oid,
+};
+
+use super::{parser::SequenceOptions, session};
+
+/// Number of values that are reserved on disk ahead of the values returned by nextval.
+const SEQ_LOG_VALS: i64 = 32;
+
+/// Errors related with sequences.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("sequence type must be smallint, integer, or bigint")]
+    InvalidType,
+
+    #[error("INCREMENT must not be zero")]
+    ZeroIncrement,
+
+    #[error("MAXVALUE ({0}) is out of range for sequence data type {1}")]
+    MaxValueOutOfRange(i64, &'static str),
+
+    #[error("MINVALUE ({0}) is out of range for sequence data type {1}")]
+    MinValueOutOfRange(i64, &'static str),
+
+    #[error("MINVALUE ({0}) must be less than MAXVALUE ({1})")]
+    MinValueNotLessThanMax(i64, i64),
+
+    #[error("START value ({0}) cannot be less than MINVALUE ({1})")]
+    StartBelowMinValue(i64, i64),
+
+    #[error("START value ({0}) cannot be greater than MAXVALUE ({1})")]
+    StartAboveMaxValue(i64, i64),
+
+    #[error("CACHE ({0}) must be greater than zero")]
+    InvalidCache(i64),
+
+    #[error("\"{0}\" is not a sequence")]
+    NotSequence(String),
+
+    #[error("nextval: reached maximum value of sequence \"{0}\" ({1})")]
+    ReachedMaxValue(String, i64),
+
+    #[error("nextval: reached minimum value of sequence \"{0}\" ({1})")]
+    ReachedMinValue(String, i64),
+
+    #[error("currval of sequence \"{0}\" is not yet defined in this session")]
+    CurrvalNotDefined(String),
+
+    #[error("lastval is not yet defined in this session")]
+    LastvalNotDefined,
+
+    #[error("setval: value {0} is out of bounds for sequence \"{1}\" ({2}..{3})")]
+    SetvalOutOfBounds(i64, String, i64, i64),
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::InvalidType => "22023",
+            Error::ZeroIncrement => "22023",
+            Error::MaxValueOutOfRange(..) => "22023",
+            Error::MinValueOutOfRange(..) => "22023",
+            Error::MinValueNotLessThanMax(..) => "22023",
+            Error::StartBelowMinValue(..) => "22023",
+            Error::StartAboveMaxValue(..) => "22023",
+            Error::InvalidCache(_) => "22023",
+            Error::NotSequence(_) => "42809",
+            Error::ReachedMaxValue(..) => "2200H",
+            Error::ReachedMinValue(..) => "2200H",
+            Error::CurrvalNotDefined(_) => "55000",
+            Error::LastvalNotDefined => "55000",
+            Error::SetvalOutOfBounds(..) => "22003",
+        }
+    }
+}
+
+/// Current position of a sequence, which may be behind the position stored on disk.
+struct SequenceState {
+    /// Relation of the sequence.
+    rel: Relation,
+
+    /// Last value returned by nextval, or the value that the next call returns if is_called is
+    /// false.
+    last_value: i64,
+
+    /// Number of values after last_value that were already reserved on disk.
+    log_cnt: i64,
+
+    is_called: bool,
+}
+
+/// Position of the sequences used since the server started, by database and sequence oid.
+static SEQUENCES: Mutex<BTreeMap<(Oid, Oid), SequenceState>> = Mutex::new(BTreeMap::new());
+
+/// Create a new sequence with the given name and options. The sequence type is data_type if the
+/// options do not have one. Return the oid of the sequence relation.
+pub fn define_sequence(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    name: &str,
+    options: &SequenceOptions,
+    data_type: Oid,
+) -> Result<Oid> {
+    let seqtypid = match &options.data_type {
+        Some(data_type) => {
+            let (name, _) = type_name(data_type)?;
+            catalog::get_pg_type_by_name(buffer_pool, db_oid, &name)?.oid
+        }
+        None => data_type,
+    };
+    let (type_min, type_max) = match seqtypid {
+        INT2_OID => (i16::MIN as i64, i16::MAX as i64),
+        INT_OID => (i32::MIN as i64, i32::MAX as i64),
+        INT8_OID => (i64::MIN, i64::MAX),
+        _ => bail!(Error::InvalidType),
+    };
+    let typname = types::lookup_type(seqtypid)?.format_type();
+
+    let increment = options.increment.unwrap_or(1);
+    if increment == 0 {
+        bail!(Error::ZeroIncrement);
+    }
+
+    let max_value = options
+        .max_value
+        .unwrap_or(if increment > 0 { type_max } else { -1 });
+    if max_value < type_min || max_value > type_max {
+        bail!(Error::MaxValueOutOfRange(max_value, typname));
+    }
+
+    let min_value = options
+        .min_value
+        .unwrap_or(if increment > 0 { 1 } else { type_min });
+    if min_value < type_min || min_value > type_max {
+        bail!(Error::MinValueOutOfRange(min_value, typname));
+    }
+
+    if min_value >= max_value {
+        bail!(Error::MinValueNotLessThanMax(min_value, max_value));
+    }
+
+    let start = options
+        .start
+        .unwrap_or(if increment > 0 { min_value } else { max_value });
+    if start < min_value {
+        bail!(Error::StartBelowMinValue(start, min_value));
+    }
+    if start > max_value {
+        bail!(Error::StartAboveMaxValue(start, max_value));
+    }
+
+    let cache = options.cache.unwrap_or(1);
+    if cache <= 0 {
+        bail!(Error::InvalidCache(cache));
+    }
+
+    let seqrelid = catalog::new_relation_oid(buffer_pool, &DEFAULTTABLESPACE_OID, db_oid)?;
+    let rel = sequence_create(
+        buffer_pool,
+        db_oid,
+        name,
+        seqrelid,
+        &sequence_tuple_desc(seqrelid),
+    )?;
+
+    // The first call of nextval returns the start value.
+    heap_insert(
+        buffer_pool,
+        &rel,
+        &sequence_tuple(seqrelid, start, 0, false)?,
+    )?;
+
+    heap_insert(
+        buffer_pool,
+        &access::open_pg_sequence_relation(db_oid),
+        &HeapTuple::with_default_header(&PgSequence {
+            seqrelid,
+            seqtypid,
+            seqstart: start,
+            seqincrement: increment,
+            seqmax: max_value,
+            seqmin: min_value,
+            seqcache: cache,
+            seqcycle: options.cycle.unwrap_or(false),
+        })?,
+    )?;
+
+    Ok(seqrelid)
+}
+
+/// Advance the given sequence and return its new value.
+pub fn nextval(name: &str) -> Result<i64> {
+    let (buffer_pool, db_oid) = session::database()?;
+    let (rel, seq) = open_sequence(&buffer_pool, &db_oid, name)?;
+
+    let mut sequences = SEQUENCES.lock().unwrap();
+    let state = match sequences.entry((db_oid, seq.seqrelid)) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(read_sequence_state(&buffer_pool, rel)?),
+    };
+
+    let value = if state.is_called {
+        match next_value(state.last_value, &seq) {
+            Some(value) => value,
+            None if seq.seqincrement > 0 => {
+                bail!(Error::ReachedMaxValue(
+                    state.rel.rel_name.clone(),
+                    seq.seqmax
+                ))
+            }
+            None => bail!(Error::ReachedMinValue(
+                state.rel.rel_name.clone(),
+                seq.seqmin
+            )),
+        }
+    } else {
+        state.last_value
+    };
+
+    if state.log_cnt == 0 {
+        // Reserve the next values on disk before returning any of them.
+        let mut logged = value;
+        let mut log_cnt = 0;
+        while log_cnt < SEQ_LOG_VALS {
+            match next_value(logged, &seq) {
+                Some(next) => logged = next,
+                None => break,
+            }
+            log_cnt += 1;
+        }
+        write_sequence_tuple(&buffer_pool, &state.rel, logged, true)?;
+        state.log_cnt = log_cnt;
+    } else {
+        state.log_cnt -= 1;
+    }
+    state.last_value = value;
+    state.is_called = true;
+
+    session::set_sequence_value(seq.seqrelid, value);
+    session::set_last_sequence(seq.seqrelid);
+    Ok(value)
+}
+
+/// Return the value most recently returned by nextval for the given sequence on the current
+/// session.
+pub fn currval(name: &str) -> Result<i64> {
+    let (buffer_pool, db_oid) = session::database()?;
+    let (rel, seq) = open_sequence(&buffer_pool, &db_oid, name)?;
+
+    session::sequence_value(seq.seqrelid)
+        .ok_or_else(|| anyhow!(Error::CurrvalNotDefined(rel.rel_name.clone())))
+}
+
+/// Return the value most recently returned by nextval on the current session, of any sequence.
+pub fn lastval() -> Result<i64> {
+    session::last_sequence()
+        .and_then(session::sequence_value)
+        .ok_or_else(|| anyhow!(Error::LastvalNotDefined))
+}
+
+/// Change the last value of the given sequence. If is_called is false the next call of nextval
+/// returns the given value, otherwise it returns the value after it.
+pub fn setval(name: &str, value: i64, is_called: bool) -> Result<i64> {
+    let (buffer_pool, db_oid) = session::database()?;
+    let (rel, seq) = open_sequence(&buffer_pool, &db_oid, name)?;
+
+    if value < seq.seqmin || value > seq.seqmax {
+        bail!(Error::SetvalOutOfBounds(
+            value,
+            rel.rel_name.clone(),
+            seq.seqmin,
+            seq.seqmax
+        ));
+    }
+
+    let mut sequences = SEQUENCES.lock().unwrap();
+    write_sequence_tuple(&buffer_pool, &rel, value, is_called)?;
+    sequences.insert(
+        (db_oid, seq.seqrelid),
+        SequenceState {
+            rel,
+            last_value: value,
+            log_cnt: 0,
+            is_called,
+        },
+    );
+
+    if is_called {
+        session::set_sequence_value(seq.seqrelid, value);
+    }
+    Ok(value)
+}
+
+/// Return the tuple description of the given sequence relation and its only row, which holds
+/// the current position of the sequence like a scan of the sequence on PostgreSQL.
+///
+/// The tuple stored on disk is ahead of the values returned by nextval, so the position of a
+/// sequence used since the server started is read from memory.
+pub fn scan_sequence(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    pg_class: &PgClass,
+) -> Result<(TupleDesc, Vec<Option<Datum>>)> {
+    let sequences = SEQUENCES.lock().unwrap();
+    let (last_value, log_cnt, is_called) = match sequences.get(&(*db_oid, pg_class.oid)) {
+        Some(state) => (state.last_value, state.log_cnt, state.is_called),
+        None => {
+            let rel = access::open_relation(
+                pg_class.oid,
+                pg_class.reltablespace,
+                db_oid,
+                &pg_class.relname,
+            );
+            let state = read_sequence_state(buffer_pool, rel)?;
+            (state.last_value, state.log_cnt, state.is_called)
+        }
+    };
+
+    Ok((
+        sequence_tuple_desc(pg_class.oid),
+        sequence_values(last_value, log_cnt, is_called)?,
+    ))
+}
+
+/// Write the current position of the sequences used since the server started to disk, so that
+/// no values are skipped when the server is started again.
+pub fn write_sequences(buffer_pool: &BufferPool) -> Result<()> {
+    let sequences = SEQUENCES.lock().unwrap();
+    for state in sequences.values() {
+        write_sequence_tuple(buffer_pool, &state.rel, state.last_value, state.is_called)?;
+    }
+    Ok(())
+}
+
+/// Restore the tuple of the sequences that lost it because the server was not shut down
+/// cleanly.
+///
+/// The tuple of a sequence is flushed to disk before nextval returns any value, so a sequence
+/// without a tuple never returned a value and starts again from its start value.
+pub fn recover_sequences(buffer_pool: &BufferPool) -> Result<()> {
+    for db_oid in catalog::databases(buffer_pool)? {
+        let mut sequences = Vec::new();
+        let mut heap = HeapScanner::new(buffer_pool, &access::open_pg_sequence_relation(&db_oid))?;
+        while let Some(tuple) = heap.next_tuple()? {
+            sequences.push(bincode::deserialize::<PgSequence>(&tuple.data)?);
+        }
+
+        for seq in sequences {
+            let pg_class = match catalog::find_pg_class_by_oid(buffer_pool, &db_oid, seq.seqrelid)?
+            {
+                Some(pg_class) => pg_class,
+                None => continue,
+            };
+            let rel = access::open_relation(
+                pg_class.oid,
+                pg_class.reltablespace,
+                &db_oid,
+                &pg_class.relname,
+            );
+            if !has_sequence_tuple(buffer_pool, &rel)? {
+                log::warn!("restoring lost tuple of sequence \"{}\"", rel.rel_name);
+                heap_insert(
+                    buffer_pool,
+                    &rel,
+                    &sequence_tuple(seq.seqrelid, seq.seqstart, 0, false)?,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Return true if the given sequence relation has a tuple stored.
+fn has_sequence_tuple(buffer_pool: &BufferPool, rel: &Relation) -> Result<bool> {
+    if buffer_pool.size_of_relation(rel)? == 0 {
+        return Ok(false);
+    }
+    let buffer = buffer_pool.fetch_buffer(rel, 1)?;
+    let has_tuple = storage::item_id_data_from_page(&buffer.page).map(|items| !items.is_empty());
+    buffer_pool.unpin_buffer(&buffer, false)?;
+    has_tuple
+}
+
+/// Return the value after the given value of a sequence, or None if the sequence reached its
+/// bound and does not cycle.
+fn next_value(value: i64, seq: &PgSequence) -> Option<i64> {
+    match value.checked_add(seq.seqincrement) {
+        Some(next) if next >= seq.seqmin && next <= seq.seqmax => Some(next),
+        _ if !seq.seqcycle => None,
+        _ if seq.seqincrement > 0 => Some(seq.seqmin),
+        _ => Some(seq.seqmax),
+    }
+}
+
+/// Open the sequence relation with the given name, which may be double quoted, and return it
+/// with its pg_sequence tuple.
+fn open_sequence(
+    buffer_pool: &BufferPool,
+    db_oid: &Oid,
+    name: &str,
+) -> Result<(Relation, PgSequence)> {
+    let name = match name
+        .strip_prefix('"')
+        .and_then(|name| name.strip_suffix('"'))
+    {
+        Some(name) => name.replace("\"\"", "\""),
+        None => name.to_string(),
+    };
+
+    let pg_class = catalog::find_pg_class_by_name(buffer_pool, db_oid, &name)?
+        .ok_or_else(|| anyhow!(catalog::Error::RelationNotFound(name.clone())))?;
+    if pg_class.relkind != RELKIND_SEQUENCE {
+        bail!(Error::NotSequence(name));
+    }
+
+    let seq = catalog::get_pg_sequence(buffer_pool, db_oid, pg_class.oid)?;
+    let rel = access::open_relation(pg_class.oid, pg_class.reltablespace, db_oid, &name);
+    Ok((rel, seq))
+}
+
+/// Read the position of a sequence that was not used since the server started from disk.
+///
+/// The values up to the stored value may have been returned before the server stopped, so none
+/// of them are reserved.
+fn read_sequence_state(buffer_pool: &BufferPool, rel: Relation) -> Result<SequenceState> {
+    let buffer = buffer_pool.fetch_buffer(&rel, 1)?;
+    let tuple = match storage::item_id_data_from_page(&buffer.page)?.first() {
+        Some(item) => HeapTuple::decode(&storage::value_from_page_item(&buffer.page, item)?),
+        None => Err(anyhow!("sequence {} has no tuple", rel.rel_name)),
+    };
+    buffer_pool.unpin_buffer(&buffer, false)?;
+
+    let tuple = tuple?;
+    let tuple_desc = sequence_tuple_desc(rel.locator.oid);
+    let attr = |attnum: usize| {
+        tuple
+            .get_attr(attnum, &tuple_desc)?
+            .ok_or_else(|| anyhow!("sequence {} has a NULL attribute", rel.rel_name))
+    };
+    let last_value = bincode::deserialize(&attr(1)?)?;
+    let is_called = bincode::deserialize(&attr(3)?)?;
+
+    Ok(SequenceState {
+        rel,
+        last_value,
+        log_cnt: 0,
+        is_called,
+    })
+}
+
+/// Overwrite the tuple of the given sequence and flush its page to disk.
+fn write_sequence_tuple(
+    buffer_pool: &BufferPool,
+    rel: &Relation,
+    last_value: i64,
+    is_called: bool,
+) -> Result<()> {
+    let tuple = sequence_tuple(rel.locator.oid, last_value, 0, is_called)?.encode()?;
+
+    let buffer = buffer_pool.fetch_buffer(rel, 1)?;
+    let result = match storage::item_id_data_from_page(&buffer.page)?.first() {
+        // All attributes have a fixed length, so the new tuple has the size of the old one.
+        Some(item) if item.length as usize == tuple.len() => PageWriter::new(&buffer.page)
+            .write_at(&tuple, SeekFrom::Start(item.offset as u64))
+            .and_then(|_| buffer_pool.flush_buffer(&buffer)),
+        _ => Err(anyhow!("sequence {} has no valid tuple", rel.rel_name)),
+    };
+    buffer_pool.unpin_buffer(&buffer, true)?;
+    result
+}
+
+/// Return the tuple stored on a sequence relation.
+fn sequence_tuple(
+    seqrelid: Oid,
+    last_value: i64,
+    log_cnt: i64,
+    is_called: bool,
+) -> Result<HeapTuple> {
+    let values = sequence_values(last_value, log_cnt, is_called)?;
+    HeapTuple::from_datums(Datums::from(values), &sequence_tuple_desc(seqrelid))
+}
+
+/// Return the attribute values of a sequence tuple.
+fn sequence_values(last_value: i64, log_cnt: i64, is_called: bool) -> Result<Vec<Option<Datum>>> {
+    Ok(vec![
+        Some(bincode::serialize(&last_value)?),
+        Some(bincode::serialize(&log_cnt)?),
+        Some(bincode::serialize(&is_called)?),
+    ])
+}
+
+/// Return the tuple description of a sequence relation.
+fn sequence_tuple_desc(seqrelid: Oid) -> TupleDesc {
+    let attribute = |attname: &str, attnum: usize, atttypid: Oid, attlen: i64| PgAttribute {
+        attrelid: seqrelid,
+        attname: String::from(attname),
+        attnum,
+        attlen,
+        atttypid,
+        atttypmod: -1,
+        attidentity: String::new(),
+    };
+
+    TupleDesc {
+        attrs: vec![
+            attribute("last_value", 1, INT8_OID, 8),
+            attribute("log_cnt", 2, INT8_OID, 8),
+            attribute("is_called", 3, BOOL_OID, 1),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(min: i64, max: i64, increment: i64, cycle: bool) -> PgSequence {
+        PgSequence {
+            seqrelid: 0,
+            seqtypid: INT8_OID,
+            seqstart: min,
+            seqincrement: increment,
+            seqmax: max,
+            seqmin: min,
+            seqcache: 1,
+            seqcycle: cycle,
+        }
+    }
+
+    #[test]
+    fn test_next_value() {
+        assert_eq!(next_value(1, &sequence(1, 10, 1, false)), Some(2));
+        assert_eq!(next_value(9, &sequence(1, 10, 3, false)), None);
+        assert_eq!(next_value(9, &sequence(1, 10, 3, true)), Some(1));
+        assert_eq!(next_value(-9, &sequence(-10, -1, -1, false)), Some(-10));
+        assert_eq!(next_value(-10, &sequence(-10, -1, -1, true)), Some(-1));
+        assert_eq!(
+            next_value(i64::MAX - 1, &sequence(1, i64::MAX, 5, false)),
+            None
+        );
+    }
+}
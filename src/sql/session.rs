@@ -0,0 +1,184 @@
+//! State of the client session served by the current task.
+//!
+//! PostgreSQL keeps the session state on global variables of the backend process that serves
+//! the client. tinydb serves each connection on its own task, so the session state is stored on
+//! a task-local variable, where it can be read by the data type functions without passing it
+//! through every call, e.g to display a timestamp with time zone on the session time zone.
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+
+use anyhow::{bail, Result};
+
+use crate::{
+    catalog::pg_type,
+    storage::BufferPool,
+    types::datetime::{self, TimeZone},
+    Datum, Oid,
+};
+
+tokio::task_local! {
+    static SESSION: RefCell<Session>;
+}
+
+/// State of a client session.
+#[derive(Clone)]
+pub struct Session {
+    /// Time zone used to read and display timestamp with time zone values.
+    timezone: TimeZone,
+
+    /// Start time of the current transaction, in microseconds since 2000-01-01 UTC.
+    transaction_timestamp: i64,
+
+    /// Oid of the database that the session is connected to.
+    database: Oid,
+
+    /// Buffer pool used by functions that access relations, e.g nextval.
+    buffer_pool: BufferPool,
+
+    /// Last value returned by nextval for each sequence used on the session.
+    sequence_values: HashMap<Oid, i64>,
+
+    /// Sequence of the last value returned by nextval on the session.
+    last_sequence: Option<Oid>,
+
+    /// Values of the parameters, $1 to $n, of the statement being planned or executed.
+    params: Vec<Param>,
+}
+
+/// Value of a parameter of a prepared statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    /// Type of the parameter, unknown if the type was not given by the client nor inferred from
+    /// the statement, in which case the value is used like a quoted literal.
+    pub typ: Oid,
+
+    pub value: Option<Datum>,
+}
+
+impl Session {
+    pub fn new(timezone: TimeZone, database: Oid, buffer_pool: BufferPool) -> Self {
+        Self {
+            timezone,
+            transaction_timestamp: datetime::current_timestamp(),
+            database,
+            buffer_pool,
+            sequence_values: HashMap::new(),
+            last_sequence: None,
+            params: Vec::new(),
+        }
+    }
+}
+
+/// Run the given future with the given session as the current session.
+pub async fn scope<F: Future>(session: Session, f: F) -> F::Output {
+    SESSION.scope(RefCell::new(session), f).await
+}
+
+/// Return the time zone of the current session, or UTC if there is no current session.
+pub fn timezone() -> TimeZone {
+    SESSION
+        .try_with(|session| session.borrow().timezone.clone())
+        .unwrap_or_default()
+}
+
+/// Change the time zone of the current session.
+pub fn set_timezone(timezone: TimeZone) {
+    let _ = SESSION.try_with(|session| session.borrow_mut().timezone = timezone);
+}
+
+/// Mark the start of a new transaction on the current session.
+pub fn start_transaction() {
+    let _ = SESSION.try_with(|session| {
+        session.borrow_mut().transaction_timestamp = datetime::current_timestamp()
+    });
+}
+
+/// Return the start time of the current transaction, in microseconds since 2000-01-01 UTC.
+///
+/// Outside of a session every call is its own transaction, so the current time is returned.
+pub fn transaction_timestamp() -> i64 {
+    SESSION
+        .try_with(|session| session.borrow().transaction_timestamp)
+        .unwrap_or_else(|_| datetime::current_timestamp())
+}
+
+/// Return the buffer pool and the database oid of the current session.
+pub fn database() -> Result<(BufferPool, Oid)> {
+    match SESSION.try_with(|session| {
+        let session = session.borrow();
+        (session.buffer_pool.clone(), session.database)
+    }) {
+        Ok(database) => Ok(database),
+        Err(_) => bail!("there is no database session on the current task"),
+    }
+}
+
+/// Return the last value returned by nextval for the given sequence on the current session.
+pub fn sequence_value(seqrelid: Oid) -> Option<i64> {
+    SESSION
+        .try_with(|session| session.borrow().sequence_values.get(&seqrelid).copied())
+        .ok()
+        .flatten()
+}
+
+/// Change the value returned by currval for the given sequence on the current session.
+pub fn set_sequence_value(seqrelid: Oid, value: i64) {
+    let _ =
+        SESSION.try_with(|session| session.borrow_mut().sequence_values.insert(seqrelid, value));
+}
+
+/// Change the sequence whose value is returned by lastval on the current session.
+pub fn set_last_sequence(seqrelid: Oid) {
+    let _ = SESSION.try_with(|session| session.borrow_mut().last_sequence = Some(seqrelid));
+}
+
+/// Return the sequence of the last value returned by nextval on the current session.
+pub fn last_sequence() -> Option<Oid> {
+    SESSION
+        .try_with(|session| session.borrow().last_sequence)
+        .ok()
+        .flatten()
+}
+
+/// Change the parameters of the statement being planned or executed on the current session.
+pub fn set_params(params: Vec<Param>) {
+    let _ = SESSION.try_with(|session| session.borrow_mut().params = params);
+}
+
+/// Return the parameters of the statement being planned or executed on the current session.
+pub fn params() -> Vec<Param> {
+    SESSION
+        .try_with(|session| session.borrow().params.clone())
+        .unwrap_or_default()
+}
+
+/// Return the parameter with the given number, starting from 1, of the statement being planned
+/// or executed on the current session.
+pub fn param(number: usize) -> Option<Param> {
+    SESSION
+        .try_with(|session| {
+            let session = session.borrow();
+            number
+                .checked_sub(1)
+                .and_then(|index| session.params.get(index).cloned())
+        })
+        .ok()
+        .flatten()
+}
+
+/// Infer the type of a parameter of unknown type from the way that it is used on the statement,
+/// e.g $1::int[]. The type of a parameter is only inferred once.
+pub fn infer_param_type(number: usize, typ: Oid) {
+    let _ = SESSION.try_with(|session| {
+        let mut session = session.borrow_mut();
+        if let Some(param) = number
+            .checked_sub(1)
+            .and_then(|index| session.params.get_mut(index))
+        {
+            if param.typ == pg_type::UNKNOWN_OID && typ != pg_type::UNKNOWN_OID {
+                param.typ = typ;
+            }
+        }
+    });
+}
@@ -104,6 +104,11 @@ impl Buffer {
         }
     }
 
+    /// Return the number of the page that is stored on buffer.
+    pub fn page_number(&self) -> PageNumber {
+        self.tag.read().unwrap().page_number
+    }
+
     fn relation(&self) -> Result<Relation> {
         let rel = self.rel.read().unwrap();
         // Match the reference from the de-refenrece value of RwLock
@@ -358,7 +363,8 @@ impl BufferPool {
             refs
         );
 
-        if self.refs.load(Ordering::SeqCst) == 0 {
+        // The buffer is available for replacement once the last reference is released.
+        if refs == 1 {
             self.lru.lock().unwrap().unpin(&bufid);
         }
         Ok(())
@@ -1,12 +1,44 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::relation::Relation;
 
-use super::{buffer::Buffer, BufferPool};
+use super::{
+    buffer::Buffer,
+    page::{page_free_space, page_init, ITEM_ID_SIZE, PAGE_HEADER_SIZE},
+    BufferPool, PAGE_SIZE,
+};
 
 /// Try to find a page in the given relation with at least the specified amount of free space.
 ///
+/// Only the last page of the relation is checked, if it doesn't have enough free space the
+/// relation is extended with a new page. The returned buffer is pinned.
+///
 // TODO: Implement visibility map to find free page to add a new tuple
-pub fn get_page_with_free_space(buffer: &BufferPool, rel: &Relation) -> Result<Buffer> {
-    buffer.fetch_buffer(rel, 1)
+pub fn get_page_with_free_space(
+    buffer_pool: &BufferPool,
+    rel: &Relation,
+    size: usize,
+) -> Result<Buffer> {
+    if size + ITEM_ID_SIZE > PAGE_SIZE - PAGE_HEADER_SIZE {
+        bail!("item of size {} is too large to fit on a page", size);
+    }
+
+    let npages = buffer_pool.size_of_relation(rel)?;
+    if npages > 0 {
+        let buffer = buffer_pool.fetch_buffer(rel, npages)?;
+        if page_free_space(&buffer.page)? >= size + ITEM_ID_SIZE {
+            return Ok(buffer);
+        }
+        buffer_pool.unpin_buffer(&buffer, false)?;
+    }
+
+    extend_relation(buffer_pool, rel)
+}
+
+/// Add a new page with the default header values at the end of the given relation. The
+/// returned buffer is pinned.
+pub fn extend_relation(buffer_pool: &BufferPool, rel: &Relation) -> Result<Buffer> {
+    let buffer = buffer_pool.alloc_buffer(rel)?;
+    page_init(&buffer.page)?;
+    Ok(buffer)
 }
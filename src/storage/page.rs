@@ -38,6 +38,20 @@ impl Default for PageHeader {
     }
 }
 
+/// Initialize the given in-memory page with the default page header values.
+pub fn page_init(page: &Page) -> Result<()> {
+    let mut page_writer = PageWriter::new(page);
+    bincode::serialize_into(&mut page_writer, &PageHeader::default())?;
+    Ok(())
+}
+
+/// Return the amount of free space on page that can be used to store a new item and its
+/// line pointer.
+pub fn page_free_space(page: &Page) -> Result<usize> {
+    let header = PageHeader::new(page)?;
+    Ok((header.end_free_space as usize).saturating_sub(header.start_free_space as usize))
+}
+
 /// Offset number of an item on buffer page.
 pub type OffsetNumber = u16;
 
@@ -55,8 +69,9 @@ pub struct ItemId {
 pub const ITEM_ID_SIZE: usize = size_of::<ItemId>();
 
 /// Add a new item to a page. The page header start_free_space and end_free_space is also updated
-/// to point to the new offsets after the item is inserted on in-memory page.
-pub fn page_add_item(page: &Page, item: &Vec<u8>) -> Result<()> {
+/// to point to the new offsets after the item is inserted on in-memory page. Return the index of
+/// the item id of the new item.
+pub fn page_add_item(page: &Page, item: &Vec<u8>) -> Result<usize> {
     let mut header = PageHeader::new(page)?;
 
     if header.start_free_space < PAGE_HEADER_SIZE as u16
@@ -65,6 +80,13 @@ pub fn page_add_item(page: &Page, item: &Vec<u8>) -> Result<()> {
         bail!("corrupted page pointers: {:#?}", header);
     }
 
+    if item.len() + ITEM_ID_SIZE > page_free_space(page)? {
+        bail!(
+            "not enough free space on page to add an item of size {}",
+            item.len()
+        );
+    }
+
     // Select the offset number to place the new item
     let item_id_offset = header.start_free_space as usize;
     let item_id = ItemId {
@@ -87,7 +109,7 @@ pub fn page_add_item(page: &Page, item: &Vec<u8>) -> Result<()> {
     page_writer.seek(io::SeekFrom::Start(0))?;
     bincode::serialize_into(&mut page_writer, &header)?;
 
-    Ok(())
+    Ok((item_id_offset - PAGE_HEADER_SIZE) / ITEM_ID_SIZE)
 }
 
 #[cfg(test)]
@@ -124,6 +146,23 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_page_add_item_without_free_space() -> Result<()> {
+        let page = Page::default();
+        page_init(&page)?;
+
+        let item = vec![0; 1000];
+        let mut added = 0;
+        while page_free_space(&page)? >= item.len() + ITEM_ID_SIZE {
+            assert_eq!(added, page_add_item(&page, &item)?);
+            added += 1;
+        }
+
+        assert_eq!(added, 8);
+        assert!(page_add_item(&page, &item).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_default_page_header_values() {
         let header = PageHeader::default();
@@ -0,0 +1,951 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    boolean, bpchar, bytea, datetime, deserialize, float, int, interval, json, jsonb, numeric, oid,
+    text, uuid, varchar, BinaryReader, Error, Type, TypeAlign, TypeCategory,
+};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Max number of dimensions of an array.
+pub const MAXDIM: usize = 6;
+
+/// Array data type of an element type.
+///
+/// Arrays have up to [MAXDIM] dimensions, each one with a lower bound that is 1 unless a
+/// different bound is declared on the text representation, e.g '[0:1]={1,2}'. The typmod of
+/// an array is the typmod of its elements, e.g the max length of a varchar(10)[].
+pub struct ArrayType {
+    oid: Oid,
+    typname: &'static str,
+    format_type: &'static str,
+    elem: &'static dyn Type,
+}
+
+impl ArrayType {
+    /// Create the array type of a data type created with CREATE TYPE, which is named after the
+    /// element type with a leading underscore.
+    pub fn new(oid: Oid, elem: &'static dyn Type) -> Self {
+        Self {
+            oid,
+            typname: Box::leak(format!("_{}", elem.typname()).into_boxed_str()),
+            format_type: Box::leak(format!("{}[]", elem.format_type()).into_boxed_str()),
+            elem,
+        }
+    }
+}
+
+macro_rules! array_type {
+    ($name:ident, $oid:expr, $typname:expr, $format_type:expr, $elem:expr) => {
+        pub static $name: ArrayType = ArrayType {
+            oid: $oid,
+            typname: $typname,
+            format_type: $format_type,
+            elem: &$elem,
+        };
+    };
+}
+
+array_type!(
+    BOOL_ARRAY,
+    pg_type::BOOL_ARRAY_OID,
+    "_bool",
+    "boolean[]",
+    boolean::Bool
+);
+array_type!(
+    BYTEA_ARRAY,
+    pg_type::BYTEA_ARRAY_OID,
+    "_bytea",
+    "bytea[]",
+    bytea::Bytea
+);
+array_type!(
+    INT8_ARRAY,
+    pg_type::INT8_ARRAY_OID,
+    "_int8",
+    "bigint[]",
+    int::Int8
+);
+array_type!(
+    INT2_ARRAY,
+    pg_type::INT2_ARRAY_OID,
+    "_int2",
+    "smallint[]",
+    int::Int2
+);
+array_type!(
+    INT4_ARRAY,
+    pg_type::INT4_ARRAY_OID,
+    "_int4",
+    "integer[]",
+    int::Int4
+);
+array_type!(
+    TEXT_ARRAY,
+    pg_type::TEXT_ARRAY_OID,
+    "_text",
+    "text[]",
+    text::Text
+);
+array_type!(
+    OID_ARRAY,
+    pg_type::OID_ARRAY_OID,
+    "_oid",
+    "oid[]",
+    oid::OidType
+);
+array_type!(
+    JSON_ARRAY,
+    pg_type::JSON_ARRAY_OID,
+    "_json",
+    "json[]",
+    json::Json
+);
+array_type!(
+    FLOAT4_ARRAY,
+    pg_type::FLOAT4_ARRAY_OID,
+    "_float4",
+    "real[]",
+    float::Float4
+);
+array_type!(
+    FLOAT8_ARRAY,
+    pg_type::FLOAT8_ARRAY_OID,
+    "_float8",
+    "double precision[]",
+    float::Float8
+);
+array_type!(
+    BPCHAR_ARRAY,
+    pg_type::BPCHAR_ARRAY_OID,
+    "_bpchar",
+    "character[]",
+    bpchar::Bpchar
+);
+array_type!(
+    VARCHAR_ARRAY,
+    pg_type::VARCHAR_ARRAY_OID,
+    "_varchar",
+    "character varying[]",
+    varchar::Varchar
+);
+array_type!(
+    NUMERIC_ARRAY,
+    pg_type::NUMERIC_ARRAY_OID,
+    "_numeric",
+    "numeric[]",
+    numeric::Numeric
+);
+array_type!(
+    DATE_ARRAY,
+    pg_type::DATE_ARRAY_OID,
+    "_date",
+    "date[]",
+    datetime::Date
+);
+array_type!(
+    TIME_ARRAY,
+    pg_type::TIME_ARRAY_OID,
+    "_time",
+    "time without time zone[]",
+    datetime::Time
+);
+array_type!(
+    TIMESTAMP_ARRAY,
+    pg_type::TIMESTAMP_ARRAY_OID,
+    "_timestamp",
+    "timestamp without time zone[]",
+    datetime::Timestamp
+);
+array_type!(
+    TIMESTAMPTZ_ARRAY,
+    pg_type::TIMESTAMPTZ_ARRAY_OID,
+    "_timestamptz",
+    "timestamp with time zone[]",
+    datetime::TimestampTz
+);
+array_type!(
+    INTERVAL_ARRAY,
+    pg_type::INTERVAL_ARRAY_OID,
+    "_interval",
+    "interval[]",
+    interval::Interval
+);
+array_type!(
+    UUID_ARRAY,
+    pg_type::UUID_ARRAY_OID,
+    "_uuid",
+    "uuid[]",
+    uuid::Uuid
+);
+array_type!(
+    JSONB_ARRAY,
+    pg_type::JSONB_ARRAY_OID,
+    "_jsonb",
+    "jsonb[]",
+    jsonb::Jsonb
+);
+
+impl Type for ArrayType {
+    fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    fn typname(&self) -> &'static str {
+        self.typname
+    }
+
+    fn format_type(&self) -> &'static str {
+        self.format_type
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Array
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        match self.elem.typalign() {
+            TypeAlign::Double => TypeAlign::Double,
+            _ => TypeAlign::Int,
+        }
+    }
+
+    fn typelem(&self) -> Oid {
+        self.elem.oid()
+    }
+
+    fn typmod_in(&self, args: &[u64]) -> Result<i32> {
+        self.elem.typmod_in(args)
+    }
+
+    fn input(&self, value: &str, typmod: i32) -> Result<Datum> {
+        ArrayValue::parse(value, self.elem, typmod)?.to_datum()
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        ArrayValue::from_datum(datum)?.to_text(self.elem)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        ArrayValue::recv(buf, self.elem, typmod)?.to_datum()
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        ArrayValue::from_datum(datum)?.send(self.elem)
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        ArrayValue::from_datum(a)?.compare(&ArrayValue::from_datum(b)?, self.elem)
+    }
+}
+
+/// A dimension of an array.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ArrayDim {
+    /// Number of elements on the dimension.
+    pub len: i32,
+
+    /// Subscript of the first element of the dimension.
+    pub lbound: i32,
+}
+
+/// Internal representation of an array, which is the datum stored on heap tuples.
+///
+/// Only the elements that are not NULL are stored, in row-major order. If the array has NULL
+/// elements a bitmap with a bit set for each element that is not NULL is also stored.
+#[derive(Serialize, Deserialize)]
+struct ArrayData {
+    elemtype: Oid,
+    dims: Vec<ArrayDim>,
+    null_bitmap: Vec<u8>,
+    values: Vec<Datum>,
+}
+
+/// An array value with its elements decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayValue {
+    /// Oid of the type of the elements.
+    pub elemtype: Oid,
+
+    /// Dimensions of the array, empty arrays have no dimensions.
+    pub dims: Vec<ArrayDim>,
+
+    /// Datums of all elements on row-major order, None for NULL elements.
+    pub elements: Vec<Option<Datum>>,
+}
+
+impl ArrayValue {
+    /// Create a one-dimensional array with the given elements.
+    pub fn new(elemtype: Oid, elements: Vec<Option<Datum>>) -> Self {
+        let dims = if elements.is_empty() {
+            Vec::new()
+        } else {
+            vec![ArrayDim {
+                len: elements.len() as i32,
+                lbound: 1,
+            }]
+        };
+        Self {
+            elemtype,
+            dims,
+            elements,
+        }
+    }
+
+    /// Create an array with one more dimension from a list of arrays with the same dimensions,
+    /// e.g ARRAY[ARRAY[1, 2], ARRAY[3, 4]].
+    pub fn stack(elemtype: Oid, arrays: Vec<ArrayValue>) -> Result<Self> {
+        let dims = match arrays.first() {
+            Some(first) => first.dims.clone(),
+            None => return Ok(Self::new(elemtype, Vec::new())),
+        };
+        if arrays.iter().any(|array| array.dims != dims) {
+            bail!(Error::ArrayDimensionMismatch);
+        }
+        if dims.is_empty() {
+            return Ok(Self::new(elemtype, Vec::new()));
+        }
+        if dims.len() + 1 > MAXDIM {
+            bail!(Error::ArrayDimensionsExceeded(dims.len() + 1));
+        }
+
+        let mut stacked = Self {
+            elemtype,
+            dims: Vec::with_capacity(dims.len() + 1),
+            elements: Vec::new(),
+        };
+        stacked.dims.push(ArrayDim {
+            len: arrays.len() as i32,
+            lbound: 1,
+        });
+        stacked.dims.extend(dims);
+        for array in arrays {
+            stacked.elements.extend(array.elements);
+        }
+        Ok(stacked)
+    }
+
+    /// Decode an array datum.
+    pub fn from_datum(datum: &[u8]) -> Result<Self> {
+        let data = deserialize::<ArrayData>(datum)?;
+        let nitems = data
+            .dims
+            .iter()
+            .map(|dim| dim.len as usize)
+            .product::<usize>();
+        let nitems = if data.dims.is_empty() { 0 } else { nitems };
+
+        let mut values = data.values.into_iter();
+        let mut elements = Vec::with_capacity(nitems);
+        for i in 0..nitems {
+            let is_null =
+                !data.null_bitmap.is_empty() && data.null_bitmap[i / 8] & (1 << (i % 8)) == 0;
+            elements.push(if is_null { None } else { values.next() });
+        }
+
+        Ok(Self {
+            elemtype: data.elemtype,
+            dims: data.dims,
+            elements,
+        })
+    }
+
+    /// Encode the array as a datum.
+    pub fn to_datum(&self) -> Result<Datum> {
+        let mut null_bitmap = Vec::new();
+        if self.elements.iter().any(Option::is_none) {
+            null_bitmap = vec![0; self.elements.len().div_ceil(8)];
+            for (i, element) in self.elements.iter().enumerate() {
+                if element.is_some() {
+                    null_bitmap[i / 8] |= 1 << (i % 8);
+                }
+            }
+        }
+
+        Ok(bincode::serialize(&ArrayData {
+            elemtype: self.elemtype,
+            dims: self.dims.clone(),
+            null_bitmap,
+            values: self.elements.iter().flatten().cloned().collect(),
+        })?)
+    }
+
+    /// Return the element at the given subscripts, or None if the element is NULL or the
+    /// subscripts are out of the array bounds.
+    pub fn element(&self, subscripts: &[i32]) -> Option<&Datum> {
+        if subscripts.len() != self.dims.len() {
+            return None;
+        }
+
+        let mut offset = 0;
+        for (subscript, dim) in subscripts.iter().zip(&self.dims) {
+            let index = subscript.checked_sub(dim.lbound)?;
+            if index < 0 || index >= dim.len {
+                return None;
+            }
+            offset = offset * dim.len as usize + index as usize;
+        }
+        self.elements[offset].as_ref()
+    }
+
+    /// Parse the text representation of an array, e.g '{1,2,NULL}' or '{{"a b",c}}'.
+    fn parse(value: &str, elem: &dyn Type, typmod: i32) -> Result<Self> {
+        let malformed = || Error::MalformedArrayLiteral(value.to_string());
+
+        let mut parser = ArrayParser {
+            chars: value.chars().collect(),
+            pos: 0,
+        };
+
+        parser.skip_whitespace();
+        let declared_dims = if parser.peek() == Some('[') {
+            Some(parser.parse_dims().ok_or_else(malformed)?)
+        } else {
+            None
+        };
+
+        if !parser.consume('{') {
+            bail!(malformed());
+        }
+        let root = parser.parse_list(1)?.ok_or_else(malformed)?;
+        parser.skip_whitespace();
+        if parser.pos != parser.chars.len() {
+            bail!(malformed());
+        }
+
+        // The dimensions are the lengths of the first sub-array of each level, and all other
+        // sub-arrays must have the same lengths.
+        let mut lens = Vec::new();
+        let mut node = &root;
+        while let Node::List(items) = node {
+            lens.push(items.len() as i32);
+            match items.first() {
+                Some(first) => node = first,
+                None => break,
+            }
+        }
+
+        let mut texts = Vec::new();
+        if !root.flatten(&lens, &mut texts) {
+            bail!(malformed());
+        }
+
+        let dims = if texts.is_empty() {
+            if lens.len() > 1 || declared_dims.is_some() {
+                bail!(malformed());
+            }
+            Vec::new()
+        } else {
+            match declared_dims {
+                Some(dims) => {
+                    if dims.len() != lens.len()
+                        || dims.iter().zip(&lens).any(|(dim, len)| dim.len != *len)
+                    {
+                        bail!(malformed());
+                    }
+                    dims
+                }
+                None => lens
+                    .iter()
+                    .map(|len| ArrayDim {
+                        len: *len,
+                        lbound: 1,
+                    })
+                    .collect(),
+            }
+        };
+
+        let elements = texts
+            .into_iter()
+            .map(|text| match text {
+                Some(text) => Ok(Some(elem.input(&text, typmod)?)),
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            elemtype: elem.oid(),
+            dims,
+            elements,
+        })
+    }
+
+    /// Return the text representation of the array.
+    fn to_text(&self, elem: &dyn Type) -> Result<String> {
+        if self.dims.is_empty() {
+            return Ok(String::from("{}"));
+        }
+
+        let mut output = String::new();
+        if self.dims.iter().any(|dim| dim.lbound != 1) {
+            for dim in &self.dims {
+                output.push_str(&format!("[{}:{}]", dim.lbound, dim.lbound + dim.len - 1));
+            }
+            output.push('=');
+        }
+
+        let mut elements = self.elements.iter();
+        self.write_dim(&mut output, 0, &mut elements, elem)?;
+        Ok(output)
+    }
+
+    fn write_dim<'a>(
+        &self,
+        output: &mut String,
+        dim: usize,
+        elements: &mut impl Iterator<Item = &'a Option<Datum>>,
+        elem: &dyn Type,
+    ) -> Result<()> {
+        output.push('{');
+        for i in 0..self.dims[dim].len {
+            if i > 0 {
+                output.push(',');
+            }
+            if dim + 1 < self.dims.len() {
+                self.write_dim(output, dim + 1, elements, elem)?;
+                continue;
+            }
+
+            match elements.next() {
+                Some(Some(datum)) => write_element(output, &elem.output(datum)?),
+                _ => output.push_str("NULL"),
+            }
+        }
+        output.push('}');
+        Ok(())
+    }
+
+    /// Decode an array received in binary format, which contains the number of dimensions, a
+    /// flag that is set if the array has NULL elements, the element type, the length and lower
+    /// bound of each dimension and then the length and the binary value of each element, with
+    /// -1 as the length of NULL elements.
+    fn recv(buf: &[u8], elem: &dyn Type, typmod: i32) -> Result<Self> {
+        let invalid = || Error::InvalidBinaryRepresentation("array");
+        let mut reader = BinaryReader::new(buf);
+
+        let ndim = reader.read_i32().ok_or_else(invalid)?;
+        let _has_nulls = reader.read_i32().ok_or_else(invalid)?;
+        let elemtype = reader.read_i32().ok_or_else(invalid)? as u32 as Oid;
+
+        if ndim < 0 {
+            bail!(invalid());
+        }
+        if ndim as usize > MAXDIM {
+            bail!(Error::ArrayDimensionsExceeded(ndim as usize));
+        }
+        if elemtype != elem.oid() {
+            bail!(Error::InvalidBinaryRepresentation(elem.format_type()));
+        }
+
+        let mut dims = Vec::with_capacity(ndim as usize);
+        for _ in 0..ndim {
+            let len = reader.read_i32().ok_or_else(invalid)?;
+            let lbound = reader.read_i32().ok_or_else(invalid)?;
+            if len < 0 {
+                bail!(invalid());
+            }
+            dims.push(ArrayDim { len, lbound });
+        }
+
+        let nitems = if dims.is_empty() {
+            0
+        } else {
+            dims.iter().map(|dim| dim.len as usize).product()
+        };
+
+        let mut elements = Vec::with_capacity(nitems.min(buf.len()));
+        for _ in 0..nitems {
+            let len = reader.read_i32().ok_or_else(invalid)?;
+            if len == -1 {
+                elements.push(None);
+                continue;
+            }
+            let value = reader.read_bytes(len).ok_or_else(invalid)?;
+            elements.push(Some(elem.recv(value, typmod)?));
+        }
+
+        if !reader.is_empty() {
+            bail!(invalid());
+        }
+        if nitems == 0 {
+            dims.clear();
+        }
+
+        Ok(Self {
+            elemtype,
+            dims,
+            elements,
+        })
+    }
+
+    /// Encode the array on the binary format, see [ArrayValue::recv].
+    fn send(&self, elem: &dyn Type) -> Result<Vec<u8>> {
+        let has_nulls = self.elements.iter().any(Option::is_none);
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(self.dims.len() as i32).to_be_bytes());
+        buf.extend_from_slice(&(has_nulls as i32).to_be_bytes());
+        buf.extend_from_slice(&(self.elemtype as u32).to_be_bytes());
+        for dim in &self.dims {
+            buf.extend_from_slice(&dim.len.to_be_bytes());
+            buf.extend_from_slice(&dim.lbound.to_be_bytes());
+        }
+
+        for element in &self.elements {
+            match element {
+                Some(datum) => {
+                    let value = elem.send(datum)?;
+                    buf.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                    buf.extend_from_slice(&value);
+                }
+                None => buf.extend_from_slice(&(-1_i32).to_be_bytes()),
+            }
+        }
+        Ok(buf)
+    }
+
+    /// Compare two arrays element by element, NULL elements are greater than any other value.
+    /// If all elements are equal, the array with more elements is greater, and then the arrays
+    /// are compared by their dimensions.
+    fn compare(&self, other: &ArrayValue, elem: &dyn Type) -> Result<Ordering> {
+        for (a, b) in self.elements.iter().zip(&other.elements) {
+            let ordering = match (a, b) {
+                (Some(a), Some(b)) => elem.compare(a, b)?,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            };
+            if ordering != Ordering::Equal {
+                return Ok(ordering);
+            }
+        }
+
+        Ok(self
+            .elements
+            .len()
+            .cmp(&other.elements.len())
+            .then_with(|| self.dims.len().cmp(&other.dims.len()))
+            .then_with(|| {
+                self.dims
+                    .iter()
+                    .zip(&other.dims)
+                    .map(|(a, b)| a.len.cmp(&b.len).then(a.lbound.cmp(&b.lbound)))
+                    .find(|ordering| *ordering != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            }))
+    }
+}
+
+/// Write an element on the text representation of an array, quoting the element if it is
+/// empty, contains any character with special meaning or could be read as a NULL.
+fn write_element(output: &mut String, value: &str) {
+    let needs_quotes = value.is_empty()
+        || value.eq_ignore_ascii_case("NULL")
+        || value
+            .chars()
+            .any(|c| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_ascii_whitespace());
+
+    if !needs_quotes {
+        output.push_str(value);
+        return;
+    }
+
+    output.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+    output.push('"');
+}
+
+/// A node of a parsed array literal.
+enum Node {
+    List(Vec<Node>),
+
+    /// An element, None if the element is NULL.
+    Item(Option<String>),
+}
+
+impl Node {
+    /// Append the elements of the node to the texts on row-major order, returning false if
+    /// the node does not have the given lengths on each level.
+    fn flatten(self, lens: &[i32], texts: &mut Vec<Option<String>>) -> bool {
+        match (self, lens.split_first()) {
+            (Node::List(items), Some((len, lens))) if items.len() as i32 == *len => {
+                items.into_iter().all(|item| item.flatten(lens, texts))
+            }
+            (Node::Item(text), None) => {
+                texts.push(text);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Parser of the text representation of arrays.
+struct ArrayParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl ArrayParser {
+    /// Parse the dimensions declared before an array literal, e.g [0:2][1:2]=.
+    fn parse_dims(&mut self) -> Option<Vec<ArrayDim>> {
+        let mut dims = Vec::new();
+        while self.consume('[') {
+            let first = self.parse_int()?;
+            let (lbound, ubound) = if self.consume(':') {
+                (first, self.parse_int()?)
+            } else {
+                (1, first)
+            };
+            if !self.consume(']') || ubound < lbound || dims.len() == MAXDIM {
+                return None;
+            }
+            dims.push(ArrayDim {
+                len: ubound.checked_sub(lbound)?.checked_add(1)?,
+                lbound,
+            });
+        }
+
+        self.skip_whitespace();
+        if !self.consume('=') {
+            return None;
+        }
+        self.skip_whitespace();
+        Some(dims)
+    }
+
+    fn parse_int(&mut self) -> Option<i32> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if matches!(self.peek(), Some('-' | '+')) {
+            self.pos += 1;
+        }
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.pos += 1;
+        }
+        let value = self.chars[start..self.pos].iter().collect::<String>();
+        self.skip_whitespace();
+        value.parse().ok()
+    }
+
+    /// Parse the items of a list after its opening brace, returning None if the list is
+    /// malformed.
+    fn parse_list(&mut self, depth: usize) -> Result<Option<Node>> {
+        if depth > MAXDIM {
+            bail!(Error::ArrayDimensionsExceeded(depth));
+        }
+
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.consume('}') {
+            return Ok(Some(Node::List(items)));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let item = if self.consume('{') {
+                self.parse_list(depth + 1)?
+            } else {
+                self.parse_element().map(Node::Item)
+            };
+            match item {
+                Some(item) => items.push(item),
+                None => return Ok(None),
+            }
+
+            self.skip_whitespace();
+            if self.consume('}') {
+                return Ok(Some(Node::List(items)));
+            }
+            if !self.consume(',') {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Parse an element, which is a quoted string or a sequence of characters until the next
+    /// delimiter. A backslash escapes the next character in both forms. Returns Some(None) for
+    /// NULL elements and None if the element is malformed.
+    fn parse_element(&mut self) -> Option<Option<String>> {
+        let mut value = String::new();
+
+        if self.consume('"') {
+            loop {
+                match self.next()? {
+                    '"' => return Some(Some(value)),
+                    '\\' => value.push(self.next()?),
+                    c => value.push(c),
+                }
+            }
+        }
+
+        // Length of the value without trailing whitespaces that are not escaped.
+        let mut significant_len = 0;
+        let mut escaped = false;
+        loop {
+            match self.peek()? {
+                ',' | '}' => break,
+                '{' | '"' => return None,
+                '\\' => {
+                    self.pos += 1;
+                    value.push(self.next()?);
+                    significant_len = value.len();
+                    escaped = true;
+                }
+                c => {
+                    self.pos += 1;
+                    value.push(c);
+                    if !c.is_whitespace() {
+                        significant_len = value.len();
+                    }
+                }
+            }
+        }
+
+        value.truncate(significant_len);
+        if value.is_empty() && !escaped {
+            return None;
+        }
+        if !escaped && value.eq_ignore_ascii_case("NULL") {
+            return Some(None);
+        }
+        Some(Some(value))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(typ: &ArrayType, value: &str) -> Result<String> {
+        typ.output(&typ.input(value, -1)?)
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let cases = [
+            (&INT4_ARRAY, "{1, 2 ,NULL}", "{1,2,NULL}"),
+            (&INT4_ARRAY, " { } ", "{}"),
+            (&INT4_ARRAY, "{{1,2},{3,4}}", "{{1,2},{3,4}}"),
+            (&INT4_ARRAY, "[0:1]={7,8}", "[0:1]={7,8}"),
+            (&INT4_ARRAY, "[1:2][1:1]={{1},{2}}", "{{1},{2}}"),
+            (
+                &TEXT_ARRAY,
+                r#"{a b , "c,d", "", "null", NULL, \"q\", "x\\y"}"#,
+                r#"{"a b","c,d","","null",NULL,"\"q\"","x\\y"}"#,
+            ),
+            (&TEXT_ARRAY, r#"{{"{}"}}"#, r#"{{"{}"}}"#),
+            (&BYTEA_ARRAY, r#"{"\\x00ff"}"#, r#"{"\\x00ff"}"#),
+        ];
+        for (typ, input, output) in cases {
+            assert_eq!(round_trip(typ, input)?, output, "{}", input);
+        }
+
+        for input in [
+            "",
+            "1,2",
+            "{1,2",
+            "{1,,2}",
+            "{1,2}}",
+            "{{1,2},{3}}",
+            "{{1},2}",
+            "{a\"b}",
+            "[1:3]={1,2}",
+            "[2:1]={}",
+            "{{{{{{{1}}}}}}}",
+        ] {
+            assert!(INT4_ARRAY.input(input, -1).is_err(), "{}", input);
+        }
+        assert!(INT4_ARRAY.input("{1,a}", -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_typmod() -> Result<()> {
+        let typmod = VARCHAR_ARRAY.typmod_in(&[2])?;
+        assert!(VARCHAR_ARRAY.input("{ab,c}", typmod).is_ok());
+        assert!(VARCHAR_ARRAY.input("{abc}", typmod).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_bitmap() -> Result<()> {
+        let elements = (0..20)
+            .map(|i| (i % 3 != 0).then(|| bincode::serialize(&(i as i32)).unwrap()))
+            .collect::<Vec<_>>();
+        let array = ArrayValue::new(pg_type::INT_OID, elements);
+        assert_eq!(ArrayValue::from_datum(&array.to_datum()?)?, array);
+        assert_eq!(array.element(&[1]), None);
+        assert_eq!(array.element(&[2]), Some(&bincode::serialize(&1_i32)?));
+        assert_eq!(array.element(&[21]), None);
+        assert_eq!(array.element(&[1, 1]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_format() -> Result<()> {
+        let datum = INT8_ARRAY.input("[0:1][1:2]={{1,NULL},{3,4}}", -1)?;
+        let binary = INT8_ARRAY.send(&datum)?;
+        assert_eq!(&binary[..12], &[0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 20]);
+        assert_eq!(INT8_ARRAY.recv(&binary, -1)?, datum);
+        assert!(INT4_ARRAY.recv(&binary, -1).is_err());
+        assert!(INT8_ARRAY.recv(&binary[..binary.len() - 1], -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let ordered = ["{}", "{1,2}", "{1,2,3}", "{1,NULL}", "{2}"];
+        for pair in ordered.windows(2) {
+            let a = INT4_ARRAY.input(pair[0], -1)?;
+            let b = INT4_ARRAY.input(pair[1], -1)?;
+            assert_eq!(INT4_ARRAY.compare(&a, &b)?, Ordering::Less, "{:?}", pair);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_stack() -> Result<()> {
+        let row = |values: &str| ArrayValue::from_datum(&INT4_ARRAY.input(values, -1).unwrap());
+        let stacked = ArrayValue::stack(pg_type::INT_OID, vec![row("{1,2}")?, row("{3,4}")?])?;
+        assert_eq!(stacked.to_text(&int::Int4)?, "{{1,2},{3,4}}");
+        assert!(ArrayValue::stack(pg_type::INT_OID, vec![row("{1,2}")?, row("{3}")?]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Boolean data type, stored as a single byte.
+pub struct Bool;
+
+impl Type for Bool {
+    fn oid(&self) -> Oid {
+        pg_type::BOOL_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "bool"
+    }
+
+    fn format_type(&self) -> &'static str {
+        "boolean"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Boolean
+    }
+
+    fn typispreferred(&self) -> bool {
+        true
+    }
+
+    fn typlen(&self) -> i32 {
+        1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Char
+    }
+
+    /// Accept the same literals of PostgreSQL: t, true, y, yes, on, 1 and their false
+    /// counterparts, ignoring case and surrounding whitespaces.
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        let datum = match value.trim().to_lowercase().as_str() {
+            "t" | "true" | "y" | "yes" | "on" | "1" => true,
+            "f" | "false" | "n" | "no" | "off" | "0" => false,
+            _ => bail!(Error::InvalidTextRepresentation(
+                "boolean",
+                value.to_string()
+            )),
+        };
+        Ok(bincode::serialize(&datum)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(deserialize::<bool>(datum)?.to_string())
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        match buf {
+            [value] => Ok(bincode::serialize(&(*value != 0))?),
+            _ => bail!(Error::InvalidBinaryRepresentation("boolean")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(vec![deserialize::<bool>(datum)? as u8])
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<bool>(a)?.cmp(&deserialize::<bool>(b)?))
+    }
+}
@@ -0,0 +1,110 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{
+    char_length, char_length_typmod, deserialize, varchar::truncate_spaces, Error, Type, TypeAlign,
+    TypeCategory,
+};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Fixed-length, blank-padded character string data type.
+///
+/// Values are padded with spaces to the length declared on typmod, which is the number of
+/// characters plus [super::VARHDRSZ]. Trailing spaces are not significant, so they are ignored
+/// when comparing values and removed when converting a value to other string types.
+pub struct Bpchar;
+
+impl Type for Bpchar {
+    fn oid(&self) -> Oid {
+        pg_type::BPCHAR_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "bpchar"
+    }
+
+    fn format_type(&self) -> &'static str {
+        "character"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::String
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn typmod_in(&self, args: &[u64]) -> Result<i32> {
+        char_length_typmod("char", args)
+    }
+
+    fn input(&self, value: &str, typmod: i32) -> Result<Datum> {
+        let len = match char_length(typmod) {
+            Some(len) => len,
+            None => return Ok(bincode::serialize(value)?),
+        };
+
+        let value = truncate_spaces(value, len, "character")?;
+        let padding = len - value.chars().count();
+        Ok(bincode::serialize(&format!(
+            "{}{}",
+            value,
+            " ".repeat(padding)
+        ))?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        deserialize::<String>(datum)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match std::str::from_utf8(buf) {
+            Ok(value) => self.input(value, typmod),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation("character")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<String>(datum)?.into_bytes())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        let a = deserialize::<String>(a)?;
+        let b = deserialize::<String>(b)?;
+        Ok(a.trim_end_matches(' ').cmp(b.trim_end_matches(' ')))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_padding() -> Result<()> {
+        let typmod = Bpchar.typmod_in(&[4])?;
+        assert_eq!(Bpchar.output(&Bpchar.input("ab", typmod)?)?, "ab  ");
+        assert_eq!(Bpchar.output(&Bpchar.input("abcd   ", typmod)?)?, "abcd");
+        assert_eq!(Bpchar.output(&Bpchar.input("ab ", -1)?)?, "ab ");
+        assert!(Bpchar.input("abcde", typmod).is_err());
+        assert!(Bpchar.typmod_in(&[0]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_ignores_trailing_spaces() -> Result<()> {
+        let a = Bpchar.input("ab", Bpchar.typmod_in(&[4])?)?;
+        let b = Bpchar.input("ab", -1)?;
+        assert_eq!(Bpchar.compare(&a, &b)?, Ordering::Equal);
+        assert_eq!(
+            Bpchar.compare(&a, &Bpchar.input("ab!", -1)?)?,
+            Ordering::Less
+        );
+        Ok(())
+    }
+}
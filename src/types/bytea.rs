@@ -0,0 +1,140 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Variable-length binary string data type.
+///
+/// The text representation is either the hex format, e.g `\x7462`, or the escape format where
+/// non printable bytes are written as a backslash followed by three octal digits, e.g `t\142`.
+/// Values are always displayed using the hex format.
+pub struct Bytea;
+
+impl Type for Bytea {
+    fn oid(&self) -> Oid {
+        pg_type::BYTEA_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "bytea"
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        let bytes = match value.strip_prefix("\\x") {
+            Some(hex) => decode_hex(hex)?,
+            None => decode_escape(value)?,
+        };
+        Ok(bincode::serialize(&bytes)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        let bytes = deserialize::<Vec<u8>>(datum)?;
+        let mut output = String::with_capacity(2 + bytes.len() * 2);
+        output.push_str("\\x");
+        for byte in bytes {
+            output.push_str(&format!("{:02x}", byte));
+        }
+        Ok(output)
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        Ok(bincode::serialize(buf)?)
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        deserialize::<Vec<u8>>(datum)
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<Vec<u8>>(a)?.cmp(&deserialize::<Vec<u8>>(b)?))
+    }
+}
+
+/// Decode a value on hex format, without the \x prefix. Whitespaces are allowed between each
+/// pair of digits.
+fn decode_hex(hex: &str) -> Result<Vec<u8>> {
+    let digit = |c: char| c.to_digit(16).ok_or(Error::InvalidHexDigit(c));
+
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    let mut chars = hex
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\t' | '\n' | '\r'));
+    while let Some(high) = chars.next() {
+        let high = digit(high)?;
+        let low = match chars.next() {
+            Some(low) => digit(low)?,
+            None => bail!(Error::OddNumberOfHexDigits),
+        };
+        bytes.push((high << 4 | low) as u8);
+    }
+    Ok(bytes)
+}
+
+/// Decode a value on escape format, where a backslash is written as \\ and any byte can be
+/// written as \ followed by its three digits octal value.
+fn decode_escape(value: &str) -> Result<Vec<u8>> {
+    let invalid = || Error::InvalidTextRepresentation("bytea", value.to_string());
+
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.as_bytes();
+    while let Some((&byte, rest)) = input.split_first() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            input = rest;
+            continue;
+        }
+
+        match rest {
+            [b'\\', rest @ ..] => {
+                bytes.push(b'\\');
+                input = rest;
+            }
+            [a @ b'0'..=b'3', b @ b'0'..=b'7', c @ b'0'..=b'7', rest @ ..] => {
+                bytes.push((a - b'0') << 6 | (b - b'0') << 3 | (c - b'0'));
+                input = rest;
+            }
+            _ => bail!(invalid()),
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: &str) -> Result<String> {
+        Bytea.output(&Bytea.input(value, -1)?)
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        assert_eq!(round_trip("\\x7462 6d")?, "\\x74626d");
+        assert_eq!(round_trip("\\xDEADbeef")?, "\\xdeadbeef");
+        assert_eq!(round_trip("tb\\\\\\000\\377")?, "\\x74625c00ff");
+        assert_eq!(round_trip("")?, "\\x");
+        assert!(round_trip("\\x7").is_err());
+        assert!(round_trip("\\xzz").is_err());
+        assert!(round_trip("\\400").is_err());
+        assert!(round_trip("a\\").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_format() -> Result<()> {
+        let datum = Bytea.recv(&[0, 1, 255], -1)?;
+        assert_eq!(Bytea.output(&datum)?, "\\x0001ff");
+        assert_eq!(Bytea.send(&datum)?, vec![0, 1, 255]);
+        Ok(())
+    }
+}
@@ -0,0 +1,657 @@
+//! Conversions between data types.
+use std::convert::TryFrom;
+
+use anyhow::{bail, Result};
+
+use super::{
+    array::ArrayValue, char_length, lookup_type, numeric::NumericVar, Error, Type, TypeCategory,
+};
+use crate::{catalog::pg_type, Datum, Oid, INVALID_OID};
+
+/// Order of the numeric types used to resolve the common type of an arithmetic expression, a
+/// value can be implicitly converted to any type with a greater precedence.
+const NUMERIC_PRECEDENCE: &[Oid] = &[
+    pg_type::INT2_OID,
+    pg_type::INT_OID,
+    pg_type::INT8_OID,
+    pg_type::NUMERIC_OID,
+    pg_type::FLOAT4_OID,
+    pg_type::FLOAT8_OID,
+];
+
+/// Return the precedence of the given numeric type, or None if the type is not a numeric type
+/// that can be used on arithmetic expressions.
+pub fn numeric_precedence(oid: Oid) -> Option<usize> {
+    NUMERIC_PRECEDENCE.iter().position(|typ| *typ == oid)
+}
+
+/// Return the numeric types that a value of the given numeric type can be implicitly converted
+/// to, including itself, in order of precedence.
+pub fn numeric_supertypes(oid: Oid) -> &'static [Oid] {
+    match numeric_precedence(oid) {
+        Some(position) => &NUMERIC_PRECEDENCE[position..],
+        None => &[],
+    }
+}
+
+/// Return true if the values of the given type can be converted to other numeric types.
+fn is_number(oid: Oid) -> bool {
+    numeric_precedence(oid).is_some() || oid == pg_type::OID_OID
+}
+
+/// Return true if the given type is a character string type.
+fn is_string(oid: Oid) -> bool {
+    matches!(
+        oid,
+        pg_type::TEXT_OID | pg_type::VARCHAR_OID | pg_type::BPCHAR_OID
+    )
+}
+
+/// Return the element type of the given type if it is an array type.
+fn array_elem(oid: Oid) -> Option<Oid> {
+    lookup_type(oid)
+        .ok()
+        .map(|typ| typ.typelem())
+        .filter(|elem| *elem != INVALID_OID)
+}
+
+/// Context in which a conversion between types is made. Contexts are ordered from the most to
+/// the least restrictive, so a conversion allowed implicitly is also allowed on assignment and
+/// explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum CoercionContext {
+    /// Conversion of operands of an expression.
+    Implicit,
+
+    /// Conversion of a value being stored on a column.
+    Assignment,
+
+    /// Conversion requested with CAST(x AS type) or x::type.
+    Explicit,
+}
+
+impl CoercionContext {
+    /// Return the code of the context stored on pg_cast.
+    pub fn as_char(&self) -> char {
+        match self {
+            CoercionContext::Implicit => 'i',
+            CoercionContext::Assignment => 'a',
+            CoercionContext::Explicit => 'e',
+        }
+    }
+}
+
+/// Conversions between builtin types and the context in which each one is allowed, which are
+/// cataloged on pg_cast.
+///
+/// Conversions of a type to itself, between array types and to or from string types using the
+/// text representation of values are not listed, see [coercion_context].
+pub const BUILTIN_CASTS: &[(Oid, Oid, CoercionContext)] = &[
+    (
+        pg_type::INT2_OID,
+        pg_type::INT_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT2_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT2_OID,
+        pg_type::FLOAT4_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT2_OID,
+        pg_type::FLOAT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT2_OID,
+        pg_type::NUMERIC_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT2_OID,
+        pg_type::OID_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::INT2_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::FLOAT4_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::FLOAT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::NUMERIC_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::OID_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT_OID,
+        pg_type::BOOL_OID,
+        CoercionContext::Explicit,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::INT2_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::INT_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::FLOAT4_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::FLOAT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::NUMERIC_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::INT8_OID,
+        pg_type::OID_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::FLOAT4_OID,
+        pg_type::INT2_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT4_OID,
+        pg_type::INT_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT4_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT4_OID,
+        pg_type::FLOAT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::FLOAT4_OID,
+        pg_type::NUMERIC_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT8_OID,
+        pg_type::INT2_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT8_OID,
+        pg_type::INT_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT8_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT8_OID,
+        pg_type::FLOAT4_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::FLOAT8_OID,
+        pg_type::NUMERIC_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::NUMERIC_OID,
+        pg_type::INT2_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::NUMERIC_OID,
+        pg_type::INT_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::NUMERIC_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::NUMERIC_OID,
+        pg_type::FLOAT4_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::NUMERIC_OID,
+        pg_type::FLOAT8_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::OID_OID,
+        pg_type::INT_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::OID_OID,
+        pg_type::INT8_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::BOOL_OID,
+        pg_type::INT_OID,
+        CoercionContext::Explicit,
+    ),
+    (
+        pg_type::TEXT_OID,
+        pg_type::VARCHAR_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::TEXT_OID,
+        pg_type::BPCHAR_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::VARCHAR_OID,
+        pg_type::TEXT_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::VARCHAR_OID,
+        pg_type::BPCHAR_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::BPCHAR_OID,
+        pg_type::TEXT_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::BPCHAR_OID,
+        pg_type::VARCHAR_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::DATE_OID,
+        pg_type::TIMESTAMP_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::DATE_OID,
+        pg_type::TIMESTAMPTZ_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::TIME_OID,
+        pg_type::INTERVAL_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::TIMESTAMP_OID,
+        pg_type::DATE_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::TIMESTAMP_OID,
+        pg_type::TIME_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::TIMESTAMP_OID,
+        pg_type::TIMESTAMPTZ_OID,
+        CoercionContext::Implicit,
+    ),
+    (
+        pg_type::TIMESTAMPTZ_OID,
+        pg_type::DATE_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::TIMESTAMPTZ_OID,
+        pg_type::TIME_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::TIMESTAMPTZ_OID,
+        pg_type::TIMESTAMP_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::JSON_OID,
+        pg_type::JSONB_OID,
+        CoercionContext::Assignment,
+    ),
+    (
+        pg_type::JSONB_OID,
+        pg_type::JSON_OID,
+        CoercionContext::Assignment,
+    ),
+];
+
+/// Return the least restrictive context in which a value of type from can be converted to type
+/// to, or None if there is no conversion between the types.
+///
+/// Besides the conversions of [BUILTIN_CASTS], arrays can be converted in the same context as
+/// their elements, and any type can be converted to a string type on assignment or from a
+/// string type explicitly using its text representation.
+pub fn coercion_context(from: Oid, to: Oid) -> Option<CoercionContext> {
+    if from == to {
+        return Some(CoercionContext::Implicit);
+    }
+
+    if let Some((_, _, context)) = BUILTIN_CASTS
+        .iter()
+        .find(|(source, target, _)| *source == from && *target == to)
+    {
+        return Some(*context);
+    }
+
+    if let (Some(from), Some(to)) = (array_elem(from), array_elem(to)) {
+        return coercion_context(from, to);
+    }
+
+    let category = |oid| lookup_type(oid).map(|typ| typ.typcategory()).ok();
+    if category(to) == Some(TypeCategory::String) {
+        Some(CoercionContext::Assignment)
+    } else if category(from) == Some(TypeCategory::String) {
+        Some(CoercionContext::Explicit)
+    } else {
+        None
+    }
+}
+
+/// Return true if a value of type from can be converted to type to in the given context.
+pub fn can_coerce(from: Oid, to: Oid, context: CoercionContext) -> bool {
+    coercion_context(from, to).is_some_and(|allowed| allowed <= context)
+}
+
+/// A numeric value used as an intermediate representation to convert between numeric types.
+enum Number {
+    Int(i64),
+
+    /// A float value with the number of significant decimal digits of its type.
+    Float(f64, usize),
+
+    Numeric(NumericVar),
+}
+
+impl Number {
+    fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(value) => *value as f64,
+            Number::Float(value, _) => *value,
+            Number::Numeric(value) => value.to_f64(),
+        }
+    }
+}
+
+/// Convert a datum of type from to a datum of type to.
+///
+/// Conversions between numeric types are done directly, checking that the value fits on the
+/// target type. Arrays are converted element by element, applying the typmod to each element.
+/// Any other conversion use the text representation of the value, removing the trailing spaces
+/// of character(n) values converted to other string types.
+///
+/// Explicit conversions to string types truncate the values that are longer than the length
+/// declared on typmod instead of failing.
+pub fn cast_datum(datum: &[u8], from: Oid, to: Oid, typmod: i32, explicit: bool) -> Result<Datum> {
+    let to_type = lookup_type(to)?;
+    if from == to && typmod < 0 {
+        return Ok(datum.to_vec());
+    }
+
+    if let (Some(from_elem), Some(to_elem)) = (array_elem(from), array_elem(to)) {
+        let mut array = ArrayValue::from_datum(datum)?;
+        for element in array.elements.iter_mut().flatten() {
+            *element = cast_datum(element, from_elem, to_elem, typmod, explicit)?;
+        }
+        array.elemtype = to_elem;
+        return array.to_datum();
+    }
+
+    if is_number(from) && is_number(to) {
+        let number = match from {
+            pg_type::OID_OID => Number::Int(bincode::deserialize::<Oid>(datum)? as i64),
+            pg_type::INT2_OID => Number::Int(bincode::deserialize::<i16>(datum)?.into()),
+            pg_type::INT_OID => Number::Int(bincode::deserialize::<i32>(datum)?.into()),
+            pg_type::INT8_OID => Number::Int(bincode::deserialize::<i64>(datum)?),
+            pg_type::FLOAT4_OID => Number::Float(bincode::deserialize::<f32>(datum)?.into(), 6),
+            pg_type::FLOAT8_OID => Number::Float(bincode::deserialize::<f64>(datum)?, 15),
+            _ => Number::Numeric(NumericVar::from_datum(datum)?),
+        };
+        return number_to_datum(number, to_type, typmod);
+    }
+
+    match (from, to) {
+        (pg_type::INT_OID, pg_type::BOOL_OID) => {
+            return Ok(bincode::serialize(
+                &(bincode::deserialize::<i32>(datum)? != 0),
+            )?);
+        }
+        (pg_type::BOOL_OID, pg_type::INT_OID) => {
+            return Ok(bincode::serialize(&i32::from(
+                bincode::deserialize::<bool>(datum)?,
+            ))?);
+        }
+        _ => {}
+    }
+
+    let value = lookup_type(from)?.output(datum)?;
+    if from == pg_type::BPCHAR_OID && is_string(to) && to != pg_type::BPCHAR_OID {
+        return input_value(to_type, value.trim_end_matches(' '), typmod, explicit);
+    }
+    input_value(to_type, &value, typmod, explicit)
+}
+
+/// Convert the text representation of a value to a datum of the given type. If the conversion
+/// is explicit, values of string types are truncated to the length declared on typmod.
+pub fn input_value(typ: &dyn Type, value: &str, typmod: i32, explicit: bool) -> Result<Datum> {
+    if explicit && typ.typcategory() == TypeCategory::String {
+        if let Some(len) = char_length(typmod) {
+            if let Some((end, _)) = value.char_indices().nth(len) {
+                return typ.input(&value[..end], typmod);
+            }
+        }
+    }
+    typ.input(value, typmod)
+}
+
+/// Convert a number to a datum of the given numeric type.
+fn number_to_datum(number: Number, typ: &dyn Type, typmod: i32) -> Result<Datum> {
+    let out_of_range = || Error::OutOfRange(typ.format_type());
+
+    let datum = match typ.oid() {
+        pg_type::FLOAT4_OID | pg_type::FLOAT8_OID => {
+            let finite = match &number {
+                Number::Float(value, _) => value.is_finite(),
+                _ => true,
+            };
+
+            let value = number.to_f64();
+            if typ.oid() == pg_type::FLOAT8_OID {
+                if value.is_infinite() && finite {
+                    bail!(out_of_range());
+                }
+                bincode::serialize(&value)?
+            } else {
+                let value = value as f32;
+                if value.is_infinite() && finite {
+                    bail!(out_of_range());
+                }
+                bincode::serialize(&value)?
+            }
+        }
+        pg_type::NUMERIC_OID => {
+            let value = match number {
+                Number::Int(value) => NumericVar::from_i64(value),
+                Number::Float(value, digits) => NumericVar::from_f64(value, digits)?,
+                Number::Numeric(value) => value,
+            };
+            value.apply_typmod(typmod)?.to_datum()?
+        }
+        oid => {
+            let value = match number {
+                Number::Int(value) => value,
+                Number::Float(value, _) => {
+                    // Float values are rounded to the nearest integer, with ties going to the
+                    // even integer like PostgreSQL does.
+                    let value = value.round_ties_even();
+                    if !(value >= i64::MIN as f64 && value < i64::MAX as f64) {
+                        bail!(out_of_range());
+                    }
+                    value as i64
+                }
+                // Numeric values are rounded with ties going away from zero.
+                Number::Numeric(value) => value.to_i64().ok_or_else(out_of_range)?,
+            };
+
+            match oid {
+                pg_type::INT2_OID => {
+                    bincode::serialize(&i16::try_from(value).map_err(|_| out_of_range())?)?
+                }
+                pg_type::INT_OID => {
+                    bincode::serialize(&i32::try_from(value).map_err(|_| out_of_range())?)?
+                }
+                pg_type::INT8_OID => bincode::serialize(&value)?,
+                pg_type::OID_OID => {
+                    bincode::serialize(&Oid::try_from(value).map_err(|_| out_of_range())?)?
+                }
+                _ => bail!(Error::UndefinedType(oid)),
+            }
+        }
+    };
+    Ok(datum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cast(value: &str, from: Oid, to: Oid) -> Result<String> {
+        let datum = lookup_type(from)?.input(value, -1)?;
+        lookup_type(to)?.output(&cast_datum(&datum, from, to, -1, false)?)
+    }
+
+    #[test]
+    fn test_numeric_casts() -> Result<()> {
+        assert_eq!(cast("42", pg_type::INT2_OID, pg_type::INT8_OID)?, "42");
+        assert_eq!(cast("2.5", pg_type::FLOAT8_OID, pg_type::INT_OID)?, "2");
+        assert_eq!(cast("3.5", pg_type::FLOAT8_OID, pg_type::INT_OID)?, "4");
+        assert_eq!(cast("-7", pg_type::INT_OID, pg_type::FLOAT4_OID)?, "-7");
+        assert!(cast("40000", pg_type::INT_OID, pg_type::INT2_OID).is_err());
+        assert!(cast("1e300", pg_type::FLOAT8_OID, pg_type::FLOAT4_OID).is_err());
+        assert!(cast("1e19", pg_type::FLOAT8_OID, pg_type::INT8_OID).is_err());
+        assert_eq!(cast("2.5", pg_type::NUMERIC_OID, pg_type::INT_OID)?, "3");
+        assert_eq!(cast("-2.5", pg_type::NUMERIC_OID, pg_type::INT_OID)?, "-3");
+        assert_eq!(
+            cast("0.1", pg_type::FLOAT4_OID, pg_type::NUMERIC_OID)?,
+            "0.1"
+        );
+        assert_eq!(
+            cast("0.1", pg_type::FLOAT8_OID, pg_type::NUMERIC_OID)?,
+            "0.1"
+        );
+        assert_eq!(
+            cast("1.25", pg_type::NUMERIC_OID, pg_type::FLOAT8_OID)?,
+            "1.25"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_casts() -> Result<()> {
+        let datum = lookup_type(pg_type::BPCHAR_OID)?.input("ab", 4 + 4)?;
+        let text = cast_datum(&datum, pg_type::BPCHAR_OID, pg_type::TEXT_OID, -1, false)?;
+        assert_eq!(lookup_type(pg_type::TEXT_OID)?.output(&text)?, "ab");
+
+        assert_eq!(
+            cast("tinydb", pg_type::TEXT_OID, pg_type::VARCHAR_OID)?,
+            "tinydb"
+        );
+        assert!(cast_datum(&text, pg_type::TEXT_OID, pg_type::VARCHAR_OID, 1 + 4, false).is_err());
+
+        let truncated = cast_datum(&text, pg_type::TEXT_OID, pg_type::VARCHAR_OID, 1 + 4, true)?;
+        assert_eq!(lookup_type(pg_type::VARCHAR_OID)?.output(&truncated)?, "a");
+        assert_eq!(cast("1", pg_type::INT_OID, pg_type::BOOL_OID)?, "true");
+        assert_eq!(cast("false", pg_type::BOOL_OID, pg_type::INT_OID)?, "0");
+        Ok(())
+    }
+
+    #[test]
+    fn test_coercion_context() {
+        let implicit = |from, to| can_coerce(from, to, CoercionContext::Implicit);
+        assert!(implicit(pg_type::INT2_OID, pg_type::FLOAT8_OID));
+        assert!(!implicit(pg_type::INT8_OID, pg_type::INT_OID));
+        assert!(!implicit(pg_type::BOOL_OID, pg_type::INT_OID));
+        assert!(implicit(pg_type::BPCHAR_OID, pg_type::TEXT_OID));
+        assert!(implicit(pg_type::INT4_ARRAY_OID, pg_type::INT8_ARRAY_OID));
+        assert!(!implicit(pg_type::INT8_ARRAY_OID, pg_type::INT4_ARRAY_OID));
+
+        let assignment = |from, to| can_coerce(from, to, CoercionContext::Assignment);
+        assert!(assignment(pg_type::INT8_OID, pg_type::INT_OID));
+        assert!(assignment(pg_type::DATE_OID, pg_type::TEXT_OID));
+        assert!(assignment(pg_type::INT8_ARRAY_OID, pg_type::INT4_ARRAY_OID));
+        assert!(!assignment(pg_type::TEXT_OID, pg_type::INT_OID));
+        assert!(!assignment(pg_type::INT_OID, pg_type::BOOL_OID));
+
+        let explicit = |from, to| can_coerce(from, to, CoercionContext::Explicit);
+        assert!(explicit(pg_type::TEXT_OID, pg_type::INT_OID));
+        assert!(explicit(pg_type::INT_OID, pg_type::BOOL_OID));
+        assert!(explicit(pg_type::TEXT_ARRAY_OID, pg_type::DATE_ARRAY_OID));
+        assert!(!explicit(pg_type::BOOL_OID, pg_type::DATE_OID));
+        assert!(!explicit(pg_type::INT_OID, pg_type::INT4_ARRAY_OID));
+    }
+
+    #[test]
+    fn test_array_casts() -> Result<()> {
+        assert_eq!(
+            cast(
+                "{1,NULL,3}",
+                pg_type::INT4_ARRAY_OID,
+                pg_type::NUMERIC_ARRAY_OID
+            )?,
+            "{1,NULL,3}"
+        );
+        assert_eq!(
+            cast(
+                "{{1.5},{2.5}}",
+                pg_type::FLOAT8_ARRAY_OID,
+                pg_type::INT2_ARRAY_OID
+            )?,
+            "{{2},{2}}"
+        );
+        assert!(cast("{70000}", pg_type::INT4_ARRAY_OID, pg_type::INT2_ARRAY_OID).is_err());
+        Ok(())
+    }
+}
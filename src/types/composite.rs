@@ -0,0 +1,413 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{
+    deserialize, lookup_type, BinaryReader, Error, Type, TypeAlign, TypeCategory, TypeKind,
+};
+use crate::{
+    catalog::{pg_attribute::PgAttribute, pg_type},
+    Datum, Oid,
+};
+
+/// Composite data type created with CREATE TYPE name AS (attributes).
+///
+/// The attributes are cataloged on pg_attribute like the columns of a table, using the oid of
+/// the pg_class entry of the type. A value is stored as the list of the datums of its
+/// attributes, with None for NULL attributes.
+pub struct CompositeType {
+    oid: Oid,
+    typname: &'static str,
+    typrelid: Oid,
+    attributes: Vec<PgAttribute>,
+}
+
+impl CompositeType {
+    pub fn new(oid: Oid, typname: &str, typrelid: Oid, attributes: Vec<PgAttribute>) -> Self {
+        Self {
+            oid,
+            typname: Box::leak(typname.to_string().into_boxed_str()),
+            typrelid,
+            attributes,
+        }
+    }
+
+    /// Return the type oid of each attribute.
+    fn field_types(&self) -> impl ExactSizeIterator<Item = Oid> + '_ {
+        self.attributes.iter().map(|attr| attr.atttypid)
+    }
+
+    /// Parse the text representation of a value, e.g '(1,"a b",)' where an empty attribute is
+    /// NULL. Attributes can be quoted, with "" as a quote inside quotes, and a backslash escapes
+    /// the next character.
+    fn parse(&self, value: &str) -> Result<Vec<Option<Datum>>> {
+        let malformed = || Error::MalformedRecordLiteral(value.to_string());
+
+        let mut chars = value.trim_start().chars().peekable();
+        if chars.next() != Some('(') {
+            bail!(malformed());
+        }
+
+        let mut values = Vec::with_capacity(self.attributes.len());
+        for (i, attr) in self.attributes.iter().enumerate() {
+            if i > 0 && chars.next() != Some(',') {
+                bail!(malformed());
+            }
+
+            if matches!(chars.peek(), Some(',') | Some(')')) {
+                values.push(None);
+                continue;
+            }
+
+            let mut field = String::new();
+            let mut in_quotes = false;
+            loop {
+                match chars.peek() {
+                    Some(',') | Some(')') if !in_quotes => break,
+                    None => bail!(malformed()),
+                    _ => {}
+                }
+                match chars.next() {
+                    Some('\\') => field.push(chars.next().ok_or_else(malformed)?),
+                    Some('"') if !in_quotes => in_quotes = true,
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    Some('"') => in_quotes = false,
+                    Some(c) => field.push(c),
+                    None => bail!(malformed()),
+                }
+            }
+
+            let typ = lookup_type(attr.atttypid)?;
+            values.push(Some(typ.input(&field, attr.atttypmod)?));
+        }
+
+        if chars.next() != Some(')') || chars.any(|c| !c.is_whitespace()) {
+            bail!(malformed());
+        }
+        Ok(values)
+    }
+}
+
+impl Type for CompositeType {
+    fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    fn typname(&self) -> &'static str {
+        self.typname
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Composite
+    }
+
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Composite
+    }
+
+    fn typrelid(&self) -> Oid {
+        self.typrelid
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        Ok(bincode::serialize(&self.parse(value)?)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        let values = deserialize::<Vec<Option<Datum>>>(datum)?;
+        output_fields(self.field_types().zip(&values))
+    }
+
+    /// Decode a value received in binary format, which contains the number of attributes and
+    /// then the type oid, the length and the binary value of each attribute, with -1 as the
+    /// length of NULL attributes.
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        let invalid = || Error::InvalidBinaryRepresentation(self.typname);
+        let mut reader = BinaryReader::new(buf);
+
+        let count = reader.read_i32().ok_or_else(invalid)?;
+        if count as usize != self.attributes.len() {
+            bail!(invalid());
+        }
+
+        let mut values = Vec::with_capacity(self.attributes.len());
+        for attr in &self.attributes {
+            let typ = reader.read_i32().ok_or_else(invalid)? as u32 as Oid;
+            if typ != attr.atttypid {
+                bail!(invalid());
+            }
+
+            let len = reader.read_i32().ok_or_else(invalid)?;
+            if len == -1 {
+                values.push(None);
+                continue;
+            }
+            let value = reader.read_bytes(len).ok_or_else(invalid)?;
+            values.push(Some(lookup_type(typ)?.recv(value, attr.atttypmod)?));
+        }
+
+        if !reader.is_empty() {
+            bail!(invalid());
+        }
+        Ok(bincode::serialize(&values)?)
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let values = deserialize::<Vec<Option<Datum>>>(datum)?;
+        send_fields(self.field_types().zip(&values))
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        let a = deserialize::<Vec<Option<Datum>>>(a)?;
+        let b = deserialize::<Vec<Option<Datum>>>(b)?;
+        compare_fields(self.field_types().zip(a.iter().zip(&b)))
+    }
+
+    fn attributes(&self) -> &[PgAttribute] {
+        &self.attributes
+    }
+}
+
+/// Pseudo-type of the values built with ROW(...) that are not converted to a composite type,
+/// e.g SELECT ROW(1, 'a').
+///
+/// The attributes of a record are not cataloged, so a value is stored as the list of the type
+/// oid and the datum of each attribute, with None for NULL attributes.
+pub struct Record;
+
+impl Record {
+    /// Create a record datum from the type oid and the datum of each attribute.
+    pub fn to_datum(fields: &[(Oid, Option<Datum>)]) -> Result<Datum> {
+        Ok(bincode::serialize(fields)?)
+    }
+}
+
+impl Type for Record {
+    fn oid(&self) -> Oid {
+        pg_type::RECORD_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "record"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Pseudo
+    }
+
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Pseudo
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, _value: &str, _typmod: i32) -> Result<Datum> {
+        bail!(Error::PseudoTypeInput(self.format_type()))
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        let fields = deserialize::<Vec<(Oid, Option<Datum>)>>(datum)?;
+        output_fields(fields.iter().map(|(typ, value)| (*typ, value)))
+    }
+
+    fn recv(&self, _buf: &[u8], _typmod: i32) -> Result<Datum> {
+        bail!(Error::PseudoTypeInput(self.format_type()))
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let fields = deserialize::<Vec<(Oid, Option<Datum>)>>(datum)?;
+        send_fields(fields.iter().map(|(typ, value)| (*typ, value)))
+    }
+
+    /// Compare two records with the same attribute types, e.g ROW(1, 'a') < ROW(1, 'b').
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        let a = deserialize::<Vec<(Oid, Option<Datum>)>>(a)?;
+        let b = deserialize::<Vec<(Oid, Option<Datum>)>>(b)?;
+        if a.len() != b.len() || a.iter().zip(&b).any(|((a, _), (b, _))| a != b) {
+            bail!(Error::UndefinedComparison(self.format_type()));
+        }
+        compare_fields(a.iter().zip(&b).map(|((typ, a), (_, b))| (*typ, (a, b))))
+    }
+}
+
+/// Return the attributes between parentheses, quoting the attributes that are empty or contain
+/// any character with special meaning. NULL attributes are left empty.
+fn output_fields<'a>(fields: impl Iterator<Item = (Oid, &'a Option<Datum>)>) -> Result<String> {
+    let mut output = String::from("(");
+    for (i, (typ, value)) in fields.enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        let value = match value {
+            Some(value) => lookup_type(typ)?.output(value)?,
+            None => continue,
+        };
+
+        let needs_quotes = value.is_empty()
+            || value
+                .chars()
+                .any(|c| matches!(c, '"' | '\\' | '(' | ')' | ',') || c.is_ascii_whitespace());
+        if !needs_quotes {
+            output.push_str(&value);
+            continue;
+        }
+
+        output.push('"');
+        for c in value.chars() {
+            if c == '"' || c == '\\' {
+                output.push(c);
+            }
+            output.push(c);
+        }
+        output.push('"');
+    }
+    output.push(')');
+    Ok(output)
+}
+
+/// Encode the attributes in binary format, which contains the number of attributes and then the
+/// type oid, the length and the binary value of each attribute, with -1 as the length of NULL
+/// attributes.
+fn send_fields<'a>(
+    fields: impl ExactSizeIterator<Item = (Oid, &'a Option<Datum>)>,
+) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(fields.len() as i32).to_be_bytes());
+    for (typ, value) in fields {
+        buf.extend_from_slice(&(typ as u32).to_be_bytes());
+        match value {
+            Some(value) => {
+                let value = lookup_type(typ)?.send(value)?;
+                buf.extend_from_slice(&(value.len() as i32).to_be_bytes());
+                buf.extend_from_slice(&value);
+            }
+            None => buf.extend_from_slice(&(-1_i32).to_be_bytes()),
+        }
+    }
+    Ok(buf)
+}
+
+/// Compare two values attribute by attribute, NULL attributes are greater than any other
+/// value.
+fn compare_fields<'a>(
+    fields: impl Iterator<Item = (Oid, (&'a Option<Datum>, &'a Option<Datum>))>,
+) -> Result<Ordering> {
+    for (typ, (a, b)) in fields {
+        let ordering = match (a, b) {
+            (Some(a), Some(b)) => lookup_type(typ)?.compare(a, b)?,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+    Ok(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{catalog::pg_type, INVALID_OID};
+
+    fn pair() -> CompositeType {
+        let attr = |attname: &str, attnum, atttypid| PgAttribute {
+            attrelid: INVALID_OID,
+            attname: attname.to_string(),
+            attnum,
+            attlen: -1,
+            atttypid,
+            atttypmod: -1,
+            attidentity: String::new(),
+        };
+        CompositeType::new(
+            1,
+            "pair",
+            INVALID_OID,
+            vec![
+                attr("a", 1, pg_type::INT_OID),
+                attr("b", 2, pg_type::TEXT_OID),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let pair = pair();
+        let cases = [
+            ("(1,abc)", "(1,abc)"),
+            ("(,)", "(,)"),
+            (r#"( 2,"a ""b"" \c")"#, r#"(2,"a ""b"" c")"#),
+            (r#"(3,"")"#, r#"(3,"")"#),
+            (r#"(4,x\,y)"#, r#"(4,"x,y")"#),
+        ];
+        for (input, output) in cases {
+            assert_eq!(pair.output(&pair.input(input, -1)?)?, output, "{}", input);
+        }
+
+        for input in ["", "(1)", "(1,a,b)", "(1,a", "(1,a) x", "1,a", "(a,b)"] {
+            assert!(pair.input(input, -1).is_err(), "{}", input);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_format() -> Result<()> {
+        let pair = pair();
+        let datum = pair.input("(7,)", -1)?;
+        let binary = pair.send(&datum)?;
+        assert_eq!(
+            binary,
+            [0, 0, 0, 2, 0, 0, 0, 23, 0, 0, 0, 4, 0, 0, 0, 7, 0, 0, 0, 25, 255, 255, 255, 255]
+        );
+        assert_eq!(pair.recv(&binary, -1)?, datum);
+        assert!(pair.recv(&binary[..8], -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let pair = pair();
+        let ordered = ["(1,b)", "(2,a)", "(2,b)", "(2,)", "(,a)"];
+        for values in ordered.windows(2) {
+            let a = pair.input(values[0], -1)?;
+            let b = pair.input(values[1], -1)?;
+            assert_eq!(pair.compare(&a, &b)?, Ordering::Less, "{:?}", values);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_record() -> Result<()> {
+        let record = Record::to_datum(&[
+            (pg_type::INT_OID, Some(bincode::serialize(&1)?)),
+            (pg_type::TEXT_OID, Some(bincode::serialize("a b")?)),
+            (pg_type::INT_OID, None),
+        ])?;
+        assert_eq!(Record.output(&record)?, r#"(1,"a b",)"#);
+        assert_eq!(Record.compare(&record, &record)?, Ordering::Equal);
+
+        let other = Record::to_datum(&[(pg_type::INT_OID, Some(bincode::serialize(&1)?))])?;
+        assert!(Record.compare(&record, &other).is_err());
+        assert!(Record.input("(1)", -1).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,1159 @@
+//! Date and time data types.
+//!
+//! Dates are stored as the number of days since 2000-01-01, times as the number of microseconds
+//! since midnight and timestamps as the number of microseconds since 2000-01-01 00:00:00, which
+//! is also the binary format of these types on the wire protocol. A timestamp with time zone is
+//! stored in UTC and displayed on the time zone of the session.
+use std::{
+    cmp::Ordering,
+    convert::TryInto,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+use chrono::{offset::LocalResult, DateTime, NaiveDateTime, Offset, TimeZone as _};
+use chrono_tz::Tz;
+
+use super::{
+    deserialize, interval::IntervalValue, undefined_operator, ArithOp, Error, Type, TypeAlign,
+    TypeCategory,
+};
+use crate::{catalog::pg_type, sql::session, Datum, Oid};
+
+pub const USECS_PER_SEC: i64 = 1_000_000;
+pub const USECS_PER_MINUTE: i64 = 60 * USECS_PER_SEC;
+pub const USECS_PER_HOUR: i64 = 60 * USECS_PER_MINUTE;
+pub const USECS_PER_DAY: i64 = 24 * USECS_PER_HOUR;
+
+/// Number of days between the Unix epoch (1970-01-01) and the epoch of the date and time types
+/// (2000-01-01).
+const POSTGRES_EPOCH_DAYS: i64 = 10957;
+
+/// Values used to represent -infinity and infinity.
+const DATE_NOBEGIN: i32 = i32::MIN;
+const DATE_NOEND: i32 = i32::MAX;
+const TIMESTAMP_NOBEGIN: i64 = i64::MIN;
+const TIMESTAMP_NOEND: i64 = i64::MAX;
+
+/// First valid day of dates and timestamps (4713-01-01 BC), and the days after the last valid
+/// day of dates (5874897-12-31) and timestamps (294276-12-31), as days since 2000-01-01.
+const MIN_DAY: i64 = days_from_civil(-4712, 1, 1) - POSTGRES_EPOCH_DAYS;
+const END_DATE_DAY: i64 = days_from_civil(5874898, 1, 1) - POSTGRES_EPOCH_DAYS;
+const END_TIMESTAMP_DAY: i64 = days_from_civil(294277, 1, 1) - POSTGRES_EPOCH_DAYS;
+
+/// Maximum offset from UTC of a time zone, in hours.
+const MAX_TZDISP_HOUR: i32 = 15;
+
+/// Rules used to compute the offset from UTC of a time zone.
+#[derive(Debug, Clone, PartialEq)]
+enum ZoneRules {
+    /// Fixed offset from UTC in seconds, positive east of Greenwich.
+    Fixed(i32),
+
+    /// Time zone of the tz database, whose offset depends on the point in time because of
+    /// daylight saving time and historical changes.
+    Named(Tz),
+}
+
+/// A time zone, either with a fixed offset from UTC or a time zone of the tz database, e.g
+/// America/Sao_Paulo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeZone {
+    /// Name of the time zone as set by the user.
+    name: String,
+
+    /// Rules used to compute the offset from UTC.
+    rules: ZoneRules,
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        Self {
+            name: String::from("UTC"),
+            rules: ZoneRules::Fixed(0),
+        }
+    }
+
+    /// Parse a time zone name. The supported names are UTC (or GMT), a number of hours east of
+    /// Greenwich like -3 or 5.5, a time zone name of the tz database like Europe/Berlin
+    /// (case insensitive), and a POSIX time zone specification without daylight saving time
+    /// rules like EST5, where the offset is the number of hours west of Greenwich.
+    pub fn parse(name: &str) -> Result<Self> {
+        let invalid = || Error::InvalidTimeZone(name.to_string());
+        let trimmed = name.trim();
+        let lower = trimmed.to_ascii_lowercase();
+
+        let rules = if matches!(lower.as_str(), "utc" | "gmt" | "etc/utc" | "etc/gmt") {
+            ZoneRules::Fixed(0)
+        } else if let Ok(hours) = lower.parse::<f64>() {
+            if hours.is_nan() || hours.abs() > MAX_TZDISP_HOUR as f64 {
+                bail!(invalid());
+            }
+            ZoneRules::Fixed((hours * 3600.0).round() as i32)
+        } else if let Ok(tz) = Tz::from_str_insensitive(trimmed) {
+            ZoneRules::Named(tz)
+        } else {
+            // A POSIX specification is an abbreviation of at least 3 letters followed by the
+            // offset.
+            let len = lower
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(lower.len());
+            if len < 3 {
+                bail!(invalid());
+            }
+            ZoneRules::Fixed(-parse_offset(&lower[len..]).ok_or_else(invalid)?)
+        };
+
+        Ok(Self {
+            name: trimmed.to_string(),
+            rules,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Offset from UTC in seconds, positive east of Greenwich, at the given point in time in
+    /// microseconds since 2000-01-01 UTC.
+    pub fn offset_at_utc(&self, timestamp: i64) -> i32 {
+        match &self.rules {
+            ZoneRules::Fixed(offset) => *offset,
+            ZoneRules::Named(tz) => match to_naive_datetime(timestamp) {
+                Some(utc) => tz.offset_from_utc_datetime(&utc).fix().local_minus_utc(),
+                None => 0,
+            },
+        }
+    }
+
+    /// Offset from UTC in seconds, positive east of Greenwich, of the given local time in
+    /// microseconds since 2000-01-01.
+    ///
+    /// Like Postgres, a local time that is repeated when the clocks are turned back uses the
+    /// offset after the transition, and a local time that is skipped when the clocks are
+    /// turned forward uses the offset before the transition.
+    pub fn offset_at_local(&self, local: i64) -> i32 {
+        match &self.rules {
+            ZoneRules::Fixed(offset) => *offset,
+            ZoneRules::Named(tz) => {
+                let naive = match to_naive_datetime(local) {
+                    Some(naive) => naive,
+                    None => return 0,
+                };
+                match tz.offset_from_local_datetime(&naive) {
+                    LocalResult::Single(offset) | LocalResult::Ambiguous(_, offset) => {
+                        offset.fix().local_minus_utc()
+                    }
+                    // Transitions are never less than a day apart, so the offset a day before
+                    // is the offset before the transition.
+                    LocalResult::None => self.offset_at_utc(local - USECS_PER_DAY),
+                }
+            }
+        }
+    }
+}
+
+/// Convert microseconds since 2000-01-01 into a date and time of the chrono crate. None is
+/// returned if the value is out of the range supported by chrono.
+fn to_naive_datetime(timestamp: i64) -> Option<NaiveDateTime> {
+    let unix_micros = timestamp.checked_add(POSTGRES_EPOCH_DAYS * USECS_PER_DAY)?;
+    DateTime::from_timestamp_micros(unix_micros).map(|datetime| datetime.naive_utc())
+}
+
+impl Default for TimeZone {
+    fn default() -> Self {
+        Self::utc()
+    }
+}
+
+/// Return the current time, in microseconds since 2000-01-01 UTC.
+pub fn current_timestamp() -> i64 {
+    let since_unix_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_micros() as i64)
+        .unwrap_or(0);
+    since_unix_epoch - POSTGRES_EPOCH_DAYS * USECS_PER_DAY
+}
+
+/// Return the start time of the current transaction on the time zone of the session, in
+/// microseconds since 2000-01-01.
+pub fn local_transaction_timestamp() -> i64 {
+    let timestamp = session::transaction_timestamp();
+    timestamp + session::timezone().offset_at_utc(timestamp) as i64 * USECS_PER_SEC
+}
+
+/// Return the current date on the time zone of the session, in days since 2000-01-01.
+pub fn local_today() -> i64 {
+    local_transaction_timestamp().div_euclid(USECS_PER_DAY)
+}
+
+/// Return the number of days since 1970-01-01 of a date of the proleptic Gregorian calendar.
+/// Years before 1 AD are represented as 0 for 1 BC, -1 for 2 BC and so on.
+const fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Years start on March, so the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = ((month + 9) % 12) as i64;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Return the year, month and day of the given number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year.rem_euclid(4) == 0 && (year.rem_euclid(100) != 0 || year.rem_euclid(400) == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Special input values of the date and time types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Special {
+    Epoch,
+    Infinity,
+    NegInfinity,
+    Now,
+    Today,
+    Tomorrow,
+    Yesterday,
+    Allballs,
+}
+
+fn parse_special(value: &str) -> Option<Special> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "epoch" => Some(Special::Epoch),
+        "infinity" | "+infinity" => Some(Special::Infinity),
+        "-infinity" => Some(Special::NegInfinity),
+        "now" => Some(Special::Now),
+        "today" => Some(Special::Today),
+        "tomorrow" => Some(Special::Tomorrow),
+        "yesterday" => Some(Special::Yesterday),
+        "allballs" => Some(Special::Allballs),
+        _ => None,
+    }
+}
+
+/// Date and time fields parsed from the text representation of a value.
+#[derive(Debug, Default)]
+struct DateTimeFields {
+    /// Year, month and day, with years before 1 AD represented as in [days_from_civil].
+    date: Option<(i64, u32, u32)>,
+
+    /// Microseconds since midnight.
+    time: Option<i64>,
+
+    /// Time zone offset in seconds, positive east of Greenwich.
+    offset: Option<i32>,
+}
+
+/// Parse a date and time in ISO 8601 format, e.g 2024-01-31 13:45:00.5+02, optionally
+/// followed by BC. Dates can also be written as month/day/year.
+fn parse_fields(value: &str, type_name: &'static str) -> Result<DateTimeFields> {
+    let invalid = || Error::InvalidTextRepresentation(type_name, value.to_string());
+    let lower = value.trim().to_ascii_lowercase();
+    let mut rest = lower.as_str();
+    let mut fields = DateTimeFields::default();
+
+    let mut bc = false;
+    if let Some(stripped) = rest.strip_suffix("bc") {
+        bc = true;
+        rest = stripped.trim_end();
+    } else if let Some(stripped) = rest.strip_suffix("ad") {
+        rest = stripped.trim_end();
+    }
+
+    let date_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '-' || c == '/'))
+        .unwrap_or(rest.len());
+    if rest.starts_with(|c: char| c.is_ascii_digit()) && rest[..date_len].contains(['-', '/']) {
+        fields.date = Some(parse_date(&rest[..date_len], bc, value, type_name)?);
+        rest = &rest[date_len..];
+        rest = rest.strip_prefix('t').unwrap_or(rest).trim_start();
+    } else if bc {
+        bail!(invalid());
+    }
+
+    let time_len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.'))
+        .unwrap_or(rest.len());
+    if rest[..time_len].contains(':') {
+        let (hour, minute, second, micros) = parse_clock(&rest[..time_len]).ok_or_else(invalid)?;
+        if hour > 24
+            || minute > 59
+            || second > 60
+            || (hour == 24 && (minute, second, micros) != (0, 0, 0))
+        {
+            bail!(Error::DatetimeFieldOverflow(value.to_string()));
+        }
+        fields.time = Some(
+            hour * USECS_PER_HOUR + minute * USECS_PER_MINUTE + second * USECS_PER_SEC + micros,
+        );
+        rest = rest[time_len..].trim_start();
+    }
+
+    if !rest.is_empty() {
+        let offset = match rest {
+            "z" | "utc" | "gmt" => 0,
+            _ if rest.starts_with(['+', '-']) => parse_offset(rest).ok_or_else(invalid)?,
+            _ => bail!(invalid()),
+        };
+        fields.offset = Some(offset);
+    }
+
+    Ok(fields)
+}
+
+/// Parse a date written as year-month-day or month/day/year.
+fn parse_date(
+    part: &str,
+    bc: bool,
+    value: &str,
+    type_name: &'static str,
+) -> Result<(i64, u32, u32)> {
+    let separator = if part.contains('-') { '-' } else { '/' };
+    let items: Vec<&str> = part.split(separator).collect();
+    if items.len() != 3
+        || items
+            .iter()
+            .any(|item| item.is_empty() || !item.bytes().all(|b| b.is_ascii_digit()))
+    {
+        bail!(Error::InvalidTextRepresentation(
+            type_name,
+            value.to_string()
+        ));
+    }
+
+    let (year, month, day) = if separator == '-' {
+        (items[0], items[1], items[2])
+    } else {
+        (items[2], items[0], items[1])
+    };
+
+    let overflow = || Error::DatetimeFieldOverflow(value.to_string());
+    let year: i64 = year.parse().map_err(|_| overflow())?;
+    let month: u32 = month.parse().map_err(|_| overflow())?;
+    let day: u32 = day.parse().map_err(|_| overflow())?;
+    if year == 0 || year > 9_999_999 {
+        bail!(overflow());
+    }
+
+    let year = if bc { 1 - year } else { year };
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        bail!(overflow());
+    }
+    Ok((year, month, day))
+}
+
+/// Parse a time written as hours:minutes[:seconds[.fraction]], returning the hours, minutes,
+/// seconds and microseconds. The fields are not range checked.
+pub(super) fn parse_clock(part: &str) -> Option<(i64, i64, i64, i64)> {
+    let items: Vec<&str> = part.split(':').collect();
+    let (hour, minute, second) = match items.as_slice() {
+        [hour, minute] => (*hour, *minute, "0"),
+        [hour, minute, second] => (*hour, *minute, *second),
+        _ => return None,
+    };
+    let (second, fraction) = second.split_once('.').unwrap_or((second, ""));
+
+    let parse = |field: &str| -> Option<i64> {
+        if field.is_empty() || !field.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        field.parse().ok()
+    };
+
+    let micros = if fraction.is_empty() {
+        0
+    } else {
+        parse(fraction)?;
+        (format!("0.{}", fraction).parse::<f64>().ok()? * 1e6).round() as i64
+    };
+    Some((parse(hour)?, parse(minute)?, parse(second)?, micros))
+}
+
+/// Parse a time zone offset written as [+-]hh[:mm[:ss]] or [+-]hhmm, returning the offset in
+/// seconds.
+fn parse_offset(value: &str) -> Option<i32> {
+    let (sign, value) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => (1, value),
+    };
+
+    let items: Vec<&str> = value.split(':').collect();
+    if items
+        .iter()
+        .any(|item| item.is_empty() || !item.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let (hours, minutes, seconds): (i32, i32, i32) = match items.as_slice() {
+        [hhmm] if hhmm.len() == 4 => (hhmm[..2].parse().ok()?, hhmm[2..].parse().ok()?, 0),
+        [hours] if hours.len() <= 2 => (hours.parse().ok()?, 0, 0),
+        [hours, minutes] => (hours.parse().ok()?, minutes.parse().ok()?, 0),
+        [hours, minutes, seconds] => (
+            hours.parse().ok()?,
+            minutes.parse().ok()?,
+            seconds.parse().ok()?,
+        ),
+        _ => return None,
+    };
+
+    if hours > MAX_TZDISP_HOUR || minutes > 59 || seconds > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Format a number of microseconds as HH:MM:SS, followed by the fractional seconds if any.
+/// Hours are not limited to a day.
+pub(super) fn format_clock(micros: u64) -> String {
+    let usecs_per_sec = USECS_PER_SEC as u64;
+    let seconds = micros / usecs_per_sec;
+    let mut out = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+
+    let fraction = micros % usecs_per_sec;
+    if fraction != 0 {
+        out.push_str(format!(".{:06}", fraction).trim_end_matches('0'));
+    }
+    out
+}
+
+/// Format a date given in days since 2000-01-01 as YYYY-MM-DD, returning also whether the date
+/// is before Christ.
+fn format_date(days: i64) -> (String, bool) {
+    let (year, month, day) = civil_from_days(days + POSTGRES_EPOCH_DAYS);
+    let (year, bc) = if year <= 0 {
+        (1 - year, true)
+    } else {
+        (year, false)
+    };
+    (format!("{:04}-{:02}-{:02}", year, month, day), bc)
+}
+
+/// Format a time zone offset given in seconds as +HH, +HH:MM or +HH:MM:SS.
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    let (hours, minutes, seconds) = (offset / 3600, offset / 60 % 60, offset % 60);
+    match (minutes, seconds) {
+        (0, 0) => format!("{}{:02}", sign, hours),
+        (_, 0) => format!("{}{:02}:{:02}", sign, hours, minutes),
+        _ => format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds),
+    }
+}
+
+/// Format a timestamp, displaying it on the time zone with the given offset in seconds if any.
+fn format_timestamp(timestamp: i64, offset: Option<i32>) -> String {
+    match timestamp {
+        TIMESTAMP_NOBEGIN => return String::from("-infinity"),
+        TIMESTAMP_NOEND => return String::from("infinity"),
+        _ => {}
+    }
+
+    let local = timestamp + offset.unwrap_or(0) as i64 * USECS_PER_SEC;
+    let (date, bc) = format_date(local.div_euclid(USECS_PER_DAY));
+    let mut out = format!(
+        "{} {}",
+        date,
+        format_clock(local.rem_euclid(USECS_PER_DAY) as u64)
+    );
+    if let Some(offset) = offset {
+        out.push_str(&format_offset(offset));
+    }
+    if bc {
+        out.push_str(" BC");
+    }
+    out
+}
+
+fn is_infinite_timestamp(timestamp: i64) -> bool {
+    timestamp == TIMESTAMP_NOBEGIN || timestamp == TIMESTAMP_NOEND
+}
+
+fn timestamp_in_range(timestamp: i64) -> bool {
+    is_infinite_timestamp(timestamp)
+        || (MIN_DAY..END_TIMESTAMP_DAY).contains(&timestamp.div_euclid(USECS_PER_DAY))
+}
+
+/// Convert a date to a timestamp at midnight of that day.
+fn date_to_timestamp(date: i32) -> Result<i64> {
+    match date {
+        DATE_NOBEGIN => Ok(TIMESTAMP_NOBEGIN),
+        DATE_NOEND => Ok(TIMESTAMP_NOEND),
+        _ if date as i64 >= END_TIMESTAMP_DAY => bail!(Error::DatetimeOutOfRange("timestamp")),
+        _ => Ok(date as i64 * USECS_PER_DAY),
+    }
+}
+
+/// Add an interval to a timestamp. Months and days are added to the date on the given time
+/// zone, keeping the day of month unless it is past the end of the resulting month, e.g
+/// 2024-01-31 + 1 month is 2024-02-29, and keeping the time of day across daylight saving time
+/// changes.
+fn timestamp_pl_interval(timestamp: i64, interval: &IntervalValue, zone: &TimeZone) -> Result<i64> {
+    if is_infinite_timestamp(timestamp) {
+        return Ok(timestamp);
+    }
+    let out_of_range = || Error::DatetimeOutOfRange("timestamp");
+
+    let mut timestamp = timestamp;
+    if interval.month != 0 || interval.day != 0 {
+        let local = timestamp + zone.offset_at_utc(timestamp) as i64 * USECS_PER_SEC;
+        let mut days = local.div_euclid(USECS_PER_DAY);
+
+        if interval.month != 0 {
+            let (year, month, day) = civil_from_days(days + POSTGRES_EPOCH_DAYS);
+
+            let months = year * 12 + month as i64 - 1 + interval.month as i64;
+            let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+            let day = day.min(days_in_month(year, month));
+
+            days = days_from_civil(year, month, day) - POSTGRES_EPOCH_DAYS;
+        }
+
+        days += interval.day as i64;
+        if !(MIN_DAY..END_TIMESTAMP_DAY).contains(&days) {
+            bail!(out_of_range());
+        }
+
+        let local = days * USECS_PER_DAY + local.rem_euclid(USECS_PER_DAY);
+        timestamp = local - zone.offset_at_local(local) as i64 * USECS_PER_SEC;
+    }
+
+    let timestamp = timestamp
+        .checked_add(interval.time)
+        .filter(|timestamp| timestamp_in_range(*timestamp) && !is_infinite_timestamp(*timestamp))
+        .ok_or_else(out_of_range)?;
+    Ok(timestamp)
+}
+
+/// Subtract two timestamps, returning the difference as days and time.
+fn timestamp_mi(a: i64, b: i64) -> Result<IntervalValue> {
+    if is_infinite_timestamp(a) || is_infinite_timestamp(b) {
+        bail!(Error::InfiniteSubtraction("timestamps"));
+    }
+    let diff = a - b;
+    Ok(IntervalValue {
+        time: diff % USECS_PER_DAY,
+        day: (diff / USECS_PER_DAY) as i32,
+        month: 0,
+    })
+}
+
+/// Read a fixed length value received in binary format.
+fn recv_be<const N: usize>(buf: &[u8], format_type: &'static str) -> Result<[u8; N]> {
+    match buf.try_into() {
+        Ok(buf) => Ok(buf),
+        Err(_) => bail!(Error::InvalidBinaryRepresentation(format_type)),
+    }
+}
+
+/// Calendar date (year, month, day).
+pub struct Date;
+
+impl Type for Date {
+    fn oid(&self) -> Oid {
+        pg_type::DATE_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "date"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::DateTime
+    }
+
+    fn typlen(&self) -> i32 {
+        4
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        let days = match parse_special(value) {
+            Some(Special::Infinity) => return Ok(bincode::serialize(&DATE_NOEND)?),
+            Some(Special::NegInfinity) => return Ok(bincode::serialize(&DATE_NOBEGIN)?),
+            Some(Special::Epoch) => -POSTGRES_EPOCH_DAYS,
+            Some(Special::Now) | Some(Special::Today) => local_today(),
+            Some(Special::Tomorrow) => local_today() + 1,
+            Some(Special::Yesterday) => local_today() - 1,
+            Some(Special::Allballs) => {
+                bail!(Error::InvalidTextRepresentation("date", value.to_string()))
+            }
+            None => match parse_fields(value, "date")?.date {
+                Some((year, month, day)) => days_from_civil(year, month, day) - POSTGRES_EPOCH_DAYS,
+                None => bail!(Error::InvalidTextRepresentation("date", value.to_string())),
+            },
+        };
+
+        if !(MIN_DAY..END_DATE_DAY).contains(&days) {
+            bail!(Error::DatetimeValueOutOfRange("date", value.to_string()));
+        }
+        Ok(bincode::serialize(&(days as i32))?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(match deserialize::<i32>(datum)? {
+            DATE_NOBEGIN => String::from("-infinity"),
+            DATE_NOEND => String::from("infinity"),
+            days => match format_date(days as i64) {
+                (date, true) => format!("{} BC", date),
+                (date, false) => date,
+            },
+        })
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        let days = i32::from_be_bytes(recv_be(buf, "date")?);
+        if days != DATE_NOBEGIN
+            && days != DATE_NOEND
+            && !(MIN_DAY..END_DATE_DAY).contains(&(days as i64))
+        {
+            bail!(Error::DatetimeOutOfRange("date"));
+        }
+        Ok(bincode::serialize(&days)?)
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<i32>(datum)?.to_be_bytes().to_vec())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<i32>(a)?.cmp(&deserialize::<i32>(b)?))
+    }
+
+    fn arith_result_type(&self, op: ArithOp, right: Oid) -> Option<Oid> {
+        match (op, right) {
+            (ArithOp::Add | ArithOp::Sub, pg_type::INT_OID) => Some(pg_type::DATE_OID),
+            (ArithOp::Sub, pg_type::DATE_OID) => Some(pg_type::INT_OID),
+            (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => Some(pg_type::TIMESTAMP_OID),
+            (ArithOp::Add, pg_type::TIME_OID) => Some(pg_type::TIMESTAMP_OID),
+            _ => None,
+        }
+    }
+
+    fn arith(&self, op: ArithOp, a: &[u8], right: Oid, b: &[u8]) -> Result<Datum> {
+        let date = deserialize::<i32>(a)?;
+
+        match (op, right) {
+            (ArithOp::Add | ArithOp::Sub, pg_type::INT_OID) => {
+                if date == DATE_NOBEGIN || date == DATE_NOEND {
+                    return Ok(a.to_vec());
+                }
+                let days = deserialize::<i32>(b)? as i64;
+                let result = if op == ArithOp::Add {
+                    date as i64 + days
+                } else {
+                    date as i64 - days
+                };
+                if !(MIN_DAY..END_DATE_DAY).contains(&result) {
+                    bail!(Error::DatetimeOutOfRange("date"));
+                }
+                Ok(bincode::serialize(&(result as i32))?)
+            }
+            (ArithOp::Sub, pg_type::DATE_OID) => {
+                let other = deserialize::<i32>(b)?;
+                if [date, other]
+                    .iter()
+                    .any(|date| *date == DATE_NOBEGIN || *date == DATE_NOEND)
+                {
+                    bail!(Error::InfiniteSubtraction("dates"));
+                }
+                Ok(bincode::serialize(&(date - other))?)
+            }
+            (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => Timestamp.arith(
+                op,
+                &bincode::serialize(&date_to_timestamp(date)?)?,
+                right,
+                b,
+            ),
+            (ArithOp::Add, pg_type::TIME_OID) => {
+                let timestamp = date_to_timestamp(date)?;
+                if is_infinite_timestamp(timestamp) {
+                    return Ok(bincode::serialize(&timestamp)?);
+                }
+                let timestamp = timestamp + deserialize::<i64>(b)?;
+                if !timestamp_in_range(timestamp) {
+                    bail!(Error::DatetimeOutOfRange("timestamp"));
+                }
+                Ok(bincode::serialize(&timestamp)?)
+            }
+            _ => Err(undefined_operator(self.format_type(), op, right)),
+        }
+    }
+}
+
+/// Time of day, without time zone.
+pub struct Time;
+
+impl Type for Time {
+    fn oid(&self) -> Oid {
+        pg_type::TIME_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "time"
+    }
+
+    fn format_type(&self) -> &'static str {
+        "time without time zone"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::DateTime
+    }
+
+    fn typlen(&self) -> i32 {
+        8
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        let invalid = || Error::InvalidTextRepresentation(self.format_type(), value.to_string());
+        let time = match parse_special(value) {
+            Some(Special::Allballs) => 0,
+            Some(Special::Now) => local_transaction_timestamp().rem_euclid(USECS_PER_DAY),
+            Some(_) => bail!(invalid()),
+            None => parse_fields(value, self.format_type())?
+                .time
+                .ok_or_else(invalid)?,
+        };
+        Ok(bincode::serialize(&time)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(format_clock(deserialize::<i64>(datum)? as u64))
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        let time = i64::from_be_bytes(recv_be(buf, self.format_type())?);
+        if !(0..=USECS_PER_DAY).contains(&time) {
+            bail!(Error::DatetimeOutOfRange("time"));
+        }
+        Ok(bincode::serialize(&time)?)
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<i64>(datum)?.to_be_bytes().to_vec())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<i64>(a)?.cmp(&deserialize::<i64>(b)?))
+    }
+
+    fn arith_result_type(&self, op: ArithOp, right: Oid) -> Option<Oid> {
+        match (op, right) {
+            (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => Some(pg_type::TIME_OID),
+            (ArithOp::Sub, pg_type::TIME_OID) => Some(pg_type::INTERVAL_OID),
+            (ArithOp::Add, pg_type::DATE_OID) => Some(pg_type::TIMESTAMP_OID),
+            _ => None,
+        }
+    }
+
+    fn arith(&self, op: ArithOp, a: &[u8], right: Oid, b: &[u8]) -> Result<Datum> {
+        let time = deserialize::<i64>(a)?;
+
+        match (op, right) {
+            (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => {
+                // Only the time part of the interval is used, wrapping around midnight.
+                let span = IntervalValue::from_datum(b)?.time.rem_euclid(USECS_PER_DAY);
+                let result = if op == ArithOp::Add {
+                    time + span
+                } else {
+                    time - span
+                };
+                Ok(bincode::serialize(&result.rem_euclid(USECS_PER_DAY))?)
+            }
+            (ArithOp::Sub, pg_type::TIME_OID) => IntervalValue {
+                time: time - deserialize::<i64>(b)?,
+                day: 0,
+                month: 0,
+            }
+            .to_datum(),
+            (ArithOp::Add, pg_type::DATE_OID) => Date.arith(op, b, pg_type::TIME_OID, a),
+            _ => Err(undefined_operator(self.format_type(), op, right)),
+        }
+    }
+}
+
+macro_rules! timestamp_type {
+    ($name:ident, $oid:path, $typname:expr, $format_type:expr, $with_tz:expr) => {
+        impl $name {
+            /// Time zone used to read and display values.
+            fn zone(&self) -> TimeZone {
+                if $with_tz {
+                    session::timezone()
+                } else {
+                    TimeZone::utc()
+                }
+            }
+        }
+
+        impl Type for $name {
+            fn oid(&self) -> Oid {
+                $oid
+            }
+
+            fn typname(&self) -> &'static str {
+                $typname
+            }
+
+            fn format_type(&self) -> &'static str {
+                $format_type
+            }
+
+            fn typcategory(&self) -> TypeCategory {
+                TypeCategory::DateTime
+            }
+
+            fn typispreferred(&self) -> bool {
+                $oid == pg_type::TIMESTAMPTZ_OID
+            }
+
+            fn typlen(&self) -> i32 {
+                8
+            }
+
+            fn typalign(&self) -> TypeAlign {
+                TypeAlign::Double
+            }
+
+            fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+                let invalid = || Error::InvalidTextRepresentation($format_type, value.to_string());
+
+                let zone = self.zone();
+
+                // The offset is only known before converting to UTC if the value has an
+                // explicit offset, otherwise the offset of the local time on the time zone of
+                // the session is used.
+                let (local, offset) = match parse_special(value) {
+                    Some(Special::Infinity) => return Ok(bincode::serialize(&TIMESTAMP_NOEND)?),
+                    Some(Special::NegInfinity) => {
+                        return Ok(bincode::serialize(&TIMESTAMP_NOBEGIN)?)
+                    }
+                    Some(Special::Epoch) => {
+                        return Ok(bincode::serialize(&(-POSTGRES_EPOCH_DAYS * USECS_PER_DAY))?)
+                    }
+                    Some(Special::Now) => {
+                        let timestamp = session::transaction_timestamp();
+                        let offset = zone.offset_at_utc(timestamp);
+                        (timestamp + offset as i64 * USECS_PER_SEC, Some(offset))
+                    }
+                    Some(Special::Today) => (local_today() * USECS_PER_DAY, None),
+                    Some(Special::Tomorrow) => ((local_today() + 1) * USECS_PER_DAY, None),
+                    Some(Special::Yesterday) => ((local_today() - 1) * USECS_PER_DAY, None),
+                    Some(Special::Allballs) => bail!(invalid()),
+                    None => {
+                        let fields = parse_fields(value, $format_type)?;
+                        let (year, month, day) = fields.date.ok_or_else(invalid)?;
+                        let days = days_from_civil(year, month, day) - POSTGRES_EPOCH_DAYS;
+                        if !(MIN_DAY..END_TIMESTAMP_DAY).contains(&days) {
+                            bail!(Error::DatetimeValueOutOfRange(
+                                "timestamp",
+                                value.to_string()
+                            ));
+                        }
+
+                        let local = days * USECS_PER_DAY + fields.time.unwrap_or(0);
+                        (local, fields.offset.filter(|_| $with_tz))
+                    }
+                };
+
+                let offset = offset.unwrap_or_else(|| zone.offset_at_local(local));
+                let timestamp = local - offset as i64 * USECS_PER_SEC;
+                if !timestamp_in_range(timestamp) {
+                    bail!(Error::DatetimeValueOutOfRange(
+                        "timestamp",
+                        value.to_string()
+                    ));
+                }
+                Ok(bincode::serialize(&timestamp)?)
+            }
+
+            fn output(&self, datum: &[u8]) -> Result<String> {
+                let timestamp = deserialize::<i64>(datum)?;
+                Ok(format_timestamp(
+                    timestamp,
+                    ($with_tz).then(|| self.zone().offset_at_utc(timestamp)),
+                ))
+            }
+
+            fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+                let timestamp = i64::from_be_bytes(recv_be(buf, $format_type)?);
+                if !timestamp_in_range(timestamp) {
+                    bail!(Error::DatetimeOutOfRange("timestamp"));
+                }
+                Ok(bincode::serialize(&timestamp)?)
+            }
+
+            fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+                Ok(deserialize::<i64>(datum)?.to_be_bytes().to_vec())
+            }
+
+            fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+                Ok(deserialize::<i64>(a)?.cmp(&deserialize::<i64>(b)?))
+            }
+
+            fn arith_result_type(&self, op: ArithOp, right: Oid) -> Option<Oid> {
+                match (op, right) {
+                    (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => Some($oid),
+                    (ArithOp::Sub, $oid) => Some(pg_type::INTERVAL_OID),
+                    _ => None,
+                }
+            }
+
+            fn arith(&self, op: ArithOp, a: &[u8], right: Oid, b: &[u8]) -> Result<Datum> {
+                let timestamp = deserialize::<i64>(a)?;
+
+                match (op, right) {
+                    (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID) => {
+                        let interval = IntervalValue::from_datum(b)?;
+                        let interval = if op == ArithOp::Add {
+                            interval
+                        } else {
+                            interval
+                                .checked_neg()
+                                .ok_or(Error::DatetimeOutOfRange("interval"))?
+                        };
+                        let result = timestamp_pl_interval(timestamp, &interval, &self.zone())?;
+                        Ok(bincode::serialize(&result)?)
+                    }
+                    (ArithOp::Sub, $oid) => {
+                        timestamp_mi(timestamp, deserialize::<i64>(b)?)?.to_datum()
+                    }
+                    _ => Err(undefined_operator(self.format_type(), op, right)),
+                }
+            }
+        }
+    };
+}
+
+/// Date and time of day, without time zone.
+pub struct Timestamp;
+
+timestamp_type!(
+    Timestamp,
+    pg_type::TIMESTAMP_OID,
+    "timestamp",
+    "timestamp without time zone",
+    false
+);
+
+/// Absolute point in time, read and displayed on the time zone of the session.
+pub struct TimestampTz;
+
+timestamp_type!(
+    TimestampTz,
+    pg_type::TIMESTAMPTZ_OID,
+    "timestamptz",
+    "timestamp with time zone",
+    true
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{int::Int4, interval::Interval, lookup_type};
+
+    fn arith(typ: &dyn Type, op: ArithOp, a: &str, right: &dyn Type, b: &str) -> Result<String> {
+        let result_type = lookup_type(typ.arith_result_type(op, right.oid()).unwrap())?;
+        let result = typ.arith(op, &typ.input(a, -1)?, right.oid(), &right.input(b, -1)?)?;
+        result_type.output(&result)
+    }
+
+    #[test]
+    fn test_civil_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 1, 1), POSTGRES_EPOCH_DAYS);
+        for days in [-800_000, -1, 0, 59, 60, 11_016, 2_000_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let cases: &[(&dyn Type, &str, &str)] = &[
+            (&Date, "2024-02-29", "2024-02-29"),
+            (&Date, "12/25/2023", "2023-12-25"),
+            (&Date, "0044-03-15 BC", "0044-03-15 BC"),
+            (&Date, "epoch", "1970-01-01"),
+            (&Date, "-infinity", "-infinity"),
+            (&Time, "13:45:00.5", "13:45:00.5"),
+            (&Time, "2024-01-01 08:30", "08:30:00"),
+            (&Time, "allballs", "00:00:00"),
+            (
+                &Timestamp,
+                "2024-01-31T10:00:00.000001",
+                "2024-01-31 10:00:00.000001",
+            ),
+            (&Timestamp, "2024-01-31 10:00:00+05", "2024-01-31 10:00:00"),
+            (&Timestamp, "infinity", "infinity"),
+            (
+                &TimestampTz,
+                "2024-01-31 10:00:00-03",
+                "2024-01-31 13:00:00+00",
+            ),
+            (
+                &TimestampTz,
+                "2024-01-31 10:00:00+05:30",
+                "2024-01-31 04:30:00+00",
+            ),
+            (&TimestampTz, "2024-01-31 10:00Z", "2024-01-31 10:00:00+00"),
+        ];
+        for (typ, input, output) in cases {
+            let datum = typ.input(input, -1)?;
+            assert_eq!(typ.output(&datum)?, *output, "{}", input);
+            assert_eq!(typ.recv(&typ.send(&datum)?, -1)?, datum);
+        }
+
+        assert_eq!(Date.send(&Date.input("2000-01-02", -1)?)?, vec![0, 0, 0, 1]);
+        assert!(Date.input("2023-02-29", -1).is_err());
+        assert!(Date.input("5874898-01-01", -1).is_err());
+        assert!(Date.input("0000-01-01", -1).is_err());
+        assert!(Time.input("24:00:01", -1).is_err());
+        assert!(Time.input("2024-01-01", -1).is_err());
+        assert!(Timestamp.input("10:00", -1).is_err());
+        assert!(Timestamp.input("294277-01-01", -1).is_err());
+        assert!(TimestampTz.input("2024-01-01 10:00 +16", -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_timezone() -> Result<()> {
+        assert_eq!(TimeZone::parse("utc")?.offset_at_utc(0), 0);
+        assert_eq!(TimeZone::parse("-3")?.offset_at_utc(0), -3 * 3600);
+        assert_eq!(TimeZone::parse("5.5")?.offset_at_utc(0), 5 * 3600 + 1800);
+        assert_eq!(TimeZone::parse("EST5")?.offset_at_utc(0), -5 * 3600);
+        assert_eq!(
+            TimeZone::parse("UTC-05:30")?.offset_at_utc(0),
+            5 * 3600 + 1800
+        );
+        assert!(TimeZone::parse("Mars/Olympus_Mons").is_err());
+        assert!(TimeZone::parse("XY").is_err());
+        assert!(TimeZone::parse("20").is_err());
+
+        // 2024-01-15 and 2024-07-15 at noon UTC.
+        let winter = (days_from_civil(2024, 1, 15) - POSTGRES_EPOCH_DAYS) * USECS_PER_DAY;
+        let summer = (days_from_civil(2024, 7, 15) - POSTGRES_EPOCH_DAYS) * USECS_PER_DAY;
+        let new_york = TimeZone::parse("america/new_york")?;
+        assert_eq!(new_york.name(), "america/new_york");
+        assert_eq!(new_york.offset_at_utc(winter), -5 * 3600);
+        assert_eq!(new_york.offset_at_utc(summer), -4 * 3600);
+        assert_eq!(TimeZone::parse("EST")?.offset_at_utc(summer), -5 * 3600);
+
+        // 2024-03-10 02:30 is skipped and 2024-11-03 01:30 is repeated in New York.
+        let skipped = (days_from_civil(2024, 3, 10) - POSTGRES_EPOCH_DAYS) * USECS_PER_DAY
+            + 150 * USECS_PER_MINUTE;
+        let repeated = (days_from_civil(2024, 11, 3) - POSTGRES_EPOCH_DAYS) * USECS_PER_DAY
+            + 90 * USECS_PER_MINUTE;
+        assert_eq!(new_york.offset_at_local(skipped), -5 * 3600);
+        assert_eq!(new_york.offset_at_local(repeated), -5 * 3600);
+
+        assert_eq!(format_offset(0), "+00");
+        assert_eq!(format_offset(-3 * 3600), "-03");
+        assert_eq!(format_offset(5 * 3600 + 1800), "+05:30");
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith() -> Result<()> {
+        let interval = &Interval;
+        assert_eq!(
+            arith(&Date, ArithOp::Add, "2024-02-28", &Int4, "2")?,
+            "2024-03-01"
+        );
+        assert_eq!(
+            arith(&Date, ArithOp::Sub, "2024-03-01", &Date, "2023-03-01")?,
+            "366"
+        );
+        assert_eq!(
+            arith(&Date, ArithOp::Add, "2024-01-31", interval, "1 mon")?,
+            "2024-02-29 00:00:00"
+        );
+        assert_eq!(
+            arith(&Date, ArithOp::Add, "2024-01-31", &Time, "10:30")?,
+            "2024-01-31 10:30:00"
+        );
+        assert_eq!(
+            arith(&Time, ArithOp::Add, "23:00", interval, "2 hours")?,
+            "01:00:00"
+        );
+        assert_eq!(
+            arith(&Time, ArithOp::Sub, "10:00", &Time, "12:30")?,
+            "-02:30:00"
+        );
+        assert_eq!(
+            arith(
+                &Timestamp,
+                ArithOp::Sub,
+                "2024-03-01",
+                interval,
+                "1 year 1 day"
+            )?,
+            "2023-02-28 00:00:00"
+        );
+        assert_eq!(
+            arith(
+                &Timestamp,
+                ArithOp::Sub,
+                "2024-03-01",
+                &Timestamp,
+                "2024-01-01 12:00"
+            )?,
+            "59 days 12:00:00"
+        );
+        assert_eq!(
+            arith(
+                &TimestampTz,
+                ArithOp::Add,
+                "2024-01-31 23:00-03",
+                interval,
+                "1 mon"
+            )?,
+            "2024-03-01 02:00:00+00"
+        );
+        assert!(arith(&Date, ArithOp::Sub, "infinity", &Date, "2024-01-01").is_err());
+        assert!(arith(&Date, ArithOp::Add, "5874897-12-31", &Int4, "1").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign, TypeCategory, TypeKind};
+use crate::{Datum, Oid};
+
+/// Enum data type created with CREATE TYPE name AS ENUM (labels).
+///
+/// A value is stored as the oid of the pg_enum row of its label, and values are ordered by the
+/// position of their labels on the type declaration.
+pub struct EnumType {
+    oid: Oid,
+    typname: &'static str,
+
+    /// Oid and name of each label, in declaration order.
+    labels: Vec<(Oid, String)>,
+}
+
+impl EnumType {
+    pub fn new(oid: Oid, typname: &str, labels: Vec<(Oid, String)>) -> Self {
+        Self {
+            oid,
+            typname: Box::leak(typname.to_string().into_boxed_str()),
+            labels,
+        }
+    }
+
+    /// Return the position of the label with the given oid on the type declaration.
+    fn position(&self, datum: &[u8]) -> Result<usize> {
+        let label = deserialize::<Oid>(datum)?;
+        match self.labels.iter().position(|(oid, _)| *oid == label) {
+            Some(position) => Ok(position),
+            None => bail!(Error::InvalidBinaryRepresentation(self.typname)),
+        }
+    }
+}
+
+impl Type for EnumType {
+    fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    fn typname(&self) -> &'static str {
+        self.typname
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Enum
+    }
+
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Enum
+    }
+
+    fn typlen(&self) -> i32 {
+        8
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        match self.labels.iter().find(|(_, label)| label == value) {
+            Some((oid, _)) => Ok(bincode::serialize(oid)?),
+            None => bail!(Error::InvalidEnumValue(self.typname, value.to_string())),
+        }
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(self.labels[self.position(datum)?].1.clone())
+    }
+
+    /// The binary representation of an enum value is the text of its label.
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match std::str::from_utf8(buf) {
+            Ok(value) => self.input(value, typmod),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation(self.typname)),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(self.output(datum)?.into_bytes())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(self.position(a)?.cmp(&self.position(b)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_declaration_order() -> Result<()> {
+        let mood = EnumType::new(
+            1,
+            "mood",
+            vec![(12, "sad".to_string()), (11, "ok".to_string())],
+        );
+        let sad = mood.input("sad", -1)?;
+        let ok = mood.input("ok", -1)?;
+        assert_eq!(mood.compare(&sad, &ok)?, Ordering::Less);
+        assert_eq!(mood.output(&ok)?, "ok");
+        assert_eq!(mood.recv(&mood.send(&sad)?, -1)?, sad);
+        assert!(mood.input("happy", -1).is_err());
+        assert!(mood.input("Sad", -1).is_err());
+        Ok(())
+    }
+}
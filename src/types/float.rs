@@ -0,0 +1,206 @@
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{Display, LowerExp},
+    mem::size_of,
+};
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, ArithOp, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Implement the Type trait for a floating-point type stored as the given native type.
+///
+/// The digits argument is the number of decimal digits of precision of the type, values with a
+/// greater decimal exponent are formatted using the exponential notation.
+macro_rules! float_type {
+    ($name:ident, $native:ty, $oid:expr, $typname:expr, $format_type:expr, $digits:expr) => {
+        impl Type for $name {
+            fn oid(&self) -> Oid {
+                $oid
+            }
+
+            fn typname(&self) -> &'static str {
+                $typname
+            }
+
+            fn format_type(&self) -> &'static str {
+                $format_type
+            }
+
+            fn typcategory(&self) -> TypeCategory {
+                TypeCategory::Numeric
+            }
+
+            fn typispreferred(&self) -> bool {
+                $oid == pg_type::FLOAT8_OID
+            }
+
+            fn typlen(&self) -> i32 {
+                size_of::<$native>() as i32
+            }
+
+            fn typalign(&self) -> TypeAlign {
+                if size_of::<$native>() == 8 {
+                    TypeAlign::Double
+                } else {
+                    TypeAlign::Int
+                }
+            }
+
+            fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+                let datum = match value.trim().parse::<$native>() {
+                    Ok(datum) if datum.is_infinite() && !is_infinity_literal(value) => {
+                        bail!(Error::ValueOutOfRange($format_type, value.to_string()))
+                    }
+                    Ok(datum) => datum,
+                    Err(_) => bail!(Error::InvalidTextRepresentation(
+                        $format_type,
+                        value.to_string()
+                    )),
+                };
+                Ok(bincode::serialize(&datum)?)
+            }
+
+            fn output(&self, datum: &[u8]) -> Result<String> {
+                Ok(format_float(deserialize::<$native>(datum)?, $digits))
+            }
+
+            fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+                match <[u8; size_of::<$native>()]>::try_from(buf) {
+                    Ok(buf) => Ok(bincode::serialize(&<$native>::from_be_bytes(buf))?),
+                    Err(_) => bail!(Error::InvalidBinaryRepresentation($format_type)),
+                }
+            }
+
+            fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+                Ok(deserialize::<$native>(datum)?.to_be_bytes().to_vec())
+            }
+
+            fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+                let a = deserialize::<$native>(a)?;
+                let b = deserialize::<$native>(b)?;
+
+                // NaN values are equal to each other and greater than any non-NaN value.
+                Ok(match (a.is_nan(), b.is_nan()) {
+                    (true, true) => Ordering::Equal,
+                    (true, false) => Ordering::Greater,
+                    (false, true) => Ordering::Less,
+                    (false, false) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+                })
+            }
+
+            fn arith_result_type(&self, op: ArithOp, right: Oid) -> Option<Oid> {
+                (op != ArithOp::Mod && right == $oid).then_some($oid)
+            }
+
+            fn arith(&self, op: ArithOp, a: &[u8], _right: Oid, b: &[u8]) -> Result<Datum> {
+                let a = deserialize::<$native>(a)?;
+                let b = deserialize::<$native>(b)?;
+
+                let result = match op {
+                    ArithOp::Add => a + b,
+                    ArithOp::Sub => a - b,
+                    ArithOp::Mul => a * b,
+                    ArithOp::Div if b == 0.0 => bail!(Error::DivisionByZero),
+                    ArithOp::Div => a / b,
+                    ArithOp::Mod => bail!(Error::UndefinedOperator(format!(
+                        "{} % {}",
+                        $format_type, $format_type
+                    ))),
+                };
+
+                if result.is_infinite() && a.is_finite() && b.is_finite() {
+                    bail!(Error::OutOfRange($format_type));
+                }
+
+                Ok(bincode::serialize(&result)?)
+            }
+
+            fn negate(&self, datum: &[u8]) -> Result<Datum> {
+                Ok(bincode::serialize(&-deserialize::<$native>(datum)?)?)
+            }
+        }
+    };
+}
+
+/// Single precision floating-point data type.
+pub struct Float4;
+
+float_type!(Float4, f32, pg_type::FLOAT4_OID, "float4", "real", 6);
+
+/// Double precision floating-point data type.
+pub struct Float8;
+
+float_type!(
+    Float8,
+    f64,
+    pg_type::FLOAT8_OID,
+    "float8",
+    "double precision",
+    15
+);
+
+/// Return true if the given value is one of the accepted spellings of infinity.
+fn is_infinity_literal(value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    let value = value.trim_start_matches(['+', '-']);
+    value == "inf" || value == "infinity"
+}
+
+/// Format a float value using the shortest representation that reads back to the same value.
+///
+/// Like PostgreSQL, the exponential notation is used when the decimal exponent is less than -4
+/// or greater than or equal to the given number of digits.
+fn format_float<T>(value: T, digits: i32) -> String
+where
+    T: Display + LowerExp + Into<f64> + Copy,
+{
+    let float: f64 = value.into();
+    if float.is_nan() {
+        return String::from("NaN");
+    }
+    if float.is_infinite() {
+        return String::from(if float > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+
+    let exponential = format!("{:e}", value);
+    let (mantissa, exponent) = match exponential.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().unwrap_or_default()),
+        None => return value.to_string(),
+    };
+
+    if float != 0.0 && (exponent < -4 || exponent >= digits) {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(format_float(1.5f64, 15), "1.5");
+        assert_eq!(format_float(0.0001f64, 15), "0.0001");
+        assert_eq!(format_float(0.00001f64, 15), "1e-05");
+        assert_eq!(format_float(1e15f64, 15), "1e+15");
+        assert_eq!(format_float(123456789012345f64, 15), "123456789012345");
+        assert_eq!(format_float(1e300f64, 15), "1e+300");
+        assert_eq!(format_float(1234567f32, 6), "1.234567e+06");
+        assert_eq!(format_float(0.1f32, 6), "0.1");
+        assert_eq!(format_float(f64::NEG_INFINITY, 15), "-Infinity");
+    }
+
+    #[test]
+    fn test_input_out_of_range() {
+        assert!(Float8.input("1e400", -1).is_err());
+        assert!(Float4.input("1e39", -1).is_err());
+        assert!(Float8.input("-Infinity", -1).is_ok());
+        assert!(Float8.input("abc", -1).is_err());
+    }
+}
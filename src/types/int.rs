@@ -0,0 +1,139 @@
+use std::{cmp::Ordering, convert::TryFrom, mem::size_of, num::IntErrorKind};
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, ArithOp, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Implement the Type trait for a signed integer type stored as the given native type.
+macro_rules! int_type {
+    ($name:ident, $native:ty, $oid:expr, $typname:expr, $format_type:expr, $align:expr) => {
+        impl Type for $name {
+            fn oid(&self) -> Oid {
+                $oid
+            }
+
+            fn typname(&self) -> &'static str {
+                $typname
+            }
+
+            fn format_type(&self) -> &'static str {
+                $format_type
+            }
+
+            fn typcategory(&self) -> TypeCategory {
+                TypeCategory::Numeric
+            }
+
+            fn typlen(&self) -> i32 {
+                size_of::<$native>() as i32
+            }
+
+            fn typalign(&self) -> TypeAlign {
+                $align
+            }
+
+            fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+                let datum = value
+                    .trim()
+                    .parse::<$native>()
+                    .map_err(|err| match err.kind() {
+                        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                            Error::ValueOutOfRange($format_type, value.to_string())
+                        }
+                        _ => Error::InvalidTextRepresentation($format_type, value.to_string()),
+                    })?;
+                Ok(bincode::serialize(&datum)?)
+            }
+
+            fn output(&self, datum: &[u8]) -> Result<String> {
+                Ok(deserialize::<$native>(datum)?.to_string())
+            }
+
+            fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+                match <[u8; size_of::<$native>()]>::try_from(buf) {
+                    Ok(buf) => Ok(bincode::serialize(&<$native>::from_be_bytes(buf))?),
+                    Err(_) => bail!(Error::InvalidBinaryRepresentation($format_type)),
+                }
+            }
+
+            fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+                Ok(deserialize::<$native>(datum)?.to_be_bytes().to_vec())
+            }
+
+            fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+                Ok(deserialize::<$native>(a)?.cmp(&deserialize::<$native>(b)?))
+            }
+
+            fn arith_result_type(&self, _op: ArithOp, right: Oid) -> Option<Oid> {
+                (right == $oid).then_some($oid)
+            }
+
+            fn arith(&self, op: ArithOp, a: &[u8], _right: Oid, b: &[u8]) -> Result<Datum> {
+                let a = deserialize::<$native>(a)?;
+                let b = deserialize::<$native>(b)?;
+
+                if b == 0 && (op == ArithOp::Div || op == ArithOp::Mod) {
+                    bail!(Error::DivisionByZero);
+                }
+
+                let result = match op {
+                    ArithOp::Add => a.checked_add(b),
+                    ArithOp::Sub => a.checked_sub(b),
+                    ArithOp::Mul => a.checked_mul(b),
+                    ArithOp::Div => a.checked_div(b),
+                    // The only overflow case is MIN % -1, which is 0.
+                    ArithOp::Mod => Some(a.checked_rem(b).unwrap_or(0)),
+                };
+
+                match result {
+                    Some(result) => Ok(bincode::serialize(&result)?),
+                    None => bail!(Error::OutOfRange($format_type)),
+                }
+            }
+
+            fn negate(&self, datum: &[u8]) -> Result<Datum> {
+                match deserialize::<$native>(datum)?.checked_neg() {
+                    Some(result) => Ok(bincode::serialize(&result)?),
+                    None => bail!(Error::OutOfRange($format_type)),
+                }
+            }
+        }
+    };
+}
+
+/// 2-byte signed integer data type.
+pub struct Int2;
+
+int_type!(
+    Int2,
+    i16,
+    pg_type::INT2_OID,
+    "int2",
+    "smallint",
+    TypeAlign::Short
+);
+
+/// 4-byte signed integer data type.
+pub struct Int4;
+
+int_type!(
+    Int4,
+    i32,
+    pg_type::INT_OID,
+    "int4",
+    "integer",
+    TypeAlign::Int
+);
+
+/// 8-byte signed integer data type.
+pub struct Int8;
+
+int_type!(
+    Int8,
+    i64,
+    pg_type::INT8_OID,
+    "int8",
+    "bigint",
+    TypeAlign::Double
+);
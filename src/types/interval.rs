@@ -0,0 +1,469 @@
+//! Time interval data type.
+use std::{
+    cmp::Ordering,
+    convert::{TryFrom, TryInto},
+    fmt,
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    datetime::{
+        format_clock, parse_clock, USECS_PER_DAY, USECS_PER_HOUR, USECS_PER_MINUTE, USECS_PER_SEC,
+    },
+    deserialize, undefined_operator, ArithOp, Error, Type, TypeAlign, TypeCategory,
+};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Number of days of a month used when converting between months and days, e.g to compare
+/// intervals.
+const DAYS_PER_MONTH: i64 = 30;
+
+/// A time interval, stored as separate months, days and microseconds because the number of days
+/// of a month and the number of hours of a day vary.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct IntervalValue {
+    pub time: i64,
+    pub day: i32,
+    pub month: i32,
+}
+
+impl IntervalValue {
+    pub fn from_datum(datum: &[u8]) -> Result<Self> {
+        deserialize::<Self>(datum)
+    }
+
+    pub fn to_datum(self) -> Result<Datum> {
+        Ok(bincode::serialize(&self)?)
+    }
+
+    /// Parse an interval written as a list of quantities followed by their units, e.g
+    /// 1 year 2 mons 3 days, where the time can also be written as hh:mm:ss. A trailing ago
+    /// negates the interval, and a quantity without unit is a number of seconds.
+    pub fn parse(value: &str) -> Result<Self> {
+        let invalid = || Error::InvalidTextRepresentation("interval", value.to_string());
+
+        let lower = value.trim().to_ascii_lowercase();
+        let lower = lower.strip_prefix('@').unwrap_or(&lower);
+
+        // Split the words on the boundaries between letters and numbers, so 1day is read as
+        // 1 day.
+        let mut tokens = Vec::new();
+        for word in lower.split_whitespace() {
+            let mut start = 0;
+            let bytes = word.as_bytes();
+            for i in 1..=bytes.len() {
+                if i == bytes.len()
+                    || bytes[i].is_ascii_alphabetic() != bytes[i - 1].is_ascii_alphabetic()
+                {
+                    tokens.push(&word[start..i]);
+                    start = i;
+                }
+            }
+        }
+
+        let mut span = Span::default();
+        let mut ago = false;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            if ago {
+                bail!(invalid());
+            }
+
+            if token == "ago" {
+                ago = true;
+            } else if token.contains(':') {
+                let (negative, clock) = match token.as_bytes()[0] {
+                    b'-' => (true, &token[1..]),
+                    b'+' => (false, &token[1..]),
+                    _ => (false, token),
+                };
+                let (hour, minute, second, micros) = parse_clock(clock).ok_or_else(invalid)?;
+                if minute > 59 || second > 59 {
+                    bail!(Error::DatetimeFieldOverflow(value.to_string()));
+                }
+                let time = (hour as f64) * USECS_PER_HOUR as f64
+                    + (minute * USECS_PER_MINUTE + second * USECS_PER_SEC + micros) as f64;
+                span.add_time(if negative { -time } else { time });
+            } else {
+                let quantity: f64 = token.parse().map_err(|_| invalid())?;
+                let unit = match tokens.peek() {
+                    Some(unit) if unit.as_bytes()[0].is_ascii_alphabetic() && *unit != "ago" => {
+                        tokens.next().unwrap()
+                    }
+                    _ => "second",
+                };
+                span.add(quantity, unit).ok_or_else(invalid)?;
+            }
+        }
+
+        if span == Span::default() && !lower.bytes().any(|b| b.is_ascii_digit()) {
+            bail!(invalid());
+        }
+        if ago {
+            span = Span {
+                month: -span.month,
+                day: -span.day,
+                time: -span.time,
+            };
+        }
+
+        let out_of_range = || Error::DatetimeValueOutOfRange("interval", value.to_string());
+        Ok(Self {
+            time: i64::try_from(span.time.round() as i128).map_err(|_| out_of_range())?,
+            day: i32::try_from(span.day as i64).map_err(|_| out_of_range())?,
+            month: i32::try_from(span.month as i64).map_err(|_| out_of_range())?,
+        })
+    }
+
+    /// Return the total length of the interval in microseconds, considering that months have
+    /// 30 days.
+    fn span(&self) -> i128 {
+        self.time as i128
+            + (self.day as i128 + self.month as i128 * DAYS_PER_MONTH as i128)
+                * USECS_PER_DAY as i128
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(Self {
+            time: self.time.checked_add(other.time)?,
+            day: self.day.checked_add(other.day)?,
+            month: self.month.checked_add(other.month)?,
+        })
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            time: self.time.checked_neg()?,
+            day: self.day.checked_neg()?,
+            month: self.month.checked_neg()?,
+        })
+    }
+
+    /// Apply the given function on each field of the interval, cascading the fractional
+    /// months down to days and the fractional days down to time, e.g 1 mon / 2 is 15 days.
+    fn scale(&self, f: impl Fn(f64) -> f64) -> Result<Self> {
+        let out_of_range = || Error::DatetimeOutOfRange("interval");
+        let fits_i32 = |value: f64| value >= i32::MIN as f64 && value <= i32::MAX as f64;
+
+        let month = f(self.month as f64);
+        let day = f(self.day as f64);
+        if !fits_i32(month) || !fits_i32(day) {
+            bail!(out_of_range());
+        }
+
+        let secs_per_day = (USECS_PER_DAY / USECS_PER_SEC) as f64;
+        let month_remainder_days = month.fract() * DAYS_PER_MONTH as f64;
+        let mut sec_remainder =
+            tsround((day.fract() + month_remainder_days.fract()) * secs_per_day);
+        let mut day = day.trunc() as i64 + month_remainder_days.trunc() as i64;
+        if sec_remainder.abs() >= secs_per_day {
+            day += (sec_remainder / secs_per_day).trunc() as i64;
+            sec_remainder %= secs_per_day;
+        }
+
+        let time = (f(self.time as f64) + sec_remainder * USECS_PER_SEC as f64).round_ties_even();
+        if !(time >= i64::MIN as f64 && time < i64::MAX as f64) {
+            bail!(out_of_range());
+        }
+
+        Ok(Self {
+            time: time as i64,
+            day: i32::try_from(day).map_err(|_| out_of_range())?,
+            month: month.trunc() as i32,
+        })
+    }
+}
+
+/// Round a number of seconds to microseconds.
+fn tsround(seconds: f64) -> f64 {
+    (seconds * USECS_PER_SEC as f64).round_ties_even() / USECS_PER_SEC as f64
+}
+
+/// Fields of an interval being parsed, which may have fractional values until all the
+/// quantities are added.
+#[derive(Debug, Default, PartialEq)]
+struct Span {
+    month: f64,
+    day: f64,
+    time: f64,
+}
+
+impl Span {
+    /// Add a quantity of the given unit, returning None if the unit is unknown.
+    fn add(&mut self, quantity: f64, unit: &str) -> Option<()> {
+        match unit {
+            "microsecond" | "microseconds" | "usec" | "usecs" | "us" => self.add_time(quantity),
+            "millisecond" | "milliseconds" | "msec" | "msecs" | "ms" => {
+                self.add_time(quantity * 1000.0)
+            }
+            "second" | "seconds" | "sec" | "secs" | "s" => {
+                self.add_time(quantity * USECS_PER_SEC as f64)
+            }
+            "minute" | "minutes" | "min" | "mins" | "m" => {
+                self.add_time(quantity * USECS_PER_MINUTE as f64)
+            }
+            "hour" | "hours" | "hr" | "hrs" | "h" => {
+                self.add_time(quantity * USECS_PER_HOUR as f64)
+            }
+            "day" | "days" | "d" => self.add_days(quantity),
+            "week" | "weeks" | "w" => self.add_days(quantity * 7.0),
+            "month" | "months" | "mon" | "mons" => self.add_months(quantity),
+            "year" | "years" | "yr" | "yrs" | "y" => self.add_months(quantity * 12.0),
+            "decade" | "decades" => self.add_months(quantity * 120.0),
+            "century" | "centuries" => self.add_months(quantity * 1200.0),
+            "millennium" | "millennia" | "millenniums" => self.add_months(quantity * 12000.0),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn add_months(&mut self, months: f64) {
+        self.month += months.trunc();
+        self.add_days(months.fract() * DAYS_PER_MONTH as f64);
+    }
+
+    fn add_days(&mut self, days: f64) {
+        self.day += days.trunc();
+        self.add_time(days.fract() * USECS_PER_DAY as f64);
+    }
+
+    fn add_time(&mut self, micros: f64) {
+        self.time += micros.round();
+    }
+}
+
+impl fmt::Display for IntervalValue {
+    /// Format the interval like the postgres interval style, e.g 1 year 2 mons -3 days
+    /// +04:05:06.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut is_zero = true;
+        let mut is_before = false;
+
+        for (value, unit) in [
+            (self.month / 12, "year"),
+            (self.month % 12, "mon"),
+            (self.day, "day"),
+        ] {
+            if value == 0 {
+                continue;
+            }
+            write!(
+                f,
+                "{}{}{} {}{}",
+                if is_zero { "" } else { " " },
+                if is_before && value > 0 { "+" } else { "" },
+                value,
+                unit,
+                if value != 1 { "s" } else { "" }
+            )?;
+            is_before = value < 0;
+            is_zero = false;
+        }
+
+        if is_zero || self.time != 0 {
+            write!(
+                f,
+                "{}{}{}",
+                if is_zero { "" } else { " " },
+                if self.time < 0 {
+                    "-"
+                } else if is_before {
+                    "+"
+                } else {
+                    ""
+                },
+                format_clock(self.time.unsigned_abs())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Time span data type.
+pub struct Interval;
+
+impl Type for Interval {
+    fn oid(&self) -> Oid {
+        pg_type::INTERVAL_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "interval"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Timespan
+    }
+
+    fn typispreferred(&self) -> bool {
+        true
+    }
+
+    fn typlen(&self) -> i32 {
+        16
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        IntervalValue::parse(value)?.to_datum()
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(IntervalValue::from_datum(datum)?.to_string())
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        if buf.len() != 16 {
+            bail!(Error::InvalidBinaryRepresentation("interval"));
+        }
+        IntervalValue {
+            time: i64::from_be_bytes(buf[..8].try_into()?),
+            day: i32::from_be_bytes(buf[8..12].try_into()?),
+            month: i32::from_be_bytes(buf[12..].try_into()?),
+        }
+        .to_datum()
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let interval = IntervalValue::from_datum(datum)?;
+        let mut buf = Vec::with_capacity(16);
+        buf.extend_from_slice(&interval.time.to_be_bytes());
+        buf.extend_from_slice(&interval.day.to_be_bytes());
+        buf.extend_from_slice(&interval.month.to_be_bytes());
+        Ok(buf)
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(IntervalValue::from_datum(a)?
+            .span()
+            .cmp(&IntervalValue::from_datum(b)?.span()))
+    }
+
+    fn arith_result_type(&self, op: ArithOp, right: Oid) -> Option<Oid> {
+        match (op, right) {
+            (ArithOp::Add | ArithOp::Sub, pg_type::INTERVAL_OID)
+            | (ArithOp::Mul | ArithOp::Div, pg_type::FLOAT8_OID) => Some(pg_type::INTERVAL_OID),
+            _ => None,
+        }
+    }
+
+    fn arith(&self, op: ArithOp, a: &[u8], right: Oid, b: &[u8]) -> Result<Datum> {
+        let interval = IntervalValue::from_datum(a)?;
+        let out_of_range = || Error::DatetimeOutOfRange("interval");
+
+        let result = match (op, right) {
+            (ArithOp::Add, pg_type::INTERVAL_OID) => interval
+                .checked_add(&IntervalValue::from_datum(b)?)
+                .ok_or_else(out_of_range)?,
+            (ArithOp::Sub, pg_type::INTERVAL_OID) => IntervalValue::from_datum(b)?
+                .checked_neg()
+                .and_then(|other| interval.checked_add(&other))
+                .ok_or_else(out_of_range)?,
+            (ArithOp::Mul, pg_type::FLOAT8_OID) => {
+                let factor = deserialize::<f64>(b)?;
+                if factor.is_nan() {
+                    bail!(out_of_range());
+                }
+                interval.scale(|value| value * factor)?
+            }
+            (ArithOp::Div, pg_type::FLOAT8_OID) => {
+                let factor = deserialize::<f64>(b)?;
+                if factor == 0.0 {
+                    bail!(Error::DivisionByZero);
+                }
+                if factor.is_nan() {
+                    bail!(out_of_range());
+                }
+                interval.scale(|value| value / factor)?
+            }
+            _ => return Err(undefined_operator(self.format_type(), op, right)),
+        };
+        result.to_datum()
+    }
+
+    fn negate(&self, datum: &[u8]) -> Result<Datum> {
+        IntervalValue::from_datum(datum)?
+            .checked_neg()
+            .ok_or(Error::DatetimeOutOfRange("interval"))?
+            .to_datum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arith(op: ArithOp, a: &str, right: Oid, b: &str) -> Result<String> {
+        let b = crate::types::lookup_type(right)?.input(b, -1)?;
+        Interval.output(&Interval.arith(op, &Interval.input(a, -1)?, right, &b)?)
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let cases = [
+            (
+                "1 year 2 months 3 days 04:05:06.7",
+                "1 year 2 mons 3 days 04:05:06.7",
+            ),
+            ("-1 year +2 mons", "-10 mons"),
+            ("1 day -2 hours", "1 day -02:00:00"),
+            ("-1 day +2 hours", "-1 days +02:00:00"),
+            ("1 day 2 hours ago", "-1 days -02:00:00"),
+            ("1.5 years", "1 year 6 mons"),
+            ("1.5 months", "1 mon 15 days"),
+            ("@ 3 mins", "00:03:00"),
+            ("1day 100:00", "1 day 100:00:00"),
+            ("2 weeks", "14 days"),
+            ("90", "00:01:30"),
+            ("0", "00:00:00"),
+        ];
+        for (input, output) in cases {
+            let datum = Interval.input(input, -1)?;
+            assert_eq!(Interval.output(&datum)?, output, "{}", input);
+            assert_eq!(Interval.recv(&Interval.send(&datum)?, -1)?, datum);
+        }
+
+        assert!(Interval.input("", -1).is_err());
+        assert!(Interval.input("1 fortnight", -1).is_err());
+        assert!(Interval.input("ago 1 day", -1).is_err());
+        assert!(Interval.input("10:61", -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith() -> Result<()> {
+        let float8 = pg_type::FLOAT8_OID;
+        assert_eq!(
+            arith(ArithOp::Add, "1 day", pg_type::INTERVAL_OID, "-2 hours")?,
+            "1 day -02:00:00"
+        );
+        assert_eq!(
+            arith(ArithOp::Sub, "1 mon", pg_type::INTERVAL_OID, "1 day")?,
+            "1 mon -1 days"
+        );
+        assert_eq!(arith(ArithOp::Div, "1 mon", float8, "2")?, "15 days");
+        assert_eq!(
+            arith(ArithOp::Mul, "1 day", float8, "1.5")?,
+            "1 day 12:00:00"
+        );
+        assert_eq!(arith(ArithOp::Mul, "100 hours", float8, "0.5")?, "50:00:00");
+        assert!(arith(ArithOp::Div, "1 day", float8, "0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let compare = |a: &str, b: &str| -> Result<Ordering> {
+            Interval.compare(&Interval.input(a, -1)?, &Interval.input(b, -1)?)
+        };
+        assert_eq!(compare("1 day", "24 hours")?, Ordering::Equal);
+        assert_eq!(compare("1 mon", "29 days")?, Ordering::Greater);
+        assert_eq!(compare("-1 year", "1 second")?, Ordering::Less);
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, jsonb::JsonbValue, Error, Type, TypeAlign};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// JSON data type stored as text.
+///
+/// Values are validated on input but kept exactly as they were written, including whitespaces
+/// and duplicated object keys.
+pub struct Json;
+
+impl Type for Json {
+    fn oid(&self) -> Oid {
+        pg_type::JSON_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "json"
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        JsonbValue::parse(value, "json")?;
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        deserialize::<String>(datum)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match std::str::from_utf8(buf) {
+            Ok(value) => self.input(value, typmod),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation("json")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<String>(datum)?.into_bytes())
+    }
+
+    /// Values of json type can not be compared, jsonb should be used instead.
+    fn compare(&self, _a: &[u8], _b: &[u8]) -> Result<Ordering> {
+        bail!(Error::UndefinedComparison("json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let value = r#"{"b": 1,  "a": [1, 2], "b": 2}"#;
+        assert_eq!(Json.output(&Json.input(value, -1)?)?, value);
+        assert!(Json.input(r#"{"a": }"#, -1).is_err());
+        Ok(())
+    }
+}
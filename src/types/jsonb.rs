@@ -0,0 +1,745 @@
+use std::{cmp::Ordering, fmt};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    array::ArrayValue, deserialize, lookup_type, numeric::NumericVar, Error, Type, TypeAlign,
+    TypeCategory,
+};
+use crate::{catalog::pg_type, sql::encode::numeric::NumericData, Datum, Oid};
+
+/// Max nesting level of arrays and objects on a JSON document.
+const MAX_DEPTH: usize = 1000;
+
+/// Version of the binary wire format of jsonb values.
+const JSONB_VERSION: u8 = 1;
+
+/// JSON data type stored on a parsed binary form.
+///
+/// Insignificant whitespaces are discarded and object keys are stored sorted without duplicates,
+/// so values are displayed on a normalized form.
+pub struct Jsonb;
+
+impl Type for Jsonb {
+    fn oid(&self) -> Oid {
+        pg_type::JSONB_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "jsonb"
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        JsonbValue::parse(value, "jsonb")?.to_datum()
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(JsonbValue::from_datum(datum)?.to_string())
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match buf.split_first() {
+            Some((&JSONB_VERSION, text)) => match std::str::from_utf8(text) {
+                Ok(value) => self.input(value, typmod),
+                Err(_) => bail!(Error::InvalidBinaryRepresentation("jsonb")),
+            },
+            _ => bail!(Error::InvalidBinaryRepresentation("jsonb")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let mut buf = vec![JSONB_VERSION];
+        buf.extend_from_slice(self.output(datum)?.as_bytes());
+        Ok(buf)
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(JsonbValue::from_datum(a)?.cmp(&JsonbValue::from_datum(b)?))
+    }
+}
+
+/// A parsed JSON value, which is the internal representation of jsonb values.
+///
+/// Numbers are stored as numeric values and object keys are sorted by length and then by
+/// their bytes, like PostgreSQL does, so keys can be searched with a binary search.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum JsonbValue {
+    Null,
+    Bool(bool),
+    Number(NumericData),
+    String(String),
+    Array(Vec<JsonbValue>),
+    Object(Vec<(String, JsonbValue)>),
+}
+
+impl JsonbValue {
+    /// Parse a JSON document, the type name is used on the error message of invalid documents.
+    pub fn parse(value: &str, type_name: &'static str) -> Result<Self> {
+        let mut parser = JsonParser {
+            input: value.as_bytes(),
+            pos: 0,
+        };
+        match parser.parse_document() {
+            Some(json) => Ok(json),
+            None => bail!(Error::InvalidTextRepresentation(
+                type_name,
+                value.to_string()
+            )),
+        }
+    }
+
+    /// Decode a jsonb datum.
+    pub fn from_datum(datum: &[u8]) -> Result<Self> {
+        deserialize::<JsonbValue>(datum)
+    }
+
+    /// Encode the value as a jsonb datum.
+    pub fn to_datum(&self) -> Result<Datum> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Convert a SQL value of the given type to a JSON value.
+    ///
+    /// Booleans and numbers are converted to the JSON equivalents, json and jsonb values are
+    /// kept as they are and any other value is converted to a string with its text
+    /// representation.
+    pub fn from_sql(datum: Option<&[u8]>, typ: Oid) -> Result<Self> {
+        let datum = match datum {
+            Some(datum) => datum,
+            None => return Ok(JsonbValue::Null),
+        };
+
+        let typ = lookup_type(typ)?;
+        match typ.oid() {
+            pg_type::JSONB_OID => return Self::from_datum(datum),
+            pg_type::JSON_OID => return Self::parse(&typ.output(datum)?, "json"),
+            pg_type::BOOL_OID => return Ok(JsonbValue::Bool(deserialize(datum)?)),
+            _ => {}
+        }
+
+        if typ.typcategory() == TypeCategory::Array {
+            return Self::from_array(&ArrayValue::from_datum(datum)?);
+        }
+
+        let text = typ.output(datum)?;
+        if typ.typcategory() == TypeCategory::Numeric {
+            // NaN and infinity can not be represented as JSON numbers.
+            if let Ok(number) = NumericVar::parse(&text) {
+                if number.to_string() != "NaN" {
+                    return Ok(JsonbValue::Number(number.to_data()?));
+                }
+            }
+        }
+        Ok(JsonbValue::String(text))
+    }
+
+    /// Convert an array to a JSON array, with a nested JSON array for each inner dimension.
+    fn from_array(array: &ArrayValue) -> Result<Self> {
+        let mut items = array
+            .elements
+            .iter()
+            .map(|element| Self::from_sql(element.as_deref(), array.elemtype))
+            .collect::<Result<Vec<_>>>()?;
+
+        for dim in array.dims.iter().skip(1).rev() {
+            let mut rows = Vec::with_capacity(items.len() / dim.len as usize);
+            let mut items_iter = items.into_iter();
+            loop {
+                let row = items_iter
+                    .by_ref()
+                    .take(dim.len as usize)
+                    .collect::<Vec<_>>();
+                if row.is_empty() {
+                    break;
+                }
+                rows.push(JsonbValue::Array(row));
+            }
+            items = rows;
+        }
+        Ok(JsonbValue::Array(items))
+    }
+
+    /// Build an object from a list of alternating keys and values of the given types, which is
+    /// the jsonb_build_object function. Keys are converted to text and can not be NULL.
+    pub fn build_object(args: &[(Oid, Option<&[u8]>)]) -> Result<Self> {
+        if !args.len().is_multiple_of(2) {
+            bail!(Error::OddNumberOfArguments);
+        }
+
+        let mut pairs = Vec::with_capacity(args.len() / 2);
+        for (i, pair) in args.chunks(2).enumerate() {
+            let key = match pair[0] {
+                (typ, Some(datum)) => lookup_type(typ)?.output(datum)?,
+                (_, None) => bail!(Error::NullObjectKey(i * 2 + 1)),
+            };
+            let (typ, value) = pair[1];
+            pairs.push((key, Self::from_sql(value, typ)?));
+        }
+        Ok(Self::object(pairs))
+    }
+
+    /// Create an object from the given key and value pairs, keeping the last value of
+    /// duplicated keys.
+    pub fn object(mut pairs: Vec<(String, JsonbValue)>) -> Self {
+        pairs.sort_by(|(a, _), (b, _)| compare_keys(a, b));
+
+        let mut object: Vec<(String, JsonbValue)> = Vec::with_capacity(pairs.len());
+        for (key, value) in pairs {
+            match object.last_mut() {
+                Some(last) if last.0 == key => last.1 = value,
+                _ => object.push((key, value)),
+            }
+        }
+        JsonbValue::Object(object)
+    }
+
+    /// Return the value of the given key if the value is an object.
+    pub fn object_field(&self, key: &str) -> Option<&JsonbValue> {
+        match self {
+            JsonbValue::Object(pairs) => pairs
+                .binary_search_by(|(k, _)| compare_keys(k, key))
+                .ok()
+                .map(|i| &pairs[i].1),
+            _ => None,
+        }
+    }
+
+    /// Return the element of the given index if the value is an array, negative indexes are
+    /// counted from the end of the array.
+    pub fn array_element(&self, index: i32) -> Option<&JsonbValue> {
+        match self {
+            JsonbValue::Array(items) => {
+                let index = if index < 0 {
+                    items.len().checked_sub(index.unsigned_abs() as usize)?
+                } else {
+                    index as usize
+                };
+                items.get(index)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return the value as text, which is the value itself for strings and the JSON
+    /// representation for any other value, or None if the value is a JSON null.
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            JsonbValue::Null => None,
+            JsonbValue::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// Return true if the value contains the other value, which is the @> operator.
+    ///
+    /// An object contains another object if it has all its keys with values that contain the
+    /// values of the other object. An array contains another array if each element of the
+    /// other array is contained on some element of the array, and also contains a scalar value
+    /// that is one of its elements. Scalar values only contain equal values.
+    pub fn contains(&self, other: &JsonbValue) -> bool {
+        match (self, other) {
+            (JsonbValue::Array(_), JsonbValue::Array(_) | JsonbValue::Object(_)) => {
+                self.contains_nested(other)
+            }
+            (JsonbValue::Array(items), scalar) => items.iter().any(|item| item == scalar),
+            _ => self.contains_nested(other),
+        }
+    }
+
+    fn contains_nested(&self, other: &JsonbValue) -> bool {
+        match (self, other) {
+            (JsonbValue::Object(_), JsonbValue::Object(pairs)) => {
+                pairs.iter().all(|(key, value)| {
+                    self.object_field(key)
+                        .is_some_and(|item| item.contains_nested(value))
+                })
+            }
+            (JsonbValue::Array(items), JsonbValue::Array(others)) => others
+                .iter()
+                .all(|other| items.iter().any(|item| item.contains_nested(other))),
+            _ => self == other,
+        }
+    }
+
+    /// Return true if the string is a key of the object, an element of the array or the
+    /// string value itself, which is the ? operator.
+    pub fn exists(&self, key: &str) -> bool {
+        match self {
+            JsonbValue::Object(_) => self.object_field(key).is_some(),
+            JsonbValue::Array(items) => items
+                .iter()
+                .any(|item| matches!(item, JsonbValue::String(value) if value == key)),
+            JsonbValue::String(value) => value == key,
+            _ => false,
+        }
+    }
+
+    /// Rank of the kind of value used to sort values of different kinds.
+    fn rank(&self) -> u8 {
+        match self {
+            JsonbValue::Null => 0,
+            JsonbValue::String(_) => 1,
+            JsonbValue::Number(_) => 2,
+            JsonbValue::Bool(_) => 3,
+            JsonbValue::Array(_) => 4,
+            JsonbValue::Object(_) => 5,
+        }
+    }
+}
+
+impl PartialEq for JsonbValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for JsonbValue {}
+
+impl PartialOrd for JsonbValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values are sorted as Object > Array > Boolean > Number > String > Null. Arrays and objects
+/// with more elements are greater, and then the elements are compared in order.
+impl Ord for JsonbValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (JsonbValue::Bool(a), JsonbValue::Bool(b)) => a.cmp(b),
+            (JsonbValue::Number(a), JsonbValue::Number(b)) => {
+                match (NumericVar::from_data(a), NumericVar::from_data(b)) {
+                    (Ok(a), Ok(b)) => a.cmp(&b),
+                    _ => Ordering::Equal,
+                }
+            }
+            (JsonbValue::String(a), JsonbValue::String(b)) => a.cmp(b),
+            (JsonbValue::Array(a), JsonbValue::Array(b)) => {
+                a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+            }
+            (JsonbValue::Object(a), JsonbValue::Object(b)) => {
+                a.len().cmp(&b.len()).then_with(|| {
+                    a.iter()
+                        .zip(b)
+                        .map(|((ka, va), (kb, vb))| compare_keys(ka, kb).then_with(|| va.cmp(vb)))
+                        .find(|ord| *ord != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                })
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl fmt::Display for JsonbValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonbValue::Null => write!(f, "null"),
+            JsonbValue::Bool(value) => write!(f, "{}", value),
+            JsonbValue::Number(data) => match NumericVar::from_data(data) {
+                Ok(number) => write!(f, "{}", number),
+                Err(_) => Err(fmt::Error),
+            },
+            JsonbValue::String(value) => write_json_string(f, value),
+            JsonbValue::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            JsonbValue::Object(pairs) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_json_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Compare object keys, shorter keys go first and keys with the same length are compared by
+/// their bytes.
+fn compare_keys(a: &str, b: &str) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Write a string as a quoted JSON string, escaping the characters that need to be escaped.
+fn write_json_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\u{8}' => write!(f, "\\b")?,
+            '\u{c}' => write!(f, "\\f")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// A recursive descent parser of JSON documents, as defined by RFC 8259.
+struct JsonParser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    /// Parse a document that contains a single value, returning None if it is malformed.
+    fn parse_document(&mut self) -> Option<JsonbValue> {
+        let value = self.parse_value(0)?;
+        self.skip_whitespace();
+        (self.pos == self.input.len()).then_some(value)
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Option<JsonbValue> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.parse_object(depth),
+            b'[' => self.parse_array(depth),
+            b'"' => self.parse_string().map(JsonbValue::String),
+            b't' => self.parse_keyword("true", JsonbValue::Bool(true)),
+            b'f' => self.parse_keyword("false", JsonbValue::Bool(false)),
+            b'n' => self.parse_keyword("null", JsonbValue::Null),
+            _ => self.parse_number(),
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Option<JsonbValue> {
+        self.pos += 1;
+        let mut pairs = Vec::new();
+
+        self.skip_whitespace();
+        if self.consume(b'}') {
+            return Some(JsonbValue::object(pairs));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            if !self.consume(b':') {
+                return None;
+            }
+            pairs.push((key, self.parse_value(depth + 1)?));
+
+            self.skip_whitespace();
+            if self.consume(b'}') {
+                return Some(JsonbValue::object(pairs));
+            }
+            if !self.consume(b',') {
+                return None;
+            }
+        }
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Option<JsonbValue> {
+        self.pos += 1;
+        let mut items = Vec::new();
+
+        self.skip_whitespace();
+        if self.consume(b']') {
+            return Some(JsonbValue::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            if self.consume(b']') {
+                return Some(JsonbValue::Array(items));
+            }
+            if !self.consume(b',') {
+                return None;
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        if !self.consume(b'"') {
+            return None;
+        }
+
+        let mut value = Vec::new();
+        loop {
+            let byte = self.next()?;
+            match byte {
+                b'"' => return String::from_utf8(value).ok(),
+                b'\\' => {
+                    let escaped = match self.next()? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.parse_unicode_escape()?,
+                        _ => return None,
+                    };
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                0..=0x1F => return None,
+                byte => value.push(byte),
+            }
+        }
+    }
+
+    /// Parse the hex digits of a \u escape, which may be followed by a second escape when the
+    /// character is encoded as a UTF-16 surrogate pair.
+    fn parse_unicode_escape(&mut self) -> Option<char> {
+        let high = self.parse_hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if !(self.consume(b'\\') && self.consume(b'u')) {
+                    return None;
+                }
+                let low = self.parse_hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return None;
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            // Text values can not contain the null character.
+            0 => return None,
+            code => code,
+        };
+        char::from_u32(code)
+    }
+
+    fn parse_hex4(&mut self) -> Option<u32> {
+        let digits = self.input.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        digits
+            .iter()
+            .try_fold(0, |code, d| Some(code << 4 | (*d as char).to_digit(16)?))
+    }
+
+    fn parse_number(&mut self) -> Option<JsonbValue> {
+        let start = self.pos;
+        self.consume(b'-');
+
+        if !self.consume(b'0') && self.skip_digits() == 0 {
+            return None;
+        }
+        if self.consume(b'.') && self.skip_digits() == 0 {
+            return None;
+        }
+        if self.consume(b'e') || self.consume(b'E') {
+            let _ = self.consume(b'+') || self.consume(b'-');
+            if self.skip_digits() == 0 {
+                return None;
+            }
+        }
+
+        let text = std::str::from_utf8(&self.input[start..self.pos]).ok()?;
+        let number = NumericVar::parse(text).ok()?;
+        Some(JsonbValue::Number(number.to_data().ok()?))
+    }
+
+    fn parse_keyword(&mut self, keyword: &str, value: JsonbValue) -> Option<JsonbValue> {
+        let end = self.pos + keyword.len();
+        if self.input.get(self.pos..end)? != keyword.as_bytes() {
+            return None;
+        }
+        self.pos = end;
+        Some(value)
+    }
+
+    /// Skip a sequence of digits, returning the number of digits skipped.
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jsonb(value: &str) -> JsonbValue {
+        JsonbValue::parse(value, "jsonb").unwrap()
+    }
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let cases = [
+            (
+                r#" {"b" : 1, "aa": [true, null, 1.50], "a": "x", "b": 2e2} "#,
+                r#"{"a": "x", "b": 200, "aa": [true, null, 1.50]}"#,
+            ),
+            (
+                r#""tab\t \"quoted\" \u00e9 \ud83d\ude00""#,
+                r#""tab\t \"quoted\" é 😀""#,
+            ),
+            ("-0.5", "-0.5"),
+            ("[]", "[]"),
+            ("{}", "{}"),
+        ];
+        for (input, output) in cases {
+            assert_eq!(Jsonb.output(&Jsonb.input(input, -1)?)?, output);
+        }
+
+        for input in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "tru",
+            "\"\\u0000\"",
+            "1 2",
+            "'a'",
+        ] {
+            assert!(Jsonb.input(input, -1).is_err(), "{}", input);
+        }
+        assert!(Jsonb.input(&"[".repeat(MAX_DEPTH + 2), -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_format() -> Result<()> {
+        let datum = Jsonb.input(r#"{"a": [1, 2]}"#, -1)?;
+        let binary = Jsonb.send(&datum)?;
+        assert_eq!(binary[0], JSONB_VERSION);
+        assert_eq!(Jsonb.recv(&binary, -1)?, datum);
+        assert!(Jsonb.recv(b"2{}", -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_access() {
+        let value = jsonb(r#"{"a": {"b": [10, "x", null]}}"#);
+        let array = value.object_field("a").unwrap().object_field("b").unwrap();
+        assert_eq!(array.array_element(0), Some(&jsonb("10")));
+        assert_eq!(array.array_element(-2).unwrap().to_text().unwrap(), "x");
+        assert_eq!(array.array_element(-1).unwrap().to_text(), None);
+        assert_eq!(array.array_element(3), None);
+        assert_eq!(array.array_element(-4), None);
+        assert_eq!(value.object_field("b"), None);
+        assert_eq!(array.object_field("a"), None);
+    }
+
+    #[test]
+    fn test_contains_and_exists() {
+        let value = jsonb(r#"{"a": 1, "tags": ["x", "y", {"z": 1}], "n": {"m": 1.0, "o": 2}}"#);
+        assert!(value.contains(&jsonb(r#"{"a": 1}"#)));
+        assert!(value.contains(&jsonb(r#"{"tags": ["y"], "n": {"m": 1}}"#)));
+        assert!(value.contains(&jsonb(r#"{"tags": [{}]}"#)));
+        assert!(!value.contains(&jsonb(r#"{"a": 2}"#)));
+        assert!(!value.contains(&jsonb(r#"{"tags": "x"}"#)));
+        assert!(jsonb("[1, 2, [3]]").contains(&jsonb("2")));
+        assert!(jsonb("[1, 2, [3]]").contains(&jsonb("[[3], 1]")));
+        assert!(!jsonb("[[1]]").contains(&jsonb("[1]")));
+        assert!(jsonb(r#""a""#).contains(&jsonb(r#""a""#)));
+
+        assert!(value.exists("tags"));
+        assert!(!value.exists("m"));
+        assert!(jsonb(r#"["x", 1]"#).exists("x"));
+        assert!(!jsonb(r#"["x", 1]"#).exists("1"));
+        assert!(jsonb(r#""x""#).exists("x"));
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let ordered = [
+            "null",
+            r#""b""#,
+            "-1",
+            "2.0",
+            "false",
+            "true",
+            "[3]",
+            "[1, 2]",
+            r#"{"b": 1}"#,
+            r#"{"a": 1, "b": 1}"#,
+        ];
+        for pair in ordered.windows(2) {
+            assert_eq!(
+                jsonb(pair[0]).cmp(&jsonb(pair[1])),
+                Ordering::Less,
+                "{:?}",
+                pair
+            );
+        }
+        assert_eq!(jsonb("1.0"), jsonb("1"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_sql() -> Result<()> {
+        let convert = |typ: Oid, value: &str| -> Result<String> {
+            let datum = lookup_type(typ)?.input(value, -1)?;
+            Ok(JsonbValue::from_sql(Some(&datum), typ)?.to_string())
+        };
+        assert_eq!(convert(pg_type::INT_OID, "42")?, "42");
+        assert_eq!(convert(pg_type::FLOAT8_OID, "NaN")?, r#""NaN""#);
+        assert_eq!(convert(pg_type::BOOL_OID, "t")?, "true");
+        assert_eq!(convert(pg_type::VARCHAR_OID, "a\"b")?, r#""a\"b""#);
+        assert_eq!(
+            convert(pg_type::JSON_OID, r#"{"b":1,"a":2}"#)?,
+            r#"{"a": 2, "b": 1}"#
+        );
+        assert_eq!(
+            JsonbValue::from_sql(None, pg_type::INT_OID)?.to_string(),
+            "null"
+        );
+        Ok(())
+    }
+}
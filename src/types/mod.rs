@@ -0,0 +1,769 @@
+//! Data types supported by tinydb.
+//!
+//! Each data type implements the [Type] trait, which contains all the operations needed to
+//! store, compare and exchange values of that type with clients. All builtin types are
+//! registered on [BUILTIN_TYPES], which is also used to fill the pg_type catalog relation when
+//! a new database is created.
+//!
+//! To add a new data type, implement the [Type] trait and register it on [BUILTIN_TYPES].
+//!
+//! Data types created with CREATE TYPE are registered with [register_type] when they are created
+//! and when the server starts, and are found by the same lookup functions as the builtin types.
+use std::{
+    cmp::Ordering,
+    convert::{TryFrom, TryInto},
+    fmt,
+    sync::RwLock,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{catalog::pg_attribute::PgAttribute, Datum, Oid, INVALID_OID};
+
+pub mod array;
+pub mod boolean;
+pub mod bpchar;
+pub mod bytea;
+pub mod cast;
+pub mod composite;
+pub mod datetime;
+pub mod enumerated;
+pub mod float;
+pub mod int;
+pub mod interval;
+pub mod json;
+pub mod jsonb;
+pub mod numeric;
+pub mod oid;
+pub mod pseudo;
+pub mod text;
+pub mod unknown;
+pub mod uuid;
+pub mod varchar;
+
+/// Errors related with data type values.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("invalid input syntax for type {0}: \"{1}\"")]
+    InvalidTextRepresentation(&'static str, String),
+
+    #[error("value \"{1}\" is out of range for type {0}")]
+    ValueOutOfRange(&'static str, String),
+
+    #[error("value too long for type {0}")]
+    StringTooLong(String),
+
+    #[error("incorrect binary data format for type {0}")]
+    InvalidBinaryRepresentation(&'static str),
+
+    #[error("type with OID {0} does not exist")]
+    UndefinedType(Oid),
+
+    #[error("{0} out of range")]
+    OutOfRange(&'static str),
+
+    #[error("division by zero")]
+    DivisionByZero,
+
+    #[error("operator does not exist: {0}")]
+    UndefinedOperator(String),
+
+    #[error("type modifier is not allowed for type \"{0}\"")]
+    TypmodNotAllowed(&'static str),
+
+    #[error("{0}")]
+    InvalidTypmod(String),
+
+    #[error("numeric field overflow")]
+    NumericFieldOverflow,
+
+    #[error("date/time field value out of range: \"{0}\"")]
+    DatetimeFieldOverflow(String),
+
+    #[error("{0} out of range: \"{1}\"")]
+    DatetimeValueOutOfRange(&'static str, String),
+
+    #[error("{0} out of range")]
+    DatetimeOutOfRange(&'static str),
+
+    #[error("cannot subtract infinite {0}")]
+    InfiniteSubtraction(&'static str),
+
+    #[error("invalid value for parameter \"TimeZone\": \"{0}\"")]
+    InvalidTimeZone(String),
+
+    #[error("invalid hexadecimal digit: \"{0}\"")]
+    InvalidHexDigit(char),
+
+    #[error("invalid hexadecimal data: odd number of digits")]
+    OddNumberOfHexDigits,
+
+    #[error("could not identify a comparison function for type {0}")]
+    UndefinedComparison(&'static str),
+
+    #[error("function {0} does not exist")]
+    UndefinedFunction(String),
+
+    #[error("argument list must have even number of elements")]
+    OddNumberOfArguments,
+
+    #[error("argument {0}: key must not be null")]
+    NullObjectKey(usize),
+
+    #[error("malformed array literal: \"{0}\"")]
+    MalformedArrayLiteral(String),
+
+    #[error("number of array dimensions ({0}) exceeds the maximum allowed ({max})", max = array::MAXDIM)]
+    ArrayDimensionsExceeded(usize),
+
+    #[error("multidimensional arrays must have array expressions with matching dimensions")]
+    ArrayDimensionMismatch,
+
+    #[error("cannot determine type of empty array")]
+    IndeterminateEmptyArray,
+
+    #[error("cannot accumulate null arrays")]
+    NullArrayAccumulation,
+
+    #[error("cannot accumulate empty arrays")]
+    EmptyArrayAccumulation,
+
+    #[error("cannot accumulate arrays of different dimensionality")]
+    ArrayAccumulationMismatch,
+
+    #[error("could not find array type for data type {0}")]
+    UndefinedArrayType(&'static str),
+
+    #[error("{0} types {1} and {2} cannot be matched")]
+    CannotMatchTypes(&'static str, &'static str, &'static str),
+
+    #[error("cannot subscript type {0} because it does not support subscripting")]
+    CannotSubscript(&'static str),
+
+    #[error("op ANY/ALL (array) requires array on right side")]
+    ArrayRequired,
+
+    #[error("array subscript must have type integer")]
+    SubscriptType,
+
+    #[error("cannot cast type {0} to {1}")]
+    CannotCoerce(&'static str, &'static str),
+
+    #[error("invalid input value for enum {0}: \"{1}\"")]
+    InvalidEnumValue(&'static str, String),
+
+    #[error("malformed record literal: \"{0}\"")]
+    MalformedRecordLiteral(String),
+
+    #[error("column notation .{0} applied to type {1}, which is not a composite type")]
+    NotCompositeType(String, &'static str),
+
+    #[error("column \"{0}\" not found in data type {1}")]
+    UndefinedField(String, &'static str),
+
+    #[error("cannot accept a value of type {0}")]
+    PseudoTypeInput(&'static str),
+
+    #[error("cannot display a value of type {0}")]
+    PseudoTypeOutput(&'static str),
+
+    #[error("function {0} is not unique")]
+    AmbiguousFunction(String),
+
+    #[error("argument of {0} must be type boolean, not type {1}")]
+    NonBooleanArgument(&'static str, &'static str),
+
+    #[error("negative substring length not allowed")]
+    NegativeSubstringLength,
+
+    #[error("zero raised to a negative power is undefined")]
+    ZeroToNegativePower,
+
+    #[error("a negative number raised to a non-integer power yields a complex result")]
+    ComplexPower,
+
+    #[error("LIKE pattern must not end with escape character")]
+    LikePatternEndsWithEscape,
+
+    #[error("invalid escape string")]
+    InvalidEscapeString,
+
+    #[error("invalid regular expression: {0}")]
+    InvalidRegularExpression(String),
+
+    #[error("invalid regular expression option: \"{0}\"")]
+    InvalidRegexpOption(char),
+
+    #[error("{0}() does not support the \"global\" option")]
+    RegexpGlobalOption(&'static str),
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error.
+    pub fn sqlstate(&self) -> &'static str {
+        match self {
+            Error::InvalidTextRepresentation(..) => "22P02",
+            Error::ValueOutOfRange(..) => "22003",
+            Error::StringTooLong(_) => "22001",
+            Error::InvalidBinaryRepresentation(_) => "22P03",
+            Error::UndefinedType(_) => "42704",
+            Error::OutOfRange(_) => "22003",
+            Error::DivisionByZero => "22012",
+            Error::UndefinedOperator(_) => "42883",
+            Error::TypmodNotAllowed(_) => "42601",
+            Error::InvalidTypmod(_) => "22023",
+            Error::NumericFieldOverflow => "22003",
+            Error::DatetimeFieldOverflow(_) => "22008",
+            Error::DatetimeValueOutOfRange(..) => "22008",
+            Error::DatetimeOutOfRange(_) => "22008",
+            Error::InfiniteSubtraction(_) => "22008",
+            Error::InvalidTimeZone(_) => "22023",
+            Error::InvalidHexDigit(_) => "22023",
+            Error::OddNumberOfHexDigits => "22023",
+            Error::UndefinedComparison(_) => "42883",
+            Error::UndefinedFunction(_) => "42883",
+            Error::OddNumberOfArguments => "22023",
+            Error::NullObjectKey(_) => "22004",
+            Error::MalformedArrayLiteral(_) => "22P02",
+            Error::ArrayDimensionsExceeded(_) => "54000",
+            Error::ArrayDimensionMismatch => "2202E",
+            Error::IndeterminateEmptyArray => "42P18",
+            Error::NullArrayAccumulation => "22004",
+            Error::EmptyArrayAccumulation => "2202E",
+            Error::ArrayAccumulationMismatch => "2202E",
+            Error::UndefinedArrayType(_) => "42704",
+            Error::CannotMatchTypes(..) => "42804",
+            Error::CannotSubscript(_) => "42804",
+            Error::ArrayRequired => "42809",
+            Error::SubscriptType => "42804",
+            Error::CannotCoerce(..) => "42846",
+            Error::InvalidEnumValue(..) => "22P02",
+            Error::MalformedRecordLiteral(_) => "22P02",
+            Error::NotCompositeType(..) => "42809",
+            Error::UndefinedField(..) => "42703",
+            Error::PseudoTypeInput(_) => "0A000",
+            Error::PseudoTypeOutput(_) => "0A000",
+            Error::AmbiguousFunction(_) => "42725",
+            Error::NonBooleanArgument(..) => "42804",
+            Error::NegativeSubstringLength => "22011",
+            Error::ZeroToNegativePower => "2201F",
+            Error::ComplexPower => "2201F",
+            Error::LikePatternEndsWithEscape => "22025",
+            Error::InvalidEscapeString => "22025",
+            Error::InvalidRegularExpression(_) => "2201B",
+            Error::InvalidRegexpOption(_) => "22023",
+            Error::RegexpGlobalOption(_) => "22023",
+        }
+    }
+}
+
+/// Alignment required when storing a value of a type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeAlign {
+    /// No alignment needed.
+    Char,
+
+    /// 2-byte alignment.
+    Short,
+
+    /// 4-byte alignment.
+    Int,
+
+    /// 8-byte alignment.
+    Double,
+}
+
+impl TypeAlign {
+    /// Return the character used to represent the alignment on pg_type.
+    pub fn as_char(&self) -> char {
+        match self {
+            TypeAlign::Char => 'c',
+            TypeAlign::Short => 's',
+            TypeAlign::Int => 'i',
+            TypeAlign::Double => 'd',
+        }
+    }
+}
+
+/// Category of a data type, used to decide which implicit conversions are allowed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeCategory {
+    Array,
+    Boolean,
+    Composite,
+    DateTime,
+    Enum,
+    Numeric,
+    Pseudo,
+    String,
+    Timespan,
+    Unknown,
+    UserDefined,
+}
+
+impl TypeCategory {
+    /// Return the character used to represent the category on pg_type.
+    pub fn as_char(&self) -> char {
+        match self {
+            TypeCategory::Array => 'A',
+            TypeCategory::Boolean => 'B',
+            TypeCategory::Composite => 'C',
+            TypeCategory::DateTime => 'D',
+            TypeCategory::Enum => 'E',
+            TypeCategory::Numeric => 'N',
+            TypeCategory::Pseudo => 'P',
+            TypeCategory::String => 'S',
+            TypeCategory::Timespan => 'T',
+            TypeCategory::Unknown => 'X',
+            TypeCategory::UserDefined => 'U',
+        }
+    }
+}
+
+/// Kind of a data type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeKind {
+    /// A base type, including the array types.
+    Base,
+
+    /// A composite type created with CREATE TYPE name AS (attributes).
+    Composite,
+
+    /// An enum type created with CREATE TYPE name AS ENUM (labels).
+    Enum,
+
+    /// A type that can not be used on columns, like unknown or any.
+    Pseudo,
+}
+
+impl TypeKind {
+    /// Return the character used to represent the kind on pg_type.
+    pub fn as_char(&self) -> char {
+        match self {
+            TypeKind::Base => 'b',
+            TypeKind::Composite => 'c',
+            TypeKind::Enum => 'e',
+            TypeKind::Pseudo => 'p',
+        }
+    }
+}
+
+/// Arithmetic operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+impl fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArithOp::Add => write!(f, "+"),
+            ArithOp::Sub => write!(f, "-"),
+            ArithOp::Mul => write!(f, "*"),
+            ArithOp::Div => write!(f, "/"),
+            ArithOp::Mod => write!(f, "%"),
+        }
+    }
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl CmpOp {
+    /// Return true if the result of comparing two values satisfies the operator.
+    pub fn matches(&self, ordering: Ordering) -> bool {
+        match self {
+            CmpOp::Eq => ordering == Ordering::Equal,
+            CmpOp::NotEq => ordering != Ordering::Equal,
+            CmpOp::Lt => ordering == Ordering::Less,
+            CmpOp::LtEq => ordering != Ordering::Greater,
+            CmpOp::Gt => ordering == Ordering::Greater,
+            CmpOp::GtEq => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CmpOp::Eq => write!(f, "="),
+            CmpOp::NotEq => write!(f, "<>"),
+            CmpOp::Lt => write!(f, "<"),
+            CmpOp::LtEq => write!(f, "<="),
+            CmpOp::Gt => write!(f, ">"),
+            CmpOp::GtEq => write!(f, ">="),
+        }
+    }
+}
+
+/// Operations of a data type.
+///
+/// A datum is the internal representation of a value, which is the representation stored on
+/// heap tuples. Values are converted from and to the external text representation using
+/// input and output, and from and to the binary representation of the wire protocol using
+/// recv and send.
+///
+/// The typmod is the type modifier declared on a column, e.g. the max length of a
+/// varchar(n), or -1 if the column has no type modifier.
+pub trait Type: Sync {
+    /// Oid of type.
+    fn oid(&self) -> Oid;
+
+    /// Name of type.
+    fn typname(&self) -> &'static str;
+
+    /// Name of type used on SQL statements and error messages, e.g integer for int4.
+    fn format_type(&self) -> &'static str {
+        self.typname()
+    }
+
+    /// Category of type.
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::UserDefined
+    }
+
+    /// Kind of type.
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Base
+    }
+
+    /// Return true if this is the preferred type of its category, which is chosen when an
+    /// argument of unknown type could be converted to more than one type of the category.
+    fn typispreferred(&self) -> bool {
+        false
+    }
+
+    /// Number of bytes of the internal representation, or -1 for variable-length types.
+    fn typlen(&self) -> i32;
+
+    /// Alignment required when storing a value of this type.
+    fn typalign(&self) -> TypeAlign;
+
+    /// Return true if the values of this type are passed by value instead of by reference.
+    fn typbyval(&self) -> bool {
+        self.typlen() > 0 && self.typlen() <= 8
+    }
+
+    /// Oid of the element type if this is an array type, or [INVALID_OID] otherwise.
+    fn typelem(&self) -> Oid {
+        INVALID_OID
+    }
+
+    /// Oid of the pg_class entry that describes the attributes of a composite type, or
+    /// [INVALID_OID] for other types.
+    fn typrelid(&self) -> Oid {
+        INVALID_OID
+    }
+
+    /// Attributes of a composite type, which is empty for other types.
+    fn attributes(&self) -> &[PgAttribute] {
+        &[]
+    }
+
+    /// Convert the modifiers declared with the type on a column, e.g the precision and scale of
+    /// numeric(p, s), to the typmod passed to the input functions.
+    fn typmod_in(&self, _args: &[u64]) -> Result<i32> {
+        bail!(Error::TypmodNotAllowed(self.format_type()))
+    }
+
+    /// Convert the external text representation of a value to a datum.
+    fn input(&self, value: &str, typmod: i32) -> Result<Datum>;
+
+    /// Convert a datum to the external text representation.
+    fn output(&self, datum: &[u8]) -> Result<String>;
+
+    /// Convert a value received in binary format to a datum.
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum>;
+
+    /// Convert a datum to the binary format.
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>>;
+
+    /// Compare two datums of this type.
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering>;
+
+    /// Return the type of the result of the arithmetic operator applied on a value of this
+    /// type and a value of the right type, or None if the operator is not defined.
+    fn arith_result_type(&self, _op: ArithOp, _right: Oid) -> Option<Oid> {
+        None
+    }
+
+    /// Apply an arithmetic operator on a datum of this type and a datum of the right type.
+    fn arith(&self, op: ArithOp, _a: &[u8], right: Oid, _b: &[u8]) -> Result<Datum> {
+        Err(undefined_operator(self.format_type(), op, right))
+    }
+
+    /// Negate a datum of this type.
+    fn negate(&self, _datum: &[u8]) -> Result<Datum> {
+        bail!(Error::UndefinedOperator(format!(
+            "- {}",
+            self.format_type()
+        )))
+    }
+}
+
+/// All builtin data types.
+pub static BUILTIN_TYPES: &[&dyn Type] = &[
+    &boolean::Bool,
+    &bytea::Bytea,
+    &int::Int8,
+    &int::Int2,
+    &int::Int4,
+    &text::Text,
+    &oid::OidType,
+    &json::Json,
+    &float::Float4,
+    &float::Float8,
+    &unknown::Unknown,
+    &bpchar::Bpchar,
+    &varchar::Varchar,
+    &numeric::Numeric,
+    &datetime::Date,
+    &datetime::Time,
+    &datetime::Timestamp,
+    &datetime::TimestampTz,
+    &interval::Interval,
+    &uuid::Uuid,
+    &jsonb::Jsonb,
+    &array::BOOL_ARRAY,
+    &array::BYTEA_ARRAY,
+    &array::INT8_ARRAY,
+    &array::INT2_ARRAY,
+    &array::INT4_ARRAY,
+    &array::TEXT_ARRAY,
+    &array::OID_ARRAY,
+    &array::JSON_ARRAY,
+    &array::FLOAT4_ARRAY,
+    &array::FLOAT8_ARRAY,
+    &array::BPCHAR_ARRAY,
+    &array::VARCHAR_ARRAY,
+    &array::NUMERIC_ARRAY,
+    &array::DATE_ARRAY,
+    &array::TIME_ARRAY,
+    &array::TIMESTAMP_ARRAY,
+    &array::TIMESTAMPTZ_ARRAY,
+    &array::INTERVAL_ARRAY,
+    &array::UUID_ARRAY,
+    &array::JSONB_ARRAY,
+    &pseudo::Any,
+    &composite::Record,
+];
+
+/// Data types created with CREATE TYPE.
+///
+/// Type oids are unique across all databases, so the types of all databases are kept on the same
+/// list. Types can not be dropped, so they live until the server stops.
+static USER_DEFINED_TYPES: RwLock<Vec<&'static dyn Type>> = RwLock::new(Vec::new());
+
+/// Register a data type created with CREATE TYPE, returning a reference to the registered type.
+pub fn register_type(typ: Box<dyn Type>) -> &'static dyn Type {
+    let typ: &'static dyn Type = Box::leak(typ);
+    USER_DEFINED_TYPES
+        .write()
+        .expect("user defined types lock poisoned")
+        .push(typ);
+    typ
+}
+
+/// Return the first builtin or user defined data type that matches the given predicate.
+fn find_type<P>(predicate: P) -> Option<&'static dyn Type>
+where
+    P: Fn(&dyn Type) -> bool,
+{
+    if let Some(typ) = BUILTIN_TYPES.iter().find(|typ| predicate(**typ)) {
+        return Some(*typ);
+    }
+    USER_DEFINED_TYPES
+        .read()
+        .expect("user defined types lock poisoned")
+        .iter()
+        .find(|typ| predicate(**typ))
+        .copied()
+}
+
+/// Size of the length header of variable-length values on PostgreSQL, which is included on the
+/// typmod of variable-length types for compatibility with PostgreSQL clients.
+const VARHDRSZ: i32 = 4;
+
+/// Maximum length that can be declared on a character(n) or character varying(n) column.
+const MAX_CHAR_LENGTH: u64 = 10 * 1024 * 1024;
+
+/// Return the data type of the given oid.
+pub fn lookup_type(oid: Oid) -> Result<&'static dyn Type> {
+    match find_type(|typ| typ.oid() == oid) {
+        Some(typ) => Ok(typ),
+        None => bail!(Error::UndefinedType(oid)),
+    }
+}
+
+/// Return the data type of the given name, if exists.
+pub fn lookup_type_by_name(name: &str) -> Option<&'static dyn Type> {
+    find_type(|typ| typ.typname() == name)
+}
+
+/// Return the array type whose elements are of the given type, if exists.
+pub fn array_type_of(elem: Oid) -> Option<&'static dyn Type> {
+    if elem == INVALID_OID {
+        return None;
+    }
+    find_type(|typ| typ.typelem() == elem)
+}
+
+/// Return the error of an arithmetic operator that is not defined for a value of type left and
+/// a value of type right.
+fn undefined_operator(left: &str, op: ArithOp, right: Oid) -> anyhow::Error {
+    match lookup_type(right) {
+        Ok(right) => {
+            Error::UndefinedOperator(format!("{} {} {}", left, op, right.format_type())).into()
+        }
+        Err(err) => err,
+    }
+}
+
+/// Convert the length declared on a character(n) or character varying(n) column to a typmod.
+fn char_length_typmod(name: &str, args: &[u64]) -> Result<i32> {
+    let len = match args {
+        [len] => *len,
+        _ => bail!(Error::InvalidTypmod(String::from("invalid type modifier"))),
+    };
+
+    if len < 1 {
+        bail!(Error::InvalidTypmod(format!(
+            "length for type {} must be at least 1",
+            name
+        )));
+    }
+    if len > MAX_CHAR_LENGTH {
+        bail!(Error::InvalidTypmod(format!(
+            "length for type {} cannot exceed {}",
+            name, MAX_CHAR_LENGTH
+        )));
+    }
+    Ok(len as i32 + VARHDRSZ)
+}
+
+/// Return the max number of characters of a string type with the given typmod, or None if the
+/// length is unlimited.
+fn char_length(typmod: i32) -> Option<usize> {
+    (typmod >= VARHDRSZ).then(|| (typmod - VARHDRSZ) as usize)
+}
+
+/// Reader of values received in binary format, which are sequences of big-endian integers and
+/// length-prefixed values.
+struct BinaryReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn read_i32(&mut self) -> Option<i32> {
+        let value = self.read_bytes(4)?;
+        Some(i32::from_be_bytes(value.try_into().ok()?))
+    }
+
+    fn read_bytes(&mut self, len: i32) -> Option<&'a [u8]> {
+        let len = usize::try_from(len).ok()?;
+        if len > self.buf.len() {
+            return None;
+        }
+        let (value, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Some(value)
+    }
+
+    /// Return true if all bytes were read.
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+/// Deserialize a fixed length datum, returning an error if datum is malformed.
+fn deserialize<'a, T>(datum: &'a [u8]) -> Result<T>
+where
+    T: serde::Deserialize<'a>,
+{
+    Ok(bincode::deserialize(datum)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::pg_type;
+
+    #[test]
+    fn test_builtin_types_are_unique() {
+        for (i, typ) in BUILTIN_TYPES.iter().enumerate() {
+            for other in &BUILTIN_TYPES[i + 1..] {
+                assert_ne!(typ.oid(), other.oid());
+                assert_ne!(typ.typname(), other.typname());
+            }
+        }
+    }
+
+    #[test]
+    fn test_text_and_binary_round_trip() -> Result<()> {
+        let values = [
+            ("bool", "true"),
+            ("int4", "-42"),
+            ("oid", "1259"),
+            ("varchar", "tinydb"),
+            ("text", "tinydb"),
+            ("bpchar", "tinydb"),
+            ("bytea", "\\x74696e796462"),
+        ];
+
+        for (name, value) in values {
+            let typ = lookup_type_by_name(name).unwrap();
+            let datum = typ.input(value, -1)?;
+            assert_eq!(typ.output(&datum)?, value);
+
+            let binary = typ.send(&datum)?;
+            assert_eq!(typ.recv(&binary, -1)?, datum);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let int4 = lookup_type(pg_type::INT_OID)?;
+        assert_eq!(
+            int4.compare(&int4.input("-10", -1)?, &int4.input("2", -1)?)?,
+            Ordering::Less
+        );
+
+        let varchar = lookup_type(pg_type::VARCHAR_OID)?;
+        assert_eq!(
+            varchar.compare(&varchar.input("b", -1)?, &varchar.input("a", -1)?)?,
+            Ordering::Greater
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let int4 = lookup_type(pg_type::INT_OID).unwrap();
+        assert!(int4.input("abc", -1).is_err());
+        assert!(int4.input("99999999999", -1).is_err());
+
+        let varchar = lookup_type(pg_type::VARCHAR_OID).unwrap();
+        let typmod = varchar.typmod_in(&[3]).unwrap();
+        assert!(varchar.input("abcd", typmod).is_err());
+        assert!(varchar.input("abc  ", typmod).is_ok());
+        assert!(varchar.typmod_in(&[0]).is_err());
+        assert!(lookup_type(0).is_err());
+    }
+}
@@ -0,0 +1,937 @@
+use std::{cmp::Ordering, convert::TryFrom, fmt};
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, ArithOp, Error, Type, TypeAlign, TypeCategory, VARHDRSZ};
+use crate::{
+    catalog::pg_type,
+    sql::encode::numeric::{NumericData, DEC_DIGITS, NBASE, NUMERIC_NAN, NUMERIC_NEG, NUMERIC_POS},
+    Datum, Oid,
+};
+
+/// Max precision that can be declared on numeric(p, s).
+const NUMERIC_MAX_PRECISION: u64 = 1000;
+
+/// Max number of digits after the decimal point of a numeric value.
+const NUMERIC_MAX_SCALE: usize = 0x3FFF;
+
+/// Max display scale of a division result.
+const NUMERIC_MAX_DISPLAY_SCALE: i64 = 1000;
+
+/// Min number of significant digits of a division result.
+const NUMERIC_MIN_SIG_DIGITS: i64 = 16;
+
+/// Max absolute value of the exponent accepted on the text representation.
+const NUMERIC_MAX_EXPONENT: i64 = 1000;
+
+/// Arbitrary precision numeric data type.
+pub struct Numeric;
+
+impl Type for Numeric {
+    fn oid(&self) -> Oid {
+        pg_type::NUMERIC_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "numeric"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Numeric
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn typmod_in(&self, args: &[u64]) -> Result<i32> {
+        let (precision, scale) = match args {
+            [precision] => (*precision, 0),
+            [precision, scale] => (*precision, *scale),
+            _ => bail!(Error::InvalidTypmod(String::from(
+                "invalid NUMERIC type modifier"
+            ))),
+        };
+
+        if !(1..=NUMERIC_MAX_PRECISION).contains(&precision) {
+            bail!(Error::InvalidTypmod(format!(
+                "NUMERIC precision {} must be between 1 and {}",
+                precision, NUMERIC_MAX_PRECISION
+            )));
+        }
+        if scale > precision {
+            bail!(Error::InvalidTypmod(format!(
+                "NUMERIC scale {} must be between 0 and precision {}",
+                scale, precision
+            )));
+        }
+
+        Ok(((precision << 16) | scale) as i32 + VARHDRSZ)
+    }
+
+    fn input(&self, value: &str, typmod: i32) -> Result<Datum> {
+        NumericVar::parse(value)?.apply_typmod(typmod)?.to_datum()
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(NumericVar::from_datum(datum)?.to_string())
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        let invalid = || Error::InvalidBinaryRepresentation("numeric");
+        let read_u16 = |offset: usize| -> Result<u16> {
+            match buf.get(offset..offset + 2) {
+                Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+                None => bail!(invalid()),
+            }
+        };
+
+        let ndigits = read_u16(0)? as usize;
+        if buf.len() != 8 + ndigits * 2 {
+            bail!(invalid());
+        }
+
+        let data = NumericData {
+            weight: read_u16(2)? as i16,
+            sign: read_u16(4)?,
+            dscale: read_u16(6)?,
+            digits: (0..ndigits)
+                .map(|i| Ok(read_u16(8 + i * 2)? as i16))
+                .collect::<Result<_>>()?,
+        };
+
+        NumericVar::from_data(&data)?
+            .apply_typmod(typmod)?
+            .to_datum()
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let data = deserialize::<NumericData>(datum)?;
+
+        let mut buf = Vec::with_capacity(8 + data.digits.len() * 2);
+        buf.extend_from_slice(&(data.digits.len() as i16).to_be_bytes());
+        buf.extend_from_slice(&data.weight.to_be_bytes());
+        buf.extend_from_slice(&data.sign.to_be_bytes());
+        buf.extend_from_slice(&data.dscale.to_be_bytes());
+        for digit in &data.digits {
+            buf.extend_from_slice(&digit.to_be_bytes());
+        }
+        Ok(buf)
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(NumericVar::from_datum(a)?.cmp(&NumericVar::from_datum(b)?))
+    }
+
+    fn arith_result_type(&self, _op: ArithOp, right: Oid) -> Option<Oid> {
+        (right == pg_type::NUMERIC_OID).then_some(pg_type::NUMERIC_OID)
+    }
+
+    fn arith(&self, op: ArithOp, a: &[u8], _right: Oid, b: &[u8]) -> Result<Datum> {
+        let a = NumericVar::from_datum(a)?;
+        let b = NumericVar::from_datum(b)?;
+
+        let result = match op {
+            ArithOp::Add => a.add(&b),
+            ArithOp::Sub => a.sub(&b),
+            ArithOp::Mul => a.mul(&b),
+            ArithOp::Div => a.div(&b, a.div_scale(&b), true)?,
+            ArithOp::Mod => a.rem(&b)?,
+        };
+        result.to_datum()
+    }
+
+    fn negate(&self, datum: &[u8]) -> Result<Datum> {
+        NumericVar::from_datum(datum)?.neg().to_datum()
+    }
+}
+
+/// A numeric value used on computations.
+///
+/// The value is stored as a sequence of decimal digits with the number of digits after the
+/// decimal point, so 12.50 has the digits 1250 and scale 2. The scale is never reduced by
+/// arithmetic operations, so it is also the number of digits after the decimal point that
+/// are displayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumericVar {
+    /// True if the value is NaN.
+    nan: bool,
+
+    /// True if the value is negative, zero is never negative.
+    negative: bool,
+
+    /// Decimal digits of the absolute value without leading zeros, most significant first.
+    digits: Vec<u8>,
+
+    /// Number of digits after the decimal point.
+    scale: usize,
+}
+
+impl NumericVar {
+    fn new(negative: bool, digits: Vec<u8>, scale: usize) -> Self {
+        let digits = strip_leading_zeros(&digits).to_vec();
+        Self {
+            nan: false,
+            negative: negative && !digits.is_empty(),
+            digits,
+            scale,
+        }
+    }
+
+    /// Return a NaN value.
+    pub fn nan() -> Self {
+        Self {
+            nan: true,
+            negative: false,
+            digits: Vec::new(),
+            scale: 0,
+        }
+    }
+
+    /// Create a numeric value from an integer.
+    pub fn from_i64(value: i64) -> Self {
+        let digits = value
+            .unsigned_abs()
+            .to_string()
+            .bytes()
+            .map(|b| b - b'0')
+            .collect();
+        Self::new(value < 0, digits, 0)
+    }
+
+    /// Create a numeric value from a float using the given number of significant digits.
+    pub fn from_f64(value: f64, significant_digits: usize) -> Result<Self> {
+        if value.is_nan() {
+            return Ok(Self::nan());
+        }
+        if value.is_infinite() {
+            bail!(Error::OutOfRange("numeric"));
+        }
+
+        let mut var = Self::parse(&format!("{:.*e}", significant_digits - 1, value))?;
+        while var.scale > 0 && var.digits.last() == Some(&0) {
+            var.digits.pop();
+            var.scale -= 1;
+        }
+        Ok(var)
+    }
+
+    /// Parse the text representation of a numeric value.
+    pub fn parse(value: &str) -> Result<Self> {
+        let invalid = || Error::InvalidTextRepresentation("numeric", value.to_string());
+
+        let s = value.trim();
+        if s.eq_ignore_ascii_case("nan") {
+            return Ok(Self::nan());
+        }
+
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(s) => (true, s),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (&s[..i], s[i + 1..].parse::<i64>().map_err(|_| invalid())?),
+            None => (s, 0),
+        };
+
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if (int_part.is_empty() && frac_part.is_empty())
+            || !int_part
+                .bytes()
+                .chain(frac_part.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            bail!(invalid());
+        }
+
+        if exponent.abs() > NUMERIC_MAX_EXPONENT {
+            bail!(Error::ValueOutOfRange("numeric", value.to_string()));
+        }
+
+        let mut digits: Vec<u8> = int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .map(|b| b - b'0')
+            .collect();
+
+        let mut scale = frac_part.len() as i64 - exponent;
+        if scale < 0 {
+            digits.resize(digits.len() + (-scale) as usize, 0);
+            scale = 0;
+        }
+
+        Ok(Self::new(negative, digits, scale as usize))
+    }
+
+    /// Decode a numeric datum.
+    pub fn from_datum(datum: &[u8]) -> Result<Self> {
+        Self::from_data(&deserialize::<NumericData>(datum)?)
+    }
+
+    /// Encode the value as a numeric datum.
+    pub fn to_datum(&self) -> Result<Datum> {
+        Ok(bincode::serialize(&self.to_data()?)?)
+    }
+
+    /// Convert the on-disk format of a numeric to a numeric value.
+    pub fn from_data(data: &NumericData) -> Result<Self> {
+        let negative = match data.sign {
+            NUMERIC_NAN => return Ok(Self::nan()),
+            NUMERIC_POS => false,
+            NUMERIC_NEG => true,
+            _ => bail!(Error::InvalidBinaryRepresentation("numeric")),
+        };
+
+        if data.dscale as usize > NUMERIC_MAX_SCALE
+            || data.digits.iter().any(|d| *d < 0 || *d as i32 >= NBASE)
+        {
+            bail!(Error::InvalidBinaryRepresentation("numeric"));
+        }
+
+        let weight = data.weight as i64;
+        let scale = data.dscale as usize;
+
+        // Expand each base NBASE digit into decimal digits, from the most significant decimal
+        // digit of the first NBASE digit until the last digit displayed.
+        let high = weight * DEC_DIGITS as i64 + DEC_DIGITS as i64 - 1;
+        let low = -(scale as i64);
+
+        let mut digits = Vec::new();
+        for exp in (low..=high).rev() {
+            let index = weight - exp.div_euclid(DEC_DIGITS as i64);
+            let group = match usize::try_from(index) {
+                Ok(index) => data.digits.get(index).copied().unwrap_or_default(),
+                Err(_) => 0,
+            };
+            digits.push(
+                ((group as i32 / 10_i32.pow(exp.rem_euclid(DEC_DIGITS as i64) as u32)) % 10) as u8,
+            );
+        }
+
+        Ok(Self::new(negative, digits, scale))
+    }
+
+    /// Convert the value to the on-disk format of a numeric.
+    pub fn to_data(&self) -> Result<NumericData> {
+        if self.nan {
+            return Ok(NumericData {
+                sign: NUMERIC_NAN,
+                weight: 0,
+                dscale: 0,
+                digits: Vec::new(),
+            });
+        }
+
+        if self.scale > NUMERIC_MAX_SCALE {
+            bail!(Error::OutOfRange("numeric"));
+        }
+
+        let mut digits = Vec::new();
+        let mut weight = 0;
+
+        if !self.digits.is_empty() {
+            weight = self.exponent().div_euclid(DEC_DIGITS as i64);
+            let low = (-(self.scale as i64)).div_euclid(DEC_DIGITS as i64);
+
+            for w in (low..=weight).rev() {
+                digits.push(self.nbase_digit(w));
+            }
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+        }
+
+        Ok(NumericData {
+            sign: if self.negative {
+                NUMERIC_NEG
+            } else {
+                NUMERIC_POS
+            },
+            weight: i16::try_from(weight).map_err(|_| Error::OutOfRange("numeric"))?,
+            dscale: self.scale as u16,
+            digits,
+        })
+    }
+
+    /// Return the decimal exponent of the most significant digit, the value must not be zero.
+    fn exponent(&self) -> i64 {
+        self.digits.len() as i64 - 1 - self.scale as i64
+    }
+
+    /// Return the decimal digit of the given exponent, e.g the exponent 0 is the units digit.
+    fn digit_at(&self, exp: i64) -> u8 {
+        match usize::try_from(self.exponent() - exp) {
+            Ok(index) => self.digits.get(index).copied().unwrap_or_default(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Return the base NBASE digit of the given weight.
+    fn nbase_digit(&self, weight: i64) -> i16 {
+        let low = weight * DEC_DIGITS as i64;
+        (low..low + DEC_DIGITS as i64)
+            .rev()
+            .fold(0, |digit, exp| digit * 10 + self.digit_at(exp) as i16)
+    }
+
+    /// Return the integer value rounded to the nearest integer, or None if it does not fit on
+    /// an i64.
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.nan {
+            return None;
+        }
+
+        let rounded = self.round(0);
+        let value = rounded.digits.iter().try_fold(0_i128, |value, digit| {
+            value.checked_mul(10)?.checked_add(*digit as i128)
+        })?;
+        i64::try_from(if rounded.negative { -value } else { value }).ok()
+    }
+
+    /// Return the nearest float value.
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Round the value to the precision and scale of the given typmod.
+    pub fn apply_typmod(self, typmod: i32) -> Result<Self> {
+        if self.nan || typmod < VARHDRSZ {
+            return Ok(self);
+        }
+
+        let typmod = typmod - VARHDRSZ;
+        let precision = (typmod >> 16) & 0xffff;
+        let scale = typmod & 0xffff;
+
+        let value = self.round(scale as usize);
+        if value.digits.len() as i64 - value.scale as i64 > (precision - scale) as i64 {
+            bail!(Error::NumericFieldOverflow);
+        }
+        Ok(value)
+    }
+
+    fn is_zero(&self) -> bool {
+        !self.nan && self.digits.is_empty()
+    }
+
+    /// Return the digits of the value with the given scale, which must be greater than or equal
+    /// to the value scale.
+    fn aligned(&self, scale: usize) -> Vec<u8> {
+        let mut digits = self.digits.clone();
+        digits.resize(digits.len() + scale - self.scale, 0);
+        digits
+    }
+
+    /// Round the value to the given scale, rounding ties away from zero.
+    pub fn round(&self, scale: usize) -> Self {
+        self.rescale(scale, true)
+    }
+
+    /// Round the value to the given number of digits after the decimal point. A negative scale
+    /// rounds to a multiple of a power of ten, e.g 1250 rounded to the scale -2 is 1300.
+    pub fn round_to(&self, scale: i64) -> Self {
+        if scale >= 0 || self.nan {
+            return self.round(scale.max(0) as usize);
+        }
+
+        // Rounding to more digits than the integer part has always returns zero.
+        let shift = (-scale).min(self.digits.len() as i64 + 1) as usize;
+        let rounded = Self::new(self.negative, self.digits.clone(), self.scale + shift).round(0);
+        let mut digits = rounded.digits;
+        digits.resize(digits.len() + shift, 0);
+        Self::new(self.negative, digits, 0)
+    }
+
+    /// Return the largest integer less than or equal to the value.
+    pub fn floor(&self) -> Self {
+        let truncated = self.rescale(0, false);
+        if self.negative && truncated.cmp(self) != Ordering::Equal {
+            truncated.sub(&Self::from_i64(1))
+        } else {
+            truncated
+        }
+    }
+
+    /// Return the smallest integer greater than or equal to the value.
+    pub fn ceil(&self) -> Self {
+        let truncated = self.rescale(0, false);
+        if !self.negative && truncated.cmp(self) != Ordering::Equal {
+            truncated.add(&Self::from_i64(1))
+        } else {
+            truncated
+        }
+    }
+
+    /// Return true if the value has no fractional part.
+    fn is_integer(&self) -> bool {
+        !self.nan && self.rescale(0, false).cmp(self) == Ordering::Equal
+    }
+
+    /// Raise the value to the power of exp.
+    ///
+    /// Like PostgreSQL, the result has at least 16 significant digits and no fewer digits after
+    /// the decimal point than any of the operands. Integer powers are computed exactly and then
+    /// rounded, other powers are computed using double precision.
+    pub fn pow(&self, exp: &Self) -> Result<Self> {
+        let one = Self::from_i64(1);
+        if exp.is_zero() || self.cmp(&one) == Ordering::Equal {
+            return Ok(one.round(NUMERIC_MIN_SIG_DIGITS as usize));
+        }
+        if self.nan || exp.nan {
+            return Ok(Self::nan());
+        }
+        if self.is_zero() {
+            if exp.negative {
+                bail!(Error::ZeroToNegativePower);
+            }
+            return Ok(Self::new(false, Vec::new(), self.scale.max(exp.scale)));
+        }
+        if self.negative && !exp.is_integer() {
+            bail!(Error::ComplexPower);
+        }
+
+        // Approximate decimal exponent of the result, used to choose the result scale.
+        let weight = exp.to_f64() * self.to_f64().abs().log10();
+        if weight > (i16::MAX as f64 + 1.0) * DEC_DIGITS as f64 {
+            bail!(Error::OutOfRange("numeric"));
+        }
+        let scale = (NUMERIC_MIN_SIG_DIGITS - weight as i64)
+            .max(self.scale as i64)
+            .max(exp.scale as i64)
+            .clamp(0, NUMERIC_MAX_DISPLAY_SCALE) as usize;
+        if weight + 1.0 < -NUMERIC_MAX_DISPLAY_SCALE as f64 {
+            return Ok(Self::new(false, Vec::new(), scale));
+        }
+
+        let n = match exp.to_i64() {
+            Some(n) if exp.is_integer() => n,
+            _ => {
+                let result = self.to_f64().powf(exp.to_f64());
+                return Ok(Self::from_f64(result, 17)?.round(scale));
+            }
+        };
+
+        // Square and multiply, keeping enough digits after the decimal point on the
+        // intermediate results to not change the rounded result.
+        let work_scale = scale + weight.abs() as usize + NUMERIC_MIN_SIG_DIGITS as usize;
+        let mut result = one.clone();
+        let mut base = self.clone();
+        let mut remaining = n.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.mul(&base).rescale(work_scale, true);
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                base = base.mul(&base).rescale(work_scale, true);
+            }
+        }
+
+        if n < 0 {
+            one.div(&result, scale, true)
+        } else {
+            Ok(result.round(scale))
+        }
+    }
+
+    fn rescale(&self, scale: usize, round: bool) -> Self {
+        if self.nan {
+            return self.clone();
+        }
+        if self.scale <= scale {
+            return Self::new(self.negative, self.aligned(scale), scale);
+        }
+
+        let cut = self.scale - scale;
+        let mut digits = self.digits.clone();
+        if digits.len() <= cut {
+            digits.splice(0..0, std::iter::repeat_n(0, cut + 1 - digits.len()));
+        }
+
+        let first_dropped = digits[digits.len() - cut];
+        digits.truncate(digits.len() - cut);
+        if round && first_dropped >= 5 {
+            digits = add_abs(&digits, &[1]);
+        }
+        Self::new(self.negative, digits, scale)
+    }
+
+    fn neg(&self) -> Self {
+        let mut value = self.clone();
+        value.negative = !self.negative && !self.nan && !self.digits.is_empty();
+        value
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        if self.nan || other.nan {
+            return Self::nan();
+        }
+
+        let scale = self.scale.max(other.scale);
+        let a = self.aligned(scale);
+        let b = other.aligned(scale);
+
+        if self.negative == other.negative {
+            return Self::new(self.negative, add_abs(&a, &b), scale);
+        }
+        match cmp_abs(&a, &b) {
+            Ordering::Less => Self::new(other.negative, sub_abs(&b, &a), scale),
+            _ => Self::new(self.negative, sub_abs(&a, &b), scale),
+        }
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self.add(&other.neg())
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        if self.nan || other.nan {
+            return Self::nan();
+        }
+        Self::new(
+            self.negative != other.negative,
+            mul_abs(&self.digits, &other.digits),
+            self.scale + other.scale,
+        )
+    }
+
+    /// Divide the value by other, computing the given number of digits after the decimal
+    /// point. The last digit is rounded if round is true, otherwise it is truncated.
+    fn div(&self, other: &Self, scale: usize, round: bool) -> Result<Self> {
+        if self.nan || other.nan {
+            return Ok(Self::nan());
+        }
+        if other.is_zero() {
+            bail!(Error::DivisionByZero);
+        }
+
+        // Compute one extra digit when rounding to decide the rounding of the last digit.
+        let result_scale = if round { scale + 1 } else { scale };
+
+        let mut dividend = self.digits.clone();
+        let mut divisor = other.digits.clone();
+        let shift = result_scale as i64 - self.scale as i64 + other.scale as i64;
+        if shift >= 0 {
+            dividend.resize(dividend.len() + shift as usize, 0);
+        } else {
+            divisor.resize(divisor.len() + (-shift) as usize, 0);
+        }
+
+        let (quotient, _) = div_abs(&dividend, &divisor);
+        let result = Self::new(self.negative != other.negative, quotient, result_scale);
+        Ok(if round { result.round(scale) } else { result })
+    }
+
+    /// Return the remainder of the division by other, with the sign of the value.
+    fn rem(&self, other: &Self) -> Result<Self> {
+        let quotient = self.div(other, 0, false)?;
+        Ok(self.sub(&quotient.mul(other)))
+    }
+
+    /// Return the scale of the division by other.
+    ///
+    /// Like PostgreSQL, the result has at least 16 significant digits and no fewer digits
+    /// after the decimal point than any of the operands.
+    fn div_scale(&self, other: &Self) -> usize {
+        let (weight1, first1) = self.first_nbase_digit();
+        let (weight2, first2) = other.first_nbase_digit();
+
+        let mut qweight = weight1 - weight2;
+        if first1 <= first2 {
+            qweight -= 1;
+        }
+
+        let scale = (NUMERIC_MIN_SIG_DIGITS - qweight * DEC_DIGITS as i64)
+            .max(self.scale as i64)
+            .max(other.scale as i64)
+            .clamp(0, NUMERIC_MAX_DISPLAY_SCALE);
+        scale as usize
+    }
+
+    /// Return the weight and the value of the first non-zero base NBASE digit, or zero for
+    /// both if the value is zero.
+    fn first_nbase_digit(&self) -> (i64, i16) {
+        if self.nan || self.digits.is_empty() {
+            return (0, 0);
+        }
+        let weight = self.exponent().div_euclid(DEC_DIGITS as i64);
+        (weight, self.nbase_digit(weight))
+    }
+}
+
+impl Ord for NumericVar {
+    /// Compare two numeric values. NaN values are equal to each other and greater than any
+    /// non-NaN value.
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.nan, other.nan) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => {}
+        }
+
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (negative, _) => {
+                let scale = self.scale.max(other.scale);
+                let ordering = cmp_abs(&self.aligned(scale), &other.aligned(scale));
+                if negative {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            }
+        }
+    }
+}
+
+impl PartialOrd for NumericVar {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for NumericVar {}
+
+impl fmt::Display for NumericVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.nan {
+            return write!(f, "NaN");
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+
+        let digits: String = self.digits.iter().map(|d| (b'0' + d) as char).collect();
+        if digits.len() > self.scale {
+            let (int_part, frac_part) = digits.split_at(digits.len() - self.scale);
+            write!(f, "{}", int_part)?;
+            if !frac_part.is_empty() {
+                write!(f, ".{}", frac_part)?;
+            }
+        } else {
+            write!(f, "0")?;
+            if self.scale > 0 {
+                write!(f, ".{:0>width$}", digits, width = self.scale)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn strip_leading_zeros(digits: &[u8]) -> &[u8] {
+    let start = digits.iter().position(|d| *d != 0).unwrap_or(digits.len());
+    &digits[start..]
+}
+
+/// Compare the absolute values of two sequences of decimal digits.
+fn cmp_abs(a: &[u8], b: &[u8]) -> Ordering {
+    let a = strip_leading_zeros(a);
+    let b = strip_leading_zeros(b);
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+fn add_abs(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let (mut i, mut j, mut carry) = (a.len(), b.len(), 0);
+
+    while i > 0 || j > 0 || carry > 0 {
+        let mut sum = carry;
+        if i > 0 {
+            i -= 1;
+            sum += a[i];
+        }
+        if j > 0 {
+            j -= 1;
+            sum += b[j];
+        }
+        result.push(sum % 10);
+        carry = sum / 10;
+    }
+
+    result.reverse();
+    result
+}
+
+/// Subtract b from a, the absolute value of a must be greater than or equal to b.
+fn sub_abs(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(a.len());
+    let (mut j, mut borrow) = (b.len(), 0);
+
+    for i in (0..a.len()).rev() {
+        let mut diff = a[i] as i8 - borrow;
+        if j > 0 {
+            j -= 1;
+            diff -= b[j] as i8;
+        }
+        borrow = if diff < 0 { 1 } else { 0 };
+        result.push((diff + borrow * 10) as u8);
+    }
+
+    result.reverse();
+    strip_leading_zeros(&result).to_vec()
+}
+
+fn mul_abs(a: &[u8], b: &[u8]) -> Vec<u8> {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result = vec![0_u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j + 1] += *x as u32 * *y as u32;
+        }
+    }
+    for k in (1..result.len()).rev() {
+        result[k - 1] += result[k] / 10;
+        result[k] %= 10;
+    }
+
+    result.into_iter().map(|d| d as u8).collect()
+}
+
+/// Divide a by b using long division, returning the quotient and the remainder.
+fn div_abs(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient = Vec::with_capacity(a.len());
+    let mut remainder = Vec::new();
+
+    for digit in a {
+        remainder.push(*digit);
+        remainder = strip_leading_zeros(&remainder).to_vec();
+
+        let mut q = 0;
+        while cmp_abs(&remainder, b) != Ordering::Less {
+            remainder = sub_abs(&remainder, b);
+            q += 1;
+        }
+        quotient.push(q);
+    }
+
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(op: ArithOp, a: &str, b: &str) -> Result<String> {
+        let result = Numeric.arith(
+            op,
+            &Numeric.input(a, -1)?,
+            pg_type::NUMERIC_OID,
+            &Numeric.input(b, -1)?,
+        )?;
+        Numeric.output(&result)
+    }
+
+    #[test]
+    fn test_text_round_trip() -> Result<()> {
+        for value in [
+            "0",
+            "1.50",
+            "-0.0015",
+            "123456789.000000001",
+            "NaN",
+            "10000",
+        ] {
+            assert_eq!(Numeric.output(&Numeric.input(value, -1)?)?, value);
+        }
+        assert_eq!(Numeric.output(&Numeric.input("1.5e3", -1)?)?, "1500");
+        assert_eq!(Numeric.output(&Numeric.input("-0", -1)?)?, "0");
+        assert!(Numeric.input("1.2.3", -1).is_err());
+        assert!(Numeric.input("", -1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_round_trip() -> Result<()> {
+        for value in ["0", "1.50", "-12345.678", "0.00001", "NaN", "100000000"] {
+            let datum = Numeric.input(value, -1)?;
+            assert_eq!(Numeric.recv(&Numeric.send(&datum)?, -1)?, datum);
+        }
+
+        // 12345.678 is stored as the digits 1 2345 6780 with weight 1.
+        let buf = Numeric.send(&Numeric.input("-12345.678", -1)?)?;
+        assert_eq!(
+            buf,
+            vec![0, 3, 0, 1, 0x40, 0, 0, 3, 0, 1, 0x09, 0x29, 0x1A, 0x7C]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_typmod() -> Result<()> {
+        let typmod = Numeric.typmod_in(&[5, 2])?;
+        assert_eq!(
+            Numeric.output(&Numeric.input("123.456", typmod)?)?,
+            "123.46"
+        );
+        assert_eq!(Numeric.output(&Numeric.input("-0.005", typmod)?)?, "-0.01");
+        assert_eq!(Numeric.output(&Numeric.input("1", typmod)?)?, "1.00");
+        assert!(Numeric.input("1000", typmod).is_err());
+        assert!(Numeric.input("999.995", typmod).is_err());
+        assert!(Numeric.typmod_in(&[0]).is_err());
+        assert!(Numeric.typmod_in(&[2, 3]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_arith() -> Result<()> {
+        assert_eq!(eval(ArithOp::Add, "0.1", "0.2")?, "0.3");
+        assert_eq!(eval(ArithOp::Sub, "1.5", "2.25")?, "-0.75");
+        assert_eq!(eval(ArithOp::Mul, "1.5", "-2.25")?, "-3.375");
+        assert_eq!(eval(ArithOp::Div, "1", "3")?, "0.33333333333333333333");
+        assert_eq!(eval(ArithOp::Div, "10", "2")?, "5.0000000000000000");
+        assert_eq!(eval(ArithOp::Div, "2", "3")?, "0.66666666666666666667");
+        assert_eq!(eval(ArithOp::Mod, "-7.5", "2")?, "-1.5");
+        assert_eq!(
+            eval(ArithOp::Mul, "99999999999999999999", "99999999999999999999")?,
+            "9999999999999999999800000000000000000001"
+        );
+        assert!(eval(ArithOp::Div, "1", "0.00").is_err());
+        assert_eq!(eval(ArithOp::Add, "NaN", "1")?, "NaN");
+        Ok(())
+    }
+
+    #[test]
+    fn test_math_functions() -> Result<()> {
+        let var = |value: &str| NumericVar::parse(value).unwrap();
+        assert_eq!(var("2.5").round_to(0).to_string(), "3");
+        assert_eq!(var("-2.45").round_to(1).to_string(), "-2.5");
+        assert_eq!(var("1250.7").round_to(-2).to_string(), "1300");
+        assert_eq!(var("49").round_to(-5).to_string(), "0");
+        assert_eq!(var("-1.5").floor().to_string(), "-2");
+        assert_eq!(var("-2.00").floor().to_string(), "-2");
+        assert_eq!(var("1.01").ceil().to_string(), "2");
+
+        let pow = |a: &str, b: &str| var(a).pow(&var(b)).map(|var| var.to_string());
+        assert_eq!(pow("2", "3")?, "8.0000000000000000");
+        assert_eq!(pow("2", "-3")?, "0.1250000000000000");
+        assert_eq!(pow("-3", "3")?, "-27.000000000000000");
+        assert_eq!(pow("10", "20")?, "100000000000000000000");
+        assert_eq!(pow("1.5", "2")?, "2.2500000000000000");
+        assert_eq!(pow("4", "0.5")?, "2.0000000000000000");
+        assert!(pow("0", "-1").is_err());
+        assert!(pow("-8", "0.5").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare() -> Result<()> {
+        let compare = |a: &str, b: &str| -> Result<Ordering> {
+            Numeric.compare(&Numeric.input(a, -1)?, &Numeric.input(b, -1)?)
+        };
+        assert_eq!(compare("1.50", "1.5")?, Ordering::Equal);
+        assert_eq!(compare("-2", "-10")?, Ordering::Greater);
+        assert_eq!(compare("NaN", "1e100")?, Ordering::Greater);
+        assert_eq!(compare("0.001", "0")?, Ordering::Greater);
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use std::{cmp::Ordering, convert::TryFrom, num::IntErrorKind};
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Object identifier data type.
+///
+/// Object identifiers are 8 bytes on tinydb, but they are sent to clients in the same 4 byte
+/// binary format of PostgreSQL.
+pub struct OidType;
+
+impl Type for OidType {
+    fn oid(&self) -> Oid {
+        pg_type::OID_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "oid"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Numeric
+    }
+
+    fn typispreferred(&self) -> bool {
+        true
+    }
+
+    fn typlen(&self) -> i32 {
+        8
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Double
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        let datum = value
+            .trim()
+            .parse::<Oid>()
+            .map_err(|err| match err.kind() {
+                IntErrorKind::PosOverflow => Error::ValueOutOfRange("oid", value.to_string()),
+                _ => Error::InvalidTextRepresentation("oid", value.to_string()),
+            })?;
+        Ok(bincode::serialize(&datum)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        Ok(deserialize::<Oid>(datum)?.to_string())
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        match <[u8; 4]>::try_from(buf) {
+            Ok(buf) => Ok(bincode::serialize(&(u32::from_be_bytes(buf) as Oid))?),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation("oid")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        let oid = deserialize::<Oid>(datum)?;
+        match u32::try_from(oid) {
+            Ok(oid) => Ok(oid.to_be_bytes().to_vec()),
+            Err(_) => bail!(Error::ValueOutOfRange("oid", oid.to_string())),
+        }
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<Oid>(a)?.cmp(&deserialize::<Oid>(b)?))
+    }
+}
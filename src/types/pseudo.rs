@@ -0,0 +1,60 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{Error, Type, TypeAlign, TypeCategory, TypeKind};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Pseudo-type used to declare function arguments that accept a value of any type, like the
+/// variadic arguments of concat. There are no values of this type.
+pub struct Any;
+
+impl Type for Any {
+    fn oid(&self) -> Oid {
+        pg_type::ANY_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "any"
+    }
+
+    fn format_type(&self) -> &'static str {
+        "\"any\""
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Pseudo
+    }
+
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Pseudo
+    }
+
+    fn typlen(&self) -> i32 {
+        4
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, _value: &str, _typmod: i32) -> Result<Datum> {
+        bail!(Error::PseudoTypeInput(self.format_type()))
+    }
+
+    fn output(&self, _datum: &[u8]) -> Result<String> {
+        bail!(Error::PseudoTypeOutput(self.format_type()))
+    }
+
+    fn recv(&self, _buf: &[u8], _typmod: i32) -> Result<Datum> {
+        bail!(Error::PseudoTypeInput(self.format_type()))
+    }
+
+    fn send(&self, _datum: &[u8]) -> Result<Vec<u8>> {
+        bail!(Error::PseudoTypeOutput(self.format_type()))
+    }
+
+    fn compare(&self, _a: &[u8], _b: &[u8]) -> Result<Ordering> {
+        bail!(Error::UndefinedComparison(self.format_type()))
+    }
+}
@@ -0,0 +1,58 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Variable-length character string data type without a length limit.
+pub struct Text;
+
+impl Type for Text {
+    fn oid(&self) -> Oid {
+        pg_type::TEXT_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "text"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::String
+    }
+
+    fn typispreferred(&self) -> bool {
+        true
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        deserialize::<String>(datum)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match std::str::from_utf8(buf) {
+            Ok(value) => self.input(value, typmod),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation("text")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<String>(datum)?.into_bytes())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<String>(a)?.cmp(&deserialize::<String>(b)?))
+    }
+}
@@ -0,0 +1,58 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use super::{deserialize, Type, TypeAlign, TypeCategory, TypeKind};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Type of string literals whose type was not resolved yet.
+///
+/// A quoted literal like '42' can be used as a value of any type, the literal is converted to
+/// the type required by the context where it is used, e.g the type of an inserted column.
+pub struct Unknown;
+
+impl Type for Unknown {
+    fn oid(&self) -> Oid {
+        pg_type::UNKNOWN_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "unknown"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::Unknown
+    }
+
+    fn typtype(&self) -> TypeKind {
+        TypeKind::Pseudo
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Char
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        deserialize::<String>(datum)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        self.input(&String::from_utf8_lossy(buf), typmod)
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<String>(datum)?.into_bytes())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<String>(a)?.cmp(&deserialize::<String>(b)?))
+    }
+}
@@ -0,0 +1,134 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{deserialize, Error, Type, TypeAlign};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Universally unique identifier data type, stored as 16 bytes.
+///
+/// The text representation is a sequence of 32 hexadecimal digits, optionally surrounded by
+/// braces and with a hyphen after any group of four digits. Values are displayed on the
+/// standard form, e.g a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11.
+pub struct Uuid;
+
+impl Type for Uuid {
+    fn oid(&self) -> Oid {
+        pg_type::UUID_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "uuid"
+    }
+
+    fn typlen(&self) -> i32 {
+        16
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Char
+    }
+
+    fn input(&self, value: &str, _typmod: i32) -> Result<Datum> {
+        match parse_uuid(value) {
+            Some(bytes) => Ok(bincode::serialize(&bytes)?),
+            None => bail!(Error::InvalidTextRepresentation("uuid", value.to_string())),
+        }
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        let bytes = deserialize::<[u8; 16]>(datum)?;
+        let mut output = String::with_capacity(36);
+        for (i, byte) in bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                output.push('-');
+            }
+            output.push_str(&format!("{:02x}", byte));
+        }
+        Ok(output)
+    }
+
+    fn recv(&self, buf: &[u8], _typmod: i32) -> Result<Datum> {
+        if buf.len() != 16 {
+            bail!(Error::InvalidBinaryRepresentation("uuid"));
+        }
+        Ok(buf.to_vec())
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<[u8; 16]>(datum)?.to_vec())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<[u8; 16]>(a)?.cmp(&deserialize::<[u8; 16]>(b)?))
+    }
+}
+
+/// Parse the text representation of a uuid, returning None if the value is malformed.
+fn parse_uuid(value: &str) -> Option<[u8; 16]> {
+    let mut input = value.as_bytes();
+    let braces = input.first() == Some(&b'{');
+    if braces {
+        input = input[1..].strip_suffix(b"}")?;
+    }
+
+    let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8);
+
+    let mut bytes = [0; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        match input {
+            [high, low, rest @ ..] => {
+                *byte = digit(*high)? << 4 | digit(*low)?;
+                input = rest;
+            }
+            _ => return None,
+        }
+
+        // A hyphen is allowed after each group of four digits, except the last one.
+        if i % 2 == 1 && i != 15 {
+            if let Some(rest) = input.strip_prefix(b"-") {
+                input = rest;
+            }
+        }
+    }
+
+    input.is_empty().then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_representation() -> Result<()> {
+        let expected = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+        for value in [
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11",
+            "{a0eebc99-9c0b4ef8-bb6d6bb9-bd380a11}",
+            "a0eebc999c0b4ef8bb6d6bb9bd380a11",
+            "a0ee-bc99-9c0b-4ef8-bb6d-6bb9-bd38-0a11",
+        ] {
+            assert_eq!(Uuid.output(&Uuid.input(value, -1)?)?, expected);
+        }
+
+        for value in [
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1",
+            "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11-",
+            "{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+            "a0eebc99--9c0b-4ef8-bb6d-6bb9bd380a11",
+            "g0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11",
+        ] {
+            assert!(Uuid.input(value, -1).is_err(), "{}", value);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_fixed_length_datum() -> Result<()> {
+        let datum = Uuid.input("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11", -1)?;
+        assert_eq!(datum.len(), Uuid.typlen() as usize);
+        assert_eq!(Uuid.recv(&Uuid.send(&datum)?, -1)?, datum);
+        Ok(())
+    }
+}
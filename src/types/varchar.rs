@@ -0,0 +1,79 @@
+use std::cmp::Ordering;
+
+use anyhow::{bail, Result};
+
+use super::{char_length, char_length_typmod, deserialize, Error, Type, TypeAlign, TypeCategory};
+use crate::{catalog::pg_type, Datum, Oid};
+
+/// Variable-length character string data type.
+///
+/// The typmod is the maximum number of characters plus [super::VARHDRSZ], or -1 if the length
+/// is unlimited.
+pub struct Varchar;
+
+impl Type for Varchar {
+    fn oid(&self) -> Oid {
+        pg_type::VARCHAR_OID
+    }
+
+    fn typname(&self) -> &'static str {
+        "varchar"
+    }
+
+    fn format_type(&self) -> &'static str {
+        "character varying"
+    }
+
+    fn typcategory(&self) -> TypeCategory {
+        TypeCategory::String
+    }
+
+    fn typlen(&self) -> i32 {
+        -1
+    }
+
+    fn typalign(&self) -> TypeAlign {
+        TypeAlign::Int
+    }
+
+    fn typmod_in(&self, args: &[u64]) -> Result<i32> {
+        char_length_typmod("varchar", args)
+    }
+
+    fn input(&self, value: &str, typmod: i32) -> Result<Datum> {
+        let value = match char_length(typmod) {
+            Some(len) => truncate_spaces(value, len, "character varying")?,
+            None => value,
+        };
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn output(&self, datum: &[u8]) -> Result<String> {
+        deserialize::<String>(datum)
+    }
+
+    fn recv(&self, buf: &[u8], typmod: i32) -> Result<Datum> {
+        match std::str::from_utf8(buf) {
+            Ok(value) => self.input(value, typmod),
+            Err(_) => bail!(Error::InvalidBinaryRepresentation("character varying")),
+        }
+    }
+
+    fn send(&self, datum: &[u8]) -> Result<Vec<u8>> {
+        Ok(deserialize::<String>(datum)?.into_bytes())
+    }
+
+    fn compare(&self, a: &[u8], b: &[u8]) -> Result<Ordering> {
+        Ok(deserialize::<String>(a)?.cmp(&deserialize::<String>(b)?))
+    }
+}
+
+/// Truncate the value to len characters, which is only allowed if the truncated characters are
+/// spaces.
+pub(super) fn truncate_spaces<'a>(value: &'a str, len: usize, type_name: &str) -> Result<&'a str> {
+    match value.char_indices().nth(len) {
+        Some((end, _)) if value[end..].chars().all(|c| c == ' ') => Ok(&value[..end]),
+        Some(_) => bail!(Error::StringTooLong(format!("{}({})", type_name, len))),
+        None => Ok(value),
+    }
+}
@@ -0,0 +1,32 @@
+create table arrays(ids int[], names varchar(5)[], tags text[], stamps date[]);
+insert into arrays values ('{1, 2, NULL}', '{ab,"c d", "", "null"}', ARRAY['x', NULL], ARRAY[DATE '2024-01-02']);
+insert into arrays values (ARRAY[[1, 2], [3, 4]], ARRAY['a"b', 'c\d'], '{}', '{2024-02-29, infinity}');
+insert into arrays values ('[0:1]={5,6}', NULL, ARRAY[['a', 'b'], ['c', 'd']], NULL);
+insert into arrays(ids) values (ARRAY[1.4, 2.6]);
+select * from arrays;
+select jsonb_agg(ids) as ids, jsonb_agg(tags) from arrays;
+
+create table elements(first int, found bool, none bool, missing int);
+insert into elements values ((ARRAY[7, 8, 9])[1], 2 = ANY('{1,2}'), 3 = ALL(ARRAY[3, 3]), (ARRAY[1, 2])[5]);
+insert into elements values ((ARRAY[[1, 2], [3, 4]])[2][1], 2 = ANY(ARRAY[1, NULL]), 3 <> ALL(ARRAY[1, 2]), (ARRAY[1])[1][1]);
+insert into elements values ((ARRAY[5, 6])[0], 1 = ANY('{}'), 1.5 > ALL(ARRAY[1, 2]), NULL);
+select * from elements;
+select array_agg(first) as firsts, array_agg(found) from elements;
+
+select * from unnest(ARRAY[[1, 2], [3, NULL]]);
+select x from unnest(ARRAY['a', 'b']) as t(x);
+select array_agg(u) from unnest(ARRAY[3.5, 4]) u;
+
+create table matrix(cells text[][], n int);
+insert into matrix values ('{{a,b},{c,d}}', 1), (ARRAY['e', 'f'], 2);
+select * from matrix;
+select array_agg(cells) from matrix where n = 2;
+select array_agg(ARRAY[first, missing]) from elements;
+select array_agg(cells) from matrix;
+select array_agg(tags) from arrays;
+select array_agg(names) from arrays where names is null;
+select array_agg(distinct first) from elements;
+select array_agg(first) from elements group by found;
+select array_agg(first) from elements having count(*) > 1;
+select unnest(ARRAY[1, 2]);
+select n, unnest(cells) from matrix;
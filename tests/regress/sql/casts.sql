@@ -0,0 +1,12 @@
+create table casts(i int, s varchar(3), b bool, d date, t text, n numeric(5,2), ch char(2));
+insert into casts values ('42', 'abc', 't', '2024-01-02', 'x', '1.005', 'a');
+insert into casts values (CAST('7' AS int), 'abcdef'::varchar(3), 1::bool, '2024-03-04 10:00'::timestamp, 42::text, 3::numeric, 'xyz'::char(2));
+insert into casts values (2.5::int, 12345::varchar(3), 0::bool, CAST(timestamp '2024-05-06 01:00:00' AS date), true::text, '2.555'::numeric(4,1), NULL::char(2));
+insert into casts values (true::int, CAST(ARRAY['a'] AS text), 'f', date '2024-01-01', ARRAY[1, 2]::text, '12.3'::float8::numeric, 'ab');
+insert into casts(t, i, s) values ('{1,2}'::int[], 1.4::float4::int8, 'a b'::char(1));
+select * from casts;
+
+create table conditions(a bool, b bool, c bool, d bool);
+insert into conditions values ('1' = 1, 1 = 1.0, '2024-01-02'::date < '2024-01-02 10:00'::timestamp, 'abc'::char(5) = 'abc'::text);
+insert into conditions values (2::int8 = ANY('{1,2}'::int2[]), '{1.5}'::float8[] = ARRAY[1.5], 'a'::varchar < 'b', 7 = '7'::int8);
+select * from conditions;
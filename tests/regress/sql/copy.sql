@@ -0,0 +1,15 @@
+create table copy_t(a int, b varchar, c boolean);
+\copy copy_t from 'tests/regress/data/copy.csv' csv header;
+\copy copy_t from 'tests/regress/data/copy.txt';
+select * from copy_t;
+copy copy_t to stdout;
+copy copy_t to stdout with (format csv, header, null 'NULL');
+copy copy_t (c, a) to stdout with (format csv, delimiter ';');
+create table copy_rollback(a int);
+\copy copy_rollback from 'tests/regress/data/copy_rollback.txt';
+select * from copy_rollback;
+create table copy_large(a int, b text);
+\copy copy_large from 'tests/regress/data/copy_large.txt';
+select * from copy_large where a < 3 or a > 1998;
+\copy copy_large from 'tests/regress/data/copy_large_rollback.txt';
+select jsonb_agg(a) from copy_large where a > 1997;
@@ -0,0 +1,31 @@
+create type mood as enum ('sad', 'ok', 'happy');
+create type pair as (a int, b text);
+create type nothing as enum ();
+
+create table person(name text, current mood, p pair, moods mood[]);
+insert into person values ('a', 'happy', '(1,x)', '{sad,ok}');
+insert into person values ('b', 'sad', '(2,"a ""b""")', '{happy,NULL}');
+insert into person values ('c', 'ok', '(,)', '{}');
+insert into person(name) values ('d');
+select * from person;
+select array_agg(current) as currents, array_agg(p) as pairs from person;
+
+create type nested as (m mood, ms mood[], p pair);
+create table nesting(x nested);
+insert into nesting values ('(ok,"{sad,ok}","(1,""a b"")")');
+insert into nesting values ('(happy,,)');
+select * from nesting;
+
+select enumsortorder, enumlabel from pg_enum;
+
+create type duplicated as enum ('a', 'b', 'a');
+insert into person(name, p) values ('e', row(3, 'y')), ('f', (4, 5));
+insert into person(name, p) values ('g', row(1));
+select name, (p).a, (p).b as second from person where (p).a > 1;
+select (x).p, ((x).p).b, (x).ms[2] from nesting;
+select row(1, 'a', null), (1, 2.5) as r, row(1, 'x')::pair;
+select (p).c from person;
+select (name).a from person;
+create table records(r record);
+create table mood(a int);
+create table pair(a int);
@@ -0,0 +1,28 @@
+create table events(id integer, d date, t time, ts timestamp, tstz timestamptz);
+insert into events values (1, '2024-02-29', '13:45:00.5', '2024-01-31 10:00:00', '2024-01-31 10:00:00-03');
+insert into events values (2, '0044-03-15 BC', '24:00', 'infinity', '-infinity');
+insert into events values (3, date '2024-01-01' + 30, time '23:00' + interval '2 hours', timestamp '2024-01-31 10:00' + interval '1 month', '2024-03-10T08:00:00Z');
+insert into events values (4, date '2024-03-01' - 1, date '2024-03-01' + time '10:30', date '2024-03-01' + interval '1 day', timestamp '2024-03-01 12:00' - interval '1 year');
+insert into events values (date '2024-03-01' - date '2024-02-01', 'epoch', 'allballs', 'epoch', '2024-06-30 23:59:59.999999+05:30');
+select * from events;
+set timezone = 'EST5'; select * from events;
+set timezone = '5.5'; select id, tstz from events;
+set timezone = 'UTC+3'; show timezone;
+set timezone = 'America/New_York'; select id, tstz from events;
+set timezone = 'Europe/Berlin'; select '2024-03-31 02:30'::timestamptz, '2024-10-27 02:30'::timestamptz, '2024-03-30 12:00'::timestamptz + interval '1 day';
+set timezone = 'Mars/Olympus_Mons';
+set timezone to -3; show timezone; select 1 as next;
+set time zone +5.5; show time zone;
+
+create table spans(id integer, iv interval);
+insert into spans values (1, '1 year 2 months 3 days 04:05:06.7');
+insert into spans values (2, '-1 year +2 mons');
+insert into spans values (3, '1 day 2 hours ago');
+insert into spans values (4, interval '1 mon' / 2);
+insert into spans values (5, interval '2' hour * 1.5);
+insert into spans values (6, 2 * interval '1 day' - interval '1 hour');
+insert into spans values (7, time '10:00' - time '12:30');
+insert into spans values (8, timestamp '2024-03-01' - timestamp '2024-01-01 12:00');
+insert into spans values (9, -interval '1.5 years');
+insert into spans values (10, '0');
+select * from spans;
@@ -0,0 +1,18 @@
+create table orders(id integer, item text, qty integer, price numeric(6,2), tags text[]);
+insert into orders values (1, 'pen', 3, 1.25, array['office', 'cheap']);
+insert into orders values (2, 'desk', 1, 150.00, array['office']);
+insert into orders values (3, 'lamp', null, 20.50, null);
+select id + 1 as next, qty * price, -qty, price / 2 as half from orders;
+select item as name, concat(item, '!') from orders where id = 1;
+select id, qty * price > 5 as big, item like 'd%', price is null, not (qty > 1) from orders;
+select id::text, id::numeric(4,1), 1::integer, price::int, 'x'::char(3), date '2024-01-01', true, interval '1 day' from orders where id = 1;
+select id, 'literal', null, tags[1], array[id, qty], (qty) from orders;
+select case when qty > 1 then item else 'single' end, case when qty > 1 then 'many' else item end, coalesce(qty, 0) + 1 as qty from orders;
+select orders.id, orders.* from orders where orders.id = 2;
+select id, upper(item) as item, round(price * qty, 1) as total from orders where price * coalesce(qty, 1) >= 20;
+select o.id, o.item from orders o where o.qty > 1;
+select o.* from orders as o where o.id = 1;
+select x.n, item from orders x(n) where n < 3;
+select array_agg(o.item) from orders o;
+select orders.id from orders o;
+select * from orders o(a, b, c, d, e, f);
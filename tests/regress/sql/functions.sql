@@ -0,0 +1,17 @@
+create table products(id integer, name text, price numeric, weight float8, code bytea);
+insert into products values (1, '  Widget ', 10.456, 2.5, '\x0102');
+insert into products values (2, 'gadget', -3.5, -1.5, '\x');
+insert into products values (3, null, null, null, null);
+select id, lower(name), upper(name), length(name), char_length(name), length(code) from products;
+select id, substring(name from 2 for 3), substring(name, 3), substr(name, 0, 3), trim(name), trim(leading ' W' from name), rtrim(name), btrim(name, ' tW') from products;
+select id, concat(id, '-', name, '-', price), replace(name, 'dg', 'DG'), position('dg' in name), strpos(name, 'x') from products;
+select id, abs(price), abs(weight), round(price), round(price, 1), round(price, -1), round(weight) from products;
+select id, floor(price), ceil(price), ceiling(weight), floor(weight), mod(id, 2), mod(price, 3) from products;
+select id, power(2, id), power(price, 2), pow(weight, 2) from products;
+select id, case when id = 1 then 'one' when id = 2 then 'two' else 'other' end from products;
+select id, case id when 2 then price * 2 end from products;
+select id, coalesce(name, 'unnamed'), coalesce(null, price, 0), nullif(id, 2), greatest(id, 2, null), least(price, 0) from products;
+select * from products where price > 0 or name is null;
+select id from products where not (id = 1) and coalesce(price, 0) <= 0;
+select x from unnest(array[1, 2, 3, 4, 5]) as u(x) where mod(x, 2) = 1;
+select products.name from products where products.id = 2;
@@ -25,3 +25,33 @@ select b from t2;
 select b, a from t2;
 select c, * from t2;
 select a, c from t2;
+
+
+-- Test inserting multiple rows and the rows of a query
+
+
+create table t4(a int, b text);
+insert into t4 values (1, 'one'), (2, 'two'), (3, 'three');
+insert into t4(b) values ('four'), ('five');
+insert into t4 select a + 10, b from t4 where a is not null;
+insert into t4(b, a) select b, a from t4 where a > 100;
+select * from t4;
+
+
+-- Test the values returned by RETURNING
+
+
+insert into t4 values (20, 'twenty') returning *;
+insert into t4(b) values ('x'), ('y') returning b, a is null as missing;
+insert into t4 select a + 100, upper(b) from t4 where a = 20 returning t4.*, a * 2 as double;
+
+
+-- Test ON CONFLICT DO NOTHING, tables have no unique indexes so no row conflicts
+
+
+insert into t4 values (30, 'thirty'), (30, 'thirty') on conflict do nothing;
+insert into t4 values (31, 'thirty one') on conflict do nothing returning a;
+insert into t4 values (32, 'thirty two') on conflict (a) do nothing;
+insert into t4 values (32, 'thirty two') on conflict on constraint t4_pkey do nothing;
+insert into t4 values (32, 'thirty two') on conflict (a) do update set b = excluded.b;
+select * from t4 where a >= 30;
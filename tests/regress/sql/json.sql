@@ -0,0 +1,19 @@
+create table payloads(id uuid, doc jsonb, raw json);
+insert into payloads values ('a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11', '{"name": "tiny", "tags": ["db", "rust"], "size": 1.50, "name": "tinydb"}', '{"b": 1,  "b": 2}');
+insert into payloads values ('{A0EEBC999C0B4EF8BB6D6BB9BD380A12}', jsonb_build_object('id', 42, 'day', DATE '2024-01-02', 'missing', null, 'ok', true), '[1, "two", null]');
+insert into payloads values ('a0eebc99-9c0b4ef8-bb6d6bb9-bd380a13', '"été 😀"', 'null');
+insert into payloads(id) values ('a0ee-bc99-9c0b-4ef8-bb6d-6bb9-bd38-0a14');
+select * from payloads;
+select jsonb_agg(doc) as docs, jsonb_agg(id) from payloads;
+
+create table lookups(field jsonb, element text, contained bool, present bool);
+insert into lookups values ('{"a": {"b": [10, "x"]}}' -> 'a', '{"a": {"b": [10, "x"]}}' -> 'a' -> 'b' ->> -1, jsonb_contains('{"a": 1, "b": [1, 2, 3]}', '{"b": [3, 1]}'), jsonb_exists('{"a": 1}', 'a'));
+insert into lookups values ('[1, 2]' -> 5, '{"a": null}' ->> 'a', jsonb_contains('[1, [2]]', '[2]'), jsonb_exists('["a", 1]', '1'));
+select * from lookups;
+insert into lookups values (null, null, '{"a": 1, "b": [1, 2, 3]}' @> '{"b": [3, 1]}', '["a", 1]' ? 'a');
+select * from lookups where contained and field ? 'b';
+select id from payloads where doc ? 'tags' or doc @> '{"ok": true}';
+select jsonb_agg(doc -> 'name') as names, jsonb_agg(doc ? 'id') from payloads;
+select '[1, 2]'::jsonb @> 1;
+select jsonb_agg(distinct 1);
+select jsonb_agg(1) group by 1;
@@ -0,0 +1,17 @@
+create table numbers(a smallint, b integer, c bigint, d real, e double precision);
+insert into numbers(a, b, c, d, e) values(32767, -2147483648, 3000000000, 1.5, 1.5);
+insert into numbers(a, b, c, d, e) values(-32768, 2147483647, -9223372036854775808, 0.1, 0.1);
+insert into numbers(a, b, c, d, e) values(2 * 3, 7 / 2, 2147483647 + 3000000000, '1e10', '1e300');
+insert into numbers(a, b, c, d, e) values(7 % 3, '42' + 1, 3000000000 * 2, 1.0 / 3, 2.5 * 4);
+insert into numbers(a, b, c, d, e) values(2.5, 3.5, -(-5), 'NaN', '-Infinity');
+insert into numbers(a, b, c, d, e) values(null, 1 + null, 1, 10 - 0.5, 2 / 4);
+select * from numbers;
+
+create table accounts(id integer, balance numeric(12, 2), rate numeric);
+insert into accounts values (1, 1234.567, 0.1 + 0.2);
+insert into accounts values (2, -0.005, 1 / 3.0);
+insert into accounts values (3, '99.995', 10 % 3.5);
+insert into accounts values (4, 3000000000, 'NaN');
+insert into accounts values (5, 2.5 * 2, 123456789012345678901234567890 * 10);
+insert into accounts values (6, '1.5e2', -7.50 / 2);
+select * from accounts;
@@ -0,0 +1,31 @@
+create table words(id integer, word text, code varchar(10));
+insert into words values (1, 'TinyDB rocks', 'AB-100%');
+insert into words values (2, 'postgres', 'CD_200');
+insert into words values (3, 'Rust', 'EF200');
+insert into words values (4, null, null);
+select id from words where word like 'Tiny%';
+select id from words where word like '_ust';
+select id from words where word ilike '%db%';
+select id from words where word not like '%s';
+select id from words where word not ilike 'rust';
+select id from words where code like '%\_%';
+select id from words where code like like_escape('%#%', '#');
+select id from words where code like like_escape('CD!_%', '!');
+select id from words where word ~ '^[a-z]+$';
+select id from words where word ~* '^tiny';
+select id from words where word !~ 's$';
+select id from words where word !~* 'r';
+select id from words where word ~ similar_to_escape('%(DB|gr)%');
+select id, regexp_replace(word, 'o', '0') from words;
+select id, regexp_replace(word, 'o|s', '_', 'g'), regexp_replace(word, '([a-z]+)$', '<\1>'), regexp_replace(word, 'T', '[\&]', 'i') from words;
+select id, regexp_match(word, '(\w)(\w+)'), regexp_match(word, 'x'), regexp_match(word, 'R.', 'i') from words;
+select * from regexp_matches('a1b22c333', '([a-z])(\d+)', 'g') as m(parts);
+select * from regexp_matches('a1b22c333', '\d+');
+select * from regexp_matches('abc', 'x');
+select id from words where code like '%#%' escape '#';
+select id from words where code not ilike 'cd!_%' escape '!';
+select id from words where word similar to '%(DB|gr)%';
+select id from words where word not similar to '[A-Z]%';
+select id from words where code similar to '%!%' escape '!';
+select id, word similar to 'Rust' as exact, word similar to 'Ru' as prefix from words;
+select id from words where word ~ 'a' escape '!';
@@ -5,3 +5,8 @@ select * from pg_attribute;
 select * from pg_database;
 
 select * from pg_tablespace;
+select * from pg_type;
+
+select * from pg_cast;
+
+select * from pg_proc;
@@ -0,0 +1,36 @@
+-- Sequences created with CREATE SEQUENCE
+create sequence seq1;
+select nextval('seq1'), nextval('seq1');
+select nextval('seq1'), currval('seq1'), lastval();
+create sequence seq2 increment by -2 start with -5 minvalue -9 maxvalue -1 cycle;
+select nextval('seq2'), nextval('seq2'), nextval('seq2'), nextval('seq2');
+create sequence seq3 as smallint start 10 increment 10;
+select nextval('seq3');
+select setval('seq3', 100), nextval('seq3');
+select setval('seq3', 5, false), nextval('seq3'), currval('seq3');
+create sequence if not exists seq3;
+select nextval('seq3');
+select * from seq1;
+select last_value, is_called from seq3 s where s.log_cnt >= 0;
+create sequence unused start 7;
+select * from unused;
+select * from pair;
+
+
+-- Serial and identity columns take the next value of their sequence by default
+create table purchases (id serial, item text default 'unknown', qty int default 1, ref bigserial);
+insert into purchases (item) values ('pen'), ('ink');
+insert into purchases values (default, 'paper', 5, default) returning *;
+insert into purchases (item, qty) select 'clip', 2 returning id, ref;
+select * from purchases;
+select nextval('purchases_id_seq');
+insert into purchases (item) values ('tape') returning id;
+create table tickets (id int generated always as identity (start with 100 increment by 10), n smallint generated by default as identity, note text);
+insert into tickets (note) values ('first'), ('second');
+insert into tickets (n, note) values (42, 'explicit');
+insert into tickets values (default, default, 'defaults') returning *;
+select * from tickets;
+create table taken_x_seq (a int);
+create table taken (x serial);
+insert into taken values (default) returning x;
+select relname, relkind from pg_class where relkind = 'S';
@@ -0,0 +1,17 @@
+create table strings(a text, b char(5), c varchar(4), d bytea, e char);
+insert into strings(a, b, c, d, e) values('tinydb', 'ab', 'abcd', '\x74696e79', 'x');
+insert into strings(a, b, c, d, e) values('trailing  ', 'abcde   ', 'ab  ', 'tiny\\db\000\377', 'y ');
+insert into strings(a, b, c, d, e) values(42, true, 1.5, '\x DE AD be ef', null);
+insert into strings(a, b, c, d, e) values('', '', '', '', '');
+select * from strings;
+
+create table notes(id integer, body text, code character(3), tag character varying(8));
+insert into notes values (1, 'a long text without a declared limit', 'ab', 'tag');
+insert into notes values (2, 'bpchar values are padded', 'x', 'tag  ');
+select * from notes;
+
+select a || '!' as bang, 'x' || c, b || c from strings;
+select 1 || 'a', 'a' || 2.5, true || 'b', 'a' || NULL, 'a' || 'b' || 'c', 'a' || date '2024-01-02';
+select textcat('a', 'b');
+select 1 || 2;
+select ARRAY['a'] || 'b';
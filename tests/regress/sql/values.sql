@@ -0,0 +1,11 @@
+select 1;
+select 1 + 1 as two, 'text', upper('tinydb'), null, true;
+select 1 where false;
+select 'yes' where 1 < 2;
+values (1, 'one'), (2, null), (3.5, 'three');
+values ('a'), ('b');
+select * from (values (1, 'one'), (2, 'two'), (3, 'three')) as v(id, name) where id >= 2;
+select column1, column2 from (values (1, true)) as v;
+select v.x * 10 as tens from (values (1), (2), (null)) as v(x);
+select * from (values ('a'::char(3), 1), ('b'::char(3), 2)) as t(c);
+select coalesce(x, 'none') from (values ('a'), (null)) as t(x);
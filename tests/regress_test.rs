@@ -1,23 +1,31 @@
 use std::{
-    fs, io,
+    fs,
+    io::{self, Read, Write},
+    net::TcpStream,
     path::Path,
     process::{Child, Command},
 };
 
+use tinydb::control::{ClusterState, ControlFile};
+
 #[test]
 fn test_regress() -> anyhow::Result<()> {
-    let sql_entries = fs::read_dir(Path::new("tests").join("regress").join("sql"))
+    let mut sql_entries = fs::read_dir(Path::new("tests").join("regress").join("sql"))
         .expect("Failed to read regress sql dir")
         .map(|res| res.map(|e| e.path()))
         .collect::<Result<Vec<_>, io::Error>>()?;
 
+    // Run the sql files in a deterministic order, since the files can depend on each other.
+    sql_entries.sort();
+
     let expected_path = Path::new("tests").join("regress").join("expected");
     let output_path = Path::new("tests").join("regress").join("output");
 
     let temp_dir = tempfile::tempdir().expect("Failed to create temp dir to regress tests");
+    let socket_dir = tempfile::tempdir().expect("Failed to create temp dir to unix socket");
 
     // tinydb command will be killed when _tinydb is dropped.
-    let _tinydb = TinyDBCommand::start(&temp_dir.path())?;
+    let _tinydb = TinyDBCommand::start(&temp_dir.path(), &socket_dir.path())?;
 
     // Wait the server to start completely.
     std::thread::sleep(std::time::Duration::from_millis(500));
@@ -56,12 +64,9 @@ fn test_regress() -> anyhow::Result<()> {
                 .output()?;
             output.extend_from_slice(&result.stdout);
 
-            assert_eq!(
-                result.stderr.len(),
-                0,
-                "Failed to execute psql: {}",
-                std::str::from_utf8(&result.stderr.as_slice())?
-            );
+            // Errors are part of the output, so statements that are expected to fail are
+            // checked against the expected file, and unexpected errors fail the test.
+            output.extend_from_slice(&result.stderr);
         }
 
         let output =
@@ -72,21 +77,193 @@ fn test_regress() -> anyhow::Result<()> {
         assert_eq!(expected_sql, output, "Failed to match file {:?}", sql_file);
     }
 
+    test_unix_socket(socket_dir.path())?;
+    test_extended_query_protocol()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_shutdown_on_sigterm() -> anyhow::Result<()> {
+    let data_dir = tempfile::tempdir().expect("Failed to create temp dir to data directory");
+    let socket_dir = tempfile::tempdir().expect("Failed to create temp dir to unix socket");
+
+    // Run the server on another port, since test_regress may be running at the same time.
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_tinydb"))
+        .arg("--init")
+        .arg("--data-dir")
+        .arg(data_dir.path())
+        .arg("--unix-socket-directory")
+        .arg(socket_dir.path())
+        .arg("--port")
+        .arg("6380")
+        .spawn()?;
+
+    let mut attempts = 0;
+    while TcpStream::connect("localhost:6380").is_err() {
+        attempts += 1;
+        assert!(attempts < 100, "Failed to connect to tinydb server");
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert_eq!(
+        ControlFile::read(data_dir.path())?.state,
+        ClusterState::InProduction
+    );
+
+    let kill = Command::new("kill")
+        .arg("-TERM")
+        .arg(cmd.id().to_string())
+        .status()?;
+    assert!(kill.success());
+
+    assert!(cmd.wait()?.success());
+    assert_eq!(
+        ControlFile::read(data_dir.path())?.state,
+        ClusterState::ShutDown
+    );
+
+    Ok(())
+}
+
+/// Check that the server accepts connections on the unix-domain socket created inside the
+/// given directory.
+fn test_unix_socket(socket_dir: &Path) -> anyhow::Result<()> {
+    assert!(socket_dir.join(".s.PGSQL.6379").exists());
+    assert!(socket_dir.join(".s.PGSQL.6379.lock").exists());
+
+    let result = Command::new("psql")
+        .arg("-h")
+        .arg(socket_dir)
+        .arg("-p")
+        .arg("6379")
+        .arg("-X")
+        .arg("-A")
+        .arg("-t")
+        .arg("-d")
+        .arg("tinydb")
+        .arg("-c")
+        .arg("select 1;")
+        .output()?;
+
+    assert_eq!(
+        result.stderr.len(),
+        0,
+        "Failed to execute psql over unix socket: {}",
+        std::str::from_utf8(&result.stderr.as_slice())?
+    );
+    assert_eq!("1\n", std::str::from_utf8(&result.stdout.as_slice())?);
+
     Ok(())
 }
 
+/// Check that a prepared statement receives parameters and sends results in binary format,
+/// using the binary format of PostgreSQL arrays.
+fn test_extended_query_protocol() -> anyhow::Result<()> {
+    let mut client = Client::connect()?;
+
+    // The type of the parameter is inferred from the cast, and the value is sent back.
+    client.send(b'P', b"\0select $1::int[] as a, 'x' as b\0\0\0")?;
+    client.send(b'D', b"S\0")?;
+    let array: &[u8] = &[
+        0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 23, 0, 0, 0, 2, 0, 0, 0,
+        1, // 1 dimension of 2 int4 with NULL
+        0, 0, 0, 4, 0, 0, 0, 7, 255, 255, 255, 255, // 7 and NULL
+    ];
+    let mut bind = b"\0\0\0\x01\0\x01\0\x01".to_vec();
+    bind.extend_from_slice(&(array.len() as u32).to_be_bytes());
+    bind.extend_from_slice(array);
+    bind.extend_from_slice(b"\0\x02\0\x01\0\0");
+    client.send(b'B', &bind)?;
+    client.send(b'E', b"\0\0\0\0\0")?;
+    client.send(b'S', b"")?;
+
+    let messages = client.receive_until_ready()?;
+    let tags: Vec<u8> = messages.iter().map(|(tag, _)| *tag).collect();
+    assert_eq!(tags, b"1tT2DCZ");
+    // ParameterDescription with the int4[] type.
+    assert_eq!(messages[1].1, [0, 1, 0, 0, 0x03, 0xef]);
+
+    let mut data_row = vec![0, 2];
+    data_row.extend_from_slice(&(array.len() as u32).to_be_bytes());
+    data_row.extend_from_slice(array);
+    data_row.extend_from_slice(&[0, 0, 0, 1, b'x']);
+    assert_eq!(messages[4].1, data_row);
+
+    // Messages after an error are ignored until the next Sync.
+    client.send(b'P', b"\0select * from missing\0\0\0")?;
+    client.send(b'B', b"\0\0\0\0\0\0\0\0")?;
+    client.send(b'E', b"\0\0\0\0\0")?;
+    client.send(b'S', b"")?;
+    let tags: Vec<u8> = client
+        .receive_until_ready()?
+        .iter()
+        .map(|(tag, _)| *tag)
+        .collect();
+    assert_eq!(tags, b"EZ");
+
+    Ok(())
+}
+
+/// Minimal client of the PostgreSQL protocol, used to send the messages of the extended query
+/// protocol, which psql does not send.
+struct Client {
+    stream: TcpStream,
+}
+
+impl Client {
+    fn connect() -> anyhow::Result<Self> {
+        let mut client = Self {
+            stream: TcpStream::connect("localhost:6379")?,
+        };
+
+        let mut startup = 196608u32.to_be_bytes().to_vec();
+        startup.extend_from_slice(b"user\0tinydb\0database\0tinydb\0\0");
+        client
+            .stream
+            .write_all(&(startup.len() as u32 + 4).to_be_bytes())?;
+        client.stream.write_all(&startup)?;
+        client.receive_until_ready()?;
+        Ok(client)
+    }
+
+    fn send(&mut self, tag: u8, body: &[u8]) -> io::Result<()> {
+        self.stream.write_all(&[tag])?;
+        self.stream
+            .write_all(&(body.len() as u32 + 4).to_be_bytes())?;
+        self.stream.write_all(body)
+    }
+
+    /// Return the tag and the body of the messages received until ReadyForQuery.
+    fn receive_until_ready(&mut self) -> io::Result<Vec<(u8, Vec<u8>)>> {
+        let mut messages = Vec::new();
+        loop {
+            let mut header = [0; 5];
+            self.stream.read_exact(&mut header)?;
+            let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+            let mut body = vec![0; len as usize - 4];
+            self.stream.read_exact(&mut body)?;
+            messages.push((header[0], body));
+            if header[0] == b'Z' {
+                return Ok(messages);
+            }
+        }
+    }
+}
+
 struct TinyDBCommand {
     cmd: Child,
 }
 
 impl TinyDBCommand {
-    fn start(data_dir: &Path) -> anyhow::Result<Self> {
+    fn start(data_dir: &Path, socket_dir: &Path) -> anyhow::Result<Self> {
         let cmd = Command::new("cargo")
             .arg("run")
             .arg("--")
             .arg("--init")
             .arg("--data-dir")
             .arg(data_dir)
+            .arg("--unix-socket-directory")
+            .arg(socket_dir)
             .spawn()?;
 
         Ok(Self { cmd })